@@ -1,20 +1,27 @@
 use std::path::PathBuf;
 
-use crate::ir::errors::{ValidationError, XyntraError};
+use crate::{
+    fusion::candidates::find_candidates,
+    ir::{
+        errors::{ValidationError, XyntraError},
+        graph::Graph,
+        visualize::{NodeGroup, to_dot, to_mermaid},
+    },
+};
 
-struct XyntraConfig {
-    input_file: Option<PathBuf>,
-    output_dir: PathBuf,
-    backend: BackendType,
-    optimisation_level: u8,
-    tile_size: usize,
-    block_size: usize,
-    enable_debug: bool,
-    export_ir: bool,
+pub struct XyntraConfig {
+    pub input_file: Option<PathBuf>,
+    pub output_dir: PathBuf,
+    pub backend: BackendType,
+    pub optimisation_level: u8,
+    pub tile_size: usize,
+    pub block_size: usize,
+    pub enable_debug: bool,
+    pub export_ir: bool,
 }
 
 #[derive(Debug, Default)]
-enum BackendType {
+pub enum BackendType {
     #[default]
     Wgsl,
     CudaPtx,
@@ -88,13 +95,13 @@ impl XyntraConfig {
             ));
         }
 
-        if let Some(ref path) = self.input_file {
-            if !path.exists() {
-                return Err(XyntraError::Validation(ValidationError::InvalidFilePath {
-                    path: path.display().to_string(),
-                    reason: "file does not exist".to_string(),
-                }));
-            }
+        if let Some(ref path) = self.input_file
+            && !path.exists()
+        {
+            return Err(XyntraError::Validation(ValidationError::InvalidFilePath {
+                path: path.display().to_string(),
+                reason: "file does not exist".to_string(),
+            }));
         }
 
         if std::fs::metadata(&self.output_dir).is_err() {
@@ -106,4 +113,33 @@ impl XyntraConfig {
 
         Ok(())
     }
+
+    /// With `export_ir` set, writes `graph` to `<output_dir>/<name>.dot` and
+    /// `<name>.mmd` with every fusion candidate drawn as a cluster, and returns
+    /// the paths written. Does nothing when the flag is off.
+    pub fn export_graph(&self, graph: &Graph, name: &str) -> Result<Vec<PathBuf>, XyntraError> {
+        if !self.export_ir {
+            return Ok(Vec::new());
+        }
+
+        let groups: Vec<NodeGroup> = find_candidates(graph).iter().map(NodeGroup::from).collect();
+        let files = [
+            (
+                self.output_dir.join(format!("{name}.dot")),
+                to_dot(graph, &groups),
+            ),
+            (
+                self.output_dir.join(format!("{name}.mmd")),
+                to_mermaid(graph, &groups),
+            ),
+        ];
+
+        let mut written = Vec::new();
+        for (path, contents) in files {
+            std::fs::write(&path, contents)
+                .map_err(|error| XyntraError::from_io(&path, "write", error))?;
+            written.push(path);
+        }
+        Ok(written)
+    }
 }
@@ -0,0 +1,187 @@
+use crate::ir::{
+    graph::Graph,
+    types::{NodeID, OpKind},
+    visualize::NodeGroup,
+};
+
+/// Kind of op chain a fusion candidate was recognised as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FusionPattern {
+    /// A reduction followed by elementwise ops on its result, e.g.
+    /// `ReduceMean -> Add -> Rsqrt`. The elementwise tail becomes the epilogue
+    /// of the reduction kernel and runs on the reduced values before they are
+    /// written out.
+    ReduceElementwise,
+    /// A convolution followed by a batch norm, a ReLU-style activation, or
+    /// both. The batch norm folds into the convolution's weights and bias and
+    /// the activation becomes its epilogue.
+    ConvBnRelu,
+}
+
+/// Nodes that could be emitted as one kernel, in data-flow order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FusionCandidate {
+    pattern: FusionPattern,
+    nodes: Vec<NodeID>,
+}
+
+impl FusionCandidate {
+    pub fn new(pattern: FusionPattern, nodes: Vec<NodeID>) -> Self {
+        FusionCandidate { pattern, nodes }
+    }
+
+    pub fn pattern(&self) -> FusionPattern {
+        self.pattern
+    }
+
+    pub fn nodes(&self) -> &[NodeID] {
+        &self.nodes
+    }
+
+    /// The node the chain starts from.
+    pub fn root(&self) -> NodeID {
+        self.nodes[0]
+    }
+
+    /// The node whose result leaves the fused kernel.
+    pub fn output(&self) -> NodeID {
+        self.nodes[self.nodes.len() - 1]
+    }
+
+    pub fn contains(&self, node_id: NodeID) -> bool {
+        self.nodes.contains(&node_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+/// Draws the candidate as a cluster labelled with its pattern.
+impl From<&FusionCandidate> for NodeGroup {
+    fn from(candidate: &FusionCandidate) -> Self {
+        NodeGroup::new(format!("{:?}", candidate.pattern), candidate.nodes.clone())
+    }
+}
+
+/// Every candidate of every known pattern, ordered by root node ID.
+pub fn find_candidates(graph: &Graph) -> Vec<FusionCandidate> {
+    let mut candidates = find_reduce_elementwise(graph);
+    candidates.extend(find_conv_bn_relu(graph));
+    candidates.sort_by_key(|candidate| candidate.root().id());
+    candidates
+}
+
+/// Reductions whose result flows through one or more elementwise ops.
+///
+/// The chain grows while the current tail has exactly one consumer and that
+/// consumer is elementwise, so no intermediate value is needed outside the
+/// kernel. It stops early when a consumer's known output shape differs from
+/// the reduction's, since that op broadcasts back to the unreduced shape and
+/// needs a separate pass over the input (as in `x - mean(x)`).
+pub fn find_reduce_elementwise(graph: &Graph) -> Vec<FusionCandidate> {
+    let mut candidates = Vec::new();
+
+    for root in graph.node_ids() {
+        let Some(node) = graph.get_node(root) else {
+            continue;
+        };
+        if !node.op().is_reduction() {
+            continue;
+        }
+
+        let reduced_shape = graph.output_type(root).map(|ty| ty.shape());
+        let mut chain = vec![root];
+        let mut tail = root;
+
+        while let Some(next) = sole_consumer(graph, tail) {
+            let Some(next_node) = graph.get_node(next) else {
+                break;
+            };
+            if !next_node.op().is_elementwise() {
+                break;
+            }
+            if let (Some(reduced), Some(next_type)) = (reduced_shape, graph.output_type(next))
+                && next_type.shape() != reduced
+            {
+                break;
+            }
+
+            chain.push(next);
+            tail = next;
+        }
+
+        if chain.len() > 1 {
+            candidates.push(FusionCandidate::new(
+                FusionPattern::ReduceElementwise,
+                chain,
+            ));
+        }
+    }
+
+    candidates
+}
+
+/// Convolutions followed by `BatchNorm`, then `Relu` or `Clip`, where at
+/// least one of the two is present. Each link must be the only consumer of
+/// the previous node, and the batch norm must normalise the convolution output
+/// rather than take it as one of its parameters.
+pub fn find_conv_bn_relu(graph: &Graph) -> Vec<FusionCandidate> {
+    let mut candidates = Vec::new();
+
+    for root in graph.node_ids() {
+        let Some(node) = graph.get_node(root) else {
+            continue;
+        };
+        if !matches!(
+            node.op(),
+            OpKind::Conv2d { .. } | OpKind::DepthwiseConv { .. }
+        ) {
+            continue;
+        }
+
+        let mut chain = vec![root];
+        let next_op = |node_id: NodeID| {
+            sole_consumer(graph, node_id)
+                .and_then(|next| graph.get_node(next))
+                .map(|next| (next.id(), next.op(), next.inputs()))
+        };
+
+        if let Some((next, OpKind::BatchNorm { .. }, inputs)) = next_op(root)
+            && inputs.first() == Some(&root.output(0))
+            && inputs[1..].iter().all(|input| input.node() != root)
+        {
+            chain.push(next);
+        }
+        if let Some((next, OpKind::Relu | OpKind::Clip { .. }, _)) = next_op(chain[chain.len() - 1])
+        {
+            chain.push(next);
+        }
+
+        if chain.len() > 1 {
+            candidates.push(FusionCandidate::new(FusionPattern::ConvBnRelu, chain));
+        }
+    }
+
+    candidates
+}
+
+/// The consumer of `node_id` when there is exactly one. A graph output counts
+/// as a consumer, since its value has to leave the kernel.
+fn sole_consumer(graph: &Graph, node_id: NodeID) -> Option<NodeID> {
+    if graph
+        .outputs()
+        .iter()
+        .any(|output| output.node() == node_id)
+    {
+        return None;
+    }
+    match graph.users(node_id) {
+        [consumer] => Some(*consumer),
+        _ => None,
+    }
+}
@@ -0,0 +1,109 @@
+use std::ops::Index;
+
+use crate::ir::{ops::Node, types::NodeID};
+
+/// Slot storage for the nodes of a graph. Freed slots are reused, and each
+/// reuse bumps the slot's generation so handles to the removed node stop
+/// matching instead of reaching whatever node moved in.
+#[derive(Default)]
+pub struct NodeArena {
+    slots: Vec<Slot>,
+    /// Freed slot indices, reused last-freed first.
+    free: Vec<u32>,
+    /// Occupied slot indices in the order their nodes were added.
+    order: Vec<u32>,
+}
+
+#[derive(Default)]
+struct Slot {
+    generation: u32,
+    node: Option<Node>,
+}
+
+impl NodeArena {
+    pub fn new() -> Self {
+        NodeArena::default()
+    }
+
+    /// Handle the next inserted node will get.
+    pub fn next_id(&self) -> NodeID {
+        match self.free.last() {
+            Some(&index) => NodeID::with_generation(index, self.slots[index as usize].generation),
+            None => NodeID::new(self.slots.len() as u32),
+        }
+    }
+
+    /// Stores the node built by `make_node`, which receives its handle.
+    pub fn insert_with(&mut self, make_node: impl FnOnce(NodeID) -> Node) -> NodeID {
+        let node_id = self.next_id();
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                self.slots.push(Slot::default());
+                node_id.id()
+            }
+        };
+
+        self.slots[index as usize].node = Some(make_node(node_id));
+        self.order.push(index);
+        node_id
+    }
+
+    /// Takes the node out and retires its handle.
+    pub fn remove(&mut self, node_id: NodeID) -> Option<Node> {
+        let index = node_id.id();
+        let slot = self.slot_mut(node_id)?;
+        let node = slot.node.take();
+        slot.generation += 1;
+
+        self.free.push(index);
+        self.order.retain(|occupied| *occupied != index);
+        node
+    }
+
+    pub fn get(&self, node_id: NodeID) -> Option<&Node> {
+        self.slots
+            .get(node_id.id() as usize)
+            .filter(|slot| slot.generation == node_id.generation())
+            .and_then(|slot| slot.node.as_ref())
+    }
+
+    pub fn get_mut(&mut self, node_id: NodeID) -> Option<&mut Node> {
+        self.slot_mut(node_id).and_then(|slot| slot.node.as_mut())
+    }
+
+    /// Whether `node_id` names a live node, not a removed one.
+    pub fn contains(&self, node_id: NodeID) -> bool {
+        self.get(node_id).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+
+    /// Live handles in the order their nodes were added.
+    pub fn ids(&self) -> Vec<NodeID> {
+        self.order
+            .iter()
+            .map(|&index| NodeID::with_generation(index, self.slots[index as usize].generation))
+            .collect()
+    }
+
+    fn slot_mut(&mut self, node_id: NodeID) -> Option<&mut Slot> {
+        self.slots
+            .get_mut(node_id.id() as usize)
+            .filter(|slot| slot.generation == node_id.generation() && slot.node.is_some())
+    }
+}
+
+impl Index<NodeID> for NodeArena {
+    type Output = Node;
+
+    fn index(&self, node_id: NodeID) -> &Node {
+        self.get(node_id).expect("no live node for handle")
+    }
+}
@@ -0,0 +1,452 @@
+use core::fmt;
+use std::collections::BTreeMap;
+
+use crate::ir::{
+    errors::ParsingError,
+    types::{DType, OpKind, ReduceKind, Window2d},
+};
+
+/// A single attribute value, mirroring the attribute kinds ONNX uses.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeValue {
+    Int(i64),
+    Float(f32),
+    String(String),
+    Ints(Vec<i64>),
+    Floats(Vec<f32>),
+    Strings(Vec<String>),
+}
+
+/// Named attributes of an op, kept sorted so printing and export are stable.
+///
+/// Built-in ops keep their attributes as typed fields on `OpKind`; this map is
+/// the untyped form used by `Custom` ops and by importers/exporters.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Attributes(BTreeMap<String, AttributeValue>);
+
+impl AttributeValue {
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            AttributeValue::Int(_) => "int",
+            AttributeValue::Float(_) => "float",
+            AttributeValue::String(_) => "string",
+            AttributeValue::Ints(_) => "ints",
+            AttributeValue::Floats(_) => "floats",
+            AttributeValue::Strings(_) => "strings",
+        }
+    }
+}
+
+impl Attributes {
+    pub fn new() -> Self {
+        Attributes::default()
+    }
+
+    pub fn with(mut self, name: impl Into<String>, value: AttributeValue) -> Self {
+        self.insert(name, value);
+        self
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, value: AttributeValue) {
+        self.0.insert(name.into(), value);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&AttributeValue> {
+        self.0.get(name)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<AttributeValue> {
+        self.0.remove(name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.contains_key(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &AttributeValue)> {
+        self.0.iter().map(|(name, value)| (name.as_str(), value))
+    }
+
+    /// Integer attribute, or `default` when absent.
+    pub fn int_or(&self, name: &str, default: i64) -> Result<i64, ParsingError> {
+        match self.0.get(name) {
+            None => Ok(default),
+            Some(AttributeValue::Int(value)) => Ok(*value),
+            Some(other) => Err(wrong_kind(name, "int", other)),
+        }
+    }
+
+    /// Float attribute, or `default` when absent. Integers are accepted too.
+    pub fn float_or(&self, name: &str, default: f32) -> Result<f32, ParsingError> {
+        match self.0.get(name) {
+            None => Ok(default),
+            Some(AttributeValue::Float(value)) => Ok(*value),
+            Some(AttributeValue::Int(value)) => Ok(*value as f32),
+            Some(other) => Err(wrong_kind(name, "float", other)),
+        }
+    }
+
+    pub fn optional_float(&self, name: &str) -> Result<Option<f32>, ParsingError> {
+        if self.contains(name) {
+            self.float_or(name, 0.0).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Boolean stored as an int, the ONNX convention.
+    pub fn bool_or(&self, name: &str, default: bool) -> Result<bool, ParsingError> {
+        Ok(self.int_or(name, default as i64)? != 0)
+    }
+
+    pub fn ints_or(&self, name: &str, default: Vec<i64>) -> Result<Vec<i64>, ParsingError> {
+        match self.0.get(name) {
+            None => Ok(default),
+            Some(AttributeValue::Ints(values)) => Ok(values.clone()),
+            Some(AttributeValue::Int(value)) => Ok(vec![*value]),
+            Some(other) => Err(wrong_kind(name, "ints", other)),
+        }
+    }
+
+    /// Non-negative integer attribute, or `default` when absent.
+    pub fn usize_or(&self, name: &str, default: usize) -> Result<usize, ParsingError> {
+        let value = self.int_or(name, default as i64)?;
+        usize::try_from(value).map_err(|_| ParsingError::InvalidFormat {
+            format: "attribute".to_string(),
+            reason: format!("attribute '{name}' should be non-negative but holds {value}"),
+        })
+    }
+
+    /// Integer list whose entries must all be non-negative, e.g. sizes.
+    pub fn usizes_or(&self, name: &str, default: Vec<usize>) -> Result<Vec<usize>, ParsingError> {
+        if !self.contains(name) {
+            return Ok(default);
+        }
+
+        self.ints_or(name, Vec::new())?
+            .into_iter()
+            .map(|value| {
+                usize::try_from(value).map_err(|_| ParsingError::InvalidFormat {
+                    format: "attribute".to_string(),
+                    reason: format!("attribute '{name}' should be non-negative but holds {value}"),
+                })
+            })
+            .collect()
+    }
+
+    pub fn string_or(&self, name: &str, default: &str) -> Result<String, ParsingError> {
+        match self.0.get(name) {
+            None => Ok(default.to_string()),
+            Some(AttributeValue::String(value)) => Ok(value.clone()),
+            Some(other) => Err(wrong_kind(name, "string", other)),
+        }
+    }
+}
+
+impl OpKind {
+    /// Typed attributes in generic form. Every attribute is written out, even
+    /// when it holds the default, so the result is self-describing.
+    pub fn attributes(&self) -> Attributes {
+        match self {
+            OpKind::MatMul {
+                transpose_a,
+                transpose_b,
+            } => Attributes::new()
+                .with("transpose_a", AttributeValue::Int(*transpose_a as i64))
+                .with("transpose_b", AttributeValue::Int(*transpose_b as i64)),
+            OpKind::Clip { min, max } => {
+                let mut attributes = Attributes::new();
+                if let Some(min) = min {
+                    attributes.insert("min", AttributeValue::Float(*min));
+                }
+                if let Some(max) = max {
+                    attributes.insert("max", AttributeValue::Float(*max));
+                }
+                attributes
+            }
+            OpKind::Cast { to } => {
+                Attributes::new().with("to", AttributeValue::String(to.name().to_string()))
+            }
+            OpKind::Dropout { ratio } => {
+                Attributes::new().with("ratio", AttributeValue::Float(*ratio))
+            }
+            OpKind::Softmax { axis } => Attributes::new().with("axis", AttributeValue::Int(*axis)),
+            OpKind::LayerNorm { axis, epsilon } => Attributes::new()
+                .with("axis", AttributeValue::Int(*axis))
+                .with("epsilon", AttributeValue::Float(*epsilon)),
+            OpKind::Reduce { axes, keepdims, .. } => Attributes::new()
+                .with("axes", AttributeValue::Ints(axes.clone()))
+                .with("keepdims", AttributeValue::Int(*keepdims as i64)),
+            OpKind::ArgMax { axis, keepdims } => Attributes::new()
+                .with("axis", AttributeValue::Int(*axis))
+                .with("keepdims", AttributeValue::Int(*keepdims as i64)),
+            OpKind::Reshape { shape } => {
+                Attributes::new().with("shape", AttributeValue::Ints(shape.clone()))
+            }
+            OpKind::Transpose { perm } => Attributes::new().with("perm", usizes(perm)),
+            OpKind::Concat { axis } | OpKind::Gather { axis } => {
+                Attributes::new().with("axis", AttributeValue::Int(*axis))
+            }
+            OpKind::Split { axis, sizes } => Attributes::new()
+                .with("axis", AttributeValue::Int(*axis))
+                .with("sizes", usizes(sizes)),
+            OpKind::Slice {
+                starts,
+                ends,
+                axes,
+                steps,
+            } => Attributes::new()
+                .with("starts", AttributeValue::Ints(starts.clone()))
+                .with("ends", AttributeValue::Ints(ends.clone()))
+                .with("axes", AttributeValue::Ints(axes.clone()))
+                .with("steps", AttributeValue::Ints(steps.clone())),
+            OpKind::Conv2d { window, groups } => window
+                .attributes()
+                .with("groups", AttributeValue::Int(*groups as i64)),
+            OpKind::DepthwiseConv { window } => window.attributes(),
+            OpKind::MaxPool {
+                kernel_shape,
+                window,
+            } => window
+                .attributes()
+                .with("kernel_shape", usizes(kernel_shape)),
+            OpKind::AvgPool {
+                kernel_shape,
+                window,
+                count_include_pad,
+            } => window
+                .attributes()
+                .with("kernel_shape", usizes(kernel_shape))
+                .with(
+                    "count_include_pad",
+                    AttributeValue::Int(*count_include_pad as i64),
+                ),
+            OpKind::BatchNorm { epsilon } => {
+                Attributes::new().with("epsilon", AttributeValue::Float(*epsilon))
+            }
+            OpKind::Input { name } | OpKind::Constant { name } => {
+                Attributes::new().with("name", AttributeValue::String(name.clone()))
+            }
+            OpKind::Custom { attributes, .. } => attributes.clone(),
+            OpKind::GlobalAvgPool
+            | OpKind::Add
+            | OpKind::Gelu
+            | OpKind::Relu
+            | OpKind::Sigmoid
+            | OpKind::Tanh
+            | OpKind::Exp
+            | OpKind::Log
+            | OpKind::Sqrt
+            | OpKind::Rsqrt
+            | OpKind::Erf
+            | OpKind::Mul
+            | OpKind::Sub
+            | OpKind::Div
+            | OpKind::Pow
+            | OpKind::Where => Attributes::new(),
+        }
+    }
+
+    /// Inverse of `name()` plus `attributes()`. Missing attributes take their
+    /// defaults, unknown attributes on a built-in op are rejected, and names
+    /// that are not built-in become `Custom` ops.
+    pub fn from_parts(name: &str, attributes: &Attributes) -> Result<OpKind, ParsingError> {
+        let op = match name {
+            "MatMul" => OpKind::MatMul {
+                transpose_a: attributes.bool_or("transpose_a", false)?,
+                transpose_b: attributes.bool_or("transpose_b", false)?,
+            },
+            "Add" => OpKind::Add,
+            "Gelu" => OpKind::Gelu,
+            "Dropout" => OpKind::Dropout {
+                ratio: attributes.float_or("ratio", 0.5)?,
+            },
+            "Softmax" => OpKind::Softmax {
+                axis: attributes.int_or("axis", -1)?,
+            },
+            "LayerNorm" => OpKind::LayerNorm {
+                axis: attributes.int_or("axis", -1)?,
+                epsilon: attributes.float_or("epsilon", 1e-5)?,
+            },
+            "Relu" => OpKind::Relu,
+            "Sigmoid" => OpKind::Sigmoid,
+            "Tanh" => OpKind::Tanh,
+            "Exp" => OpKind::Exp,
+            "Log" => OpKind::Log,
+            "Sqrt" => OpKind::Sqrt,
+            "Rsqrt" => OpKind::Rsqrt,
+            "Erf" => OpKind::Erf,
+            "Clip" => OpKind::Clip {
+                min: attributes.optional_float("min")?,
+                max: attributes.optional_float("max")?,
+            },
+            "Cast" => {
+                let to = attributes.string_or("to", "")?;
+                OpKind::Cast {
+                    to: DType::from_name(&to).ok_or_else(|| ParsingError::InvalidFormat {
+                        format: "attribute".to_string(),
+                        reason: format!("'{to}' is not a known dtype for Cast"),
+                    })?,
+                }
+            }
+            "Mul" => OpKind::Mul,
+            "Sub" => OpKind::Sub,
+            "Div" => OpKind::Div,
+            "Pow" => OpKind::Pow,
+            "Where" => OpKind::Where,
+            "ArgMax" => OpKind::ArgMax {
+                axis: attributes.int_or("axis", 0)?,
+                keepdims: attributes.bool_or("keepdims", true)?,
+            },
+            "Reshape" => OpKind::Reshape {
+                shape: attributes.ints_or("shape", Vec::new())?,
+            },
+            "Transpose" => OpKind::Transpose {
+                perm: attributes.usizes_or("perm", Vec::new())?,
+            },
+            "Concat" => OpKind::Concat {
+                axis: attributes.int_or("axis", 0)?,
+            },
+            "Split" => OpKind::Split {
+                axis: attributes.int_or("axis", 0)?,
+                sizes: attributes.usizes_or("sizes", Vec::new())?,
+            },
+            "Slice" => OpKind::Slice {
+                starts: attributes.ints_or("starts", Vec::new())?,
+                ends: attributes.ints_or("ends", Vec::new())?,
+                axes: attributes.ints_or("axes", Vec::new())?,
+                steps: attributes.ints_or("steps", Vec::new())?,
+            },
+            "Gather" => OpKind::Gather {
+                axis: attributes.int_or("axis", 0)?,
+            },
+            "Conv2d" => OpKind::Conv2d {
+                window: Window2d::from_attributes(attributes)?,
+                groups: attributes.usize_or("groups", 1)?,
+            },
+            "DepthwiseConv" => OpKind::DepthwiseConv {
+                window: Window2d::from_attributes(attributes)?,
+            },
+            "MaxPool" => OpKind::MaxPool {
+                kernel_shape: fixed(attributes, "kernel_shape", [1, 1])?,
+                window: Window2d::from_attributes(attributes)?,
+            },
+            "AvgPool" => OpKind::AvgPool {
+                kernel_shape: fixed(attributes, "kernel_shape", [1, 1])?,
+                window: Window2d::from_attributes(attributes)?,
+                count_include_pad: attributes.bool_or("count_include_pad", false)?,
+            },
+            "GlobalAvgPool" => OpKind::GlobalAvgPool,
+            "Input" => OpKind::Input {
+                name: attributes.string_or("name", "")?,
+            },
+            "Constant" => OpKind::Constant {
+                name: attributes.string_or("name", "")?,
+            },
+            "BatchNorm" => OpKind::BatchNorm {
+                epsilon: attributes.float_or("epsilon", 1e-5)?,
+            },
+            _ => match ReduceKind::from_op_name(name) {
+                Some(kind) => OpKind::Reduce {
+                    kind,
+                    axes: attributes.ints_or("axes", Vec::new())?,
+                    keepdims: attributes.bool_or("keepdims", true)?,
+                },
+                None => {
+                    return Ok(OpKind::Custom {
+                        name: name.to_string(),
+                        attributes: attributes.clone(),
+                    });
+                }
+            },
+        };
+
+        let known = op.attributes();
+        if let Some((unknown, _)) = attributes.iter().find(|(key, _)| !known.contains(key)) {
+            return Err(ParsingError::InvalidFormat {
+                format: "attribute".to_string(),
+                reason: format!("op '{name}' has no attribute '{unknown}'"),
+            });
+        }
+
+        Ok(op)
+    }
+}
+
+impl Window2d {
+    pub fn attributes(&self) -> Attributes {
+        Attributes::new()
+            .with("strides", usizes(&self.strides))
+            .with("pads", usizes(&self.pads))
+            .with("dilations", usizes(&self.dilations))
+    }
+
+    /// Reads `strides`, `pads` and `dilations`, defaulting like
+    /// [`Window2d::default`].
+    pub fn from_attributes(attributes: &Attributes) -> Result<Self, ParsingError> {
+        let defaults = Window2d::default();
+        Ok(Window2d {
+            strides: fixed(attributes, "strides", defaults.strides)?,
+            pads: fixed(attributes, "pads", defaults.pads)?,
+            dilations: fixed(attributes, "dilations", defaults.dilations)?,
+        })
+    }
+}
+
+/// Non-negative integer list of exactly `N` entries.
+fn fixed<const N: usize>(
+    attributes: &Attributes,
+    name: &str,
+    default: [usize; N],
+) -> Result<[usize; N], ParsingError> {
+    let values = attributes.usizes_or(name, default.to_vec())?;
+    let found = values.len();
+    values.try_into().map_err(|_| ParsingError::InvalidFormat {
+        format: "attribute".to_string(),
+        reason: format!("attribute '{name}' should have {N} entries but has {found}"),
+    })
+}
+
+fn usizes(values: &[usize]) -> AttributeValue {
+    AttributeValue::Ints(values.iter().map(|&value| value as i64).collect())
+}
+
+fn wrong_kind(name: &str, expected: &str, found: &AttributeValue) -> ParsingError {
+    ParsingError::InvalidFormat {
+        format: "attribute".to_string(),
+        reason: format!(
+            "attribute '{name}' should be {expected} but is {}",
+            found.kind_name()
+        ),
+    }
+}
+
+impl fmt::Display for AttributeValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Debug formatting keeps floats recognisable (`1.0`) and quotes strings
+        fn list<T: fmt::Debug>(values: &[T]) -> String {
+            values
+                .iter()
+                .map(|value| format!("{value:?}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        }
+
+        match self {
+            AttributeValue::Int(value) => write!(f, "{value}"),
+            AttributeValue::Float(value) => write!(f, "{value:?}"),
+            AttributeValue::String(value) => write!(f, "{value:?}"),
+            AttributeValue::Ints(values) => write!(f, "[{}]", list(values)),
+            AttributeValue::Floats(values) => write!(f, "[{}]", list(values)),
+            AttributeValue::Strings(values) => write!(f, "[{}]", list(values)),
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use crate::ir::{
+    errors::ValidationError,
+    symbolic::{Dim, ShapeConstraints},
+    types::TensorShape,
+};
+
+/// How a shape is read when broadcast to a larger target shape.
+///
+/// Built by [`TensorShape::broadcast_map`]. Kernels use it to turn an index into
+/// the broadcast result back into an index (or linear offset) into the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BroadcastMap {
+    source: TensorShape,
+    target: TensorShape,
+    /// Target axes the source is stretched along, ascending.
+    expanded: Vec<usize>,
+}
+
+impl TensorShape {
+    /// NumPy-style broadcast of any number of shapes. Shapes are right-aligned and
+    /// a dimension of 1 stretches to match; anything else must agree exactly.
+    pub fn broadcast(shapes: &[&TensorShape]) -> Result<TensorShape, ValidationError> {
+        TensorShape::broadcast_constrained(shapes, &mut ShapeConstraints::new())
+    }
+
+    /// Broadcast that records the equalities it relies on. A symbolic dimension
+    /// facing anything other than a static 1 is assumed equal to it, as ONNX
+    /// runtimes do, and that assumption is added to `constraints`.
+    pub fn broadcast_constrained(
+        shapes: &[&TensorShape],
+        constraints: &mut ShapeConstraints,
+    ) -> Result<TensorShape, ValidationError> {
+        let rank = shapes.iter().map(|shape| shape.rank()).max().unwrap_or(0);
+        let mut dims = vec![Dim::Static(1); rank];
+
+        for shape in shapes {
+            let offset = rank - shape.rank();
+            for (axis, dim) in shape.dims().iter().enumerate() {
+                let dim = constraints.resolve(dim);
+                let current = &mut dims[offset + axis];
+                if *current == Dim::Static(1) {
+                    *current = dim;
+                } else if dim != Dim::Static(1) && dim != *current {
+                    constraints.require_equal(current, &dim).map_err(|_| {
+                        ValidationError::IncompatibleShapes {
+                            op: "Broadcast".to_string(),
+                            shapes: shapes.iter().map(|shape| shape.to_string()).collect(),
+                        }
+                    })?;
+                    *current = constraints.resolve(current);
+                }
+            }
+        }
+
+        Ok(TensorShape::from_dims(
+            dims.iter().map(|dim| constraints.resolve(dim)).collect(),
+        ))
+    }
+
+    pub fn broadcast_with(&self, other: &TensorShape) -> Result<TensorShape, ValidationError> {
+        TensorShape::broadcast(&[self, other])
+    }
+
+    pub fn can_broadcast_to(&self, target: &TensorShape) -> bool {
+        self.broadcast_map(target).is_some()
+    }
+
+    /// Axes of `target` this shape has to be stretched along: axes it lacks
+    /// entirely plus axes where it has size 1 and the target does not.
+    pub fn expanded_dims(&self, target: &TensorShape) -> Option<Vec<usize>> {
+        self.broadcast_map(target).map(|map| map.expanded)
+    }
+
+    /// Index mapping from `target` back into this shape, or `None` when this shape
+    /// cannot be broadcast to `target` without changing the target.
+    pub fn broadcast_map(&self, target: &TensorShape) -> Option<BroadcastMap> {
+        if self.rank() > target.rank() {
+            return None;
+        }
+
+        let offset = target.rank() - self.rank();
+        let mut expanded: Vec<usize> = (0..offset).collect();
+
+        for (axis, dim) in self.dims().iter().enumerate() {
+            if *dim == target.dims()[offset + axis] {
+                continue;
+            }
+            if *dim != Dim::Static(1) {
+                return None;
+            }
+            expanded.push(offset + axis);
+        }
+
+        Some(BroadcastMap {
+            source: self.clone(),
+            target: target.clone(),
+            expanded,
+        })
+    }
+}
+
+impl BroadcastMap {
+    pub fn source(&self) -> &TensorShape {
+        &self.source
+    }
+
+    pub fn target(&self) -> &TensorShape {
+        &self.target
+    }
+
+    pub fn expanded_dims(&self) -> &[usize] {
+        &self.expanded
+    }
+
+    /// True when no broadcasting happens and indices pass through unchanged.
+    pub fn is_identity(&self) -> bool {
+        self.expanded.is_empty()
+    }
+
+    /// Source index for a given index into the target.
+    pub fn map_index(&self, target_index: &[usize]) -> Vec<usize> {
+        let offset = self.target.rank() - self.source.rank();
+
+        self.source
+            .dims()
+            .iter()
+            .enumerate()
+            .map(|(axis, dim)| {
+                if *dim == Dim::Static(1) {
+                    0
+                } else {
+                    target_index[offset + axis]
+                }
+            })
+            .collect()
+    }
+
+    /// Row-major strides of the source expressed per target axis, with 0 on
+    /// expanded axes, so `sum(index[i] * stride[i])` is the source offset.
+    /// `None` when the source has symbolic dimensions.
+    pub fn source_strides(&self) -> Option<Vec<usize>> {
+        let source_dims = self.source.static_dims()?;
+        let offset = self.target.rank() - self.source.rank();
+        let mut strides = vec![0; self.target.rank()];
+        let mut stride = 1;
+
+        for axis in (0..self.source.rank()).rev() {
+            let target_axis = offset + axis;
+            if !self.expanded.contains(&target_axis) {
+                strides[target_axis] = stride;
+            }
+            stride *= source_dims[axis];
+        }
+
+        Some(strides)
+    }
+}
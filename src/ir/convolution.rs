@@ -0,0 +1,31 @@
+use crate::ir::{symbolic::Dim, types::Window2d};
+
+impl Window2d {
+    /// Size of spatial output dimension `axis` (0 for height, 1 for width)
+    /// given the input size and the kernel size along it:
+    /// `(input + pads - dilation * (kernel - 1) - 1) / stride + 1`.
+    ///
+    /// `None` when the dilated kernel does not fit into the padded input, or
+    /// when a symbolic input would need division by a stride above 1.
+    pub fn output_dim(&self, axis: usize, input: &Dim, kernel: usize) -> Option<Dim> {
+        let stride = self.strides[axis];
+        let dilation = self.dilations[axis];
+        if kernel == 0 || stride == 0 || dilation == 0 {
+            return None;
+        }
+
+        let padding = self.pads[axis] + self.pads[axis + 2];
+        let span = dilation * (kernel - 1) + 1;
+
+        match input.as_static() {
+            Some(size) => {
+                let padded = size + padding;
+                (padded >= span).then(|| Dim::Static((padded - span) / stride + 1))
+            }
+            None if stride == 1 => {
+                Some(input.clone() + Dim::Static(padding) - Dim::Static(span - 1))
+            }
+            None => None,
+        }
+    }
+}
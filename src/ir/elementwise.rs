@@ -0,0 +1,71 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+
+use crate::ir::types::{DType, OpKind};
+
+impl OpKind {
+    /// Reference semantics of an elementwise op on one element per input,
+    /// computed in f32. Golden tests and constant folding compare against this.
+    ///
+    /// Dropout is the identity, as at inference time. Casts to integer types
+    /// truncate toward zero and saturate; casts between float types are not
+    /// rounded to the narrower precision. Returns `None` for ops that are not
+    /// elementwise or when the operand count does not match the op's arity.
+    pub fn evaluate_scalar(&self, operands: &[f32]) -> Option<f32> {
+        if !self.is_elementwise() || !self.arity().accepts(operands.len()) {
+            return None;
+        }
+
+        let x = operands[0];
+        let value = match self {
+            OpKind::Add => x + operands[1],
+            OpKind::Sub => x - operands[1],
+            OpKind::Mul => x * operands[1],
+            OpKind::Div => x / operands[1],
+            OpKind::Pow => x.powf(operands[1]),
+            OpKind::Where => {
+                if x != 0.0 {
+                    operands[1]
+                } else {
+                    operands[2]
+                }
+            }
+            OpKind::Relu => x.max(0.0),
+            OpKind::Sigmoid => 1.0 / (1.0 + (-x).exp()),
+            OpKind::Tanh => x.tanh(),
+            OpKind::Exp => x.exp(),
+            OpKind::Log => x.ln(),
+            OpKind::Sqrt => x.sqrt(),
+            OpKind::Rsqrt => 1.0 / x.sqrt(),
+            OpKind::Erf => erf(x),
+            OpKind::Gelu => 0.5 * x * (1.0 + erf(x * FRAC_1_SQRT_2)),
+            OpKind::Dropout { .. } => x,
+            OpKind::Clip { min, max } => {
+                let lower = min.map_or(x, |min| x.max(min));
+                max.map_or(lower, |max| lower.min(max))
+            }
+            OpKind::Cast { to } => match to {
+                DType::F32 | DType::F16 | DType::BF16 => x,
+                DType::I32 => (x as i32) as f32,
+                DType::I8 => (x as i8) as f32,
+                DType::Bool => (x != 0.0) as u8 as f32,
+            },
+            _ => return None,
+        };
+
+        Some(value)
+    }
+}
+
+/// Error function, Abramowitz & Stegun 7.1.26 (absolute error below 1.5e-7).
+pub fn erf(x: f32) -> f32 {
+    let sign = x.signum();
+    let x = f64::from(x.abs());
+
+    let t = 1.0 / (1.0 + 0.327_591_1 * x);
+    let polynomial = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+
+    sign * (1.0 - polynomial * (-x * x).exp()) as f32
+}
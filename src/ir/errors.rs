@@ -1,4 +1,5 @@
 use core::fmt;
+use std::{io, path::Path};
 
 #[derive(Debug)]
 pub enum XyntraError {
@@ -37,6 +38,15 @@ pub enum ValidationError {
         expected: usize,
         found: usize,
     },
+    InvalidAttribute {
+        op: String,
+        attribute: String,
+        reason: String,
+    },
+    InvalidGraphBoundary {
+        name: String,
+        reason: String,
+    },
     InvalidConfigValue {
         field: String,
         value: String,
@@ -78,6 +88,25 @@ pub enum InternalError {
     NotImplemented { feature: String },
 }
 
+impl XyntraError {
+    /// Classifies a failed `operation` ("read", "write") on `path`: a missing
+    /// file or denied access is a system error, anything else means the path
+    /// itself is unusable.
+    pub(crate) fn from_io(path: &Path, operation: &str, error: io::Error) -> Self {
+        let path = path.display().to_string();
+        match error.kind() {
+            io::ErrorKind::NotFound => XyntraError::System(SystemError::FileNotFound { path }),
+            io::ErrorKind::PermissionDenied => XyntraError::System(SystemError::PermissionDenied {
+                operation: format!("{operation} {path}"),
+            }),
+            _ => XyntraError::Validation(ValidationError::InvalidFilePath {
+                path,
+                reason: error.to_string(),
+            }),
+        }
+    }
+}
+
 impl fmt::Display for ValidationError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -131,6 +160,24 @@ impl fmt::Display for ValidationError {
                 )
             }
 
+            ValidationError::InvalidAttribute {
+                op,
+                attribute,
+                reason,
+            } => {
+                write!(
+                    f,
+                    "Invalid attribute '{attribute}' on operation '{op}': {reason}."
+                )
+            }
+
+            ValidationError::InvalidGraphBoundary { name, reason } => {
+                write!(
+                    f,
+                    "Invalid graph input, output or constant '{name}': {reason}."
+                )
+            }
+
             ValidationError::InvalidConfigValue {
                 field,
                 value,
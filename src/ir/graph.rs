@@ -1,14 +1,48 @@
-use std::collections::HashMap;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
 
 use crate::ir::{
+    arena::NodeArena,
+    errors::ValidationError,
     ops::Node,
-    types::{NodeID, OpKind},
+    tensor::Tensor,
+    types::{NodeID, OpCategory, OpKind, TensorType, ValueRef},
 };
 
 #[derive(Default)]
 pub struct Graph {
-    nodes: HashMap<NodeID, Node>,
-    next_id: u32,
+    nodes: NodeArena,
+    /// `Input` nodes in declaration order, which is the calling convention.
+    inputs: Vec<NodeID>,
+    outputs: Vec<GraphOutput>,
+    /// Data of every `Constant` node.
+    initializers: HashMap<NodeID, Tensor>,
+    /// Readers of IDs that have no node yet, handed over when it is added.
+    pending_users: HashMap<NodeID, Vec<NodeID>>,
+}
+
+/// A value the graph hands back to its caller, under a name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphOutput {
+    name: String,
+    value: ValueRef,
+}
+
+impl GraphOutput {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> ValueRef {
+        self.value
+    }
+
+    /// The node producing the output value.
+    pub fn node(&self) -> NodeID {
+        self.value.node()
+    }
 }
 
 impl Graph {
@@ -16,21 +50,460 @@ impl Graph {
         Graph::default()
     }
 
-    pub fn add_node(&mut self, op: OpKind, inputs: Vec<NodeID>, outputs: Vec<NodeID>) -> NodeID {
-        let new_node_id: NodeID = NodeID::new(self.next_id);
-        self.next_id += 1;
-        let new_node: Node = Node {
-            id: new_node_id,
-            op,
-            inputs,
-            outputs,
-        };
+    /// Adds a node reading output 0 of each input node, which is the only
+    /// output of most ops. Use `add_node_with_values` to read other outputs.
+    pub fn add_node(&mut self, op: OpKind, inputs: Vec<NodeID>) -> NodeID {
+        let inputs = inputs.into_iter().map(ValueRef::from).collect();
+        self.add_node_with_values(op, inputs)
+    }
+
+    /// Adds a node and records it as a consumer of each node it reads.
+    pub fn add_node_with_values(&mut self, op: OpKind, inputs: Vec<ValueRef>) -> NodeID {
+        let users = self
+            .pending_users
+            .remove(&self.nodes.next_id())
+            .unwrap_or_default();
+        let producers: Vec<NodeID> = inputs.iter().map(ValueRef::node).collect();
+        let new_node_id = self
+            .nodes
+            .insert_with(|node_id| Node::new(node_id, op, inputs, users));
 
-        self.nodes.insert(new_node_id, new_node);
+        for producer in producers {
+            self.link(producer, new_node_id);
+        }
         new_node_id
     }
 
+    /// Adds a node whose output type is known up front (graph inputs, weights, ...).
+    pub fn add_node_with_type(
+        &mut self,
+        op: OpKind,
+        inputs: Vec<NodeID>,
+        output_type: TensorType,
+    ) -> NodeID {
+        let node_id = self.add_node(op, inputs);
+        if let Some(node) = self.nodes.get_mut(node_id) {
+            node.output_types = vec![output_type];
+        }
+        node_id
+    }
+
+    /// Declares a graph input. Inputs are numbered in the order they are added.
+    pub fn add_input(&mut self, name: impl Into<String>, input_type: TensorType) -> NodeID {
+        let node_id =
+            self.add_node_with_type(OpKind::Input { name: name.into() }, vec![], input_type);
+        self.inputs.push(node_id);
+        node_id
+    }
+
+    /// Adds a constant tensor, e.g. a weight, as a `Constant` node.
+    pub fn add_initializer(&mut self, name: impl Into<String>, tensor: Tensor) -> NodeID {
+        let node_id = self.add_node_with_type(
+            OpKind::Constant { name: name.into() },
+            vec![],
+            tensor.tensor_type().clone(),
+        );
+        self.initializers.insert(node_id, tensor);
+        node_id
+    }
+
+    /// Marks a value as a graph output; a bare `NodeID` means its output 0.
+    pub fn add_output(
+        &mut self,
+        name: impl Into<String>,
+        value: impl Into<ValueRef>,
+    ) -> Result<(), ValidationError> {
+        let value = value.into();
+        if !self.nodes.contains(value.node()) {
+            return Err(ValidationError::MissingNode {
+                node_id: value.node().id(),
+            });
+        }
+
+        self.outputs.push(GraphOutput {
+            name: name.into(),
+            value,
+        });
+        Ok(())
+    }
+
+    pub fn inputs(&self) -> &[NodeID] {
+        &self.inputs
+    }
+
+    pub fn outputs(&self) -> &[GraphOutput] {
+        &self.outputs
+    }
+
+    pub fn initializer(&self, node_id: NodeID) -> Option<&Tensor> {
+        self.initializers.get(&node_id)
+    }
+
+    /// Constant nodes with their data, in ascending node ID order.
+    pub fn initializers(&self) -> Vec<(NodeID, &Tensor)> {
+        let mut initializers: Vec<(NodeID, &Tensor)> = self
+            .initializers
+            .iter()
+            .map(|(node_id, tensor)| (*node_id, tensor))
+            .collect();
+        initializers.sort_by_key(|(node_id, _)| node_id.id());
+        initializers
+    }
+
+    /// The graph input or constant with the given name.
+    pub fn find_value(&self, name: &str) -> Option<NodeID> {
+        self.node_ids().into_iter().find(|node_id| {
+            matches!(
+                self.nodes[*node_id].op(),
+                OpKind::Input { name: value_name } | OpKind::Constant { name: value_name }
+                    if value_name == name
+            )
+        })
+    }
+
+    pub fn set_output_type(
+        &mut self,
+        node_id: NodeID,
+        output_type: TensorType,
+    ) -> Result<(), ValidationError> {
+        let node = self
+            .nodes
+            .get_mut(node_id)
+            .ok_or(ValidationError::MissingNode {
+                node_id: node_id.id(),
+            })?;
+        node.output_types = vec![output_type];
+        Ok(())
+    }
+
+    /// Sets the type of every output of a multi-output node such as `Split`.
+    pub fn set_output_types(
+        &mut self,
+        node_id: NodeID,
+        output_types: Vec<TensorType>,
+    ) -> Result<(), ValidationError> {
+        let node = self
+            .nodes
+            .get_mut(node_id)
+            .ok_or(ValidationError::MissingNode {
+                node_id: node_id.id(),
+            })?;
+        node.output_types = output_types;
+        Ok(())
+    }
+
+    pub fn output_type(&self, node_id: NodeID) -> Option<&TensorType> {
+        self.nodes.get(node_id).and_then(|node| node.output_type())
+    }
+
+    /// Type of one specific output, if known.
+    pub fn value_type(&self, value: ValueRef) -> Option<&TensorType> {
+        self.nodes
+            .get(value.node())
+            .and_then(|node| node.output_types().get(value.port()))
+    }
+
     pub fn get_node(&self, node_id: NodeID) -> Option<&Node> {
-        self.nodes.get(&node_id)
+        self.nodes.get(node_id)
+    }
+
+    pub fn contains_node(&self, node_id: NodeID) -> bool {
+        self.nodes.contains(node_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Points `node_id` at new inputs and updates the consumer lists of the
+    /// producers it starts and stops reading from. Every input must name an
+    /// existing node; cycles are left for the validator to report.
+    pub fn set_inputs(
+        &mut self,
+        node_id: NodeID,
+        inputs: Vec<ValueRef>,
+    ) -> Result<(), ValidationError> {
+        self.require_node(node_id)?;
+        for input in &inputs {
+            self.require_node(input.node())?;
+        }
+
+        let previous = std::mem::replace(
+            &mut self.nodes.get_mut(node_id).expect("checked above").inputs,
+            inputs,
+        );
+        for input in previous {
+            self.unlink(input.node(), node_id);
+        }
+        let producers: Vec<NodeID> = self.nodes[node_id].input_nodes().collect();
+        for producer in producers {
+            self.link(producer, node_id);
+        }
+        Ok(())
+    }
+
+    /// Swaps the op a node computes and returns the old one. The node's output
+    /// types are cleared because they described the old op. Graph inputs and
+    /// constants cannot be swapped in or out this way, since their names and
+    /// data live in the graph's boundary lists.
+    pub fn replace_op(&mut self, node_id: NodeID, op: OpKind) -> Result<OpKind, ValidationError> {
+        let node = self
+            .nodes
+            .get_mut(node_id)
+            .ok_or(ValidationError::MissingNode {
+                node_id: node_id.id(),
+            })?;
+
+        if let Some(source) = [&node.op, &op]
+            .into_iter()
+            .find(|op| op.category() == OpCategory::Source)
+        {
+            return Err(ValidationError::InvalidGraphBoundary {
+                name: source.name().to_string(),
+                reason: "inputs and constants cannot replace or be replaced by other ops"
+                    .to_string(),
+            });
+        }
+
+        node.output_types.clear();
+        Ok(std::mem::replace(&mut node.op, op))
+    }
+
+    /// Makes every reader of `old`, including graph outputs, read `new`
+    /// instead, and returns how many uses were rewritten. When `new` itself
+    /// reads `old`, as in replacing `x` with `f(x)`, that read is kept.
+    pub fn replace_all_uses_with(
+        &mut self,
+        old: ValueRef,
+        new: ValueRef,
+    ) -> Result<usize, ValidationError> {
+        self.require_node(old.node())?;
+        self.require_node(new.node())?;
+        if old == new {
+            return Ok(0);
+        }
+
+        let mut rewritten = 0;
+        let users = self.users(old.node()).to_vec();
+        for user in users.into_iter().filter(|user| *user != new.node()) {
+            let mut inputs = self.nodes[user].inputs.clone();
+            let mut changed = false;
+            for input in inputs.iter_mut().filter(|input| **input == old) {
+                *input = new;
+                changed = true;
+                rewritten += 1;
+            }
+            if changed {
+                self.set_inputs(user, inputs)?;
+            }
+        }
+
+        for output in self.outputs.iter_mut().filter(|output| output.value == old) {
+            output.value = new;
+            rewritten += 1;
+        }
+
+        Ok(rewritten)
+    }
+
+    /// Adds a node computing `op` on `producer` and routes `consumer`'s reads of
+    /// `producer` through it, e.g. to place a `Cast` on one edge. Fails when
+    /// `consumer` does not read `producer`.
+    pub fn insert_between(
+        &mut self,
+        producer: ValueRef,
+        consumer: NodeID,
+        op: OpKind,
+    ) -> Result<NodeID, ValidationError> {
+        let reads_producer = self
+            .nodes
+            .get(consumer)
+            .ok_or(ValidationError::MissingNode {
+                node_id: consumer.id(),
+            })?
+            .inputs()
+            .contains(&producer);
+        if !reads_producer {
+            return Err(ValidationError::InvalidNodeConnection {
+                from: producer.node().id(),
+                to: consumer.id(),
+                reason: format!("consumer does not read output {}", producer.port()),
+            });
+        }
+
+        let inserted = self.add_node_with_values(op, vec![producer]);
+
+        let inputs = self.nodes[consumer]
+            .inputs()
+            .iter()
+            .map(|&input| {
+                if input == producer {
+                    inserted.output(0)
+                } else {
+                    input
+                }
+            })
+            .collect();
+        self.set_inputs(consumer, inputs)?;
+        Ok(inserted)
+    }
+
+    /// Deletes a node nothing reads any more and returns it. Removing a node
+    /// that still feeds another node or a graph output is rejected, as it would
+    /// leave a dangling reference.
+    pub fn remove_node(&mut self, node_id: NodeID) -> Result<Node, ValidationError> {
+        self.require_node(node_id)?;
+
+        if let Some(user) = self.users(node_id).first() {
+            return Err(ValidationError::InvalidNodeConnection {
+                from: node_id.id(),
+                to: user.id(),
+                reason: "cannot remove a node that is still read".to_string(),
+            });
+        }
+        if let Some(output) = self.outputs.iter().find(|output| output.node() == node_id) {
+            return Err(ValidationError::InvalidGraphBoundary {
+                name: output.name.clone(),
+                reason: format!("cannot remove node {} which produces it", node_id.id()),
+            });
+        }
+
+        let node = self.nodes.remove(node_id).expect("checked above");
+        for producer in node.input_nodes() {
+            self.unlink(producer, node_id);
+        }
+        self.inputs.retain(|input| *input != node_id);
+        self.initializers.remove(&node_id);
+        Ok(node)
+    }
+
+    /// Nodes reading any output of `node_id`, each listed once in ascending ID
+    /// order. Empty for unknown IDs.
+    pub fn users(&self, node_id: NodeID) -> &[NodeID] {
+        self.nodes
+            .get(node_id)
+            .map(|node| node.outputs().as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Number of node inputs reading any output of `node_id`, so a node fed
+    /// twice to the same `Add` counts twice. Graph outputs are not counted.
+    pub fn use_count(&self, node_id: NodeID) -> usize {
+        self.users(node_id)
+            .iter()
+            .map(|user| {
+                self.nodes[*user]
+                    .input_nodes()
+                    .filter(|input| *input == node_id)
+                    .count()
+            })
+            .sum()
+    }
+
+    /// Node IDs in the order the nodes were added, so callers get the same
+    /// walk on every run. Until a node is removed this is ascending ID order.
+    pub fn node_ids(&self) -> Vec<NodeID> {
+        self.nodes.ids()
+    }
+
+    /// Producers before consumers, ties broken by the lowest node ID. Inputs that
+    /// point at missing nodes are ignored; a cycle is reported with the nodes that
+    /// could not be ordered.
+    pub fn topological_order(&self) -> Result<Vec<NodeID>, ValidationError> {
+        let mut in_degree: HashMap<NodeID, usize> = HashMap::new();
+        let mut consumers: HashMap<NodeID, Vec<NodeID>> = HashMap::new();
+
+        for node_id in self.node_ids() {
+            let node = &self.nodes[node_id];
+            let mut degree = 0;
+            for input in node.input_nodes() {
+                if self.nodes.contains(input) {
+                    degree += 1;
+                    consumers.entry(input).or_default().push(node_id);
+                }
+            }
+            in_degree.insert(node_id, degree);
+        }
+
+        let mut ready: BinaryHeap<Reverse<NodeID>> = in_degree
+            .iter()
+            .filter(|(_, degree)| **degree == 0)
+            .map(|(id, _)| Reverse(*id))
+            .collect();
+        let mut order = Vec::with_capacity(self.nodes.len());
+
+        while let Some(Reverse(node_id)) = ready.pop() {
+            order.push(node_id);
+
+            for consumer in consumers.get(&node_id).into_iter().flatten() {
+                if let Some(degree) = in_degree.get_mut(consumer) {
+                    *degree -= 1;
+                    if *degree == 0 {
+                        ready.push(Reverse(*consumer));
+                    }
+                }
+            }
+        }
+
+        if order.len() != self.nodes.len() {
+            let mut cycle_path: Vec<u32> = in_degree
+                .iter()
+                .filter(|(_, degree)| **degree > 0)
+                .map(|(id, _)| id.id())
+                .collect();
+            cycle_path.sort_unstable();
+            return Err(ValidationError::CyclicGraph { cycle_path });
+        }
+
+        Ok(order)
+    }
+
+    fn require_node(&self, node_id: NodeID) -> Result<(), ValidationError> {
+        if self.nodes.contains(node_id) {
+            Ok(())
+        } else {
+            Err(ValidationError::MissingNode {
+                node_id: node_id.id(),
+            })
+        }
+    }
+
+    /// Records `consumer` in the consumer list of `producer`, kept sorted by ID.
+    fn link(&mut self, producer: NodeID, consumer: NodeID) {
+        let users = match self.nodes.get_mut(producer) {
+            Some(node) => &mut node.outputs,
+            None => self.pending_users.entry(producer).or_default(),
+        };
+        if let Err(position) = users.binary_search(&consumer) {
+            users.insert(position, consumer);
+        }
+    }
+
+    /// Drops `consumer` from the consumer list of `producer` once it no longer
+    /// reads any of its outputs.
+    fn unlink(&mut self, producer: NodeID, consumer: NodeID) {
+        let still_reads = self
+            .nodes
+            .get(consumer)
+            .is_some_and(|node| node.input_nodes().any(|input| input == producer));
+        if still_reads {
+            return;
+        }
+
+        match self.nodes.get_mut(producer) {
+            Some(node) => node.outputs.retain(|output| *output != consumer),
+            None => {
+                if let Some(users) = self.pending_users.get_mut(&producer) {
+                    users.retain(|user| *user != consumer);
+                    if users.is_empty() {
+                        self.pending_users.remove(&producer);
+                    }
+                }
+            }
+        }
     }
 }
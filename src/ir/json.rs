@@ -0,0 +1,445 @@
+use core::fmt;
+
+use crate::ir::errors::ParsingError;
+
+/// A parsed JSON document. Numbers keep their literal text so integers wider
+/// than `f64` and `f32` values convert without a lossy detour, and objects
+/// keep their keys in document order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    /// Parses a complete document; trailing content other than whitespace is
+    /// an error.
+    pub fn parse(source: &str) -> Result<JsonValue, ParsingError> {
+        let mut reader = Reader {
+            chars: source.chars().collect(),
+            next: 0,
+            line: 1,
+            column: 1,
+            depth: 0,
+        };
+        let value = reader.value()?;
+        reader.skip_whitespace();
+        if reader.next < reader.chars.len() {
+            return Err(reader.error("unexpected content after the document"));
+        }
+        Ok(value)
+    }
+
+    pub fn number(value: impl fmt::Display) -> JsonValue {
+        JsonValue::Number(value.to_string())
+    }
+
+    pub fn string(value: impl Into<String>) -> JsonValue {
+        JsonValue::String(value.into())
+    }
+
+    pub fn object<K: Into<String>>(entries: impl IntoIterator<Item = (K, JsonValue)>) -> JsonValue {
+        JsonValue::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.into(), value))
+                .collect(),
+        )
+    }
+
+    /// Value of `key` if this is an object that has it.
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(entries) => entries
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            JsonValue::Number(text) => text.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            JsonValue::Number(text) => text.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[JsonValue]> {
+        match self {
+            JsonValue::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    /// Indented rendering with two spaces per level. Arrays and objects that
+    /// fit within 80 columns stay on one line.
+    pub fn to_pretty_string(&self) -> String {
+        let mut text = String::new();
+        self.write_pretty(&mut text, 0);
+        text.push('\n');
+        text
+    }
+
+    fn write_pretty(&self, text: &mut String, depth: usize) {
+        const WIDTH: usize = 80;
+        let indent = |text: &mut String, depth: usize| text.push_str(&"  ".repeat(depth));
+
+        let compact = self.to_string();
+        let line_start = text.rfind('\n').map_or(0, |newline| newline + 1);
+        if text.len() - line_start + compact.len() <= WIDTH {
+            text.push_str(&compact);
+            return;
+        }
+
+        match self {
+            JsonValue::Array(items) if !items.is_empty() => {
+                text.push_str("[\n");
+                for (i, item) in items.iter().enumerate() {
+                    indent(text, depth + 1);
+                    item.write_pretty(text, depth + 1);
+                    text.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
+                }
+                indent(text, depth);
+                text.push(']');
+            }
+            JsonValue::Object(entries) if !entries.is_empty() => {
+                text.push_str("{\n");
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    indent(text, depth + 1);
+                    write_string(text, key);
+                    text.push_str(": ");
+                    value.write_pretty(text, depth + 1);
+                    text.push_str(if i + 1 < entries.len() { ",\n" } else { "\n" });
+                }
+                indent(text, depth);
+                text.push('}');
+            }
+            _ => text.push_str(&compact),
+        }
+    }
+}
+
+/// Compact rendering with `", "` between array items, as used for the
+/// single-line arrays in the pretty form.
+impl fmt::Display for JsonValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonValue::Null => write!(f, "null"),
+            JsonValue::Bool(value) => write!(f, "{value}"),
+            JsonValue::Number(text) => write!(f, "{text}"),
+            JsonValue::String(value) => {
+                let mut text = String::new();
+                write_string(&mut text, value);
+                write!(f, "{text}")
+            }
+            JsonValue::Array(items) => {
+                let items: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+                write!(f, "[{}]", items.join(", "))
+            }
+            JsonValue::Object(entries) => {
+                let entries: Vec<String> = entries
+                    .iter()
+                    .map(|(key, value)| format!("{}: {value}", JsonValue::string(key.as_str())))
+                    .collect();
+                write!(f, "{{{}}}", entries.join(", "))
+            }
+        }
+    }
+}
+
+fn write_string(text: &mut String, value: &str) {
+    text.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => text.push_str("\\\""),
+            '\\' => text.push_str("\\\\"),
+            '\n' => text.push_str("\\n"),
+            '\r' => text.push_str("\\r"),
+            '\t' => text.push_str("\\t"),
+            c if (c as u32) < 0x20 => text.push_str(&format!("\\u{:04x}", c as u32)),
+            c => text.push(c),
+        }
+    }
+    text.push('"');
+}
+
+fn error_at(line: usize, column: usize, reason: &str) -> ParsingError {
+    ParsingError::InvalidFormat {
+        format: "json".to_string(),
+        reason: format!("line {line}, column {column}: {reason}"),
+    }
+}
+
+/// Deepest nesting of arrays and objects the reader accepts, so hostile
+/// input fails with an error instead of overflowing the stack.
+const MAX_DEPTH: usize = 128;
+
+struct Reader {
+    chars: Vec<char>,
+    next: usize,
+    line: usize,
+    column: usize,
+    /// Arrays and objects currently open.
+    depth: usize,
+}
+
+impl Reader {
+    fn error(&self, reason: &str) -> ParsingError {
+        error_at(self.line, self.column, reason)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.next).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.next += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.bump();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), ParsingError> {
+        self.skip_whitespace();
+        if self.peek() == Some(expected) {
+            self.bump();
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{expected}'")))
+        }
+    }
+
+    fn keyword(&mut self, word: &str, value: JsonValue) -> Result<JsonValue, ParsingError> {
+        for expected in word.chars() {
+            if self.peek() != Some(expected) {
+                return Err(self.error(&format!("expected '{word}'")));
+            }
+            self.bump();
+        }
+        Ok(value)
+    }
+
+    fn value(&mut self) -> Result<JsonValue, ParsingError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(open @ ('{' | '[')) => {
+                if self.depth == MAX_DEPTH {
+                    return Err(self.error(&format!("nesting deeper than {MAX_DEPTH} levels")));
+                }
+                self.depth += 1;
+                let value = if open == '{' {
+                    self.object()
+                } else {
+                    self.array()
+                };
+                self.depth -= 1;
+                value
+            }
+            Some('"') => Ok(JsonValue::String(self.string()?)),
+            Some('t') => self.keyword("true", JsonValue::Bool(true)),
+            Some('f') => self.keyword("false", JsonValue::Bool(false)),
+            Some('n') => self.keyword("null", JsonValue::Null),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number(),
+            Some(c) => Err(self.error(&format!("unexpected character '{c}'"))),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn object(&mut self) -> Result<JsonValue, ParsingError> {
+        self.bump();
+        let mut entries: Vec<(String, JsonValue)> = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.bump();
+            return Ok(JsonValue::Object(entries));
+        }
+
+        loop {
+            self.skip_whitespace();
+            if self.peek() != Some('"') {
+                return Err(self.error("expected a string key"));
+            }
+            let (line, column) = (self.line, self.column);
+            let key = self.string()?;
+            if entries.iter().any(|(existing, _)| *existing == key) {
+                return Err(error_at(line, column, &format!("duplicate key \"{key}\"")));
+            }
+            self.expect(':')?;
+            let value = self.value()?;
+            entries.push((key, value));
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => {
+                    self.bump();
+                }
+                Some('}') => {
+                    self.bump();
+                    return Ok(JsonValue::Object(entries));
+                }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<JsonValue, ParsingError> {
+        self.bump();
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.bump();
+            return Ok(JsonValue::Array(items));
+        }
+
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => {
+                    self.bump();
+                }
+                Some(']') => {
+                    self.bump();
+                    return Ok(JsonValue::Array(items));
+                }
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, ParsingError> {
+        self.bump();
+        let mut text = String::new();
+        loop {
+            match self.bump() {
+                None => return Err(self.error("unterminated string")),
+                Some('"') => return Ok(text),
+                Some('\\') => {
+                    let escaped = match self.bump() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => self.unicode_escape()?,
+                        _ => return Err(self.error("unknown escape in string")),
+                    };
+                    text.push(escaped);
+                }
+                Some(c) if (c as u32) < 0x20 => {
+                    return Err(self.error("control character in string"));
+                }
+                Some(c) => text.push(c),
+            }
+        }
+    }
+
+    /// The four hex digits after `\u`, combining surrogate pairs.
+    fn unicode_escape(&mut self) -> Result<char, ParsingError> {
+        let high = self.hex4()?;
+        if !(0xd800..0xdc00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| self.error("invalid unicode escape"));
+        }
+        if self.bump() != Some('\\') || self.bump() != Some('u') {
+            return Err(self.error("unpaired surrogate in unicode escape"));
+        }
+        let low = self.hex4()?;
+        if !(0xdc00..0xe000).contains(&low) {
+            return Err(self.error("unpaired surrogate in unicode escape"));
+        }
+        char::from_u32(0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00))
+            .ok_or_else(|| self.error("invalid unicode escape"))
+    }
+
+    fn hex4(&mut self) -> Result<u32, ParsingError> {
+        let mut value = 0;
+        for _ in 0..4 {
+            let digit = self
+                .bump()
+                .and_then(|c| c.to_digit(16))
+                .ok_or_else(|| self.error("expected four hex digits"))?;
+            value = value * 16 + digit;
+        }
+        Ok(value)
+    }
+
+    fn number(&mut self) -> Result<JsonValue, ParsingError> {
+        let start = self.next;
+        if self.peek() == Some('-') {
+            self.bump();
+        }
+        match self.peek() {
+            Some('0') => {
+                self.bump();
+            }
+            Some(c) if c.is_ascii_digit() => self.digits(),
+            _ => return Err(self.error("expected a digit")),
+        }
+        if self.peek() == Some('.') {
+            self.bump();
+            if !self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                return Err(self.error("expected a digit after '.'"));
+            }
+            self.digits();
+        }
+        if matches!(self.peek(), Some('e' | 'E')) {
+            self.bump();
+            if matches!(self.peek(), Some('+' | '-')) {
+                self.bump();
+            }
+            if !self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                return Err(self.error("expected a digit in the exponent"));
+            }
+            self.digits();
+        }
+        Ok(JsonValue::Number(
+            self.chars[start..self.next].iter().collect(),
+        ))
+    }
+
+    fn digits(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.bump();
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use crate::ir::{errors::ValidationError, types::TensorShape};
+
+/// Order in which a tensor's elements sit in memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MemoryFormat {
+    /// C order: the last dimension is contiguous.
+    RowMajor,
+    /// Fortran order: the first dimension is contiguous.
+    ColumnMajor,
+    /// NHWC storage of a logical NCHW tensor.
+    ChannelsLast,
+    /// Anything else, e.g. a transposed or sliced view.
+    Strided,
+}
+
+/// Where each element of a tensor lives in its buffer: element offset of the
+/// first element plus one stride (in elements) per dimension.
+///
+/// Strides can be zero (an expanded dimension) but not negative. Layouts are only
+/// defined for static shapes; symbolic dimensions have to be resolved first.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TensorLayout {
+    strides: Vec<usize>,
+    offset: usize,
+}
+
+impl TensorLayout {
+    /// Dense row-major layout, the default for every tensor.
+    pub fn contiguous(shape: &TensorShape) -> Result<Self, ValidationError> {
+        TensorLayout::with_format(shape, MemoryFormat::RowMajor)
+    }
+
+    pub fn with_format(shape: &TensorShape, format: MemoryFormat) -> Result<Self, ValidationError> {
+        let dims = static_dims(shape)?;
+
+        let strides = match format {
+            MemoryFormat::RowMajor => row_major_strides(&dims),
+            MemoryFormat::ColumnMajor => column_major_strides(&dims),
+            MemoryFormat::ChannelsLast => {
+                if dims.len() != 4 {
+                    return Err(ValidationError::InvalidTensorShape {
+                        expected: "rank-4 NCHW shape for channels-last layout".to_string(),
+                        found: shape.to_string(),
+                    });
+                }
+                channels_last_strides(&dims)
+            }
+            MemoryFormat::Strided => {
+                return Err(ValidationError::InvalidTensorShape {
+                    expected: "explicit strides for a strided layout".to_string(),
+                    found: shape.to_string(),
+                });
+            }
+        };
+
+        Ok(TensorLayout { strides, offset: 0 })
+    }
+
+    /// Arbitrary strides and offset, e.g. taken from an imported tensor.
+    pub fn strided(strides: Vec<usize>, offset: usize) -> Self {
+        TensorLayout { strides, offset }
+    }
+
+    pub fn strides(&self) -> &[usize] {
+        &self.strides
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn rank(&self) -> usize {
+        self.strides.len()
+    }
+
+    /// Classifies the layout for `shape`. Dimensions of size 1 are ignored, since
+    /// their stride never affects addressing.
+    pub fn memory_format(&self, shape: &TensorShape) -> MemoryFormat {
+        let Some(dims) = shape.static_dims() else {
+            return MemoryFormat::Strided;
+        };
+        if dims.len() != self.strides.len() {
+            return MemoryFormat::Strided;
+        }
+
+        if self.matches(&dims, &row_major_strides(&dims)) {
+            MemoryFormat::RowMajor
+        } else if self.matches(&dims, &column_major_strides(&dims)) {
+            MemoryFormat::ColumnMajor
+        } else if dims.len() == 4 && self.matches(&dims, &channels_last_strides(&dims)) {
+            MemoryFormat::ChannelsLast
+        } else {
+            MemoryFormat::Strided
+        }
+    }
+
+    /// True when the elements are dense and in row-major order, so a kernel can
+    /// index the buffer linearly from `offset`.
+    pub fn is_contiguous(&self, shape: &TensorShape) -> bool {
+        self.memory_format(shape) == MemoryFormat::RowMajor
+    }
+
+    /// Buffer position of the element at `index`.
+    pub fn element_offset(&self, index: &[usize]) -> usize {
+        self.offset
+            + index
+                .iter()
+                .zip(&self.strides)
+                .map(|(i, stride)| i * stride)
+                .sum::<usize>()
+    }
+
+    /// Number of buffer elements the view can touch, counting from the start of
+    /// the buffer. Zero for empty tensors.
+    pub fn storage_span(&self, shape: &TensorShape) -> Result<usize, ValidationError> {
+        let dims = static_dims(shape)?;
+        if dims.contains(&0) {
+            return Ok(0);
+        }
+
+        let last = dims
+            .iter()
+            .zip(&self.strides)
+            .map(|(dim, stride)| (dim - 1) * stride)
+            .sum::<usize>();
+        Ok(self.offset + last + 1)
+    }
+
+    /// Reorders the dimensions without moving data: dimension `i` of the result
+    /// is dimension `perm[i]` of `self`. Pair with [`TensorShape::permute`].
+    pub fn transpose(&self, perm: &[usize]) -> Result<Self, ValidationError> {
+        check_permutation(perm, self.rank())?;
+
+        Ok(TensorLayout {
+            strides: perm.iter().map(|&axis| self.strides[axis]).collect(),
+            offset: self.offset,
+        })
+    }
+
+    /// Compares strides, ignoring dimensions of size 1.
+    fn matches(&self, dims: &[usize], expected: &[usize]) -> bool {
+        dims.iter()
+            .zip(self.strides.iter().zip(expected))
+            .all(|(dim, (stride, expected))| *dim == 1 || stride == expected)
+    }
+}
+
+impl TensorShape {
+    /// Dimension `i` of the result is dimension `perm[i]` of `self`.
+    pub fn permute(&self, perm: &[usize]) -> Result<TensorShape, ValidationError> {
+        check_permutation(perm, self.rank())?;
+        Ok(TensorShape::from_dims(
+            perm.iter().map(|&axis| self.dims()[axis].clone()).collect(),
+        ))
+    }
+}
+
+pub fn row_major_strides(dims: &[usize]) -> Vec<usize> {
+    let mut strides = vec![0; dims.len()];
+    let mut stride = 1;
+    for axis in (0..dims.len()).rev() {
+        strides[axis] = stride;
+        stride *= dims[axis];
+    }
+    strides
+}
+
+pub fn column_major_strides(dims: &[usize]) -> Vec<usize> {
+    let mut strides = vec![0; dims.len()];
+    let mut stride = 1;
+    for (axis, dim) in dims.iter().enumerate() {
+        strides[axis] = stride;
+        stride *= dim;
+    }
+    strides
+}
+
+/// Strides of a logical NCHW tensor stored as NHWC.
+fn channels_last_strides(dims: &[usize]) -> Vec<usize> {
+    let (c, h, w) = (dims[1], dims[2], dims[3]);
+    vec![h * w * c, 1, w * c, c]
+}
+
+fn static_dims(shape: &TensorShape) -> Result<Vec<usize>, ValidationError> {
+    shape
+        .static_dims()
+        .ok_or_else(|| ValidationError::InvalidTensorShape {
+            expected: "static shape".to_string(),
+            found: shape.to_string(),
+        })
+}
+
+fn check_permutation(perm: &[usize], rank: usize) -> Result<(), ValidationError> {
+    let mut seen = vec![false; rank];
+    let valid = perm.len() == rank
+        && perm.iter().all(|&axis| {
+            let fresh = axis < rank && !seen[axis];
+            if fresh {
+                seen[axis] = true;
+            }
+            fresh
+        });
+
+    if valid {
+        Ok(())
+    } else {
+        Err(ValidationError::InvalidTensorShape {
+            expected: format!("permutation of 0..{rank}"),
+            found: format!("{perm:?}"),
+        })
+    }
+}
@@ -1,5 +1,22 @@
+pub mod arena;
+pub mod attributes;
+pub mod broadcast;
+pub mod convolution;
+pub mod elementwise;
 pub mod errors;
 pub mod graph;
+pub mod json;
+pub mod layout;
+pub mod movement;
 pub mod ops;
+pub mod reduction;
+pub mod serialization;
+pub mod shape_inference;
+pub mod subgraph;
+pub mod symbolic;
+pub mod tensor;
+pub mod text;
+pub mod traversal;
 pub mod types;
 pub mod validation;
+pub mod visualize;
@@ -0,0 +1,185 @@
+use crate::ir::{
+    layout::{TensorLayout, row_major_strides},
+    shape_inference::infer_output_types,
+    symbolic::ShapeConstraints,
+    types::{OpKind, TensorShape, TensorType},
+};
+
+/// One axis of a `Slice`, with the axis resolved against the input rank.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AxisSlice {
+    pub axis: usize,
+    pub start: i64,
+    pub end: i64,
+    pub step: i64,
+}
+
+impl OpKind {
+    /// Movement ops whose outputs can be described as strided views of the
+    /// input buffer, so fusion can fold them into the consumer's indexing
+    /// instead of launching a copy. Whether a particular instance really is a
+    /// view also depends on the input layout, see [`OpKind::view_layouts`].
+    pub fn is_view(&self) -> bool {
+        matches!(
+            self,
+            OpKind::Reshape { .. }
+                | OpKind::Transpose { .. }
+                | OpKind::Split { .. }
+                | OpKind::Slice { .. }
+        )
+    }
+
+    /// Layout of each output as a view of `input`'s buffer, or `None` when the
+    /// op has to copy: it is not a view op, the input shape is not static, a
+    /// reshape reads a non-contiguous input, or a slice walks backwards. It is
+    /// also `None` when the op does not fit its input, e.g. split sizes that do
+    /// not add up to the split dimension.
+    pub fn view_layouts(&self, input: &TensorType) -> Option<Vec<TensorLayout>> {
+        if !self.is_view() {
+            return None;
+        }
+
+        let dims = input.shape().static_dims()?;
+        let layout = input.effective_layout().ok()?;
+
+        match self {
+            OpKind::Transpose { perm } => {
+                let perm = resolve_perm(perm, dims.len());
+                Some(vec![layout.transpose(&perm).ok()?])
+            }
+            OpKind::Reshape { .. } => {
+                if !input.is_contiguous() {
+                    return None;
+                }
+                let output = infer_output_types(self, &[input], &mut ShapeConstraints::new())
+                    .ok()?
+                    .remove(0);
+                let strides = row_major_strides(&output.shape().static_dims()?);
+                Some(vec![TensorLayout::strided(strides, layout.offset())])
+            }
+            OpKind::Split { axis, sizes } => {
+                let axis = input.shape().normalize_axis(*axis)?;
+                let total = sizes
+                    .iter()
+                    .try_fold(0usize, |total, size| total.checked_add(*size))?;
+                if total != dims[axis] {
+                    return None;
+                }
+                let stride = layout.strides()[axis];
+                let mut offset = layout.offset();
+                let mut views = Vec::with_capacity(sizes.len());
+                for size in sizes {
+                    views.push(TensorLayout::strided(layout.strides().to_vec(), offset));
+                    offset = offset.checked_add(size.checked_mul(stride)?)?;
+                }
+                Some(views)
+            }
+            OpKind::Slice {
+                starts,
+                ends,
+                axes,
+                steps,
+            } => {
+                let mut strides = layout.strides().to_vec();
+                let mut offset = layout.offset();
+                for slice in input.shape().slice_axes(starts, ends, axes, steps)? {
+                    let (start, _) =
+                        slice_range(dims[slice.axis], slice.start, slice.end, slice.step);
+                    let step = usize::try_from(slice.step).ok()?;
+                    offset += start * strides[slice.axis];
+                    strides[slice.axis] *= step;
+                }
+                Some(vec![TensorLayout::strided(strides, offset)])
+            }
+            _ => None,
+        }
+    }
+}
+
+impl TensorShape {
+    /// Pairs up the attribute lists of a `Slice` and resolves its axes. Empty
+    /// `axes` means the leading dimensions and empty `steps` a step of 1.
+    /// Returns `None` when the lists disagree in length or an axis is out of
+    /// range or repeated.
+    pub fn slice_axes(
+        &self,
+        starts: &[i64],
+        ends: &[i64],
+        axes: &[i64],
+        steps: &[i64],
+    ) -> Option<Vec<AxisSlice>> {
+        let count = starts.len();
+        if ends.len() != count
+            || (!axes.is_empty() && axes.len() != count)
+            || (!steps.is_empty() && steps.len() != count)
+        {
+            return None;
+        }
+
+        let mut slices = Vec::with_capacity(count);
+        for i in 0..count {
+            let axis = match axes.get(i) {
+                Some(&axis) => self.normalize_axis(axis)?,
+                None if i < self.rank() => i,
+                None => return None,
+            };
+            let step = steps.get(i).copied().unwrap_or(1);
+            if step == 0 || slices.iter().any(|slice: &AxisSlice| slice.axis == axis) {
+                return None;
+            }
+            slices.push(AxisSlice {
+                axis,
+                start: starts[i],
+                end: ends[i],
+                step,
+            });
+        }
+
+        Some(slices)
+    }
+}
+
+/// First index and number of elements a slice takes from a dimension of size
+/// `dim`, with bounds clamped the way ONNX does. `step` must be non-zero.
+pub fn slice_range(dim: usize, start: i64, end: i64, step: i64) -> (usize, usize) {
+    if dim == 0 {
+        return (0, 0);
+    }
+
+    let dim = dim as i64;
+    let resolve = |bound: i64| {
+        if bound < 0 {
+            bound.saturating_add(dim)
+        } else {
+            bound
+        }
+    };
+
+    let (start, end) = if step > 0 {
+        (resolve(start).clamp(0, dim), resolve(end).clamp(0, dim))
+    } else {
+        (
+            resolve(start).clamp(0, dim - 1),
+            resolve(end).clamp(-1, dim - 1),
+        )
+    };
+
+    let span = if step > 0 { end - start } else { start - end };
+    let magnitude = step.abs();
+    let len = if span > 0 {
+        (span + magnitude - 1) / magnitude
+    } else {
+        0
+    };
+
+    (start.max(0) as usize, len as usize)
+}
+
+/// `perm`, or the reversed dimension order when it is empty.
+pub fn resolve_perm(perm: &[usize], rank: usize) -> Vec<usize> {
+    if perm.is_empty() {
+        (0..rank).rev().collect()
+    } else {
+        perm.to_vec()
+    }
+}
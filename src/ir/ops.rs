@@ -1,22 +1,33 @@
-use crate::ir::types::{NodeID, OpKind};
+use crate::ir::types::{NodeID, OpKind, TensorType, ValueRef};
 
 pub struct Node {
     pub id: NodeID,
     pub op: OpKind,
-    pub inputs: Vec<NodeID>,
+    /// Values read by the node, each a specific output of its producer.
+    pub inputs: Vec<ValueRef>,
+    /// Nodes reading from this one, in ascending ID order. `Graph` keeps this
+    /// up to date as nodes are added and rewired.
     pub outputs: Vec<NodeID>,
+    /// Type of each value the node produces; empty until declared or inferred.
+    pub output_types: Vec<TensorType>,
 }
 
 impl Node {
-    pub fn new(id: NodeID, op: OpKind, inputs: Vec<NodeID>, outputs: Vec<NodeID>) -> Self {
+    pub fn new(id: NodeID, op: OpKind, inputs: Vec<ValueRef>, outputs: Vec<NodeID>) -> Self {
         Node {
             id,
             op,
             inputs,
             outputs,
+            output_types: Vec::new(),
         }
     }
 
+    pub fn with_output_type(mut self, output_type: TensorType) -> Self {
+        self.output_types = vec![output_type];
+        self
+    }
+
     pub fn id(&self) -> NodeID {
         self.id
     }
@@ -25,11 +36,25 @@ impl Node {
         &self.op
     }
 
-    pub fn inputs(&self) -> &Vec<NodeID> {
+    pub fn inputs(&self) -> &Vec<ValueRef> {
         &self.inputs
     }
 
     pub fn outputs(&self) -> &Vec<NodeID> {
         &self.outputs
     }
+
+    pub fn output_types(&self) -> &Vec<TensorType> {
+        &self.output_types
+    }
+
+    /// Nodes this node reads from, in input order and possibly repeated.
+    pub fn input_nodes(&self) -> impl Iterator<Item = NodeID> + '_ {
+        self.inputs.iter().map(|input| input.node())
+    }
+
+    /// Type of the node's primary output, if known.
+    pub fn output_type(&self) -> Option<&TensorType> {
+        self.output_types.first()
+    }
 }
@@ -0,0 +1,93 @@
+use crate::ir::{
+    symbolic::Dim,
+    types::{ReduceKind, TensorShape},
+};
+
+impl TensorShape {
+    /// Resolves reduction axes against this shape, sorted ascending. An empty
+    /// list means every dimension. Returns `None` when an axis is out of range
+    /// or names the same dimension twice (e.g. `1` and `-2` on a rank-3 shape).
+    pub fn normalize_axes(&self, axes: &[i64]) -> Option<Vec<usize>> {
+        if axes.is_empty() {
+            return Some((0..self.rank()).collect());
+        }
+
+        let mut normalized = axes
+            .iter()
+            .map(|&axis| self.normalize_axis(axis))
+            .collect::<Option<Vec<usize>>>()?;
+        normalized.sort_unstable();
+
+        let unique = normalized.windows(2).all(|pair| pair[0] != pair[1]);
+        unique.then_some(normalized)
+    }
+
+    /// Shape left after reducing over the given (normalized) axes: each one is
+    /// dropped, or kept as size 1 when `keepdims` is set.
+    pub fn reduce(&self, axes: &[usize], keepdims: bool) -> TensorShape {
+        let dims = self
+            .dims()
+            .iter()
+            .enumerate()
+            .filter_map(|(axis, dim)| match (axes.contains(&axis), keepdims) {
+                (false, _) => Some(dim.clone()),
+                (true, true) => Some(Dim::Static(1)),
+                (true, false) => None,
+            })
+            .collect();
+        TensorShape::from_dims(dims)
+    }
+}
+
+impl ReduceKind {
+    /// Starting value of the accumulator.
+    pub fn identity(&self) -> f32 {
+        match self {
+            ReduceKind::Sum | ReduceKind::Mean => 0.0,
+            ReduceKind::Max => f32::NEG_INFINITY,
+            ReduceKind::Min => f32::INFINITY,
+        }
+    }
+
+    /// Folds one element into the accumulator.
+    pub fn combine(&self, accumulator: f32, value: f32) -> f32 {
+        match self {
+            ReduceKind::Sum | ReduceKind::Mean => accumulator + value,
+            ReduceKind::Max => accumulator.max(value),
+            ReduceKind::Min => accumulator.min(value),
+        }
+    }
+
+    /// Turns the accumulator into the result once `count` elements are in.
+    pub fn finalize(&self, accumulator: f32, count: usize) -> f32 {
+        match self {
+            ReduceKind::Mean => accumulator / count as f32,
+            _ => accumulator,
+        }
+    }
+
+    /// Reference result of reducing `values`, the semantics kernels are tested
+    /// against. `None` for an empty input, which only `Sum` could give meaning to.
+    pub fn evaluate(&self, values: &[f32]) -> Option<f32> {
+        if values.is_empty() {
+            return None;
+        }
+
+        let accumulator = values.iter().fold(self.identity(), |accumulator, &value| {
+            self.combine(accumulator, value)
+        });
+        Some(self.finalize(accumulator, values.len()))
+    }
+}
+
+/// Reference semantics of `ArgMax` over one reduced lane: the index of the
+/// first largest element.
+pub fn argmax(values: &[f32]) -> Option<usize> {
+    let mut best: Option<(usize, f32)> = None;
+    for (index, &value) in values.iter().enumerate() {
+        if best.is_none_or(|(_, max)| value > max) {
+            best = Some((index, value));
+        }
+    }
+    best.map(|(index, _)| index)
+}
@@ -0,0 +1,621 @@
+use std::collections::HashMap;
+
+use crate::ir::{
+    attributes::{AttributeValue, Attributes},
+    errors::ParsingError,
+    graph::Graph,
+    json::JsonValue,
+    layout::TensorLayout,
+    ops::Node,
+    symbolic::Dim,
+    tensor::Tensor,
+    text::{decode_hex, encode_hex, parse_dim},
+    types::{DType, NodeID, OpKind, TensorShape, TensorType, ValueRef},
+    validation::GraphValidator,
+};
+
+/// Value of the `format` field in exported documents.
+pub const JSON_FORMAT: &str = "xyntra-ir";
+
+/// Schema version written by `export_json`. `import_json` reads only this
+/// version, so bump it whenever the layout below changes incompatibly.
+pub const JSON_VERSION: i64 = 1;
+
+/// Conversion to and from the JSON schema used by `export_json`. Errors name
+/// the offending field by its path, such as `nodes[2].op.name`.
+pub trait JsonSerialize: Sized {
+    fn to_json(&self) -> JsonValue;
+    fn from_json(value: &JsonValue) -> Result<Self, ParsingError>;
+}
+
+/// Writes a graph as an indented JSON document:
+///
+/// ```json
+/// {
+///   "format": "xyntra-ir",
+///   "version": 1,
+///   "nodes": [
+///     {
+///       "id": 0,
+///       "op": {"name": "Input", "attributes": {"name": {"string": "x"}}},
+///       "inputs": [],
+///       "output_types": [{"dtype": "f32", "shape": ["batch", 4]}]
+///     },
+///     {
+///       "id": 1,
+///       "op": {"name": "Relu", "attributes": {}},
+///       "inputs": [{"node": 0, "port": 0}],
+///       "output_types": [{"dtype": "f32", "shape": ["batch", 4]}]
+///     }
+///   ],
+///   "inputs": [0],
+///   "outputs": [{"name": "y", "node": 1, "port": 0}]
+/// }
+/// ```
+///
+/// Constants carry their data as a `tensor` entry with hex-encoded bytes.
+pub fn export_json(graph: &Graph) -> String {
+    graph.to_json().to_pretty_string()
+}
+
+/// Reads a document written by `export_json` and validates the result.
+pub fn import_json(source: &str) -> Result<Graph, ParsingError> {
+    Graph::from_json(&JsonValue::parse(source)?)
+}
+
+impl JsonSerialize for TensorShape {
+    fn to_json(&self) -> JsonValue {
+        JsonValue::Array(
+            self.dims()
+                .iter()
+                .map(|dim| match dim {
+                    Dim::Static(size) => JsonValue::number(size),
+                    Dim::Symbolic(_) => JsonValue::string(dim.to_string()),
+                })
+                .collect(),
+        )
+    }
+
+    fn from_json(value: &JsonValue) -> Result<Self, ParsingError> {
+        read_shape(&Field::root(value))
+    }
+}
+
+impl JsonSerialize for TensorType {
+    fn to_json(&self) -> JsonValue {
+        let mut entries = vec![
+            ("dtype", JsonValue::string(self.dtype().to_string())),
+            ("shape", self.shape().to_json()),
+        ];
+        if let Some(layout) = self.layout() {
+            entries.push((
+                "layout",
+                JsonValue::object([
+                    ("strides", numbers(layout.strides())),
+                    ("offset", JsonValue::number(layout.offset())),
+                ]),
+            ));
+        }
+        JsonValue::object(entries)
+    }
+
+    fn from_json(value: &JsonValue) -> Result<Self, ParsingError> {
+        read_type(&Field::root(value))
+    }
+}
+
+impl JsonSerialize for Tensor {
+    fn to_json(&self) -> JsonValue {
+        JsonValue::object([
+            ("type", self.tensor_type().to_json()),
+            ("data", JsonValue::string(encode_hex(self.data()))),
+        ])
+    }
+
+    fn from_json(value: &JsonValue) -> Result<Self, ParsingError> {
+        read_tensor(&Field::root(value))
+    }
+}
+
+/// `{"name": ..., "attributes": {...}}`, plus `"custom": true` for custom
+/// ops so one named like a built-in stays custom. Each attribute is a
+/// one-entry object naming its kind, such as `{"ints": [0, 1]}`.
+impl JsonSerialize for OpKind {
+    fn to_json(&self) -> JsonValue {
+        let attributes = self
+            .attributes()
+            .iter()
+            .map(|(name, value)| (name.to_string(), attribute_to_json(value)))
+            .collect();
+
+        let mut entries = vec![("name", JsonValue::string(self.name()))];
+        if matches!(self, OpKind::Custom { .. }) {
+            entries.push(("custom", JsonValue::Bool(true)));
+        }
+        entries.push(("attributes", JsonValue::Object(attributes)));
+        JsonValue::object(entries)
+    }
+
+    fn from_json(value: &JsonValue) -> Result<Self, ParsingError> {
+        read_op(&Field::root(value))
+    }
+}
+
+/// A node's ID, op, inputs and output types. Consumer lists are left out;
+/// `Graph` rebuilds them from the inputs.
+impl JsonSerialize for Node {
+    fn to_json(&self) -> JsonValue {
+        JsonValue::object([
+            ("id", JsonValue::number(self.id().id())),
+            ("op", self.op().to_json()),
+            (
+                "inputs",
+                JsonValue::Array(self.inputs().iter().map(value_to_json).collect()),
+            ),
+            (
+                "output_types",
+                JsonValue::Array(self.output_types().iter().map(|ty| ty.to_json()).collect()),
+            ),
+        ])
+    }
+
+    fn from_json(value: &JsonValue) -> Result<Self, ParsingError> {
+        let field = Field::root(value);
+        let id = NodeID::new(field.get("id")?.u32()?);
+        let inputs = field
+            .get("inputs")?
+            .items()?
+            .iter()
+            .map(|input| read_value(input).map(|(node, port)| NodeID::new(node).output(port)))
+            .collect::<Result<_, _>>()?;
+
+        let mut node = Node::new(id, read_op(&field.get("op")?)?, inputs, vec![]);
+        node.output_types = read_output_types(&field)?;
+        Ok(node)
+    }
+}
+
+impl JsonSerialize for Graph {
+    fn to_json(&self) -> JsonValue {
+        let nodes = self
+            .nodes()
+            .map(|node| {
+                let mut json = node.to_json();
+                if let (Some(tensor), JsonValue::Object(entries)) =
+                    (self.initializer(node.id()), &mut json)
+                {
+                    entries.push(("tensor".to_string(), tensor.to_json()));
+                }
+                json
+            })
+            .collect();
+        let inputs = self
+            .inputs()
+            .iter()
+            .map(|input| JsonValue::number(input.id()))
+            .collect();
+        let outputs = self
+            .outputs()
+            .iter()
+            .map(|output| {
+                JsonValue::object([
+                    ("name", JsonValue::string(output.name())),
+                    ("node", JsonValue::number(output.node().id())),
+                    ("port", JsonValue::number(output.value().port())),
+                ])
+            })
+            .collect();
+
+        JsonValue::object([
+            ("format", JsonValue::string(JSON_FORMAT)),
+            ("version", JsonValue::number(JSON_VERSION)),
+            ("nodes", JsonValue::Array(nodes)),
+            ("inputs", JsonValue::Array(inputs)),
+            ("outputs", JsonValue::Array(outputs)),
+        ])
+    }
+
+    fn from_json(value: &JsonValue) -> Result<Self, ParsingError> {
+        let graph = read_graph(&Field::root(value))?;
+
+        GraphValidator::new(&graph).validate().map_err(|errors| {
+            let errors: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+            ParsingError::InvalidFormat {
+                format: "json".to_string(),
+                reason: format!("graph is invalid: {}", errors.join("; ")),
+            }
+        })?;
+        Ok(graph)
+    }
+}
+
+fn numbers<T: std::fmt::Display>(values: &[T]) -> JsonValue {
+    JsonValue::Array(values.iter().map(JsonValue::number).collect())
+}
+
+/// JSON has no NaN or infinities, so those are written as strings.
+fn float_to_json(value: f32) -> JsonValue {
+    if value.is_finite() {
+        JsonValue::number(format!("{value:?}"))
+    } else {
+        JsonValue::string(format!("{value:?}"))
+    }
+}
+
+fn attribute_to_json(value: &AttributeValue) -> JsonValue {
+    let json = match value {
+        AttributeValue::Int(value) => JsonValue::number(value),
+        AttributeValue::Float(value) => float_to_json(*value),
+        AttributeValue::String(value) => JsonValue::string(value.as_str()),
+        AttributeValue::Ints(values) => numbers(values),
+        AttributeValue::Floats(values) => {
+            JsonValue::Array(values.iter().map(|value| float_to_json(*value)).collect())
+        }
+        AttributeValue::Strings(values) => JsonValue::Array(
+            values
+                .iter()
+                .map(|value| JsonValue::string(value.as_str()))
+                .collect(),
+        ),
+    };
+    JsonValue::object([(value.kind_name(), json)])
+}
+
+fn value_to_json(value: &ValueRef) -> JsonValue {
+    JsonValue::object([
+        ("node", JsonValue::number(value.node().id())),
+        ("port", JsonValue::number(value.port())),
+    ])
+}
+
+/// A JSON value together with its path in the document, so schema errors
+/// can say where they are.
+struct Field<'a> {
+    value: &'a JsonValue,
+    path: String,
+}
+
+impl<'a> Field<'a> {
+    fn root(value: &'a JsonValue) -> Self {
+        Field {
+            value,
+            path: String::new(),
+        }
+    }
+
+    fn invalid(&self, reason: impl std::fmt::Display) -> ParsingError {
+        let path = if self.path.is_empty() {
+            "document"
+        } else {
+            &self.path
+        };
+        ParsingError::InvalidFormat {
+            format: "json".to_string(),
+            reason: format!("{path}: {reason}"),
+        }
+    }
+
+    fn child(&self, key: &str) -> String {
+        if self.path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}.{key}", self.path)
+        }
+    }
+
+    fn optional(&self, key: &str) -> Result<Option<Field<'a>>, ParsingError> {
+        if !matches!(self.value, JsonValue::Object(_)) {
+            return Err(self.invalid("expected an object"));
+        }
+        Ok(self
+            .value
+            .get(key)
+            .filter(|value| **value != JsonValue::Null)
+            .map(|value| Field {
+                value,
+                path: self.child(key),
+            }))
+    }
+
+    fn get(&self, key: &str) -> Result<Field<'a>, ParsingError> {
+        self.optional(key)?
+            .ok_or_else(|| ParsingError::MissingRequiredField {
+                field: self.child(key),
+            })
+    }
+
+    fn items(&self) -> Result<Vec<Field<'a>>, ParsingError> {
+        let items = self
+            .value
+            .as_array()
+            .ok_or_else(|| self.invalid("expected an array"))?;
+        Ok(items
+            .iter()
+            .enumerate()
+            .map(|(i, value)| Field {
+                value,
+                path: format!("{}[{i}]", self.path),
+            })
+            .collect())
+    }
+
+    fn entries(&self) -> Result<Vec<(&'a str, Field<'a>)>, ParsingError> {
+        let JsonValue::Object(entries) = self.value else {
+            return Err(self.invalid("expected an object"));
+        };
+        Ok(entries
+            .iter()
+            .map(|(key, value)| {
+                let field = Field {
+                    value,
+                    path: self.child(key),
+                };
+                (key.as_str(), field)
+            })
+            .collect())
+    }
+
+    fn str(&self) -> Result<&'a str, ParsingError> {
+        self.value
+            .as_str()
+            .ok_or_else(|| self.invalid("expected a string"))
+    }
+
+    fn bool(&self) -> Result<bool, ParsingError> {
+        self.value
+            .as_bool()
+            .ok_or_else(|| self.invalid("expected true or false"))
+    }
+
+    fn int(&self) -> Result<i64, ParsingError> {
+        self.value
+            .as_i64()
+            .ok_or_else(|| self.invalid("expected an integer"))
+    }
+
+    fn usize(&self) -> Result<usize, ParsingError> {
+        usize::try_from(self.int()?).map_err(|_| self.invalid("expected a non-negative integer"))
+    }
+
+    fn u32(&self) -> Result<u32, ParsingError> {
+        u32::try_from(self.int()?).map_err(|_| self.invalid("expected a node ID"))
+    }
+
+    /// A number, or one of the strings `NaN`, `inf` and `-inf`.
+    fn float(&self) -> Result<f32, ParsingError> {
+        match self.value {
+            JsonValue::Number(_) => self.value.as_f32(),
+            JsonValue::String(text) if matches!(text.as_str(), "NaN" | "inf" | "-inf") => {
+                text.parse().ok()
+            }
+            _ => None,
+        }
+        .ok_or_else(|| self.invalid("expected a number"))
+    }
+}
+
+fn read_shape(field: &Field) -> Result<TensorShape, ParsingError> {
+    let dims = field
+        .items()?
+        .iter()
+        .map(|dim| match dim.value {
+            JsonValue::String(text) => parse_dim(text).map_err(|error| match error {
+                ParsingError::InvalidFormat { reason, .. } => {
+                    dim.invalid(format!("bad dimension: {reason}"))
+                }
+                other => other,
+            }),
+            _ => dim.usize().map(Dim::Static),
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(TensorShape::from_dims(dims))
+}
+
+fn read_type(field: &Field) -> Result<TensorType, ParsingError> {
+    let dtype_field = field.get("dtype")?;
+    let dtype = DType::from_name(dtype_field.str()?)
+        .ok_or_else(|| dtype_field.invalid("unknown element type"))?;
+    let tensor_type = TensorType::new(dtype, read_shape(&field.get("shape")?)?);
+
+    let Some(layout) = field.optional("layout")? else {
+        return Ok(tensor_type);
+    };
+    let strides = layout
+        .get("strides")?
+        .items()?
+        .iter()
+        .map(Field::usize)
+        .collect::<Result<_, _>>()?;
+    let offset = layout.get("offset")?.usize()?;
+    tensor_type
+        .with_layout(TensorLayout::strided(strides, offset))
+        .map_err(|error| layout.invalid(error))
+}
+
+fn read_tensor(field: &Field) -> Result<Tensor, ParsingError> {
+    let tensor_type = read_type(&field.get("type")?)?;
+    let data_field = field.get("data")?;
+    let data =
+        decode_hex(data_field.str()?).ok_or_else(|| data_field.invalid("malformed hex data"))?;
+    Tensor::new(tensor_type, data).map_err(|error| data_field.invalid(error))
+}
+
+fn read_attribute(field: &Field) -> Result<AttributeValue, ParsingError> {
+    let entries = field.entries()?;
+    let [(kind, value)] = entries.as_slice() else {
+        return Err(field.invalid("expected one entry naming the attribute kind"));
+    };
+
+    let value = match *kind {
+        "int" => AttributeValue::Int(value.int()?),
+        "float" => AttributeValue::Float(value.float()?),
+        "string" => AttributeValue::String(value.str()?.to_string()),
+        "ints" => AttributeValue::Ints(
+            value
+                .items()?
+                .iter()
+                .map(Field::int)
+                .collect::<Result<_, _>>()?,
+        ),
+        "floats" => AttributeValue::Floats(
+            value
+                .items()?
+                .iter()
+                .map(Field::float)
+                .collect::<Result<_, _>>()?,
+        ),
+        "strings" => AttributeValue::Strings(
+            value
+                .items()?
+                .iter()
+                .map(|item| item.str().map(str::to_string))
+                .collect::<Result<_, _>>()?,
+        ),
+        other => return Err(field.invalid(format!("unknown attribute kind \"{other}\""))),
+    };
+    Ok(value)
+}
+
+fn read_op(field: &Field) -> Result<OpKind, ParsingError> {
+    let name = field.get("name")?.str()?.to_string();
+    let mut attributes = Attributes::new();
+    if let Some(entries) = field.optional("attributes")? {
+        for (key, value) in entries.entries()? {
+            attributes.insert(key, read_attribute(&value)?);
+        }
+    }
+
+    let custom = match field.optional("custom")? {
+        Some(custom) => custom.bool()?,
+        None => false,
+    };
+    if custom {
+        return Ok(OpKind::Custom { name, attributes });
+    }
+
+    let op = OpKind::from_parts(&name, &attributes).map_err(|error| match error {
+        ParsingError::InvalidFormat { reason, .. } => field.invalid(reason),
+        other => other,
+    })?;
+    if matches!(op, OpKind::Custom { .. }) {
+        return Err(ParsingError::UnsupportedOperation { op_name: name });
+    }
+    Ok(op)
+}
+
+/// `{"node": id, "port": n}`; the port defaults to 0.
+fn read_value(field: &Field) -> Result<(u32, usize), ParsingError> {
+    let port = match field.optional("port")? {
+        Some(port) => port.usize()?,
+        None => 0,
+    };
+    Ok((field.get("node")?.u32()?, port))
+}
+
+fn read_output_types(node: &Field) -> Result<Vec<TensorType>, ParsingError> {
+    match node.optional("output_types")? {
+        Some(types) => types.items()?.iter().map(read_type).collect(),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Creates the nodes in document order, then wires inputs and outputs by the
+/// IDs used in the document, which need not match the IDs handed out here.
+fn read_graph(field: &Field) -> Result<Graph, ParsingError> {
+    let format = field.get("format")?;
+    if format.str()? != JSON_FORMAT {
+        return Err(format.invalid(format!("expected \"{JSON_FORMAT}\"")));
+    }
+    let version = field.get("version")?;
+    if version.int()? != JSON_VERSION {
+        return Err(version.invalid(format!(
+            "unsupported version {}, this build reads version {JSON_VERSION}",
+            version.value
+        )));
+    }
+
+    let mut declared_inputs = Vec::new();
+    for input in field.get("inputs")?.items()? {
+        declared_inputs.push((input.u32()?, input));
+    }
+
+    let mut graph = Graph::new();
+    let mut ids: HashMap<u32, NodeID> = HashMap::new();
+    let mut wiring = Vec::new();
+
+    for node in field.get("nodes")?.items()? {
+        let id_field = node.get("id")?;
+        let id = id_field.u32()?;
+        if ids.contains_key(&id) {
+            return Err(id_field.invalid(format!("node ID {id} is used twice")));
+        }
+        let op = read_op(&node.get("op")?)?;
+        let output_types = read_output_types(&node)?;
+        let declared = declared_inputs.iter().any(|(input, _)| *input == id);
+
+        let node_id = match (op, node.optional("tensor")?) {
+            (OpKind::Input { name }, None) if declared => {
+                let [input_type] = output_types.as_slice() else {
+                    return Err(node.invalid("graph input needs exactly one output type"));
+                };
+                graph.add_input(name, input_type.clone())
+            }
+            (OpKind::Constant { name }, Some(tensor)) if !declared => {
+                let node_id = graph.add_initializer(name, read_tensor(&tensor)?);
+                graph
+                    .set_output_types(node_id, output_types)
+                    .expect("node was just added");
+                node_id
+            }
+            (_, Some(tensor)) => {
+                return Err(tensor.invalid("only constant nodes carry a tensor"));
+            }
+            (_, None) if declared => {
+                return Err(node.invalid("graph inputs must be Input nodes"));
+            }
+            (op, None) => {
+                let node_id = graph.add_node_with_values(op, vec![]);
+                graph
+                    .set_output_types(node_id, output_types)
+                    .expect("node was just added");
+                node_id
+            }
+        };
+        ids.insert(id, node_id);
+        wiring.push((node_id, node));
+    }
+
+    let resolve = |field: &Field| {
+        let (id, port) = read_value(field)?;
+        ids.get(&id)
+            .map(|node_id| node_id.output(port))
+            .ok_or_else(|| field.invalid(format!("no node with ID {id}")))
+    };
+
+    for (_, input) in &declared_inputs {
+        let id = input.u32()?;
+        if !ids.contains_key(&id) {
+            return Err(input.invalid(format!("no node with ID {id}")));
+        }
+    }
+    for (node_id, node) in wiring {
+        let inputs = node
+            .get("inputs")?
+            .items()?
+            .iter()
+            .map(resolve)
+            .collect::<Result<_, _>>()?;
+        graph
+            .set_inputs(node_id, inputs)
+            .expect("every ID resolves to a node");
+    }
+    for output in field.get("outputs")?.items()? {
+        let name = output.get("name")?.str()?;
+        let value = resolve(&output)?;
+        graph
+            .add_output(name, value)
+            .map_err(|error| output.invalid(error))?;
+    }
+
+    Ok(graph)
+}
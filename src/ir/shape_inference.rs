@@ -0,0 +1,704 @@
+use crate::ir::{
+    errors::ValidationError,
+    graph::Graph,
+    movement::{resolve_perm, slice_range},
+    symbolic::{Dim, ShapeConstraints},
+    types::{DType, NodeID, OpCategory, OpKind, TensorShape, TensorType, Window2d},
+    validation::ValidationResult,
+};
+
+/// Fills in the output type of every node whose inputs have known types.
+///
+/// Graph inputs, constants and `Custom` ops keep whatever type was declared on
+/// them. Nodes whose inputs are still untyped are skipped rather than reported,
+/// since the missing information is upstream of them.
+///
+/// Symbolic dimensions are carried through; every equality the ops rely on
+/// (matching contraction dims, broadcast partners) is recorded in the pass's
+/// `ShapeConstraints` and contradictions are reported as errors.
+pub struct ShapeInference<'a> {
+    graph: &'a mut Graph,
+    constraints: ShapeConstraints,
+}
+
+impl<'a> ShapeInference<'a> {
+    pub fn new(graph: &'a mut Graph) -> Self {
+        ShapeInference::with_constraints(graph, ShapeConstraints::new())
+    }
+
+    /// Starts from already known facts, e.g. a batch size bound by the caller.
+    pub fn with_constraints(graph: &'a mut Graph, constraints: ShapeConstraints) -> Self {
+        ShapeInference { graph, constraints }
+    }
+
+    pub fn constraints(&self) -> &ShapeConstraints {
+        &self.constraints
+    }
+
+    pub fn into_constraints(self) -> ShapeConstraints {
+        self.constraints
+    }
+
+    pub fn run(&mut self) -> ValidationResult {
+        let order = self
+            .graph
+            .topological_order()
+            .map_err(|error| vec![error])?;
+        let mut errors = Vec::new();
+        let mut inferred_nodes = Vec::new();
+
+        for node_id in order {
+            match self.infer_node(node_id) {
+                Ok(Some(inferred)) => {
+                    // The node exists: it came out of the topological order
+                    let _ = self.graph.set_output_types(node_id, inferred);
+                    inferred_nodes.push(node_id);
+                }
+                Ok(None) => {}
+                Err(error) => errors.push(error),
+            }
+        }
+
+        // Symbols solved further down the graph sharpen types inferred earlier
+        for node_id in inferred_nodes {
+            let Some(node) = self.graph.get_node(node_id) else {
+                continue;
+            };
+            let resolved = node
+                .output_types()
+                .iter()
+                .map(|output_type| self.resolve_type(output_type))
+                .collect();
+            let _ = self.graph.set_output_types(node_id, resolved);
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// `output_type` with every solved symbol substituted, keeping its layout.
+    fn resolve_type(&self, output_type: &TensorType) -> TensorType {
+        let resolved = TensorType::new(
+            output_type.dtype(),
+            self.constraints.resolve_shape(output_type.shape()),
+        );
+        match output_type.layout() {
+            Some(layout) => resolved
+                .clone()
+                .with_layout(layout.clone())
+                .unwrap_or(resolved),
+            None => resolved,
+        }
+    }
+
+    fn infer_node(&mut self, node_id: NodeID) -> Result<Option<Vec<TensorType>>, ValidationError> {
+        let Some(node) = self.graph.get_node(node_id) else {
+            return Ok(None);
+        };
+
+        if matches!(
+            node.op().category(),
+            OpCategory::Opaque | OpCategory::Source
+        ) || !node.op().arity().accepts(node.inputs().len())
+        {
+            return Ok(None);
+        }
+
+        let mut input_types = Vec::with_capacity(node.inputs().len());
+        for input in node.inputs() {
+            match self.graph.value_type(*input) {
+                Some(input_type) => input_types.push(input_type),
+                None => return Ok(None),
+            }
+        }
+
+        let inferred_types = infer_output_types(node.op(), &input_types, &mut self.constraints)?;
+
+        let mut resolved_types = Vec::with_capacity(inferred_types.len());
+        for (index, inferred) in inferred_types.iter().enumerate() {
+            let mut resolved = TensorType::new(
+                inferred.dtype(),
+                self.constraints.resolve_shape(inferred.shape()),
+            );
+
+            if let Some(declared) = node.output_types().get(index) {
+                // Inferred type first, then the declared one
+                let mismatch = || ValidationError::IncompatibleShapes {
+                    op: node.op().name().to_string(),
+                    shapes: vec![inferred.to_string(), declared.to_string()],
+                };
+                if declared.dtype() != inferred.dtype() {
+                    return Err(mismatch());
+                }
+                self.constraints
+                    .unify_shapes(inferred.shape(), declared.shape())
+                    .map_err(|_| mismatch())?;
+
+                // A declared memory layout is a fact about the buffer, keep it
+                if let Some(layout) = declared.layout() {
+                    resolved = resolved.with_layout(layout.clone())?;
+                }
+            }
+
+            resolved_types.push(resolved);
+        }
+
+        Ok(Some(resolved_types))
+    }
+}
+
+/// Output type of `op` applied to inputs of the given types. Equalities between
+/// symbolic dimensions that the op requires are added to `constraints`.
+///
+/// For ops with several outputs this is the type of the first one; see
+/// [`infer_output_types`].
+pub fn infer_output_type(
+    op: &OpKind,
+    inputs: &[&TensorType],
+    constraints: &mut ShapeConstraints,
+) -> Result<TensorType, ValidationError> {
+    Ok(infer_output_types(op, inputs, constraints)?.remove(0))
+}
+
+/// Type of every output of `op`, in output order.
+pub fn infer_output_types(
+    op: &OpKind,
+    inputs: &[&TensorType],
+    constraints: &mut ShapeConstraints,
+) -> Result<Vec<TensorType>, ValidationError> {
+    if !op.arity().accepts(inputs.len())
+        || matches!(op.category(), OpCategory::Opaque | OpCategory::Source)
+    {
+        return Err(incompatible(op, inputs));
+    }
+
+    // Where's condition is boolean and Gather's indices are integers; every
+    // other input shares one element type
+    let data_inputs = match op {
+        OpKind::Where => {
+            if inputs[0].dtype() != DType::Bool {
+                return Err(incompatible(op, inputs));
+            }
+            &inputs[1..]
+        }
+        OpKind::Gather { .. } => {
+            if !matches!(inputs[1].dtype(), DType::I32 | DType::I8) {
+                return Err(incompatible(op, inputs));
+            }
+            &inputs[..1]
+        }
+        _ => inputs,
+    };
+    let dtype = data_inputs[0].dtype();
+    if data_inputs.iter().any(|input| input.dtype() != dtype)
+        || (op.requires_floating_point() && !dtype.is_floating_point())
+        || (op.is_reduction() && dtype == DType::Bool)
+    {
+        return Err(incompatible(op, inputs));
+    }
+
+    let shape = match op {
+        OpKind::MatMul {
+            transpose_a,
+            transpose_b,
+        } => {
+            let lhs = transpose_last_two(inputs[0].shape(), *transpose_a);
+            let rhs = transpose_last_two(inputs[1].shape(), *transpose_b);
+            infer_matmul(&lhs, &rhs, constraints).ok_or_else(|| incompatible(op, inputs))?
+        }
+        OpKind::Add | OpKind::Mul | OpKind::Sub | OpKind::Div | OpKind::Pow | OpKind::Where => {
+            let shapes: Vec<&TensorShape> = inputs.iter().map(|input| input.shape()).collect();
+            TensorShape::broadcast_constrained(&shapes, constraints)
+                .map_err(|_| incompatible(op, inputs))?
+        }
+        OpKind::Gelu
+        | OpKind::Dropout { .. }
+        | OpKind::Relu
+        | OpKind::Sigmoid
+        | OpKind::Tanh
+        | OpKind::Exp
+        | OpKind::Log
+        | OpKind::Sqrt
+        | OpKind::Rsqrt
+        | OpKind::Erf
+        | OpKind::Clip { .. }
+        | OpKind::Cast { .. } => inputs[0].shape().clone(),
+        OpKind::Softmax { axis } => {
+            check_axis(op, inputs[0].shape(), *axis)?;
+            inputs[0].shape().clone()
+        }
+        OpKind::LayerNorm { axis, .. } => {
+            // Scale and bias cover the normalised dimensions and must broadcast
+            // onto them without growing them
+            let data = inputs[0].shape();
+            let first = check_axis(op, data, *axis)?;
+            let normalized = TensorShape::from_dims(data.dims()[first..].to_vec());
+            for param in &inputs[1..] {
+                let combined =
+                    TensorShape::broadcast_constrained(&[&normalized, param.shape()], constraints)
+                        .map_err(|_| incompatible(op, inputs))?;
+                if combined != constraints.resolve_shape(&normalized) {
+                    return Err(incompatible(op, inputs));
+                }
+            }
+
+            // Mean and inverse standard deviation keep the normalised
+            // dimensions as size 1
+            let axes: Vec<usize> = (first..data.rank()).collect();
+            let statistics = TensorType::new(dtype, data.reduce(&axes, true));
+            return Ok(vec![
+                TensorType::new(dtype, data.clone()),
+                statistics.clone(),
+                statistics,
+            ]);
+        }
+        OpKind::Reduce { axes, keepdims, .. } => {
+            let data = inputs[0].shape();
+            let axes =
+                data.normalize_axes(axes)
+                    .ok_or_else(|| ValidationError::InvalidAttribute {
+                        op: op.name().to_string(),
+                        attribute: "axes".to_string(),
+                        reason: format!("{axes:?} are out of range or repeated for shape {data}"),
+                    })?;
+            data.reduce(&axes, *keepdims)
+        }
+        OpKind::ArgMax { axis, keepdims } => {
+            let data = inputs[0].shape();
+            let axis = check_axis(op, data, *axis)?;
+            data.reduce(&[axis], *keepdims)
+        }
+        OpKind::Reshape { shape } => infer_reshape(op, inputs, shape, constraints)?,
+        OpKind::Transpose { perm } => {
+            let data = inputs[0].shape();
+            let perm = resolve_perm(perm, data.rank());
+            data.permute(&perm)
+                .map_err(|_| ValidationError::InvalidAttribute {
+                    op: op.name().to_string(),
+                    attribute: "perm".to_string(),
+                    reason: format!("{perm:?} is not a permutation of the dimensions of {data}"),
+                })?
+        }
+        OpKind::Concat { axis } => infer_concat(op, inputs, *axis, constraints)?,
+        OpKind::Split { axis, sizes } => {
+            let data = inputs[0].shape();
+            let axis = check_axis(op, data, *axis)?;
+            let total = Dim::Static(sizes.iter().sum());
+            constraints
+                .require_equal(&data.dims()[axis], &total)
+                .map_err(|_| incompatible(op, inputs))?;
+
+            let pieces = sizes
+                .iter()
+                .map(|&size| {
+                    let mut dims = data.dims().to_vec();
+                    dims[axis] = Dim::Static(size);
+                    TensorType::new(
+                        dtype,
+                        constraints.resolve_shape(&TensorShape::from_dims(dims)),
+                    )
+                })
+                .collect();
+            return Ok(pieces);
+        }
+        OpKind::Slice {
+            starts,
+            ends,
+            axes,
+            steps,
+        } => infer_slice(op, inputs, starts, ends, axes, steps)?,
+        OpKind::Gather { axis } => {
+            let data = inputs[0].shape();
+            let axis = check_axis(op, data, *axis)?;
+            let mut dims = data.dims()[..axis].to_vec();
+            dims.extend_from_slice(inputs[1].shape().dims());
+            dims.extend_from_slice(&data.dims()[axis + 1..]);
+            TensorShape::from_dims(dims)
+        }
+        OpKind::Conv2d { window, groups } => {
+            infer_conv(op, inputs, window, Some(*groups), constraints)?
+        }
+        OpKind::DepthwiseConv { window } => infer_conv(op, inputs, window, None, constraints)?,
+        OpKind::MaxPool {
+            kernel_shape,
+            window,
+        }
+        | OpKind::AvgPool {
+            kernel_shape,
+            window,
+            ..
+        } => {
+            let data = inputs[0].shape();
+            if data.rank() != 4 {
+                return Err(incompatible(op, inputs));
+            }
+            let mut dims = data.dims().to_vec();
+            for axis in 0..2 {
+                dims[axis + 2] = window
+                    .output_dim(axis, &dims[axis + 2], kernel_shape[axis])
+                    .ok_or_else(|| incompatible(op, inputs))?;
+            }
+            TensorShape::from_dims(dims)
+        }
+        OpKind::GlobalAvgPool => {
+            let data = inputs[0].shape();
+            if data.rank() != 4 {
+                return Err(incompatible(op, inputs));
+            }
+            data.reduce(&[2, 3], true)
+        }
+        OpKind::BatchNorm { .. } => {
+            // Every parameter holds one value per channel
+            let data = inputs[0].shape();
+            if data.rank() < 2 {
+                return Err(incompatible(op, inputs));
+            }
+            for param in &inputs[1..] {
+                let shape = param.shape();
+                if shape.rank() != 1 {
+                    return Err(incompatible(op, inputs));
+                }
+                constraints
+                    .require_equal(&data.dims()[1], &shape.dims()[0])
+                    .map_err(|_| incompatible(op, inputs))?;
+            }
+            data.clone()
+        }
+        OpKind::Input { .. } | OpKind::Constant { .. } | OpKind::Custom { .. } => {
+            return Err(incompatible(op, inputs));
+        }
+    };
+
+    let output_dtype = match op {
+        OpKind::Cast { to } => *to,
+        OpKind::ArgMax { .. } => DType::I32,
+        _ => dtype,
+    };
+
+    Ok(vec![TensorType::new(
+        output_dtype,
+        constraints.resolve_shape(&shape),
+    )])
+}
+
+fn incompatible(op: &OpKind, inputs: &[&TensorType]) -> ValidationError {
+    ValidationError::IncompatibleShapes {
+        op: op.name().to_string(),
+        shapes: inputs.iter().map(|input| input.to_string()).collect(),
+    }
+}
+
+fn check_axis(op: &OpKind, shape: &TensorShape, axis: i64) -> Result<usize, ValidationError> {
+    shape
+        .normalize_axis(axis)
+        .ok_or_else(|| ValidationError::InvalidAttribute {
+            op: op.name().to_string(),
+            attribute: "axis".to_string(),
+            reason: format!("{axis} is out of range for shape {shape}"),
+        })
+}
+
+/// Resolves `0` and `-1` entries of a reshape target. Element counts are
+/// compared after cancelling dimensions that appear on both sides, so reshapes
+/// such as `[batch, seq, 768] -> [batch, seq, 12, 64]` check out symbolically.
+fn infer_reshape(
+    op: &OpKind,
+    inputs: &[&TensorType],
+    target: &[i64],
+    constraints: &mut ShapeConstraints,
+) -> Result<TensorShape, ValidationError> {
+    let data = inputs[0].shape();
+    let invalid = |reason: String| ValidationError::InvalidAttribute {
+        op: op.name().to_string(),
+        attribute: "shape".to_string(),
+        reason,
+    };
+
+    let mut dims: Vec<Option<Dim>> = Vec::with_capacity(target.len());
+    for (position, &entry) in target.iter().enumerate() {
+        let dim = match entry {
+            -1 => None,
+            0 => Some(data.dims().get(position).cloned().ok_or_else(|| {
+                invalid(format!(
+                    "entry {position} copies a dimension {data} does not have"
+                ))
+            })?),
+            size if size > 0 => Some(Dim::Static(size as usize)),
+            size => return Err(invalid(format!("{size} is not a valid dimension"))),
+        };
+        dims.push(dim);
+    }
+    if dims.iter().filter(|dim| dim.is_none()).count() > 1 {
+        return Err(invalid("at most one entry can be -1".to_string()));
+    }
+
+    // Cancel dimensions that appear on both sides, then compare what is left
+    let mut remaining: Vec<Dim> = data.dims().to_vec();
+    let mut unmatched: Vec<Dim> = Vec::new();
+    for dim in dims.iter().flatten() {
+        match remaining
+            .iter()
+            .position(|candidate| constraints.are_equal(candidate, dim))
+        {
+            Some(index) => {
+                remaining.swap_remove(index);
+            }
+            None => unmatched.push(dim.clone()),
+        }
+    }
+    let (input_symbolic, input_static) = split_product(&remaining);
+    let (output_symbolic, output_static) = split_product(&unmatched);
+
+    let mismatch = || incompatible(op, inputs);
+    let inferred = match (input_symbolic.as_slice(), output_symbolic.as_slice()) {
+        ([], []) => {
+            let has_wildcard = dims.iter().any(Option::is_none);
+            if has_wildcard && output_static != 0 && input_static % output_static == 0 {
+                Some(Dim::Static(input_static / output_static))
+            } else if !has_wildcard && input_static == output_static {
+                None
+            } else {
+                return Err(mismatch());
+            }
+        }
+        ([symbol], []) if dims.iter().any(Option::is_none) => {
+            if output_static == 0 || input_static % output_static != 0 {
+                return Err(mismatch());
+            }
+            Some(symbol.clone() * (input_static / output_static) as i64)
+        }
+        // Without a wildcard the element counts must be equal, which can be
+        // recorded while each side has at most one symbolic factor
+        (input_rest, output_rest)
+            if dims.iter().all(Option::is_some)
+                && input_rest.len() <= 1
+                && output_rest.len() <= 1 =>
+        {
+            let count = |symbolic: &[Dim], constant: usize| match symbolic {
+                [dim] => dim.clone() * constant as i64,
+                _ => Dim::Static(constant),
+            };
+            constraints
+                .require_equal(
+                    &count(input_rest, input_static),
+                    &count(output_rest, output_static),
+                )
+                .map_err(|_| mismatch())?;
+            None
+        }
+        // Products of symbols are not tracked, so equal counts cannot be shown
+        _ => return Err(mismatch()),
+    };
+
+    Ok(TensorShape::from_dims(
+        dims.into_iter()
+            .map(|dim| dim.or_else(|| inferred.clone()).unwrap_or(Dim::Static(0)))
+            .collect(),
+    ))
+}
+
+/// Symbolic dimensions and the product of the static ones.
+fn split_product(dims: &[Dim]) -> (Vec<Dim>, usize) {
+    let mut symbolic = Vec::new();
+    let mut product = 1;
+    for dim in dims {
+        match dim.as_static() {
+            Some(value) => product *= value,
+            None => symbolic.push(dim.clone()),
+        }
+    }
+    (symbolic, product)
+}
+
+fn infer_concat(
+    op: &OpKind,
+    inputs: &[&TensorType],
+    axis: i64,
+    constraints: &mut ShapeConstraints,
+) -> Result<TensorShape, ValidationError> {
+    let first = inputs[0].shape();
+    let axis = check_axis(op, first, axis)?;
+
+    let mut dims = first.dims().to_vec();
+    for input in &inputs[1..] {
+        let shape = input.shape();
+        if shape.rank() != first.rank() {
+            return Err(incompatible(op, inputs));
+        }
+        for (position, dim) in shape.dims().iter().enumerate() {
+            if position == axis {
+                dims[axis] = dims[axis].clone() + dim.clone();
+            } else {
+                constraints
+                    .require_equal(&dims[position], dim)
+                    .map_err(|_| incompatible(op, inputs))?;
+            }
+        }
+    }
+
+    Ok(TensorShape::from_dims(dims))
+}
+
+/// Sliced static dimensions follow ONNX clamping. A symbolic dimension can only
+/// be sliced from a non-negative start to the end with step 1.
+fn infer_slice(
+    op: &OpKind,
+    inputs: &[&TensorType],
+    starts: &[i64],
+    ends: &[i64],
+    axes: &[i64],
+    steps: &[i64],
+) -> Result<TensorShape, ValidationError> {
+    let data = inputs[0].shape();
+    let slices = data.slice_axes(starts, ends, axes, steps).ok_or_else(|| {
+        ValidationError::InvalidAttribute {
+            op: op.name().to_string(),
+            attribute: "axes".to_string(),
+            reason: format!("slice bounds do not line up with the dimensions of {data}"),
+        }
+    })?;
+
+    let mut dims = data.dims().to_vec();
+    for slice in slices {
+        let dim = &dims[slice.axis];
+        dims[slice.axis] = match dim.as_static() {
+            Some(size) => Dim::Static(slice_range(size, slice.start, slice.end, slice.step).1),
+            None if slice.step == 1 && slice.start >= 0 && slice.end == i64::MAX => {
+                dim.clone() - Dim::Static(slice.start as usize)
+            }
+            None => return Err(incompatible(op, inputs)),
+        };
+    }
+
+    Ok(TensorShape::from_dims(dims))
+}
+
+/// NCHW convolution. `groups` is `None` for a depthwise convolution, where it
+/// equals the number of input channels.
+fn infer_conv(
+    op: &OpKind,
+    inputs: &[&TensorType],
+    window: &Window2d,
+    groups: Option<usize>,
+    constraints: &mut ShapeConstraints,
+) -> Result<TensorShape, ValidationError> {
+    let data = inputs[0].shape();
+    let weights = inputs[1].shape();
+    if data.rank() != 4 || weights.rank() != 4 {
+        return Err(incompatible(op, inputs));
+    }
+    let mismatch = |_| incompatible(op, inputs);
+
+    let out_channels = weights.dims()[0].clone();
+    let group_channels = &weights.dims()[1];
+    match groups {
+        Some(groups) => {
+            constraints
+                .require_equal(&data.dims()[1], &(group_channels.clone() * groups as i64))
+                .map_err(mismatch)?;
+            if out_channels
+                .as_static()
+                .is_some_and(|out| out % groups != 0)
+            {
+                return Err(incompatible(op, inputs));
+            }
+        }
+        None => {
+            constraints
+                .require_equal(group_channels, &Dim::Static(1))
+                .map_err(mismatch)?;
+            let channels = constraints.resolve(&data.dims()[1]).as_static();
+            let outputs = constraints.resolve(&out_channels).as_static();
+            if let (Some(channels), Some(outputs)) = (channels, outputs)
+                && (channels == 0 || outputs % channels != 0)
+            {
+                return Err(incompatible(op, inputs));
+            }
+        }
+    }
+
+    if let Some(bias) = inputs.get(2) {
+        let bias = bias.shape();
+        if bias.rank() != 1 {
+            return Err(incompatible(op, inputs));
+        }
+        constraints
+            .require_equal(&bias.dims()[0], &out_channels)
+            .map_err(mismatch)?;
+    }
+
+    let mut dims = vec![data.dims()[0].clone(), out_channels];
+    for axis in 0..2 {
+        let kernel = weights.dims()[axis + 2]
+            .as_static()
+            .ok_or_else(|| incompatible(op, inputs))?;
+        dims.push(
+            window
+                .output_dim(axis, &data.dims()[axis + 2], kernel)
+                .ok_or_else(|| incompatible(op, inputs))?,
+        );
+    }
+
+    Ok(TensorShape::from_dims(dims))
+}
+
+/// Swaps the two innermost dimensions when `transpose` is set; vectors are left
+/// alone since transposing them is a no-op.
+fn transpose_last_two(shape: &TensorShape, transpose: bool) -> TensorShape {
+    if !transpose || shape.rank() < 2 {
+        return shape.clone();
+    }
+
+    let mut dims = shape.dims().to_vec();
+    let rank = dims.len();
+    dims.swap(rank - 2, rank - 1);
+    TensorShape::from_dims(dims)
+}
+
+/// NumPy `matmul` semantics: rank-1 operands are promoted to matrices and the
+/// promoted dimension is dropped again afterwards; leading dimensions broadcast.
+fn infer_matmul(
+    lhs: &TensorShape,
+    rhs: &TensorShape,
+    constraints: &mut ShapeConstraints,
+) -> Option<TensorShape> {
+    if lhs.is_scalar() || rhs.is_scalar() {
+        return None;
+    }
+
+    let mut lhs_dims = lhs.dims().to_vec();
+    let mut rhs_dims = rhs.dims().to_vec();
+    let lhs_vector = lhs_dims.len() == 1;
+    let rhs_vector = rhs_dims.len() == 1;
+    if lhs_vector {
+        lhs_dims.insert(0, Dim::Static(1));
+    }
+    if rhs_vector {
+        rhs_dims.push(Dim::Static(1));
+    }
+
+    let m = lhs_dims[lhs_dims.len() - 2].clone();
+    let n = rhs_dims[rhs_dims.len() - 1].clone();
+    constraints
+        .require_equal(&lhs_dims[lhs_dims.len() - 1], &rhs_dims[rhs_dims.len() - 2])
+        .ok()?;
+
+    let lhs_batch = TensorShape::from_dims(lhs_dims[..lhs_dims.len() - 2].to_vec());
+    let rhs_batch = TensorShape::from_dims(rhs_dims[..rhs_dims.len() - 2].to_vec());
+    let mut dims = TensorShape::broadcast_constrained(&[&lhs_batch, &rhs_batch], constraints)
+        .ok()?
+        .dims()
+        .to_vec();
+
+    if !lhs_vector {
+        dims.push(m);
+    }
+    if !rhs_vector {
+        dims.push(n);
+    }
+
+    Some(TensorShape::from_dims(dims))
+}
@@ -0,0 +1,429 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::ir::{
+    errors::ValidationError,
+    graph::Graph,
+    ops::Node,
+    types::{NodeID, OpKind, ValueRef},
+};
+
+/// Where the nodes and values of a source graph ended up after being copied
+/// into another graph.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NodeMapping {
+    nodes: HashMap<NodeID, NodeID>,
+    /// Values produced outside the copied nodes that were turned into graph
+    /// inputs of the copy.
+    values: HashMap<ValueRef, ValueRef>,
+}
+
+impl NodeMapping {
+    /// The copy of a source node.
+    pub fn node(&self, node_id: NodeID) -> Option<NodeID> {
+        self.nodes.get(&node_id).copied()
+    }
+
+    /// The value standing in for a source value: the same port of the node's
+    /// copy, or the graph input that replaced it at the boundary.
+    pub fn value(&self, value: ValueRef) -> Option<ValueRef> {
+        match self.nodes.get(&value.node()) {
+            Some(node_id) => Some(node_id.output(value.port())),
+            None => self.values.get(&value).copied(),
+        }
+    }
+
+    /// Source and copied node IDs, in ascending source ID order.
+    pub fn pairs(&self) -> Vec<(NodeID, NodeID)> {
+        let mut pairs: Vec<(NodeID, NodeID)> = self
+            .nodes
+            .iter()
+            .map(|(source, copy)| (*source, *copy))
+            .collect();
+        pairs.sort_by_key(|(source, _)| source.id());
+        pairs
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+impl Graph {
+    /// Deep copy with fresh, densely numbered IDs, plus the table from old IDs
+    /// to new ones. Inputs, outputs and constants keep their names. Fails when
+    /// a node reads a value whose node is not in the graph.
+    pub fn clone_with_mapping(&self) -> Result<(Graph, NodeMapping), ValidationError> {
+        let mut copy = Graph::new();
+        let mapping = copy.merge(self)?;
+        Ok((copy, mapping))
+    }
+
+    /// Copies every node of `other` into this graph under fresh IDs, keeping
+    /// its inputs, outputs and constants as boundary values of this graph.
+    /// Fails without changing anything when one of those names is taken here,
+    /// or when `other` reads or outputs a value whose node it lacks.
+    pub fn merge(&mut self, other: &Graph) -> Result<NodeMapping, ValidationError> {
+        // A name `other` uses twice, such as an input passed straight through
+        // to an output of the same name, is not a collision
+        let taken: HashSet<String> = self.boundary_names();
+        let other_names = other.boundary_names_in_order().into_iter().chain(
+            other
+                .outputs()
+                .iter()
+                .map(|output| output.name().to_string()),
+        );
+        for name in other_names {
+            if taken.contains(&name) {
+                return Err(ValidationError::InvalidGraphBoundary {
+                    name,
+                    reason: "defined by both merged graphs".to_string(),
+                });
+            }
+        }
+        let read = other.nodes().flat_map(|node| node.inputs().iter().copied());
+        let output = other.outputs().iter().map(|output| output.value());
+        if let Some(dangling) = read
+            .chain(output)
+            .find(|value| !other.contains_node(value.node()))
+        {
+            return Err(ValidationError::MissingNode {
+                node_id: dangling.node().id(),
+            });
+        }
+
+        // Graph inputs first, so they keep their calling order
+        let nodes: Vec<NodeID> = other
+            .inputs()
+            .iter()
+            .copied()
+            .chain(
+                other
+                    .node_ids()
+                    .into_iter()
+                    .filter(|node_id| !other.inputs().contains(node_id)),
+            )
+            .collect();
+        let mut mapping = NodeMapping::default();
+        self.copy_nodes(other, &nodes, &mut mapping, |_, _| None)?;
+        for output in other.outputs() {
+            let value = mapping
+                .value(output.value())
+                .expect("every node was copied");
+            self.add_output(output.name(), value)?;
+        }
+        Ok(mapping)
+    }
+
+    /// Copies `other` in place of a call to it: its graph inputs are replaced
+    /// by `arguments`, in order, and its constants become constants of this
+    /// graph, renamed with a numeric suffix when the name is taken. Graph
+    /// outputs of `other` are not added here; look them up in the mapping.
+    pub fn inline(
+        &mut self,
+        other: &Graph,
+        arguments: &[ValueRef],
+    ) -> Result<NodeMapping, ValidationError> {
+        if arguments.len() != other.inputs().len() {
+            return Err(ValidationError::InvalidGraphBoundary {
+                name: "inlined graph".to_string(),
+                reason: format!(
+                    "takes {} inputs but {} arguments were given",
+                    other.inputs().len(),
+                    arguments.len()
+                ),
+            });
+        }
+        for argument in arguments {
+            if !self.contains_node(argument.node()) {
+                return Err(ValidationError::MissingNode {
+                    node_id: argument.node().id(),
+                });
+            }
+        }
+
+        let mut mapping = NodeMapping::default();
+        for (input, argument) in other.inputs().iter().zip(arguments) {
+            mapping.values.insert(input.output(0), *argument);
+        }
+        let body: Vec<NodeID> = other
+            .node_ids()
+            .into_iter()
+            .filter(|node_id| !other.inputs().contains(node_id))
+            .collect();
+        self.copy_nodes(other, &body, &mut mapping, |_, _| None)?;
+        Ok(mapping)
+    }
+
+    /// Standalone graph holding copies of `nodes`. Values they read from
+    /// outside the set become graph inputs, except constants, which are copied
+    /// along. Values read outside the set, or graph outputs, become outputs.
+    /// Boundary values need a known type.
+    pub fn extract(&self, nodes: &[NodeID]) -> Result<(Graph, NodeMapping), ValidationError> {
+        for node_id in nodes {
+            if !self.contains_node(*node_id) {
+                return Err(ValidationError::MissingNode {
+                    node_id: node_id.id(),
+                });
+            }
+        }
+
+        let selected: HashSet<NodeID> = nodes.iter().copied().collect();
+        let mut outputs = Vec::new();
+        for node_id in self.node_ids() {
+            if !selected.contains(&node_id) {
+                continue;
+            }
+            for user in self.users(node_id) {
+                if selected.contains(user) {
+                    continue;
+                }
+                for input in self
+                    .get_node(*user)
+                    .into_iter()
+                    .flat_map(|user| user.inputs())
+                {
+                    if input.node() == node_id && !outputs.contains(input) {
+                        outputs.push(*input);
+                    }
+                }
+            }
+        }
+        for output in self.outputs() {
+            if selected.contains(&output.node()) && !outputs.contains(&output.value()) {
+                outputs.push(output.value());
+            }
+        }
+        outputs.sort_by_key(|value| (value.node().id(), value.port()));
+
+        self.extract_selected(&selected, &[], &outputs)
+    }
+
+    /// Standalone graph computing `outputs` from `inputs`: every node on a
+    /// path back from an output, stopping at the given input values. Graph
+    /// inputs and constants reached along the way are copied too.
+    pub fn extract_between(
+        &self,
+        inputs: &[ValueRef],
+        outputs: &[ValueRef],
+    ) -> Result<(Graph, NodeMapping), ValidationError> {
+        for value in inputs.iter().chain(outputs) {
+            if !self.contains_node(value.node()) {
+                return Err(ValidationError::MissingNode {
+                    node_id: value.node().id(),
+                });
+            }
+        }
+
+        let mut selected = HashSet::new();
+        let mut stack: Vec<NodeID> = outputs
+            .iter()
+            .filter(|output| !inputs.contains(output))
+            .map(|output| output.node())
+            .collect();
+        while let Some(node_id) = stack.pop() {
+            if !selected.insert(node_id) {
+                continue;
+            }
+            let Some(node) = self.get_node(node_id) else {
+                continue;
+            };
+            for input in node.inputs() {
+                if !inputs.contains(input) && !selected.contains(&input.node()) {
+                    stack.push(input.node());
+                }
+            }
+        }
+
+        self.extract_selected(&selected, inputs, outputs)
+    }
+
+    /// Builds the extracted graph: declared inputs first, then one input per
+    /// other outside value in the order the copied nodes read them.
+    fn extract_selected(
+        &self,
+        selected: &HashSet<NodeID>,
+        inputs: &[ValueRef],
+        outputs: &[ValueRef],
+    ) -> Result<(Graph, NodeMapping), ValidationError> {
+        let mut extracted = Graph::new();
+        let mut mapping = NodeMapping::default();
+
+        // Constants travel with the nodes that read them
+        let mut nodes: Vec<NodeID> = self
+            .node_ids()
+            .into_iter()
+            .filter(|node_id| selected.contains(node_id))
+            .collect();
+        for node_id in nodes.clone() {
+            for producer in self.predecessors(node_id) {
+                if self.initializer(producer).is_some()
+                    && !selected.contains(&producer)
+                    && !nodes.contains(&producer)
+                    && !inputs.iter().any(|input| input.node() == producer)
+                {
+                    nodes.push(producer);
+                }
+            }
+        }
+        nodes.sort_by_key(|node_id| node_id.id());
+
+        for input in inputs {
+            extracted.add_boundary_input(self, *input, &mut mapping)?;
+        }
+        extracted.copy_nodes(self, &nodes, &mut mapping, |graph, value| {
+            Some(graph.add_boundary_input(self, value, &mut NodeMapping::default()))
+        })?;
+
+        for output in outputs {
+            let value = mapping.value(*output).ok_or(ValidationError::MissingNode {
+                node_id: output.node().id(),
+            })?;
+            let name = self.value_name(*output);
+            extracted.add_output(name, value)?;
+        }
+
+        Ok((extracted, mapping))
+    }
+
+    /// Adds a graph input standing in for `value` of `source` and records it
+    /// in the mapping.
+    fn add_boundary_input(
+        &mut self,
+        source: &Graph,
+        value: ValueRef,
+        mapping: &mut NodeMapping,
+    ) -> Result<ValueRef, ValidationError> {
+        let name = source.value_name(value);
+        let value_type =
+            source
+                .value_type(value)
+                .cloned()
+                .ok_or(ValidationError::InvalidGraphBoundary {
+                    name: name.clone(),
+                    reason: "boundary value has no known type".to_string(),
+                })?;
+        let input = self.add_input(name, value_type).output(0);
+        mapping.values.insert(value, input);
+        Ok(input)
+    }
+
+    /// Copies `nodes` of `source` into this graph and wires up their inputs.
+    /// Inputs produced by nodes that were not copied resolve through the
+    /// mapping, then through `outside`, which may add a stand-in value.
+    fn copy_nodes(
+        &mut self,
+        source: &Graph,
+        nodes: &[NodeID],
+        mapping: &mut NodeMapping,
+        mut outside: impl FnMut(&mut Graph, ValueRef) -> Option<Result<ValueRef, ValidationError>>,
+    ) -> Result<(), ValidationError> {
+        // Nodes first, so inputs can point forwards and around cycles
+        for node_id in nodes {
+            let node = source
+                .get_node(*node_id)
+                .ok_or(ValidationError::MissingNode {
+                    node_id: node_id.id(),
+                })?;
+            let copy = self.copy_node(source, node);
+            mapping.nodes.insert(*node_id, copy);
+        }
+
+        for node_id in nodes {
+            let node = source.get_node(*node_id).expect("checked above");
+            let mut inputs = Vec::with_capacity(node.inputs().len());
+            for input in node.inputs() {
+                let value = match mapping.value(*input) {
+                    Some(value) => value,
+                    None => match outside(self, *input) {
+                        Some(value) => {
+                            let value = value?;
+                            mapping.values.insert(*input, value);
+                            value
+                        }
+                        None => {
+                            return Err(ValidationError::MissingNode {
+                                node_id: input.node().id(),
+                            });
+                        }
+                    },
+                };
+                inputs.push(value);
+            }
+            self.set_inputs(mapping.nodes[node_id], inputs)?;
+        }
+
+        Ok(())
+    }
+
+    /// Adds an unconnected copy of `node`, keeping graph inputs and constants
+    /// registered as such.
+    fn copy_node(&mut self, source: &Graph, node: &Node) -> NodeID {
+        let copy = match (node.op(), node.output_type()) {
+            (OpKind::Input { name }, Some(input_type)) if source.inputs().contains(&node.id()) => {
+                return self.add_input(name.clone(), input_type.clone());
+            }
+            (OpKind::Constant { name }, _) if source.initializer(node.id()).is_some() => {
+                let tensor = source
+                    .initializer(node.id())
+                    .expect("checked above")
+                    .clone();
+                let name = self.unique_name(name);
+                return self.add_initializer(name, tensor);
+            }
+            _ => self.add_node_with_values(node.op().clone(), vec![]),
+        };
+        self.set_output_types(copy, node.output_types().to_vec())
+            .expect("node was just added");
+        copy
+    }
+
+    /// `name`, or `name_1`, `name_2`, ... when an input, constant or output
+    /// already uses it.
+    fn unique_name(&self, name: &str) -> String {
+        let taken = self.boundary_names();
+        let mut candidate = name.to_string();
+        let mut suffix = 1;
+        while taken.contains(&candidate) {
+            candidate = format!("{name}_{suffix}");
+            suffix += 1;
+        }
+        candidate
+    }
+
+    /// Name for a value crossing an extraction boundary: the graph output,
+    /// input or constant name it already has, or one built from its position.
+    fn value_name(&self, value: ValueRef) -> String {
+        if let Some(output) = self.outputs().iter().find(|output| output.value() == value) {
+            return output.name().to_string();
+        }
+        match self.get_node(value.node()).map(Node::op) {
+            Some(OpKind::Input { name } | OpKind::Constant { name }) => name.clone(),
+            _ => format!("value_{}_{}", value.node().id(), value.port()),
+        }
+    }
+
+    fn boundary_names(&self) -> HashSet<String> {
+        self.boundary_names_in_order()
+            .into_iter()
+            .chain(
+                self.outputs()
+                    .iter()
+                    .map(|output| output.name().to_string()),
+            )
+            .collect()
+    }
+
+    /// Names of graph inputs and constants, in node order.
+    fn boundary_names_in_order(&self) -> Vec<String> {
+        self.nodes()
+            .filter_map(|node| match node.op() {
+                OpKind::Input { name } | OpKind::Constant { name } => Some(name.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+}
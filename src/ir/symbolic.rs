@@ -0,0 +1,388 @@
+use core::fmt;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    ops::{Add, Mul, Sub},
+};
+
+use crate::ir::{errors::ValidationError, types::TensorShape};
+
+/// One dimension of a tensor shape: either known at compile time or an affine
+/// expression over named symbols such as `batch` or `2*seq + 1`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Dim {
+    Static(usize),
+    Symbolic(DimExpr),
+}
+
+/// `c0 + c1*s1 + c2*s2 + ...` with integer coefficients. Terms are kept sorted
+/// and never hold a zero coefficient, so structural equality is semantic
+/// equality for affine expressions.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct DimExpr {
+    terms: BTreeMap<String, i64>,
+    constant: i64,
+}
+
+/// Equalities between dimensions gathered while checking a graph.
+///
+/// Each equality is solved for one of its symbols when possible, so later
+/// lookups see e.g. `batch` as `4` or `seq_out` as `2*seq`. Equalities that
+/// cannot be solved yet are kept and re-checked as more symbols become known.
+#[derive(Debug, Clone, Default)]
+pub struct ShapeConstraints {
+    solved: BTreeMap<String, DimExpr>,
+    pending: Vec<DimExpr>,
+}
+
+impl Dim {
+    pub fn symbol(name: impl Into<String>) -> Self {
+        Dim::Symbolic(DimExpr::symbol(name))
+    }
+
+    /// Wraps an expression, collapsing it to `Static` when it has no symbols.
+    /// Negative constants stay symbolic since no real dimension can hold them.
+    pub fn from_expr(expr: DimExpr) -> Self {
+        if expr.is_constant() && expr.constant >= 0 {
+            Dim::Static(expr.constant as usize)
+        } else {
+            Dim::Symbolic(expr)
+        }
+    }
+
+    pub fn as_static(&self) -> Option<usize> {
+        match self {
+            Dim::Static(value) => Some(*value),
+            Dim::Symbolic(_) => None,
+        }
+    }
+
+    pub fn is_static(&self) -> bool {
+        matches!(self, Dim::Static(_))
+    }
+
+    pub fn to_expr(&self) -> DimExpr {
+        match self {
+            Dim::Static(value) => DimExpr::constant(*value as i64),
+            Dim::Symbolic(expr) => expr.clone(),
+        }
+    }
+
+    pub fn evaluate(&self, bindings: &HashMap<String, usize>) -> Option<usize> {
+        match self {
+            Dim::Static(value) => Some(*value),
+            Dim::Symbolic(expr) => expr.evaluate(bindings),
+        }
+    }
+}
+
+impl DimExpr {
+    pub fn symbol(name: impl Into<String>) -> Self {
+        let mut terms = BTreeMap::new();
+        terms.insert(name.into(), 1);
+        DimExpr { terms, constant: 0 }
+    }
+
+    pub fn constant(value: i64) -> Self {
+        DimExpr {
+            terms: BTreeMap::new(),
+            constant: value,
+        }
+    }
+
+    pub fn terms(&self) -> &BTreeMap<String, i64> {
+        &self.terms
+    }
+
+    pub fn constant_term(&self) -> i64 {
+        self.constant
+    }
+
+    pub fn is_constant(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    pub fn symbols(&self) -> impl Iterator<Item = &str> {
+        self.terms.keys().map(|name| name.as_str())
+    }
+
+    pub fn scale(&self, factor: i64) -> Self {
+        if factor == 0 {
+            return DimExpr::constant(0);
+        }
+
+        DimExpr {
+            terms: self
+                .terms
+                .iter()
+                .map(|(name, coefficient)| (name.clone(), coefficient * factor))
+                .collect(),
+            constant: self.constant * factor,
+        }
+    }
+
+    /// Replaces every occurrence of `name` with `replacement`.
+    pub fn substitute(&self, name: &str, replacement: &DimExpr) -> Self {
+        let Some(&coefficient) = self.terms.get(name) else {
+            return self.clone();
+        };
+
+        let mut rest = self.clone();
+        rest.terms.remove(name);
+        rest + replacement.scale(coefficient)
+    }
+
+    pub fn evaluate(&self, bindings: &HashMap<String, usize>) -> Option<usize> {
+        let mut total = self.constant;
+        for (name, coefficient) in &self.terms {
+            total += coefficient * (*bindings.get(name)? as i64);
+        }
+        usize::try_from(total).ok()
+    }
+}
+
+impl Add for DimExpr {
+    type Output = DimExpr;
+
+    fn add(mut self, other: DimExpr) -> DimExpr {
+        for (name, coefficient) in other.terms {
+            let entry = self.terms.entry(name).or_insert(0);
+            *entry += coefficient;
+        }
+        self.terms.retain(|_, coefficient| *coefficient != 0);
+        self.constant += other.constant;
+        self
+    }
+}
+
+impl Sub for DimExpr {
+    type Output = DimExpr;
+
+    fn sub(self, other: DimExpr) -> DimExpr {
+        self + other.scale(-1)
+    }
+}
+
+impl Add for Dim {
+    type Output = Dim;
+
+    fn add(self, other: Dim) -> Dim {
+        Dim::from_expr(self.to_expr() + other.to_expr())
+    }
+}
+
+impl Sub for Dim {
+    type Output = Dim;
+
+    fn sub(self, other: Dim) -> Dim {
+        Dim::from_expr(self.to_expr() - other.to_expr())
+    }
+}
+
+impl Mul<i64> for Dim {
+    type Output = Dim;
+
+    fn mul(self, factor: i64) -> Dim {
+        Dim::from_expr(self.to_expr().scale(factor))
+    }
+}
+
+impl From<usize> for Dim {
+    fn from(value: usize) -> Self {
+        Dim::Static(value)
+    }
+}
+
+impl ShapeConstraints {
+    pub fn new() -> Self {
+        ShapeConstraints::default()
+    }
+
+    /// Records `symbol == value`.
+    pub fn bind(&mut self, symbol: &str, value: usize) -> Result<(), ValidationError> {
+        self.require_equal(&Dim::symbol(symbol), &Dim::Static(value))
+    }
+
+    /// Records `lhs == rhs`, failing if it contradicts what is already known.
+    /// A failed equality leaves the constraints as they were.
+    pub fn require_equal(&mut self, lhs: &Dim, rhs: &Dim) -> Result<(), ValidationError> {
+        let difference = self.resolve(lhs).to_expr() - self.resolve(rhs).to_expr();
+
+        // Solving rewrites state before re-checking pending equalities, so
+        // work on a copy and keep it only if everything still holds
+        let mut attempt = self.clone();
+        if attempt.require_zero(difference) {
+            *self = attempt;
+            Ok(())
+        } else {
+            Err(ValidationError::InvalidTensorShape {
+                expected: lhs.to_string(),
+                found: rhs.to_string(),
+            })
+        }
+    }
+
+    /// Requires two shapes to have the same rank and pairwise equal dimensions.
+    pub fn unify_shapes(
+        &mut self,
+        lhs: &TensorShape,
+        rhs: &TensorShape,
+    ) -> Result<(), ValidationError> {
+        let mismatch = || ValidationError::InvalidTensorShape {
+            expected: lhs.to_string(),
+            found: rhs.to_string(),
+        };
+
+        if lhs.rank() != rhs.rank() {
+            return Err(mismatch());
+        }
+
+        for (l, r) in lhs.dims().iter().zip(rhs.dims()) {
+            self.require_equal(l, r).map_err(|_| mismatch())?;
+        }
+
+        Ok(())
+    }
+
+    /// `dim` with every solved symbol substituted.
+    pub fn resolve(&self, dim: &Dim) -> Dim {
+        match dim {
+            Dim::Static(_) => dim.clone(),
+            Dim::Symbolic(expr) => Dim::from_expr(self.resolve_expr(expr)),
+        }
+    }
+
+    pub fn resolve_shape(&self, shape: &TensorShape) -> TensorShape {
+        TensorShape::from_dims(shape.dims().iter().map(|dim| self.resolve(dim)).collect())
+    }
+
+    /// True when the two dimensions are provably equal under what is known.
+    pub fn are_equal(&self, lhs: &Dim, rhs: &Dim) -> bool {
+        self.resolve(lhs) == self.resolve(rhs)
+    }
+
+    pub fn value_of(&self, symbol: &str) -> Option<usize> {
+        self.resolve(&Dim::symbol(symbol)).as_static()
+    }
+
+    /// Every symbol that has been solved, with what it resolves to.
+    pub fn solutions(&self) -> impl Iterator<Item = (&str, Dim)> {
+        self.solved
+            .iter()
+            .map(|(name, expr)| (name.as_str(), Dim::from_expr(expr.clone())))
+    }
+
+    /// Symbols mentioned by equalities that could not be solved yet.
+    pub fn unresolved_symbols(&self) -> BTreeSet<String> {
+        self.pending
+            .iter()
+            .flat_map(|expr| expr.symbols().map(|name| name.to_string()))
+            .collect()
+    }
+
+    fn resolve_expr(&self, expr: &DimExpr) -> DimExpr {
+        let mut resolved = expr.clone();
+        for name in expr.symbols() {
+            if let Some(solution) = self.solved.get(name) {
+                resolved = resolved.substitute(name, solution);
+            }
+        }
+        resolved
+    }
+
+    /// Records `expr == 0` for an already resolved expression.
+    fn require_zero(&mut self, expr: DimExpr) -> bool {
+        let expr = self.resolve_expr(&expr);
+
+        if expr.is_constant() {
+            return expr.constant == 0;
+        }
+
+        // Prefer a symbol with a unit coefficient: it can always be solved exactly.
+        if let Some((name, coefficient)) = expr
+            .terms
+            .iter()
+            .find(|(_, coefficient)| coefficient.abs() == 1)
+            .map(|(name, coefficient)| (name.clone(), *coefficient))
+        {
+            let mut rest = expr.clone();
+            rest.terms.remove(&name);
+            // coefficient * name + rest == 0  =>  name == -rest / coefficient
+            let solution = rest.scale(-coefficient);
+            return self.solve(name, solution);
+        }
+
+        if expr.terms.len() == 1 {
+            let (name, coefficient) = expr
+                .terms
+                .iter()
+                .next()
+                .map(|(name, coefficient)| (name.clone(), *coefficient))
+                .unwrap_or_default();
+            if expr.constant % coefficient != 0 {
+                return false;
+            }
+            return self.solve(name, DimExpr::constant(-expr.constant / coefficient));
+        }
+
+        self.pending.push(expr);
+        true
+    }
+
+    fn solve(&mut self, name: String, solution: DimExpr) -> bool {
+        if solution.is_constant() && solution.constant < 0 {
+            return false;
+        }
+
+        for expr in self.solved.values_mut() {
+            *expr = expr.substitute(&name, &solution);
+        }
+        self.solved.insert(name, solution);
+
+        // Earlier unsolvable equalities may now be solvable or contradictory
+        let pending = std::mem::take(&mut self.pending);
+        pending.into_iter().all(|expr| self.require_zero(expr))
+    }
+}
+
+impl fmt::Display for Dim {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Dim::Static(value) => write!(f, "{value}"),
+            Dim::Symbolic(expr) => write!(f, "{expr}"),
+        }
+    }
+}
+
+impl fmt::Display for DimExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut first = true;
+
+        for (name, coefficient) in &self.terms {
+            let magnitude = coefficient.abs();
+            let sign = if *coefficient < 0 { "-" } else { "+" };
+            if first {
+                if *coefficient < 0 {
+                    write!(f, "-")?;
+                }
+            } else {
+                write!(f, " {sign} ")?;
+            }
+            if magnitude == 1 {
+                write!(f, "{name}")?;
+            } else {
+                write!(f, "{magnitude}*{name}")?;
+            }
+            first = false;
+        }
+
+        if first {
+            write!(f, "{}", self.constant)
+        } else if self.constant > 0 {
+            write!(f, " + {}", self.constant)
+        } else if self.constant < 0 {
+            write!(f, " - {}", -self.constant)
+        } else {
+            Ok(())
+        }
+    }
+}
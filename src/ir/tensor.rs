@@ -0,0 +1,177 @@
+use crate::ir::{
+    errors::ValidationError,
+    types::{DType, TensorShape, TensorType},
+};
+
+/// A constant tensor such as a weight: its type plus the element data, densely
+/// packed in row-major order with every element little-endian.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tensor {
+    tensor_type: TensorType,
+    data: Vec<u8>,
+}
+
+impl Tensor {
+    /// Wraps raw element bytes, which must fill a static shape exactly.
+    pub fn new(tensor_type: TensorType, data: Vec<u8>) -> Result<Self, ValidationError> {
+        let expected =
+            tensor_type
+                .size_in_bytes()
+                .ok_or_else(|| ValidationError::InvalidTensorShape {
+                    expected: "static shape of addressable size for constant data".to_string(),
+                    found: tensor_type.shape().to_string(),
+                })?;
+
+        if data.len() != expected {
+            return Err(ValidationError::InvalidTensorShape {
+                expected: format!("{expected} bytes of data for {tensor_type}"),
+                found: format!("{} bytes", data.len()),
+            });
+        }
+
+        Ok(Tensor { tensor_type, data })
+    }
+
+    pub fn from_f32(shape: TensorShape, values: &[f32]) -> Result<Self, ValidationError> {
+        let data = values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        Tensor::new(TensorType::new(DType::F32, shape), data)
+    }
+
+    pub fn from_i32(shape: TensorShape, values: &[i32]) -> Result<Self, ValidationError> {
+        let data = values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        Tensor::new(TensorType::new(DType::I32, shape), data)
+    }
+
+    pub fn scalar_f32(value: f32) -> Self {
+        Tensor {
+            tensor_type: TensorType::new(DType::F32, TensorShape::new(vec![])),
+            data: value.to_le_bytes().to_vec(),
+        }
+    }
+
+    pub fn tensor_type(&self) -> &TensorType {
+        &self.tensor_type
+    }
+
+    pub fn dtype(&self) -> DType {
+        self.tensor_type.dtype()
+    }
+
+    pub fn shape(&self) -> &TensorShape {
+        self.tensor_type.shape()
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Number of elements.
+    pub fn len(&self) -> usize {
+        self.data.len() / self.dtype().size_in_bytes()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Every element widened to f32, whatever the stored type. Integers beyond
+    /// 2^24 lose precision; booleans become 0 or 1.
+    pub fn to_f32_vec(&self) -> Vec<f32> {
+        let width = self.dtype().size_in_bytes();
+        self.data
+            .chunks_exact(width)
+            .map(|bytes| match self.dtype() {
+                DType::F32 => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+                DType::F16 => f16_to_f32(u16::from_le_bytes([bytes[0], bytes[1]])),
+                DType::BF16 => {
+                    f32::from_bits((u16::from_le_bytes([bytes[0], bytes[1]]) as u32) << 16)
+                }
+                DType::I32 => i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f32,
+                DType::I8 => bytes[0] as i8 as f32,
+                DType::Bool => (bytes[0] != 0) as u8 as f32,
+            })
+            .collect()
+    }
+
+    /// Elements as integers, for index and shape tensors. `None` for floating
+    /// point data.
+    pub fn to_i64_vec(&self) -> Option<Vec<i64>> {
+        let width = self.dtype().size_in_bytes();
+        let values = self.data.chunks_exact(width);
+        match self.dtype() {
+            DType::I32 => Some(
+                values
+                    .map(|bytes| {
+                        i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as i64
+                    })
+                    .collect(),
+            ),
+            DType::I8 => Some(values.map(|bytes| bytes[0] as i8 as i64).collect()),
+            DType::Bool => Some(values.map(|bytes| (bytes[0] != 0) as i64).collect()),
+            DType::F32 | DType::F16 | DType::BF16 => None,
+        }
+    }
+}
+
+/// IEEE 754 half precision to single precision, exact for every input.
+pub fn f16_to_f32(bits: u16) -> f32 {
+    let negative = bits & 0x8000 != 0;
+    let exponent = ((bits >> 10) & 0x1f) as u32;
+    let mantissa = (bits & 0x3ff) as u32;
+
+    let magnitude = match exponent {
+        // Zero and subnormals: mantissa * 2^-24
+        0 => mantissa as f32 / 16_777_216.0,
+        // Infinity and NaN
+        0x1f => f32::from_bits(0x7f80_0000 | (mantissa << 13)),
+        _ => f32::from_bits(((exponent + 112) << 23) | (mantissa << 13)),
+    };
+
+    if negative { -magnitude } else { magnitude }
+}
+
+/// Single precision to IEEE 754 half precision, rounding to the nearest value
+/// with ties to even. Magnitudes beyond the half range become infinity.
+pub fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+
+    if exponent == 0xff {
+        // Infinity, or NaN with a quiet bit so it stays NaN
+        let nan = if mantissa != 0 { 0x200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+    if exponent < -10 {
+        return sign;
+    }
+    // Subnormal halves make the implicit leading bit explicit
+    let (significand, shift) = if exponent <= 0 {
+        (mantissa | 0x80_0000, (14 - exponent) as u32)
+    } else {
+        (((exponent as u32) << 23) | mantissa, 13)
+    };
+
+    let half = significand >> shift;
+    let remainder = significand & ((1 << shift) - 1);
+    let halfway = 1 << (shift - 1);
+    // A carry out of the mantissa moves into the exponent, up to infinity
+    let rounded = if remainder > halfway || (remainder == halfway && half & 1 == 1) {
+        half + 1
+    } else {
+        half
+    };
+    sign | rounded as u16
+}
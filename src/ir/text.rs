@@ -0,0 +1,1105 @@
+use std::{collections::HashMap, fmt::Write};
+
+use crate::ir::{
+    attributes::{AttributeValue, Attributes},
+    errors::{ParsingError, ValidationError},
+    graph::Graph,
+    layout::TensorLayout,
+    ops::Node,
+    symbolic::{Dim, DimExpr},
+    tensor::Tensor,
+    types::{DType, NodeID, OpKind, TensorShape, TensorType, ValueRef},
+};
+
+/// Renders a graph in the `.xir` text format, one statement per node in
+/// insertion order followed by the graph outputs:
+///
+/// ```text
+/// graph {
+///   %0 = input "x" : f32[batch, 4]
+///   %1 = constant "w" : f32[4, 2] = dense<[0.5, 0.5, 0.5, 0.5, 0.5, 0.5, 0.5, 0.5]>
+///   %2 = MatMul(%0, %1) {transpose_a = 0, transpose_b = 0} : f32[batch, 2]
+///   %3 = Split(%2) {axis = 1, sizes = [1, 1]} : (f32[batch, 1], f32[batch, 1])
+///   %4 = custom "Scale"(%3#1) {factor = 2.0}
+///   output "y" = %4
+/// }
+/// ```
+///
+/// Values are named after node IDs and `#n` selects output `n`. Every
+/// attribute is written out, floats always carry a decimal point or exponent,
+/// and constant data is written as a list when that reproduces its bytes and
+/// as little-endian hex otherwise, so `parse_graph` rebuilds the same graph.
+pub fn print_graph(graph: &Graph) -> String {
+    let mut text = String::from("graph {\n");
+
+    for node in graph.nodes() {
+        text.push_str("  ");
+        print_node(&mut text, graph, node);
+        text.push('\n');
+    }
+    for output in graph.outputs() {
+        let _ = writeln!(
+            text,
+            "  output {} = {}",
+            quote(output.name()),
+            value_name(graph, output.value())
+        );
+    }
+
+    text.push_str("}\n");
+    text
+}
+
+/// Parses the `.xir` text format written by `print_graph`. Value names may
+/// be any `%` identifier and `//` starts a comment. Errors are reported as
+/// `ParsingError::InvalidFormat` with the line and column of the offending
+/// token.
+pub fn parse_graph(source: &str) -> Result<Graph, ParsingError> {
+    let tokens = tokenize(source)?;
+    let statements = Parser { tokens, next: 0 }.parse_graph()?;
+    build_graph(statements)
+}
+
+/// Parses a single dimension as written inside a `.xir` shape, such as `4`
+/// or `2*seq - 1`.
+pub(crate) fn parse_dim(source: &str) -> Result<Dim, ParsingError> {
+    let mut parser = Parser {
+        tokens: tokenize(source)?,
+        next: 0,
+    };
+    let dim = parser.parse_dim()?;
+    if parser.peek().kind != TokenKind::End {
+        return Err(parser.error_here("end of dimension"));
+    }
+    Ok(dim)
+}
+
+fn print_node(text: &mut String, graph: &Graph, node: &Node) {
+    let _ = write!(text, "{} = ", value_name(graph, node.id().into()));
+
+    match (node.op(), node.output_types().as_slice()) {
+        (OpKind::Input { name }, [input_type]) if graph.inputs().contains(&node.id()) => {
+            let _ = write!(text, "input {} : {}", quote(name), print_type(input_type));
+            return;
+        }
+        (OpKind::Constant { name }, _) if graph.initializer(node.id()).is_some() => {
+            let tensor = graph.initializer(node.id()).expect("checked above");
+            let _ = write!(
+                text,
+                "constant {} : {} = dense<{}>",
+                quote(name),
+                print_type(tensor.tensor_type()),
+                print_data(tensor)
+            );
+            return;
+        }
+        (OpKind::Custom { name, .. }, _) => {
+            let _ = write!(text, "custom {}", quote(name));
+        }
+        (op, _) => text.push_str(op.name()),
+    }
+
+    let inputs: Vec<String> = node
+        .inputs()
+        .iter()
+        .map(|input| value_name(graph, *input))
+        .collect();
+    let _ = write!(text, "({})", inputs.join(", "));
+
+    let attributes = node.op().attributes();
+    if !attributes.is_empty() {
+        let attributes: Vec<String> = attributes
+            .iter()
+            .map(|(name, value)| format!("{name} = {}", print_attribute(value)))
+            .collect();
+        let _ = write!(text, " {{{}}}", attributes.join(", "));
+    }
+
+    match node.output_types().as_slice() {
+        [] => {}
+        [output_type] => {
+            let _ = write!(text, " : {}", print_type(output_type));
+        }
+        output_types => {
+            let types: Vec<String> = output_types.iter().map(print_type).collect();
+            let _ = write!(text, " : ({})", types.join(", "));
+        }
+    }
+}
+
+/// `%id`, `%id#port` for outputs past the first. References to nodes that no
+/// longer exist get a name no statement defines, so they fail to parse
+/// rather than silently pointing at another node.
+fn value_name(graph: &Graph, value: ValueRef) -> String {
+    let node = if graph.contains_node(value.node()) {
+        format!("%{}", value.node().id())
+    } else {
+        format!("%missing_{}", value.node().id())
+    };
+    match value.port() {
+        0 => node,
+        port => format!("{node}#{port}"),
+    }
+}
+
+pub(crate) fn print_type(tensor_type: &TensorType) -> String {
+    match tensor_type.layout() {
+        None => tensor_type.to_string(),
+        Some(layout) => format!(
+            "{tensor_type} layout<{}, {}>",
+            print_list(layout.strides()),
+            layout.offset()
+        ),
+    }
+}
+
+pub(crate) fn print_attribute(value: &AttributeValue) -> String {
+    match value {
+        AttributeValue::Int(value) => value.to_string(),
+        AttributeValue::Float(value) => format!("{value:?}"),
+        AttributeValue::String(value) => quote(value),
+        AttributeValue::Ints(values) => print_list(values),
+        AttributeValue::Floats(values) if values.is_empty() => "floats[]".to_string(),
+        AttributeValue::Floats(values) => {
+            let values: Vec<String> = values.iter().map(|value| format!("{value:?}")).collect();
+            format!("[{}]", values.join(", "))
+        }
+        AttributeValue::Strings(values) if values.is_empty() => "strings[]".to_string(),
+        AttributeValue::Strings(values) => {
+            let values: Vec<String> = values.iter().map(|value| quote(value)).collect();
+            format!("[{}]", values.join(", "))
+        }
+    }
+}
+
+fn print_list<T: ToString>(values: &[T]) -> String {
+    let values: Vec<String> = values.iter().map(T::to_string).collect();
+    format!("[{}]", values.join(", "))
+}
+
+/// Element list when it encodes back to the same bytes, hex otherwise.
+fn print_data(tensor: &Tensor) -> String {
+    let list = match tensor.dtype() {
+        DType::F32 => {
+            let values: Vec<String> = tensor
+                .to_f32_vec()
+                .iter()
+                .map(|value| format!("{value:?}"))
+                .collect();
+            Some(format!("[{}]", values.join(", ")))
+        }
+        DType::I32 | DType::I8 | DType::Bool => {
+            tensor.to_i64_vec().map(|values| print_list(&values))
+        }
+        DType::F16 | DType::BF16 => None,
+    };
+
+    if let Some(list) = list
+        && encode_list(tensor.dtype(), &list_elements(&list)).as_deref() == Some(tensor.data())
+    {
+        return list;
+    }
+
+    quote(&encode_hex(tensor.data()))
+}
+
+fn list_elements(list: &str) -> Vec<String> {
+    list.trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(", ")
+        .filter(|element| !element.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Little-endian bytes of a list of element literals.
+fn encode_list(dtype: DType, elements: &[String]) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(elements.len() * dtype.size_in_bytes());
+    for element in elements {
+        match dtype {
+            DType::F32 => bytes.extend(element.parse::<f32>().ok()?.to_le_bytes()),
+            DType::I32 => bytes.extend(element.parse::<i32>().ok()?.to_le_bytes()),
+            DType::I8 => bytes.extend(element.parse::<i8>().ok()?.to_le_bytes()),
+            DType::Bool => match element.as_str() {
+                "0" => bytes.push(0),
+                "1" => bytes.push(1),
+                _ => return None,
+            },
+            DType::F16 | DType::BF16 => return None,
+        }
+    }
+    Some(bytes)
+}
+
+fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn syntax_error(line: usize, column: usize, reason: impl Into<String>) -> ParsingError {
+    ParsingError::InvalidFormat {
+        format: "xir".to_string(),
+        reason: format!("line {line}, column {column}: {}", reason.into()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    /// `%name`, without the sigil.
+    Value(String),
+    Ident(String),
+    /// Unsigned integer or float literal, kept as written so it converts to
+    /// the target type without a detour through another width.
+    Int(String),
+    Float(String),
+    Str(String),
+    Punct(char),
+    End,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    line: usize,
+    column: usize,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match &self.kind {
+            TokenKind::Value(name) => format!("'%{name}'"),
+            TokenKind::Ident(name) => format!("'{name}'"),
+            TokenKind::Int(text) | TokenKind::Float(text) => format!("'{text}'"),
+            TokenKind::Str(text) => quote(text),
+            TokenKind::Punct(c) => format!("'{c}'"),
+            TokenKind::End => "end of input".to_string(),
+        }
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '.'
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, ParsingError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let (mut i, mut line, mut column) = (0, 1, 1);
+
+    while i < chars.len() {
+        let (start_line, start_column) = (line, column);
+        let c = chars[i];
+        let start = i;
+
+        let kind = match c {
+            '\n' => {
+                i += 1;
+                line += 1;
+                column = 1;
+                continue;
+            }
+            c if c.is_whitespace() => {
+                i += 1;
+                column += 1;
+                continue;
+            }
+            '/' if chars.get(i + 1) == Some(&'/') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            '%' => {
+                i += 1;
+                while i < chars.len() && is_name_char(chars[i]) {
+                    i += 1;
+                }
+                if i == start + 1 {
+                    return Err(syntax_error(
+                        line,
+                        column,
+                        "expected a value name after '%'",
+                    ));
+                }
+                TokenKind::Value(chars[start + 1..i].iter().collect())
+            }
+            '"' => {
+                i += 1;
+                let mut text = String::new();
+                loop {
+                    match chars.get(i) {
+                        None | Some('\n') => {
+                            return Err(syntax_error(line, column, "unterminated string"));
+                        }
+                        Some('"') => break,
+                        Some('\\') => {
+                            let escaped = match chars.get(i + 1) {
+                                Some('"') => '"',
+                                Some('\\') => '\\',
+                                Some('n') => '\n',
+                                Some('t') => '\t',
+                                _ => {
+                                    return Err(syntax_error(
+                                        line,
+                                        column + (i - start),
+                                        "unknown escape in string",
+                                    ));
+                                }
+                            };
+                            text.push(escaped);
+                            i += 2;
+                        }
+                        Some(c) => {
+                            text.push(*c);
+                            i += 1;
+                        }
+                    }
+                }
+                i += 1;
+                TokenKind::Str(text)
+            }
+            c if c.is_ascii_digit() => {
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+                let mut float = false;
+                if chars.get(i) == Some(&'.') && chars.get(i + 1).is_some_and(char::is_ascii_digit)
+                {
+                    float = true;
+                    i += 1;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+                if matches!(chars.get(i), Some('e' | 'E')) {
+                    let mut exponent = i + 1;
+                    if matches!(chars.get(exponent), Some('+' | '-')) {
+                        exponent += 1;
+                    }
+                    if chars.get(exponent).is_some_and(char::is_ascii_digit) {
+                        float = true;
+                        i = exponent;
+                        while i < chars.len() && chars[i].is_ascii_digit() {
+                            i += 1;
+                        }
+                    }
+                }
+                let text: String = chars[start..i].iter().collect();
+                if float {
+                    TokenKind::Float(text)
+                } else {
+                    TokenKind::Int(text)
+                }
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                while i < chars.len() && is_name_char(chars[i]) {
+                    i += 1;
+                }
+                TokenKind::Ident(chars[start..i].iter().collect())
+            }
+            '=' | '(' | ')' | '{' | '}' | '[' | ']' | ',' | ':' | '<' | '>' | '*' | '+' | '-'
+            | '#' => {
+                i += 1;
+                TokenKind::Punct(c)
+            }
+            c => {
+                return Err(syntax_error(
+                    line,
+                    column,
+                    format!("unexpected character '{c}'"),
+                ));
+            }
+        };
+
+        column += i - start;
+        tokens.push(Token {
+            kind,
+            line: start_line,
+            column: start_column,
+        });
+    }
+
+    tokens.push(Token {
+        kind: TokenKind::End,
+        line,
+        column,
+    });
+    Ok(tokens)
+}
+
+/// A value reference as written, with the position of its name.
+struct Use {
+    name: String,
+    port: usize,
+    line: usize,
+    column: usize,
+}
+
+enum Definition {
+    Input {
+        name: String,
+        input_type: TensorType,
+    },
+    Constant {
+        name: String,
+        tensor: Tensor,
+    },
+    Op {
+        op: OpKind,
+        inputs: Vec<Use>,
+        output_types: Vec<TensorType>,
+    },
+}
+
+enum Statement {
+    Node {
+        result: String,
+        definition: Definition,
+        line: usize,
+        column: usize,
+    },
+    Output {
+        name: String,
+        value: Use,
+    },
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    next: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.next]
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.next].clone();
+        if token.kind != TokenKind::End {
+            self.next += 1;
+        }
+        token
+    }
+
+    fn error_here(&self, expected: &str) -> ParsingError {
+        let token = self.peek();
+        syntax_error(
+            token.line,
+            token.column,
+            format!("expected {expected}, found {}", token.describe()),
+        )
+    }
+
+    fn at_punct(&self, c: char) -> bool {
+        self.peek().kind == TokenKind::Punct(c)
+    }
+
+    fn at_ident(&self, name: &str) -> bool {
+        matches!(&self.peek().kind, TokenKind::Ident(ident) if ident == name)
+    }
+
+    fn expect_punct(&mut self, c: char) -> Result<Token, ParsingError> {
+        if self.at_punct(c) {
+            Ok(self.advance())
+        } else {
+            Err(self.error_here(&format!("'{c}'")))
+        }
+    }
+
+    fn expect_ident(&mut self, name: &str) -> Result<(), ParsingError> {
+        if self.at_ident(name) {
+            self.advance();
+            Ok(())
+        } else {
+            Err(self.error_here(&format!("'{name}'")))
+        }
+    }
+
+    fn expect_string(&mut self) -> Result<String, ParsingError> {
+        match self.peek().kind.clone() {
+            TokenKind::Str(text) => {
+                self.advance();
+                Ok(text)
+            }
+            _ => Err(self.error_here("a string")),
+        }
+    }
+
+    fn expect_usize(&mut self) -> Result<usize, ParsingError> {
+        let token = self.peek().clone();
+        match &token.kind {
+            TokenKind::Int(text) => {
+                self.advance();
+                text.parse().map_err(|_| {
+                    syntax_error(
+                        token.line,
+                        token.column,
+                        format!("'{text}' is out of range"),
+                    )
+                })
+            }
+            _ => Err(self.error_here("a non-negative integer")),
+        }
+    }
+
+    fn parse_graph(mut self) -> Result<Vec<Statement>, ParsingError> {
+        self.expect_ident("graph")?;
+        self.expect_punct('{')?;
+
+        let mut statements = Vec::new();
+        while !self.at_punct('}') {
+            statements.push(self.parse_statement()?);
+        }
+        self.expect_punct('}')?;
+
+        if self.peek().kind != TokenKind::End {
+            return Err(self.error_here("end of input"));
+        }
+        Ok(statements)
+    }
+
+    fn parse_statement(&mut self) -> Result<Statement, ParsingError> {
+        if self.at_ident("output") {
+            self.advance();
+            let name = self.expect_string()?;
+            self.expect_punct('=')?;
+            let value = self.parse_use()?;
+            return Ok(Statement::Output { name, value });
+        }
+
+        let token = self.peek().clone();
+        let TokenKind::Value(result) = token.kind else {
+            return Err(self.error_here("a value definition or 'output'"));
+        };
+        self.advance();
+        self.expect_punct('=')?;
+
+        let definition = if self.at_ident("input") {
+            self.advance();
+            let name = self.expect_string()?;
+            self.expect_punct(':')?;
+            let input_type = self.parse_type()?;
+            Definition::Input { name, input_type }
+        } else if self.at_ident("constant") {
+            self.advance();
+            let name = self.expect_string()?;
+            self.expect_punct(':')?;
+            let tensor = self.parse_constant()?;
+            Definition::Constant { name, tensor }
+        } else {
+            self.parse_op()?
+        };
+
+        Ok(Statement::Node {
+            result,
+            definition,
+            line: token.line,
+            column: token.column,
+        })
+    }
+
+    fn parse_use(&mut self) -> Result<Use, ParsingError> {
+        let token = self.peek().clone();
+        let TokenKind::Value(name) = token.kind else {
+            return Err(self.error_here("a value"));
+        };
+        self.advance();
+
+        let port = if self.at_punct('#') {
+            self.advance();
+            self.expect_usize()?
+        } else {
+            0
+        };
+        Ok(Use {
+            name,
+            port,
+            line: token.line,
+            column: token.column,
+        })
+    }
+
+    fn parse_op(&mut self) -> Result<Definition, ParsingError> {
+        let token = self.peek().clone();
+        let custom_name = if self.at_ident("custom") {
+            self.advance();
+            Some(self.expect_string()?)
+        } else if let TokenKind::Ident(_) = token.kind {
+            self.advance();
+            None
+        } else {
+            return Err(self.error_here("an op name"));
+        };
+
+        self.expect_punct('(')?;
+        let mut inputs = Vec::new();
+        while !self.at_punct(')') {
+            if !inputs.is_empty() {
+                self.expect_punct(',')?;
+            }
+            inputs.push(self.parse_use()?);
+        }
+        self.expect_punct(')')?;
+
+        let attributes = if self.at_punct('{') {
+            self.parse_attributes()?
+        } else {
+            Attributes::new()
+        };
+
+        let op = match (custom_name, &token.kind) {
+            (Some(name), _) => OpKind::Custom { name, attributes },
+            (None, TokenKind::Ident(name)) => {
+                let op = OpKind::from_parts(name, &attributes).map_err(|error| {
+                    let reason = match error {
+                        ParsingError::InvalidFormat { reason, .. } => reason,
+                        other => format!("{other:?}"),
+                    };
+                    syntax_error(token.line, token.column, reason)
+                })?;
+                if matches!(op, OpKind::Custom { .. }) {
+                    return Err(syntax_error(
+                        token.line,
+                        token.column,
+                        format!("unknown op '{name}', write custom ops as custom \"{name}\""),
+                    ));
+                }
+                op
+            }
+            (None, _) => unreachable!("checked above"),
+        };
+
+        let output_types = if self.at_punct(':') {
+            self.advance();
+            if self.at_punct('(') {
+                self.advance();
+                let mut types = vec![self.parse_type()?];
+                while self.at_punct(',') {
+                    self.advance();
+                    types.push(self.parse_type()?);
+                }
+                self.expect_punct(')')?;
+                types
+            } else {
+                vec![self.parse_type()?]
+            }
+        } else {
+            Vec::new()
+        };
+
+        Ok(Definition::Op {
+            op,
+            inputs,
+            output_types,
+        })
+    }
+
+    fn parse_attributes(&mut self) -> Result<Attributes, ParsingError> {
+        self.expect_punct('{')?;
+        let mut attributes = Attributes::new();
+        while !self.at_punct('}') {
+            if !attributes.is_empty() {
+                self.expect_punct(',')?;
+            }
+            let token = self.peek().clone();
+            let TokenKind::Ident(name) = token.kind else {
+                return Err(self.error_here("an attribute name"));
+            };
+            self.advance();
+            if attributes.contains(&name) {
+                return Err(syntax_error(
+                    token.line,
+                    token.column,
+                    format!("attribute '{name}' is given twice"),
+                ));
+            }
+            self.expect_punct('=')?;
+            let value = self.parse_attribute_value()?;
+            attributes.insert(name, value);
+        }
+        self.expect_punct('}')?;
+        Ok(attributes)
+    }
+
+    fn parse_attribute_value(&mut self) -> Result<AttributeValue, ParsingError> {
+        if self.at_ident("floats") || self.at_ident("strings") {
+            let floats = self.at_ident("floats");
+            self.advance();
+            self.expect_punct('[')?;
+            self.expect_punct(']')?;
+            return Ok(if floats {
+                AttributeValue::Floats(Vec::new())
+            } else {
+                AttributeValue::Strings(Vec::new())
+            });
+        }
+
+        if let TokenKind::Str(text) = self.peek().kind.clone() {
+            self.advance();
+            return Ok(AttributeValue::String(text));
+        }
+
+        if self.at_punct('[') {
+            self.advance();
+            let mut elements = Vec::new();
+            while !self.at_punct(']') {
+                if !elements.is_empty() {
+                    self.expect_punct(',')?;
+                }
+                let token = self.peek().clone();
+                let element = match &token.kind {
+                    TokenKind::Str(text) => {
+                        self.advance();
+                        AttributeValue::String(text.clone())
+                    }
+                    _ => self.parse_number()?,
+                };
+                elements.push((token, element));
+            }
+            self.expect_punct(']')?;
+            return list_value(elements);
+        }
+
+        self.parse_number()
+    }
+
+    /// Int or float literal with an optional sign; `NaN` and `inf` are floats.
+    fn parse_number(&mut self) -> Result<AttributeValue, ParsingError> {
+        let negative = if self.at_punct('-') {
+            self.advance();
+            true
+        } else {
+            false
+        };
+
+        let token = self.peek().clone();
+        let sign = if negative { "-" } else { "" };
+        let out_of_range = |text: &str| {
+            syntax_error(
+                token.line,
+                token.column,
+                format!("'{text}' is out of range"),
+            )
+        };
+        let value = match &token.kind {
+            TokenKind::Int(text) => AttributeValue::Int(
+                format!("{sign}{text}")
+                    .parse()
+                    .map_err(|_| out_of_range(text))?,
+            ),
+            TokenKind::Float(text) => AttributeValue::Float(
+                format!("{sign}{text}")
+                    .parse()
+                    .map_err(|_| out_of_range(text))?,
+            ),
+            TokenKind::Ident(name) if name == "NaN" || name == "inf" => {
+                AttributeValue::Float(format!("{sign}{name}").parse().expect("valid float"))
+            }
+            _ => return Err(self.error_here("a number")),
+        };
+        self.advance();
+        Ok(value)
+    }
+
+    fn parse_type(&mut self) -> Result<TensorType, ParsingError> {
+        let token = self.peek().clone();
+        let TokenKind::Ident(name) = &token.kind else {
+            return Err(self.error_here("an element type"));
+        };
+        let dtype = DType::from_name(name).ok_or_else(|| {
+            syntax_error(
+                token.line,
+                token.column,
+                format!("unknown element type '{name}'"),
+            )
+        })?;
+        self.advance();
+
+        self.expect_punct('[')?;
+        let mut dims = Vec::new();
+        while !self.at_punct(']') {
+            if !dims.is_empty() {
+                self.expect_punct(',')?;
+            }
+            dims.push(self.parse_dim()?);
+        }
+        self.expect_punct(']')?;
+        let tensor_type = TensorType::new(dtype, TensorShape::from_dims(dims));
+
+        if !self.at_ident("layout") {
+            return Ok(tensor_type);
+        }
+        let token = self.advance();
+        self.expect_punct('<')?;
+        self.expect_punct('[')?;
+        let mut strides = Vec::new();
+        while !self.at_punct(']') {
+            if !strides.is_empty() {
+                self.expect_punct(',')?;
+            }
+            strides.push(self.expect_usize()?);
+        }
+        self.expect_punct(']')?;
+        self.expect_punct(',')?;
+        let offset = self.expect_usize()?;
+        self.expect_punct('>')?;
+
+        tensor_type
+            .with_layout(TensorLayout::strided(strides, offset))
+            .map_err(|error| syntax_error(token.line, token.column, validation_reason(error)))
+    }
+
+    /// Affine dimension such as `4`, `batch` or `2*seq - 1`.
+    fn parse_dim(&mut self) -> Result<Dim, ParsingError> {
+        let mut expr = DimExpr::constant(0);
+        let mut negative = if self.at_punct('-') {
+            self.advance();
+            true
+        } else {
+            false
+        };
+
+        loop {
+            let token = self.peek().clone();
+            let term = match &token.kind {
+                TokenKind::Int(text) => {
+                    self.advance();
+                    let value: i64 = text.parse().map_err(|_| {
+                        syntax_error(
+                            token.line,
+                            token.column,
+                            format!("'{text}' is out of range"),
+                        )
+                    })?;
+                    if self.at_punct('*') {
+                        self.advance();
+                        let symbol = self.expect_symbol()?;
+                        DimExpr::symbol(symbol).scale(value)
+                    } else {
+                        DimExpr::constant(value)
+                    }
+                }
+                TokenKind::Ident(_) => DimExpr::symbol(self.expect_symbol()?),
+                _ => return Err(self.error_here("a dimension")),
+            };
+            expr = if negative { expr - term } else { expr + term };
+
+            if self.at_punct('+') || self.at_punct('-') {
+                negative = self.at_punct('-');
+                self.advance();
+            } else {
+                return Ok(Dim::from_expr(expr));
+            }
+        }
+    }
+
+    fn expect_symbol(&mut self) -> Result<String, ParsingError> {
+        match self.peek().kind.clone() {
+            TokenKind::Ident(name) => {
+                self.advance();
+                Ok(name)
+            }
+            _ => Err(self.error_here("a symbol")),
+        }
+    }
+
+    /// `<type> = dense<[elements]>` or `<type> = dense<"0x...">`.
+    fn parse_constant(&mut self) -> Result<Tensor, ParsingError> {
+        let tensor_type = self.parse_type()?;
+        self.expect_punct('=')?;
+        self.expect_ident("dense")?;
+        let open = self.expect_punct('<')?;
+
+        let data = if let TokenKind::Str(text) = self.peek().kind.clone() {
+            let token = self.advance();
+            decode_hex(&text)
+                .ok_or_else(|| syntax_error(token.line, token.column, "malformed hex data"))?
+        } else {
+            self.expect_punct('[')?;
+            let mut elements = Vec::new();
+            while !self.at_punct(']') {
+                if !elements.is_empty() {
+                    self.expect_punct(',')?;
+                }
+                let literal = match self.parse_number()? {
+                    AttributeValue::Int(value) => value.to_string(),
+                    AttributeValue::Float(value) => format!("{value:?}"),
+                    _ => unreachable!("parse_number only yields numbers"),
+                };
+                elements.push(literal);
+            }
+            self.expect_punct(']')?;
+            encode_list(tensor_type.dtype(), &elements).ok_or_else(|| {
+                syntax_error(
+                    open.line,
+                    open.column,
+                    format!(
+                        "elements do not fit {}; write {} data as hex",
+                        tensor_type.dtype(),
+                        tensor_type.dtype()
+                    ),
+                )
+            })?
+        };
+        self.expect_punct('>')?;
+
+        Tensor::new(tensor_type, data)
+            .map_err(|error| syntax_error(open.line, open.column, validation_reason(error)))
+    }
+}
+
+/// A list attribute whose elements all have the same kind.
+fn list_value(elements: Vec<(Token, AttributeValue)>) -> Result<AttributeValue, ParsingError> {
+    let Some((_, first)) = elements.first() else {
+        return Ok(AttributeValue::Ints(Vec::new()));
+    };
+    let kind = first.kind_name();
+    if let Some((token, element)) = elements
+        .iter()
+        .find(|(_, element)| element.kind_name() != kind)
+    {
+        return Err(syntax_error(
+            token.line,
+            token.column,
+            format!("list mixes {kind} and {} elements", element.kind_name()),
+        ));
+    }
+
+    let elements = elements.into_iter().map(|(_, element)| element);
+    Ok(match kind {
+        "int" => AttributeValue::Ints(
+            elements
+                .filter_map(|element| match element {
+                    AttributeValue::Int(value) => Some(value),
+                    _ => None,
+                })
+                .collect(),
+        ),
+        "float" => AttributeValue::Floats(
+            elements
+                .filter_map(|element| match element {
+                    AttributeValue::Float(value) => Some(value),
+                    _ => None,
+                })
+                .collect(),
+        ),
+        _ => AttributeValue::Strings(
+            elements
+                .filter_map(|element| match element {
+                    AttributeValue::String(value) => Some(value),
+                    _ => None,
+                })
+                .collect(),
+        ),
+    })
+}
+
+/// `0x` followed by two lowercase hex digits per byte.
+pub(crate) fn encode_hex(bytes: &[u8]) -> String {
+    let digits: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+    format!("0x{digits}")
+}
+
+pub(crate) fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    let digits = text.strip_prefix("0x")?;
+    if digits.len() % 2 != 0 {
+        return None;
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(digits.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn validation_reason(error: ValidationError) -> String {
+    error.to_string().trim_end_matches('.').to_string()
+}
+
+/// Creates every node first so inputs may refer forwards, then wires inputs
+/// and graph outputs by name.
+fn build_graph(statements: Vec<Statement>) -> Result<Graph, ParsingError> {
+    let mut graph = Graph::new();
+    let mut names: HashMap<String, NodeID> = HashMap::new();
+    let mut wiring = Vec::new();
+    let mut outputs = Vec::new();
+
+    for statement in statements {
+        let (result, definition, line, column) = match statement {
+            Statement::Node {
+                result,
+                definition,
+                line,
+                column,
+            } => (result, definition, line, column),
+            Statement::Output { name, value } => {
+                outputs.push((name, value));
+                continue;
+            }
+        };
+        if names.contains_key(&result) {
+            return Err(syntax_error(
+                line,
+                column,
+                format!("value '%{result}' is defined twice"),
+            ));
+        }
+
+        let node_id = match definition {
+            Definition::Input { name, input_type } => graph.add_input(name, input_type),
+            Definition::Constant { name, tensor } => graph.add_initializer(name, tensor),
+            Definition::Op {
+                op,
+                inputs,
+                output_types,
+            } => {
+                let node_id = graph.add_node_with_values(op, vec![]);
+                graph
+                    .set_output_types(node_id, output_types)
+                    .expect("node was just added");
+                wiring.push((node_id, inputs));
+                node_id
+            }
+        };
+        names.insert(result, node_id);
+    }
+
+    let resolve = |value: &Use| {
+        names
+            .get(&value.name)
+            .map(|node_id| node_id.output(value.port))
+            .ok_or_else(|| {
+                syntax_error(
+                    value.line,
+                    value.column,
+                    format!("value '%{}' is not defined", value.name),
+                )
+            })
+    };
+
+    for (node_id, inputs) in wiring {
+        let inputs = inputs.iter().map(resolve).collect::<Result<Vec<_>, _>>()?;
+        graph
+            .set_inputs(node_id, inputs)
+            .expect("every name resolves to a node");
+    }
+    for (name, value) in outputs {
+        let resolved = resolve(&value)?;
+        graph
+            .add_output(name, resolved)
+            .expect("every name resolves to a node");
+    }
+
+    Ok(graph)
+}
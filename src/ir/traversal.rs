@@ -0,0 +1,174 @@
+use std::collections::{HashSet, VecDeque};
+
+use crate::ir::{graph::Graph, ops::Node, types::NodeID};
+
+/// Which edges a walk follows from each node.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Towards consumers, in ascending node ID order.
+    Successors,
+    /// Towards producers, in input order.
+    Predecessors,
+}
+
+/// Breadth-first walk from a start node, yielding each reachable node once,
+/// the start included.
+pub struct Bfs<'a> {
+    graph: &'a Graph,
+    direction: Direction,
+    queue: VecDeque<NodeID>,
+    seen: HashSet<NodeID>,
+}
+
+/// Depth-first walk from a start node in pre-order, yielding each reachable
+/// node once, the start included. Neighbours are visited in the same order as
+/// `Bfs` visits them.
+pub struct Dfs<'a> {
+    graph: &'a Graph,
+    direction: Direction,
+    stack: Vec<NodeID>,
+    seen: HashSet<NodeID>,
+}
+
+impl Graph {
+    /// Every node in the order it was added.
+    pub fn nodes(&self) -> impl Iterator<Item = &Node> + '_ {
+        self.node_ids()
+            .into_iter()
+            .filter_map(|node_id| self.get_node(node_id))
+    }
+
+    /// Distinct nodes `node_id` reads from, in the order of its inputs.
+    pub fn predecessors(&self, node_id: NodeID) -> Vec<NodeID> {
+        let mut predecessors = Vec::new();
+        if let Some(node) = self.get_node(node_id) {
+            for input in node.input_nodes() {
+                if self.contains_node(input) && !predecessors.contains(&input) {
+                    predecessors.push(input);
+                }
+            }
+        }
+        predecessors
+    }
+
+    /// Distinct nodes reading from `node_id`, in ascending ID order.
+    pub fn successors(&self, node_id: NodeID) -> Vec<NodeID> {
+        self.users(node_id).to_vec()
+    }
+
+    pub fn neighbors(&self, node_id: NodeID, direction: Direction) -> Vec<NodeID> {
+        match direction {
+            Direction::Successors => self.successors(node_id),
+            Direction::Predecessors => self.predecessors(node_id),
+        }
+    }
+
+    pub fn bfs(&self, start: NodeID, direction: Direction) -> Bfs<'_> {
+        let mut walk = Bfs {
+            graph: self,
+            direction,
+            queue: VecDeque::new(),
+            seen: HashSet::new(),
+        };
+        if self.contains_node(start) {
+            walk.queue.push_back(start);
+            walk.seen.insert(start);
+        }
+        walk
+    }
+
+    pub fn dfs(&self, start: NodeID, direction: Direction) -> Dfs<'_> {
+        let mut walk = Dfs {
+            graph: self,
+            direction,
+            stack: Vec::new(),
+            seen: HashSet::new(),
+        };
+        if self.contains_node(start) {
+            walk.stack.push(start);
+        }
+        walk
+    }
+
+    /// Depth-first post-order along successor edges, starting from each
+    /// unvisited node in insertion order. Every node appears once, even inside
+    /// a cycle.
+    pub fn post_order(&self) -> Vec<NodeID> {
+        let mut order = Vec::with_capacity(self.len());
+        let mut seen = HashSet::new();
+
+        for root in self.node_ids() {
+            if !seen.insert(root) {
+                continue;
+            }
+
+            // Each frame is a node plus the index of the next successor to visit.
+            let mut stack = vec![(root, 0)];
+            while let Some(&mut (node_id, ref mut next)) = stack.last_mut() {
+                let successors = self.users(node_id);
+                match successors.get(*next) {
+                    Some(&successor) => {
+                        *next += 1;
+                        if seen.insert(successor) {
+                            stack.push((successor, 0));
+                        }
+                    }
+                    None => {
+                        order.push(node_id);
+                        stack.pop();
+                    }
+                }
+            }
+        }
+
+        order
+    }
+
+    /// Reverse of `post_order`: producers before consumers on an acyclic
+    /// graph. Unlike `topological_order` it keeps each chain together rather
+    /// than interleaving independent branches by ID.
+    pub fn reverse_post_order(&self) -> Vec<NodeID> {
+        let mut order = self.post_order();
+        order.reverse();
+        order
+    }
+}
+
+impl Iterator for Bfs<'_> {
+    type Item = NodeID;
+
+    fn next(&mut self) -> Option<NodeID> {
+        let node_id = self.queue.pop_front()?;
+        for neighbor in self.graph.neighbors(node_id, self.direction) {
+            if self.seen.insert(neighbor) {
+                self.queue.push_back(neighbor);
+            }
+        }
+        Some(node_id)
+    }
+}
+
+impl Iterator for Dfs<'_> {
+    type Item = NodeID;
+
+    fn next(&mut self) -> Option<NodeID> {
+        loop {
+            let node_id = self.stack.pop()?;
+            if !self.seen.insert(node_id) {
+                continue;
+            }
+            // Pushed in reverse so the first neighbour is visited first
+            for neighbor in self
+                .graph
+                .neighbors(node_id, self.direction)
+                .into_iter()
+                .rev()
+            {
+                if !self.seen.contains(&neighbor) {
+                    self.stack.push(neighbor);
+                }
+            }
+            return Some(node_id);
+        }
+    }
+}
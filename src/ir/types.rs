@@ -1,47 +1,713 @@
+use core::fmt;
+use std::collections::BTreeSet;
+
+use crate::ir::{
+    attributes::Attributes, errors::ValidationError, layout::TensorLayout, symbolic::Dim,
+};
+
+/// Handle to a node: its slot in the graph plus the slot's generation, so a
+/// handle kept past the node's removal no longer matches once the slot is
+/// reused. Handles order by slot, then generation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeID {
+    index: u32,
+    generation: u32,
+}
+
+/// One output of a node, i.e. the value an edge carries. Most ops have a
+/// single output, port 0, which is what a bare `NodeID` converts to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ValueRef {
+    node: NodeID,
+    port: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TensorShape(Vec<Dim>);
+
+/// Element type of a tensor.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct NodeID(u32);
+pub enum DType {
+    F32,
+    F16,
+    BF16,
+    I32,
+    I8,
+    Bool,
+}
 
+/// What flows along an edge: element type, shape, and optionally how the
+/// elements are laid out in memory (dense row-major when unset).
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct TensorShape(Vec<usize>);
+pub struct TensorType {
+    dtype: DType,
+    shape: TensorShape,
+    layout: Option<TensorLayout>,
+}
 
+/// Axes follow the ONNX convention: negative values count from the last dimension.
+#[derive(Debug, Clone, PartialEq)]
 pub enum OpKind {
-    MatMul,
+    MatMul {
+        transpose_a: bool,
+        transpose_b: bool,
+    },
     Add,
     Gelu,
-    Dropout,
-    Softmax,
-    LayerNorm,
-    Custom(String),
+    Dropout {
+        ratio: f32,
+    },
+    Softmax {
+        axis: i64,
+    },
+    /// Normalises over every dimension from `axis` to the last.
+    LayerNorm {
+        axis: i64,
+        epsilon: f32,
+    },
+
+    // Unary elementwise
+    Relu,
+    Sigmoid,
+    Tanh,
+    Exp,
+    Log,
+    Sqrt,
+    Rsqrt,
+    Erf,
+    /// Clamps to `[min, max]`; an unset bound leaves that side open.
+    Clip {
+        min: Option<f32>,
+        max: Option<f32>,
+    },
+    Cast {
+        to: DType,
+    },
+
+    // Binary elementwise
+    Mul,
+    Sub,
+    Div,
+    Pow,
+    /// `condition ? x : y` with all three inputs broadcast together.
+    Where,
+
+    // Reductions
+    /// Reduces over `axes`, or over every dimension when `axes` is empty.
+    /// With `keepdims` the reduced dimensions stay in place with size 1.
+    Reduce {
+        kind: ReduceKind,
+        axes: Vec<i64>,
+        keepdims: bool,
+    },
+    /// Index of the largest element along `axis` as `i32`; ties go to the first.
+    ArgMax {
+        axis: i64,
+        keepdims: bool,
+    },
+
+    // Data movement
+    /// ONNX reshape semantics: `0` copies the input dimension at the same
+    /// position and a single `-1` is inferred from the element count.
+    Reshape {
+        shape: Vec<i64>,
+    },
+    /// Output dimension `i` is input dimension `perm[i]`; an empty `perm`
+    /// reverses the dimensions.
+    Transpose {
+        perm: Vec<usize>,
+    },
+    Concat {
+        axis: i64,
+    },
+    /// Cuts `axis` into consecutive pieces of the given sizes, one output each.
+    Split {
+        axis: i64,
+        sizes: Vec<usize>,
+    },
+    /// ONNX slice semantics: out-of-range bounds are clamped. Empty `axes`
+    /// means the leading dimensions, empty `steps` means a step of 1.
+    Slice {
+        starts: Vec<i64>,
+        ends: Vec<i64>,
+        axes: Vec<i64>,
+        steps: Vec<i64>,
+    },
+    /// Picks entries of the first input along `axis` using the integer
+    /// indices in the second input.
+    Gather {
+        axis: i64,
+    },
+
+    // Vision
+    /// 2D convolution over NCHW input. Inputs are the data, weights laid out
+    /// as `[out_channels, in_channels / groups, kh, kw]`, and an optional bias.
+    Conv2d {
+        window: Window2d,
+        groups: usize,
+    },
+    /// Convolution with one group per input channel; weights are
+    /// `[in_channels * multiplier, 1, kh, kw]`.
+    DepthwiseConv {
+        window: Window2d,
+    },
+    MaxPool {
+        kernel_shape: [usize; 2],
+        window: Window2d,
+    },
+    /// With `count_include_pad` padded positions count towards the divisor.
+    AvgPool {
+        kernel_shape: [usize; 2],
+        window: Window2d,
+        count_include_pad: bool,
+    },
+    /// Averages each channel down to `1x1`.
+    GlobalAvgPool,
+    /// Inference-mode batch norm: data, scale, bias, running mean and running
+    /// variance, the last four with one entry per channel.
+    BatchNorm {
+        epsilon: f32,
+    },
+
+    // Graph boundary
+    /// A named graph input; its type is declared on the node.
+    Input {
+        name: String,
+    },
+    /// A named constant whose data is held by the graph as an initializer.
+    Constant {
+        name: String,
+    },
+
+    Custom {
+        name: String,
+        attributes: Attributes,
+    },
+}
+
+/// Sliding-window parameters shared by convolutions and pooling, over the
+/// height and width dimensions of NCHW tensors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Window2d {
+    pub strides: [usize; 2],
+    /// Padding as `[top, left, bottom, right]`, the ONNX order.
+    pub pads: [usize; 4],
+    pub dilations: [usize; 2],
+}
+
+/// How a `Reduce` op combines the elements along its axes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReduceKind {
+    Sum,
+    Mean,
+    Max,
+    Min,
+}
+
+/// Broad family of an op, used by passes that treat similar ops alike.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OpCategory {
+    /// One output element per output position, computed from the input
+    /// elements at the same (broadcast) position.
+    Elementwise,
+    /// Matrix products and convolutions.
+    Contraction,
+    /// Ops that collapse axes or sliding windows into single values.
+    Reduction,
+    /// Ops that reduce over an axis internally and rescale by the result.
+    Normalization,
+    /// Ops that rearrange or select elements without computing new values.
+    Movement,
+    /// Graph inputs and constants, which provide values without computing them.
+    Source,
+    /// Anything the compiler has no semantics for.
+    Opaque,
+}
+
+/// Number of inputs an operation accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arity {
+    Exact(usize),
+    AtLeast(usize),
+    Range { min: usize, max: usize },
+    Variadic,
 }
 
 impl NodeID {
+    /// Handle to slot `id` in its first generation, the only one a slot has
+    /// until a node in it is removed.
     pub fn new(id: u32) -> Self {
-        NodeID(id)
+        NodeID::with_generation(id, 0)
     }
 
+    pub fn with_generation(id: u32, generation: u32) -> Self {
+        NodeID {
+            index: id,
+            generation,
+        }
+    }
+
+    /// Slot index, which is what errors and textual formats report.
     pub fn id(&self) -> u32 {
-        self.0
+        self.index
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    /// Handle to output `port` of this node.
+    pub fn output(self, port: usize) -> ValueRef {
+        ValueRef::new(self, port)
+    }
+}
+
+impl ValueRef {
+    pub fn new(node: NodeID, port: usize) -> Self {
+        ValueRef { node, port }
+    }
+
+    pub fn node(&self) -> NodeID {
+        self.node
+    }
+
+    pub fn port(&self) -> usize {
+        self.port
+    }
+}
+
+impl From<NodeID> for ValueRef {
+    fn from(node: NodeID) -> Self {
+        ValueRef::new(node, 0)
+    }
+}
+
+impl OpKind {
+    pub fn matmul() -> Self {
+        OpKind::MatMul {
+            transpose_a: false,
+            transpose_b: false,
+        }
+    }
+
+    pub fn dropout() -> Self {
+        OpKind::Dropout { ratio: 0.5 }
+    }
+
+    pub fn softmax() -> Self {
+        OpKind::Softmax { axis: -1 }
+    }
+
+    pub fn layer_norm() -> Self {
+        OpKind::LayerNorm {
+            axis: -1,
+            epsilon: 1e-5,
+        }
+    }
+
+    /// Reduction that keeps the reduced dimensions, the ONNX default.
+    pub fn reduce(kind: ReduceKind, axes: Vec<i64>) -> Self {
+        OpKind::Reduce {
+            kind,
+            axes,
+            keepdims: true,
+        }
+    }
+
+    pub fn custom(name: impl Into<String>) -> Self {
+        OpKind::Custom {
+            name: name.into(),
+            attributes: Attributes::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            OpKind::MatMul { .. } => "MatMul",
+            OpKind::Add => "Add",
+            OpKind::Gelu => "Gelu",
+            OpKind::Dropout { .. } => "Dropout",
+            OpKind::Softmax { .. } => "Softmax",
+            OpKind::LayerNorm { .. } => "LayerNorm",
+            OpKind::Relu => "Relu",
+            OpKind::Sigmoid => "Sigmoid",
+            OpKind::Tanh => "Tanh",
+            OpKind::Exp => "Exp",
+            OpKind::Log => "Log",
+            OpKind::Sqrt => "Sqrt",
+            OpKind::Rsqrt => "Rsqrt",
+            OpKind::Erf => "Erf",
+            OpKind::Clip { .. } => "Clip",
+            OpKind::Cast { .. } => "Cast",
+            OpKind::Mul => "Mul",
+            OpKind::Sub => "Sub",
+            OpKind::Div => "Div",
+            OpKind::Pow => "Pow",
+            OpKind::Where => "Where",
+            OpKind::Reduce { kind, .. } => kind.op_name(),
+            OpKind::ArgMax { .. } => "ArgMax",
+            OpKind::Reshape { .. } => "Reshape",
+            OpKind::Transpose { .. } => "Transpose",
+            OpKind::Concat { .. } => "Concat",
+            OpKind::Split { .. } => "Split",
+            OpKind::Slice { .. } => "Slice",
+            OpKind::Gather { .. } => "Gather",
+            OpKind::Conv2d { .. } => "Conv2d",
+            OpKind::DepthwiseConv { .. } => "DepthwiseConv",
+            OpKind::MaxPool { .. } => "MaxPool",
+            OpKind::AvgPool { .. } => "AvgPool",
+            OpKind::GlobalAvgPool => "GlobalAvgPool",
+            OpKind::BatchNorm { .. } => "BatchNorm",
+            OpKind::Input { .. } => "Input",
+            OpKind::Constant { .. } => "Constant",
+            OpKind::Custom { name, .. } => name,
+        }
+    }
+
+    pub fn arity(&self) -> Arity {
+        match self {
+            OpKind::MatMul { .. }
+            | OpKind::Add
+            | OpKind::Mul
+            | OpKind::Sub
+            | OpKind::Div
+            | OpKind::Pow
+            | OpKind::Gather { .. } => Arity::Exact(2),
+            OpKind::Gelu
+            | OpKind::Dropout { .. }
+            | OpKind::Softmax { .. }
+            | OpKind::Relu
+            | OpKind::Sigmoid
+            | OpKind::Tanh
+            | OpKind::Exp
+            | OpKind::Log
+            | OpKind::Sqrt
+            | OpKind::Rsqrt
+            | OpKind::Erf
+            | OpKind::Clip { .. }
+            | OpKind::Cast { .. }
+            | OpKind::Reduce { .. }
+            | OpKind::ArgMax { .. }
+            | OpKind::Reshape { .. }
+            | OpKind::Transpose { .. }
+            | OpKind::Split { .. }
+            | OpKind::Slice { .. }
+            | OpKind::MaxPool { .. }
+            | OpKind::AvgPool { .. }
+            | OpKind::GlobalAvgPool => Arity::Exact(1),
+            OpKind::Concat { .. } => Arity::AtLeast(1),
+            OpKind::Where => Arity::Exact(3),
+            OpKind::Input { .. } | OpKind::Constant { .. } => Arity::Exact(0),
+            OpKind::BatchNorm { .. } => Arity::Exact(5),
+            // Data, weights, optional bias
+            OpKind::Conv2d { .. } | OpKind::DepthwiseConv { .. } => Arity::Range { min: 2, max: 3 },
+            // Input, optional scale, optional bias
+            OpKind::LayerNorm { .. } => Arity::Range { min: 1, max: 3 },
+            OpKind::Custom { .. } => Arity::Variadic,
+        }
+    }
+
+    pub fn category(&self) -> OpCategory {
+        match self {
+            OpKind::MatMul { .. } | OpKind::Conv2d { .. } | OpKind::DepthwiseConv { .. } => {
+                OpCategory::Contraction
+            }
+            OpKind::Reduce { .. }
+            | OpKind::ArgMax { .. }
+            | OpKind::MaxPool { .. }
+            | OpKind::AvgPool { .. }
+            | OpKind::GlobalAvgPool => OpCategory::Reduction,
+            OpKind::Softmax { .. } | OpKind::LayerNorm { .. } | OpKind::BatchNorm { .. } => {
+                OpCategory::Normalization
+            }
+            OpKind::Reshape { .. }
+            | OpKind::Transpose { .. }
+            | OpKind::Concat { .. }
+            | OpKind::Split { .. }
+            | OpKind::Slice { .. }
+            | OpKind::Gather { .. } => OpCategory::Movement,
+            OpKind::Input { .. } | OpKind::Constant { .. } => OpCategory::Source,
+            OpKind::Custom { .. } => OpCategory::Opaque,
+            _ => OpCategory::Elementwise,
+        }
+    }
+
+    pub fn is_elementwise(&self) -> bool {
+        self.category() == OpCategory::Elementwise
+    }
+
+    /// Number of values the op can produce. `Custom` ops declare theirs through
+    /// their output types, so this reports 1 for them.
+    pub fn output_count(&self) -> usize {
+        match self {
+            OpKind::Split { sizes, .. } => sizes.len(),
+            // Normalised data, then the mean and inverse standard deviation
+            OpKind::LayerNorm { .. } => 3,
+            _ => 1,
+        }
+    }
+
+    pub fn is_reduction(&self) -> bool {
+        self.category() == OpCategory::Reduction
+    }
+
+    /// Ops that only accept floating-point inputs.
+    pub fn requires_floating_point(&self) -> bool {
+        matches!(
+            self,
+            OpKind::Gelu
+                | OpKind::Sigmoid
+                | OpKind::Tanh
+                | OpKind::Exp
+                | OpKind::Log
+                | OpKind::Sqrt
+                | OpKind::Rsqrt
+                | OpKind::Erf
+                | OpKind::AvgPool { .. }
+                | OpKind::GlobalAvgPool
+                | OpKind::BatchNorm { .. }
+        )
+    }
+}
+
+impl Default for Window2d {
+    /// Unit strides and dilations, no padding.
+    fn default() -> Self {
+        Window2d {
+            strides: [1, 1],
+            pads: [0; 4],
+            dilations: [1, 1],
+        }
+    }
+}
+
+impl ReduceKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ReduceKind::Sum => "Sum",
+            ReduceKind::Mean => "Mean",
+            ReduceKind::Max => "Max",
+            ReduceKind::Min => "Min",
+        }
+    }
+
+    /// Name of the op performing this reduction, e.g. `ReduceSum`.
+    pub fn op_name(&self) -> &'static str {
+        match self {
+            ReduceKind::Sum => "ReduceSum",
+            ReduceKind::Mean => "ReduceMean",
+            ReduceKind::Max => "ReduceMax",
+            ReduceKind::Min => "ReduceMin",
+        }
+    }
+
+    pub fn from_op_name(name: &str) -> Option<ReduceKind> {
+        match name {
+            "ReduceSum" => Some(ReduceKind::Sum),
+            "ReduceMean" => Some(ReduceKind::Mean),
+            "ReduceMax" => Some(ReduceKind::Max),
+            "ReduceMin" => Some(ReduceKind::Min),
+            _ => None,
+        }
+    }
+}
+
+impl Arity {
+    pub fn accepts(&self, count: usize) -> bool {
+        match *self {
+            Arity::Exact(n) => count == n,
+            Arity::AtLeast(min) => count >= min,
+            Arity::Range { min, max } => count >= min && count <= max,
+            Arity::Variadic => true,
+        }
     }
 }
 
 impl TensorShape {
     pub fn new(dims: Vec<usize>) -> Self {
-        TensorShape(dims)
+        TensorShape(dims.into_iter().map(Dim::Static).collect())
+    }
+
+    /// Builds a shape that may contain symbolic dimensions.
+    pub fn from_dims(dims: Vec<Dim>) -> Self {
+        TensorShape(
+            dims.into_iter()
+                .map(|dim| match dim {
+                    Dim::Symbolic(expr) => Dim::from_expr(expr),
+                    static_dim => static_dim,
+                })
+                .collect(),
+        )
     }
 
     pub fn rank(&self) -> usize {
         self.0.len()
     }
 
-    pub fn size(&self) -> usize {
+    /// Number of elements, or `None` when a dimension is symbolic or the
+    /// count does not fit in a `usize`.
+    pub fn size(&self) -> Option<usize> {
         let mut final_size: usize = 1;
-        for num in self.0.iter() {
-            final_size *= num
+        for dim in self.0.iter() {
+            final_size = final_size.checked_mul(dim.as_static()?)?;
         }
-        final_size
+        Some(final_size)
     }
 
     pub fn is_scalar(&self) -> bool {
         self.0.is_empty()
     }
+
+    pub fn is_static(&self) -> bool {
+        self.0.iter().all(Dim::is_static)
+    }
+
+    pub fn dims(&self) -> &[Dim] {
+        &self.0
+    }
+
+    /// Resolves a possibly negative axis against this shape's rank.
+    pub fn normalize_axis(&self, axis: i64) -> Option<usize> {
+        let rank = self.rank() as i64;
+        let normalized = if axis < 0 { axis + rank } else { axis };
+        (0..rank)
+            .contains(&normalized)
+            .then_some(normalized as usize)
+    }
+
+    pub fn static_dims(&self) -> Option<Vec<usize>> {
+        self.0.iter().map(Dim::as_static).collect()
+    }
+
+    /// Every symbol referenced by any dimension.
+    pub fn symbols(&self) -> BTreeSet<String> {
+        self.0
+            .iter()
+            .filter_map(|dim| match dim {
+                Dim::Symbolic(expr) => Some(expr.symbols().map(|name| name.to_string())),
+                Dim::Static(_) => None,
+            })
+            .flatten()
+            .collect()
+    }
+}
+
+impl DType {
+    pub fn from_name(name: &str) -> Option<DType> {
+        match name {
+            "f32" => Some(DType::F32),
+            "f16" => Some(DType::F16),
+            "bf16" => Some(DType::BF16),
+            "i32" => Some(DType::I32),
+            "i8" => Some(DType::I8),
+            "bool" => Some(DType::Bool),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DType::F32 => "f32",
+            DType::F16 => "f16",
+            DType::BF16 => "bf16",
+            DType::I32 => "i32",
+            DType::I8 => "i8",
+            DType::Bool => "bool",
+        }
+    }
+
+    pub fn size_in_bytes(&self) -> usize {
+        match self {
+            DType::F32 | DType::I32 => 4,
+            DType::F16 | DType::BF16 => 2,
+            DType::I8 | DType::Bool => 1,
+        }
+    }
+
+    pub fn is_floating_point(&self) -> bool {
+        matches!(self, DType::F32 | DType::F16 | DType::BF16)
+    }
+}
+
+impl TensorType {
+    pub fn new(dtype: DType, shape: TensorShape) -> Self {
+        TensorType {
+            dtype,
+            shape,
+            layout: None,
+        }
+    }
+
+    /// Attaches an explicit layout, which must have one stride per dimension.
+    pub fn with_layout(self, layout: TensorLayout) -> Result<Self, ValidationError> {
+        if layout.rank() != self.shape.rank() {
+            return Err(ValidationError::InvalidTensorShape {
+                expected: format!("layout of rank {}", self.shape.rank()),
+                found: format!("strides {:?}", layout.strides()),
+            });
+        }
+
+        Ok(TensorType {
+            layout: Some(layout),
+            ..self
+        })
+    }
+
+    pub fn layout(&self) -> Option<&TensorLayout> {
+        self.layout.as_ref()
+    }
+
+    /// The declared layout, or the dense row-major default.
+    pub fn effective_layout(&self) -> Result<TensorLayout, ValidationError> {
+        match &self.layout {
+            Some(layout) => Ok(layout.clone()),
+            None => TensorLayout::contiguous(&self.shape),
+        }
+    }
+
+    pub fn is_contiguous(&self) -> bool {
+        self.layout
+            .as_ref()
+            .is_none_or(|layout| layout.is_contiguous(&self.shape))
+    }
+
+    pub fn dtype(&self) -> DType {
+        self.dtype
+    }
+
+    pub fn shape(&self) -> &TensorShape {
+        &self.shape
+    }
+
+    /// Total storage needed for the tensor in bytes, if its shape is static
+    /// and the total fits in a `usize`.
+    pub fn size_in_bytes(&self) -> Option<usize> {
+        self.shape.size()?.checked_mul(self.dtype.size_in_bytes())
+    }
+}
+
+impl fmt::Display for TensorShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{}]",
+            self.0
+                .iter()
+                .map(|dim| dim.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+impl fmt::Display for DType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl fmt::Display for TensorType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.dtype, self.shape)
+    }
 }
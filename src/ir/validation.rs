@@ -1,4 +1,11 @@
-use crate::ir::{errors::ValidationError, graph::Graph, types::NodeID};
+use std::collections::{HashMap, HashSet};
+
+use crate::ir::{
+    errors::ValidationError,
+    graph::Graph,
+    ops::Node,
+    types::{Arity, NodeID, OpKind},
+};
 
 pub struct GraphValidator<'a> {
     graph: &'a Graph,
@@ -18,6 +25,10 @@ fn single_error(error: ValidationError) -> ValidationResult {
     Err(vec![error])
 }
 
+fn as_result(errors: Vec<ValidationError>) -> ValidationResult {
+    if errors.is_empty() { ok() } else { Err(errors) }
+}
+
 fn combine_results(results: Vec<ValidationResult>) -> ValidationResult {
     let mut all_errors = Vec::new();
 
@@ -28,12 +39,18 @@ fn combine_results(results: Vec<ValidationResult>) -> ValidationResult {
     }
 
     if all_errors.is_empty() {
-        return ok();
+        ok()
     } else {
-        return Err(all_errors);
+        Err(all_errors)
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum VisitState {
+    InProgress,
+    Done,
+}
+
 impl ValidationContext {
     fn new() -> Self {
         ValidationContext { current_node: None }
@@ -46,6 +63,10 @@ impl ValidationContext {
     fn clear_current_node(&mut self) {
         self.current_node = None
     }
+
+    fn current_node(&self) -> Option<NodeID> {
+        self.current_node
+    }
 }
 
 impl<'a> GraphValidator<'a> {
@@ -53,19 +74,416 @@ impl<'a> GraphValidator<'a> {
         GraphValidator { graph }
     }
 
+    /// Every input and output of every node must point at a node that exists,
+    /// every input must read an output port its producer has, and a declared
+    /// consumer must actually read from the node naming it.
     pub fn validate_node_references(&self) -> ValidationResult {
-        todo!("Implement node reference validation")
+        let mut context = ValidationContext::new();
+        let mut results = Vec::new();
+
+        for node_id in self.graph.node_ids() {
+            let Some(node) = self.graph.get_node(node_id) else {
+                continue;
+            };
+            context.set_current_node(node_id);
+            results.push(as_result(self.node_reference_errors(node, &context)));
+            context.clear_current_node();
+        }
+
+        combine_results(results)
     }
 
+    /// Depth-first search along input edges. Each back edge found yields one
+    /// `CyclicGraph` error whose path is listed in data-flow order.
     pub fn detect_cycles(&self) -> ValidationResult {
-        todo!("Implement cycle detection")
+        let mut states: HashMap<NodeID, VisitState> = HashMap::new();
+        let mut results = Vec::new();
+
+        for root in self.graph.node_ids() {
+            if states.contains_key(&root) {
+                continue;
+            }
+
+            // Each frame is a node plus the index of the next input to visit.
+            let mut stack: Vec<(NodeID, usize)> = vec![(root, 0)];
+            states.insert(root, VisitState::InProgress);
+
+            while let Some(&mut (node_id, ref mut next_input)) = stack.last_mut() {
+                let inputs = match self.graph.get_node(node_id) {
+                    Some(node) => node.inputs(),
+                    None => {
+                        states.insert(node_id, VisitState::Done);
+                        stack.pop();
+                        continue;
+                    }
+                };
+
+                if *next_input >= inputs.len() {
+                    states.insert(node_id, VisitState::Done);
+                    stack.pop();
+                    continue;
+                }
+
+                let input = inputs[*next_input].node();
+                *next_input += 1;
+
+                if !self.graph.contains_node(input) {
+                    continue;
+                }
+
+                match states.get(&input) {
+                    None => {
+                        states.insert(input, VisitState::InProgress);
+                        stack.push((input, 0));
+                    }
+                    Some(VisitState::InProgress) => {
+                        let start = stack.iter().position(|(id, _)| *id == input).unwrap_or(0);
+                        let mut cycle_path: Vec<u32> =
+                            stack[start..].iter().map(|(id, _)| id.id()).collect();
+                        cycle_path.reverse();
+                        // Start the path at its smallest ID so it reads the same however the
+                        // search entered the cycle.
+                        if let Some(min_pos) = cycle_path
+                            .iter()
+                            .enumerate()
+                            .min_by_key(|(_, id)| **id)
+                            .map(|(pos, _)| pos)
+                        {
+                            cycle_path.rotate_left(min_pos);
+                        }
+                        results.push(single_error(ValidationError::CyclicGraph { cycle_path }));
+                    }
+                    Some(VisitState::Done) => {}
+                }
+            }
+        }
+
+        combine_results(results)
     }
 
+    /// Checks the number of inputs on each node against its op's arity, and that
+    /// attribute values are in range.
     pub fn validate_operation_constraints(&self) -> ValidationResult {
-        todo!("Implement operation constraint validation")
+        let mut results = Vec::new();
+
+        for node_id in self.graph.node_ids() {
+            let Some(node) = self.graph.get_node(node_id) else {
+                continue;
+            };
+            results.push(self.node_constraint_errors(node));
+        }
+
+        combine_results(results)
+    }
+
+    /// Checks the graph's declared inputs, outputs and constants: every input
+    /// node is declared and typed, every constant has data of its declared
+    /// type, outputs point at existing nodes, and names are unique.
+    pub fn validate_boundaries(&self) -> ValidationResult {
+        let mut results = Vec::new();
+        let boundary_error = |name: &str, reason: &str| {
+            single_error(ValidationError::InvalidGraphBoundary {
+                name: name.to_string(),
+                reason: reason.to_string(),
+            })
+        };
+
+        let declared_inputs: HashSet<NodeID> = self.graph.inputs().iter().copied().collect();
+        let mut value_names = HashSet::new();
+
+        for node_id in self.graph.node_ids() {
+            let Some(node) = self.graph.get_node(node_id) else {
+                continue;
+            };
+
+            match node.op() {
+                OpKind::Input { name } => {
+                    if !declared_inputs.contains(&node_id) {
+                        results.push(boundary_error(
+                            name,
+                            "input node is not a declared graph input",
+                        ));
+                    }
+                    if node.output_type().is_none() {
+                        results.push(boundary_error(name, "graph input has no declared type"));
+                    }
+                    if !value_names.insert(name.as_str()) {
+                        results.push(boundary_error(
+                            name,
+                            "name is used by another input or constant",
+                        ));
+                    }
+                }
+                OpKind::Constant { name } => {
+                    match self.graph.initializer(node_id) {
+                        None => results.push(boundary_error(name, "constant has no data")),
+                        Some(tensor) if node.output_type() != Some(tensor.tensor_type()) => results
+                            .push(boundary_error(
+                                name,
+                                "constant data does not match the node's declared type",
+                            )),
+                        Some(_) => {}
+                    }
+                    if !value_names.insert(name.as_str()) {
+                        results.push(boundary_error(
+                            name,
+                            "name is used by another input or constant",
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for input in self.graph.inputs() {
+            if !matches!(
+                self.graph.get_node(*input).map(|node| node.op()),
+                Some(OpKind::Input { .. })
+            ) {
+                results.push(single_error(ValidationError::MissingNode {
+                    node_id: input.id(),
+                }));
+            }
+        }
+
+        let mut output_names = HashSet::new();
+        for output in self.graph.outputs() {
+            if !self.graph.contains_node(output.node()) {
+                results.push(single_error(ValidationError::MissingNode {
+                    node_id: output.node().id(),
+                }));
+            }
+            if !output_names.insert(output.name()) {
+                results.push(boundary_error(
+                    output.name(),
+                    "name is used by another output",
+                ));
+            }
+        }
+
+        combine_results(results)
     }
 
     pub fn validate(&self) -> ValidationResult {
-        todo!("Implement comprehensive validation")
+        combine_results(vec![
+            self.validate_node_references(),
+            self.detect_cycles(),
+            self.validate_operation_constraints(),
+            self.validate_boundaries(),
+        ])
+    }
+
+    /// The reference, cycle and operation errors, grouped by the node they
+    /// concern. A cycle is reported on every node along it. Boundary problems
+    /// belong to the graph rather than one node and are left out.
+    pub fn errors_by_node(&self) -> HashMap<NodeID, Vec<ValidationError>> {
+        let mut context = ValidationContext::new();
+        let mut errors: HashMap<NodeID, Vec<ValidationError>> = HashMap::new();
+
+        for node_id in self.graph.node_ids() {
+            let Some(node) = self.graph.get_node(node_id) else {
+                continue;
+            };
+            context.set_current_node(node_id);
+            let mut node_errors = self.node_reference_errors(node, &context);
+            context.clear_current_node();
+            if let Err(constraint_errors) = self.node_constraint_errors(node) {
+                node_errors.extend(constraint_errors);
+            }
+            if !node_errors.is_empty() {
+                errors.insert(node_id, node_errors);
+            }
+        }
+
+        if let Err(cycles) = self.detect_cycles() {
+            let live: HashMap<u32, NodeID> = self
+                .graph
+                .node_ids()
+                .into_iter()
+                .map(|node_id| (node_id.id(), node_id))
+                .collect();
+            for cycle in cycles {
+                let ValidationError::CyclicGraph { cycle_path } = cycle else {
+                    continue;
+                };
+                for index in &cycle_path {
+                    if let Some(node_id) = live.get(index) {
+                        errors
+                            .entry(*node_id)
+                            .or_default()
+                            .push(ValidationError::CyclicGraph {
+                                cycle_path: cycle_path.clone(),
+                            });
+                    }
+                }
+            }
+        }
+
+        errors
+    }
+
+    fn node_reference_errors(
+        &self,
+        node: &Node,
+        context: &ValidationContext,
+    ) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+
+        for input in node.inputs() {
+            let Some(producer) = self.graph.get_node(input.node()) else {
+                errors.push(ValidationError::MissingNode {
+                    node_id: input.node().id(),
+                });
+                continue;
+            };
+
+            if let Some(count) = port_count(producer)
+                && input.port() >= count
+            {
+                errors.push(ValidationError::InvalidNodeConnection {
+                    from: input.node().id(),
+                    to: node.id().id(),
+                    reason: format!(
+                        "reads output {} but the producer has {count} output(s)",
+                        input.port()
+                    ),
+                });
+            }
+        }
+
+        for output in node.outputs() {
+            let Some(consumer) = self.graph.get_node(*output) else {
+                errors.push(ValidationError::MissingNode {
+                    node_id: output.id(),
+                });
+                continue;
+            };
+
+            if let Some(current) = context.current_node()
+                && !consumer.input_nodes().any(|input| input == current)
+            {
+                errors.push(ValidationError::InvalidNodeConnection {
+                    from: current.id(),
+                    to: output.id(),
+                    reason: "consumer does not list the node as an input".to_string(),
+                });
+            }
+        }
+
+        errors
+    }
+
+    fn node_constraint_errors(&self, node: &Node) -> ValidationResult {
+        let arity = node.op().arity();
+        let found = node.inputs().len();
+        let mut results = Vec::new();
+
+        if !arity.accepts(found) {
+            let expected = match arity {
+                Arity::Exact(n) | Arity::AtLeast(n) => n,
+                Arity::Range { min, max } => {
+                    if found < min {
+                        min
+                    } else {
+                        max
+                    }
+                }
+                Arity::Variadic => found,
+            };
+
+            results.push(single_error(ValidationError::InvalidOpInputCount {
+                op: node.op().name().to_string(),
+                expected,
+                found,
+            }));
+        }
+
+        results.push(validate_attributes(node.op()));
+        combine_results(results)
+    }
+}
+
+/// Range checks that do not depend on input shapes; axes are checked during
+/// shape inference, once the rank is known.
+fn validate_attributes(op: &OpKind) -> ValidationResult {
+    let invalid = |attribute: &str, reason: &str| {
+        single_error(ValidationError::InvalidAttribute {
+            op: op.name().to_string(),
+            attribute: attribute.to_string(),
+            reason: reason.to_string(),
+        })
+    };
+
+    match op {
+        OpKind::Dropout { ratio } if !(0.0..1.0).contains(ratio) => {
+            invalid("ratio", "must be in [0, 1)")
+        }
+        OpKind::LayerNorm { epsilon, .. } if !(epsilon.is_finite() && *epsilon > 0.0) => {
+            invalid("epsilon", "must be positive and finite")
+        }
+        OpKind::Clip {
+            min: Some(min),
+            max: Some(max),
+        } if min > max => invalid("min", "must not exceed max"),
+        OpKind::Reshape { shape } if shape.iter().any(|&dim| dim < -1) => {
+            invalid("shape", "entries must be -1, 0 or positive")
+        }
+        OpKind::Reshape { shape } if shape.iter().filter(|&&dim| dim == -1).count() > 1 => {
+            invalid("shape", "at most one entry can be -1")
+        }
+        OpKind::Split { sizes, .. } if sizes.is_empty() => {
+            invalid("sizes", "must name at least one piece")
+        }
+        OpKind::Slice {
+            starts,
+            ends,
+            axes,
+            steps,
+        } if ends.len() != starts.len()
+            || (!axes.is_empty() && axes.len() != starts.len())
+            || (!steps.is_empty() && steps.len() != starts.len()) =>
+        {
+            invalid(
+                "starts",
+                "starts, ends, axes and steps must have the same length",
+            )
+        }
+        OpKind::Slice { steps, .. } if steps.contains(&0) => invalid("steps", "must be non-zero"),
+        OpKind::Conv2d { groups: 0, .. } => invalid("groups", "must be at least 1"),
+        OpKind::Conv2d { window, .. }
+        | OpKind::DepthwiseConv { window }
+        | OpKind::MaxPool { window, .. }
+        | OpKind::AvgPool { window, .. }
+            if window.strides.contains(&0) =>
+        {
+            invalid("strides", "must be at least 1 in each dimension")
+        }
+        OpKind::Conv2d { window, .. }
+        | OpKind::DepthwiseConv { window }
+        | OpKind::MaxPool { window, .. }
+        | OpKind::AvgPool { window, .. }
+            if window.dilations.contains(&0) =>
+        {
+            invalid("dilations", "must be at least 1 in each dimension")
+        }
+        OpKind::MaxPool { kernel_shape, .. } | OpKind::AvgPool { kernel_shape, .. }
+            if kernel_shape.contains(&0) =>
+        {
+            invalid("kernel_shape", "must be at least 1 in each dimension")
+        }
+        OpKind::BatchNorm { epsilon } if !(epsilon.is_finite() && *epsilon > 0.0) => {
+            invalid("epsilon", "must be positive and finite")
+        }
+        _ => ok(),
+    }
+}
+
+/// Number of outputs a node provides. `Custom` ops only declare theirs through
+/// output types, so an untyped custom node gives `None` and is not checked.
+fn port_count(node: &Node) -> Option<usize> {
+    match node.op() {
+        OpKind::Custom { .. } if node.output_types().is_empty() => None,
+        OpKind::Custom { .. } => Some(node.output_types().len()),
+        op => Some(op.output_count()),
     }
 }
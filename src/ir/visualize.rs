@@ -0,0 +1,377 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Write,
+};
+
+use crate::ir::{
+    graph::Graph,
+    text::{print_attribute, print_type},
+    types::{NodeID, OpKind},
+    validation::GraphValidator,
+};
+
+/// Nodes drawn inside one labelled box, such as a fusion group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeGroup {
+    label: String,
+    nodes: Vec<NodeID>,
+}
+
+impl NodeGroup {
+    pub fn new(label: impl Into<String>, nodes: Vec<NodeID>) -> Self {
+        NodeGroup {
+            label: label.into(),
+            nodes,
+        }
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn nodes(&self) -> &[NodeID] {
+        &self.nodes
+    }
+}
+
+/// Renders the graph as a Graphviz digraph. Each node shows its ID, op,
+/// attributes and output types; each group becomes a dashed cluster, and
+/// nodes with validation errors are filled red with the errors as tooltip.
+/// A node listed in several groups is drawn in the first.
+pub fn to_dot(graph: &Graph, groups: &[NodeGroup]) -> String {
+    let drawing = Drawing::new(graph, groups);
+    let mut dot = String::from(
+        "digraph xyntra {\n  rankdir=TB;\n  node [shape=box, fontname=\"monospace\"];\n",
+    );
+
+    let draw_node = |dot: &mut String, indent: &str, node: &DrawnNode| {
+        let shape = match node.kind {
+            NodeKind::Input => "ellipse",
+            NodeKind::Constant => "note",
+            NodeKind::Op => "box",
+            NodeKind::Missing => "box, style=dashed",
+        };
+        let _ = write!(
+            dot,
+            "{indent}{} [label=\"{}\", shape={shape}",
+            node.key,
+            dot_escape(&node.lines.join("\n"))
+        );
+        if !node.errors.is_empty() {
+            let _ = write!(
+                dot,
+                ", style=filled, fillcolor=\"#fdd\", color=\"#c00\", penwidth=2, tooltip=\"{}\"",
+                dot_escape(&node.errors.join("\n"))
+            );
+        }
+        dot.push_str("];\n");
+    };
+
+    for (index, group) in drawing.groups.iter().enumerate() {
+        let _ = writeln!(dot, "  subgraph cluster_{index} {{");
+        let _ = writeln!(
+            dot,
+            "    label=\"{}\";\n    style=dashed;\n    color=\"#36c\";",
+            dot_escape(&group.label)
+        );
+        for &member in &group.members {
+            draw_node(&mut dot, "    ", &drawing.nodes[member]);
+        }
+        dot.push_str("  }\n");
+    }
+    for (index, node) in drawing.nodes.iter().enumerate() {
+        if !drawing.grouped.contains(&index) {
+            draw_node(&mut dot, "  ", node);
+        }
+    }
+
+    for edge in &drawing.edges {
+        let _ = write!(dot, "  {} -> {}", edge.from, edge.to);
+        if edge.port > 0 {
+            let _ = write!(dot, " [label=\"#{}\"]", edge.port);
+        }
+        dot.push_str(";\n");
+    }
+    for (index, (name, from, port)) in drawing.outputs.iter().enumerate() {
+        let _ = writeln!(
+            dot,
+            "  output_{index} [label=\"{}\", shape=doubleoctagon];",
+            dot_escape(name)
+        );
+        let _ = write!(dot, "  {from} -> output_{index}");
+        if *port > 0 {
+            let _ = write!(dot, " [label=\"#{port}\"]");
+        }
+        dot.push_str(";\n");
+    }
+
+    dot.push_str("}\n");
+    dot
+}
+
+/// Renders the graph as a Mermaid flowchart with the same content as
+/// `to_dot`: groups become subgraphs and nodes with validation errors get
+/// the `error` class.
+pub fn to_mermaid(graph: &Graph, groups: &[NodeGroup]) -> String {
+    let drawing = Drawing::new(graph, groups);
+    let mut mermaid = String::from("flowchart TD\n");
+
+    let draw_node = |mermaid: &mut String, indent: &str, node: &DrawnNode| {
+        let label = node
+            .lines
+            .iter()
+            .map(|line| mermaid_escape(line))
+            .collect::<Vec<_>>()
+            .join("<br/>");
+        let (open, close) = match node.kind {
+            NodeKind::Input => ("([", "])"),
+            NodeKind::Constant => ("[(", ")]"),
+            NodeKind::Op | NodeKind::Missing => ("[", "]"),
+        };
+        let _ = writeln!(mermaid, "{indent}{}{open}\"{label}\"{close}", node.key);
+    };
+
+    for (index, group) in drawing.groups.iter().enumerate() {
+        let _ = writeln!(
+            mermaid,
+            "  subgraph group_{index} [\"{}\"]",
+            mermaid_escape(&group.label)
+        );
+        for &member in &group.members {
+            draw_node(&mut mermaid, "    ", &drawing.nodes[member]);
+        }
+        mermaid.push_str("  end\n");
+    }
+    for (index, node) in drawing.nodes.iter().enumerate() {
+        if !drawing.grouped.contains(&index) {
+            draw_node(&mut mermaid, "  ", node);
+        }
+    }
+
+    for edge in &drawing.edges {
+        match edge.port {
+            0 => {
+                let _ = writeln!(mermaid, "  {} --> {}", edge.from, edge.to);
+            }
+            port => {
+                let _ = writeln!(mermaid, "  {} -->|\"#35;{port}\"| {}", edge.from, edge.to);
+            }
+        }
+    }
+    for (index, (name, from, port)) in drawing.outputs.iter().enumerate() {
+        let _ = writeln!(
+            mermaid,
+            "  output_{index}{{{{\"{}\"}}}}",
+            mermaid_escape(name)
+        );
+        match port {
+            0 => {
+                let _ = writeln!(mermaid, "  {from} --> output_{index}");
+            }
+            port => {
+                let _ = writeln!(mermaid, "  {from} -->|\"#35;{port}\"| output_{index}");
+            }
+        }
+    }
+
+    let failing: Vec<&str> = drawing
+        .nodes
+        .iter()
+        .filter(|node| !node.errors.is_empty())
+        .map(|node| node.key.as_str())
+        .collect();
+    if !failing.is_empty() {
+        mermaid.push_str("  classDef error fill:#fdd,stroke:#c00,stroke-width:2px\n");
+        let _ = writeln!(mermaid, "  class {} error", failing.join(","));
+    }
+    let missing: Vec<&str> = drawing
+        .nodes
+        .iter()
+        .filter(|node| node.kind == NodeKind::Missing)
+        .map(|node| node.key.as_str())
+        .collect();
+    if !missing.is_empty() {
+        mermaid.push_str("  classDef missing stroke-dasharray:4 4\n");
+        let _ = writeln!(mermaid, "  class {} missing", missing.join(","));
+    }
+
+    mermaid
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum NodeKind {
+    Input,
+    Constant,
+    Op,
+    /// Placeholder for a node that is referenced but no longer exists.
+    Missing,
+}
+
+struct DrawnNode {
+    key: String,
+    kind: NodeKind,
+    lines: Vec<String>,
+    errors: Vec<String>,
+}
+
+struct Edge {
+    from: String,
+    to: String,
+    port: usize,
+}
+
+struct DrawnGroup {
+    label: String,
+    /// Indices into `Drawing::nodes`.
+    members: Vec<usize>,
+}
+
+/// What both renderers draw, gathered once in graph insertion order.
+struct Drawing {
+    nodes: Vec<DrawnNode>,
+    edges: Vec<Edge>,
+    groups: Vec<DrawnGroup>,
+    grouped: BTreeSet<usize>,
+    /// Name, producer key and port of each graph output.
+    outputs: Vec<(String, String, usize)>,
+}
+
+impl Drawing {
+    fn new(graph: &Graph, groups: &[NodeGroup]) -> Self {
+        let errors = GraphValidator::new(graph).errors_by_node();
+        let mut nodes = Vec::new();
+        let mut positions: HashMap<NodeID, usize> = HashMap::new();
+        let mut missing: BTreeSet<u32> = BTreeSet::new();
+        let mut edges = Vec::new();
+
+        let mut key_of = |graph: &Graph, node_id: NodeID| {
+            if graph.contains_node(node_id) {
+                format!("n{}", node_id.id())
+            } else {
+                missing.insert(node_id.id());
+                format!("missing_{}", node_id.id())
+            }
+        };
+
+        for node in graph.nodes() {
+            let (kind, mut lines) = match node.op() {
+                OpKind::Input { name } => (
+                    NodeKind::Input,
+                    vec![format!("%{} input {name:?}", node.id().id())],
+                ),
+                OpKind::Constant { name } => (
+                    NodeKind::Constant,
+                    vec![format!("%{} constant {name:?}", node.id().id())],
+                ),
+                OpKind::Custom { name, .. } => (
+                    NodeKind::Op,
+                    vec![format!("%{} custom {name:?}", node.id().id())],
+                ),
+                op => (
+                    NodeKind::Op,
+                    vec![format!("%{} {}", node.id().id(), op.name())],
+                ),
+            };
+            if kind == NodeKind::Op {
+                lines.extend(
+                    node.op()
+                        .attributes()
+                        .iter()
+                        .map(|(name, value)| format!("{name} = {}", print_attribute(value))),
+                );
+            }
+            lines.extend(node.output_types().iter().map(print_type));
+
+            let key = key_of(graph, node.id());
+            for input in node.inputs() {
+                edges.push(Edge {
+                    from: key_of(graph, input.node()),
+                    to: key.clone(),
+                    port: input.port(),
+                });
+            }
+
+            positions.insert(node.id(), nodes.len());
+            nodes.push(DrawnNode {
+                key,
+                kind,
+                lines,
+                errors: errors
+                    .get(&node.id())
+                    .map(|errors| errors.iter().map(|error| error.to_string()).collect())
+                    .unwrap_or_default(),
+            });
+        }
+
+        let outputs = graph
+            .outputs()
+            .iter()
+            .map(|output| {
+                (
+                    output.name().to_string(),
+                    key_of(graph, output.node()),
+                    output.value().port(),
+                )
+            })
+            .collect();
+
+        for index in missing {
+            nodes.push(DrawnNode {
+                key: format!("missing_{index}"),
+                kind: NodeKind::Missing,
+                lines: vec![format!("missing node {index}")],
+                errors: Vec::new(),
+            });
+        }
+
+        let mut grouped = BTreeSet::new();
+        let groups = groups
+            .iter()
+            .map(|group| DrawnGroup {
+                label: group.label.clone(),
+                members: group
+                    .nodes
+                    .iter()
+                    .filter_map(|node_id| positions.get(node_id).copied())
+                    .filter(|position| grouped.insert(*position))
+                    .collect(),
+            })
+            .collect();
+
+        Drawing {
+            nodes,
+            edges,
+            groups,
+            grouped,
+            outputs,
+        }
+    }
+}
+
+fn dot_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Mermaid labels take HTML-like entity codes written `#code;`.
+fn mermaid_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("#quot;"),
+            '#' => escaped.push_str("#35;"),
+            '<' => escaped.push_str("#lt;"),
+            '>' => escaped.push_str("#gt;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -1,2 +1,5 @@
 pub mod config;
+pub mod fusion;
 pub mod ir;
+pub mod onnx;
+pub mod torchscript;
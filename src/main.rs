@@ -1,5 +1,3 @@
-pub mod ir;
-
 fn main() {
     println!("Hello, world!");
 }
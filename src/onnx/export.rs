@@ -0,0 +1,1040 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+    path::Path,
+};
+
+use crate::{
+    ir::{
+        attributes::{AttributeValue, Attributes},
+        errors::{ValidationError, XyntraError},
+        graph::Graph,
+        ops::Node,
+        symbolic::{Dim, DimExpr},
+        tensor::f32_to_f16,
+        types::{DType, NodeID, OpKind, TensorType, ValueRef, Window2d},
+        visualize::NodeGroup,
+    },
+    onnx::{
+        opset::{XYNTRA_DOMAIN, XYNTRA_OPSET, introduced_in},
+        proto::{
+            AttributeProto, Dimension, FunctionProto, GraphProto, ModelProto, NodeProto,
+            OperatorSetIdProto, TensorProto, TensorShapeProto, TensorTypeProto, TypeProto,
+            ValueInfoProto, attribute_type, data_type,
+        },
+    },
+};
+
+/// Default-domain opset the exporter writes, the first where every parameter
+/// the IR folds into attributes (Reduce axes, Split sizes, Clip bounds, ...)
+/// is an input. Graphs using newer ops get the opset that introduced them.
+pub const EXPORT_OPSET: i64 = 18;
+
+/// Writes a graph to a `.onnx` file with [`export_onnx`].
+pub fn save_onnx(
+    path: impl AsRef<Path>,
+    graph: &Graph,
+    groups: &[NodeGroup],
+) -> Result<(), XyntraError> {
+    let path = path.as_ref();
+    let bytes = export_onnx(graph, groups).map_err(XyntraError::Validation)?;
+    std::fs::write(path, bytes).map_err(|error| XyntraError::from_io(path, "write", error))
+}
+
+/// Serialises the model built by [`export_model`].
+pub fn export_onnx(graph: &Graph, groups: &[NodeGroup]) -> Result<Vec<u8>, ValidationError> {
+    Ok(export_model(graph, groups)?.encode())
+}
+
+/// Converts a graph into an ONNX model that `import_model` reads back into
+/// the same graph, up to node numbering. Dimensions that are expressions of
+/// symbols, such as `2*seq + 1`, are written as unknown and come back as
+/// fresh symbols.
+///
+/// Nodes outside `groups` become standard ONNX nodes; parameters ONNX takes
+/// as inputs are written as `Constant` nodes. Ops without an ONNX
+/// counterpart are spelled out and come back that way: `Rsqrt` as `Sqrt`
+/// and `Div`, `ArgMax` with a `Cast` to INT32, and transposed `MatMul`
+/// operands beyond 2D as `Transpose` nodes. Each group becomes one node in the
+/// [`XYNTRA_DOMAIN`](crate::onnx::opset::XYNTRA_DOMAIN) domain calling a
+/// model-local function that holds the group's nodes, named after the
+/// group's label and position. The function takes every value the group
+/// reads from outside, weights included, and returns every value read after
+/// it. `Custom` ops are written as nodes of that domain too.
+///
+/// Values keep their input, constant or output name and are otherwise named
+/// after their node, such as `Relu_3`. Every value with a known type is
+/// declared, with symbolic dimensions as `dim_param`s.
+///
+/// Groups must not share nodes, hold graph inputs or constants, or be fed
+/// back their own results through nodes outside them, which would make the
+/// calling node depend on itself.
+pub fn export_model(graph: &Graph, groups: &[NodeGroup]) -> Result<ModelProto, ValidationError> {
+    let opset = graph
+        .nodes()
+        .map(|node| required_opset(node.op()))
+        .max()
+        .unwrap_or(EXPORT_OPSET);
+    let exporter = Exporter {
+        graph,
+        names: value_names(graph),
+        opset,
+    };
+    let units = exporter.order_units(groups)?;
+
+    let mut main = GraphProto {
+        name: "xyntra".to_string(),
+        ..GraphProto::default()
+    };
+    let mut functions = Vec::new();
+    let mut defined = Vec::new();
+    for unit in units {
+        match unit {
+            Unit::Node(node_id) => {
+                let node = node_at(graph, node_id);
+                exporter.convert(node, &mut main.node)?;
+                defined.extend(exporter.output_values(node));
+            }
+            Unit::Group(index) => {
+                let (call, function, outputs) = exporter.fused_call(index, &groups[index])?;
+                main.node.push(call);
+                functions.push(function);
+                defined.extend(outputs);
+            }
+        }
+    }
+
+    // Declared inputs first, in calling order
+    let boundary_nodes = graph.inputs().iter().copied().chain(
+        graph
+            .node_ids()
+            .into_iter()
+            .filter(|node_id| !graph.inputs().contains(node_id)),
+    );
+    for node_id in boundary_nodes {
+        let node = node_at(graph, node_id);
+        match node.op() {
+            OpKind::Input { name } => {
+                let input_type = node
+                    .output_type()
+                    .ok_or_else(|| boundary(name, "has no type"))?;
+                main.input.push(value_info(name, input_type));
+            }
+            OpKind::Constant { name } => {
+                let tensor = graph
+                    .initializer(node.id())
+                    .ok_or_else(|| boundary(name, "has no data"))?;
+                main.initializer.push(TensorProto {
+                    dims: tensor.shape().dims().iter().map(static_dim).collect(),
+                    data_type: elem_type(tensor.dtype()),
+                    name: name.clone(),
+                    raw_data: tensor.data().to_vec(),
+                    ..TensorProto::default()
+                });
+            }
+            _ => {}
+        }
+    }
+
+    for output in graph.outputs() {
+        let name = exporter.name(output.value());
+        if name != output.name() {
+            main.node.push(onnx_node(
+                "Identity",
+                format!("Identity_{}", output.name()),
+                vec![name],
+                vec![output.name().to_string()],
+                vec![],
+            ));
+        }
+        let info = match graph.value_type(output.value()) {
+            Some(output_type) => value_info(output.name(), output_type),
+            None => ValueInfoProto {
+                name: output.name().to_string(),
+                ..ValueInfoProto::default()
+            },
+        };
+        main.output.push(info);
+    }
+
+    let declared: HashSet<&str> = graph.outputs().iter().map(|output| output.name()).collect();
+    for value in defined {
+        let name = exporter.name(value);
+        if let Some(value_type) = graph.value_type(value)
+            && !declared.contains(name.as_str())
+        {
+            main.value_info.push(value_info(&name, value_type));
+        }
+    }
+
+    let mut opset_import = vec![OperatorSetIdProto {
+        domain: String::new(),
+        version: opset,
+    }];
+    if !functions.is_empty() || main.node.iter().any(|node| node.domain == XYNTRA_DOMAIN) {
+        opset_import.push(OperatorSetIdProto {
+            domain: XYNTRA_DOMAIN.to_string(),
+            version: XYNTRA_OPSET,
+        });
+    }
+    Ok(ModelProto {
+        // Version 9 pairs with opsets 19 and 20; 8 is the first with functions
+        ir_version: if opset > 18 { 9 } else { 8 },
+        opset_import,
+        producer_name: "xyntra".to_string(),
+        producer_version: env!("CARGO_PKG_VERSION").to_string(),
+        graph: Some(main),
+        functions,
+        ..ModelProto::default()
+    })
+}
+
+/// What the main graph holds in one place: a single node, or the call to a
+/// fused group by its index in the group list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Unit {
+    Node(NodeID),
+    Group(usize),
+}
+
+struct Exporter<'g> {
+    graph: &'g Graph,
+    names: HashMap<ValueRef, String>,
+    opset: i64,
+}
+
+impl<'g> Exporter<'g> {
+    /// The ONNX name of a value.
+    fn name(&self, value: ValueRef) -> String {
+        self.names.get(&value).cloned().unwrap_or_else(|| {
+            let op = self
+                .graph
+                .get_node(value.node())
+                .map_or("value", |node| node.op().name());
+            generated_name(op, value.node(), value.port())
+        })
+    }
+
+    /// Nodes and fused groups in an order where every value is defined before
+    /// it is read: the graph's topological order, with each group placed
+    /// once everything it reads is available.
+    fn order_units(&self, groups: &[NodeGroup]) -> Result<Vec<Unit>, ValidationError> {
+        let mut unit_of = HashMap::new();
+        for (index, group) in groups.iter().enumerate() {
+            if group.nodes().is_empty() {
+                return Err(boundary(group.label(), "has no nodes"));
+            }
+            for node_id in group.nodes() {
+                let node = self
+                    .graph
+                    .get_node(*node_id)
+                    .ok_or(ValidationError::MissingNode {
+                        node_id: node_id.id(),
+                    })?;
+                if is_boundary(node) {
+                    return Err(boundary(
+                        group.label(),
+                        &format!("holds {} node {}", node.op().name(), node_id.id()),
+                    ));
+                }
+                if let Some(Unit::Group(other)) = unit_of.insert(*node_id, Unit::Group(index)) {
+                    return Err(boundary(
+                        group.label(),
+                        &format!(
+                            "shares node {} with group '{}'",
+                            node_id.id(),
+                            groups[other].label()
+                        ),
+                    ));
+                }
+            }
+        }
+
+        let mut units = Vec::new();
+        let mut position = HashMap::new();
+        for node_id in self.graph.topological_order()? {
+            if is_boundary(node_at(self.graph, node_id)) {
+                continue;
+            }
+            let unit = unit_of
+                .get(&node_id)
+                .copied()
+                .unwrap_or(Unit::Node(node_id));
+            position.entry(unit).or_insert_with(|| {
+                units.push(unit);
+                units.len() - 1
+            });
+        }
+
+        let mut consumers: Vec<HashSet<usize>> = vec![HashSet::new(); units.len()];
+        let mut in_degree = vec![0; units.len()];
+        for node in self.graph.nodes() {
+            let Some(consumer) = self.unit_position(node.id(), &unit_of, &position) else {
+                continue;
+            };
+            for producer in node.input_nodes() {
+                if let Some(producer) = self.unit_position(producer, &unit_of, &position)
+                    && producer != consumer
+                    && consumers[producer].insert(consumer)
+                {
+                    in_degree[consumer] += 1;
+                }
+            }
+        }
+
+        // Ties go to the unit seen first, so without groups this is the
+        // graph's own topological order
+        let mut ready: BinaryHeap<Reverse<usize>> = (0..units.len())
+            .filter(|unit| in_degree[*unit] == 0)
+            .map(Reverse)
+            .collect();
+        let mut order = Vec::with_capacity(units.len());
+        while let Some(Reverse(unit)) = ready.pop() {
+            order.push(units[unit]);
+            for &consumer in &consumers[unit] {
+                in_degree[consumer] -= 1;
+                if in_degree[consumer] == 0 {
+                    ready.push(Reverse(consumer));
+                }
+            }
+        }
+
+        if order.len() != units.len() {
+            let stuck = (0..units.len())
+                .filter(|unit| in_degree[*unit] > 0)
+                .find_map(|unit| match units[unit] {
+                    Unit::Group(index) => Some(index),
+                    Unit::Node(_) => None,
+                })
+                .expect("the graph itself is acyclic, so a group closes the cycle");
+            return Err(boundary(
+                groups[stuck].label(),
+                "reads a value computed from its own results outside the group",
+            ));
+        }
+        Ok(order)
+    }
+
+    fn unit_position(
+        &self,
+        node_id: NodeID,
+        unit_of: &HashMap<NodeID, Unit>,
+        position: &HashMap<Unit, usize>,
+    ) -> Option<usize> {
+        let unit = unit_of
+            .get(&node_id)
+            .copied()
+            .unwrap_or(Unit::Node(node_id));
+        position.get(&unit).copied()
+    }
+
+    /// The calling node and function for a group, plus the values the call
+    /// defines in the main graph.
+    fn fused_call(
+        &self,
+        index: usize,
+        group: &NodeGroup,
+    ) -> Result<(NodeProto, FunctionProto, Vec<ValueRef>), ValidationError> {
+        let members: HashSet<NodeID> = group.nodes().iter().copied().collect();
+        let body: Vec<&Node> = self
+            .graph
+            .topological_order()?
+            .into_iter()
+            .filter(|node_id| members.contains(node_id))
+            .map(|node_id| node_at(self.graph, node_id))
+            .collect();
+
+        let mut inputs: Vec<ValueRef> = Vec::new();
+        for node in &body {
+            for input in node.inputs() {
+                if !members.contains(&input.node()) && !inputs.contains(input) {
+                    inputs.push(*input);
+                }
+            }
+        }
+
+        // Results read after the group, in the order `Graph::extract` uses
+        let mut outputs: Vec<ValueRef> = self
+            .graph
+            .nodes()
+            .filter(|node| !members.contains(&node.id()))
+            .flat_map(|node| node.inputs().iter().copied())
+            .chain(self.graph.outputs().iter().map(|output| output.value()))
+            .filter(|value| members.contains(&value.node()))
+            .collect();
+        outputs.sort_by_key(|value| (value.node().id(), value.port()));
+        outputs.dedup();
+
+        let mut nodes = Vec::new();
+        for node in &body {
+            self.convert(node, &mut nodes)?;
+        }
+        let name = function_name(group.label(), index);
+        let input_names: Vec<String> = inputs.iter().map(|value| self.name(*value)).collect();
+        let output_names: Vec<String> = outputs.iter().map(|value| self.name(*value)).collect();
+
+        let mut opset_import = vec![OperatorSetIdProto {
+            domain: String::new(),
+            version: self.opset,
+        }];
+        if nodes.iter().any(|node| node.domain == XYNTRA_DOMAIN) {
+            opset_import.push(OperatorSetIdProto {
+                domain: XYNTRA_DOMAIN.to_string(),
+                version: XYNTRA_OPSET,
+            });
+        }
+
+        let call = NodeProto {
+            input: input_names.clone(),
+            output: output_names.clone(),
+            name: name.clone(),
+            op_type: name.clone(),
+            domain: XYNTRA_DOMAIN.to_string(),
+            ..NodeProto::default()
+        };
+        let function = FunctionProto {
+            name,
+            input: input_names,
+            output: output_names,
+            node: nodes,
+            doc_string: format!("Fused group '{}'", group.label()),
+            opset_import,
+            domain: XYNTRA_DOMAIN.to_string(),
+            ..FunctionProto::default()
+        };
+        Ok((call, function, outputs))
+    }
+
+    /// The values of a node that are given a name: output 0, outputs
+    /// something reads, and every piece of a Split.
+    fn output_values(&self, node: &Node) -> Vec<ValueRef> {
+        let read: HashSet<ValueRef> = self
+            .graph
+            .users(node.id())
+            .iter()
+            .filter_map(|user| self.graph.get_node(*user))
+            .flat_map(|user| user.inputs().iter().copied())
+            .chain(self.graph.outputs().iter().map(|output| output.value()))
+            .filter(|value| value.node() == node.id())
+            .collect();
+        (0..output_count(node))
+            .map(|port| node.id().output(port))
+            .filter(|value| {
+                value.port() == 0
+                    || read.contains(value)
+                    || matches!(node.op(), OpKind::Split { .. })
+            })
+            .collect()
+    }
+
+    /// Appends the ONNX nodes computing `node`.
+    fn convert(&self, node: &Node, nodes: &mut Vec<NodeProto>) -> Result<(), ValidationError> {
+        let mut outputs = vec![String::new(); output_count(node)];
+        for value in self.output_values(node) {
+            outputs[value.port()] = self.name(value);
+        }
+        while outputs.last().is_some_and(String::is_empty) {
+            outputs.pop();
+        }
+        let mut writer = NodeWriter {
+            name: generated_name(node.op().name(), node.id(), 0),
+            nodes,
+        };
+        let inputs: Vec<String> = node
+            .inputs()
+            .iter()
+            .map(|value| self.name(*value))
+            .collect();
+        let input_type = |index: usize| {
+            node.inputs()
+                .get(index)
+                .and_then(|value| self.graph.value_type(*value))
+        };
+        let dtype = input_type(0).map_or(DType::F32, TensorType::dtype);
+
+        let simple = match node.op() {
+            OpKind::Add => Some("Add"),
+            OpKind::Sub => Some("Sub"),
+            OpKind::Mul => Some("Mul"),
+            OpKind::Div => Some("Div"),
+            OpKind::Pow => Some("Pow"),
+            OpKind::Where => Some("Where"),
+            OpKind::Gelu => Some("Gelu"),
+            OpKind::Relu => Some("Relu"),
+            OpKind::Sigmoid => Some("Sigmoid"),
+            OpKind::Tanh => Some("Tanh"),
+            OpKind::Exp => Some("Exp"),
+            OpKind::Log => Some("Log"),
+            OpKind::Sqrt => Some("Sqrt"),
+            OpKind::Erf => Some("Erf"),
+            OpKind::GlobalAvgPool => Some("GlobalAveragePool"),
+            _ => None,
+        };
+        if let Some(op_type) = simple {
+            writer.node(op_type, inputs, outputs, vec![]);
+            return Ok(());
+        }
+
+        match node.op() {
+            OpKind::MatMul {
+                transpose_a,
+                transpose_b,
+            } => {
+                let ranks = (input_type(0), input_type(1));
+                let ranks = (
+                    ranks.0.map(|input_type| input_type.shape().rank()),
+                    ranks.1.map(|input_type| input_type.shape().rank()),
+                );
+                if !transpose_a && !transpose_b {
+                    writer.node("MatMul", inputs, outputs, vec![]);
+                } else if ranks == (Some(2), Some(2)) {
+                    let attributes = vec![
+                        int_attribute("transA", *transpose_a as i64),
+                        int_attribute("transB", *transpose_b as i64),
+                    ];
+                    writer.node("Gemm", inputs, outputs, attributes);
+                } else {
+                    let mut operands = inputs;
+                    for (index, transposed, rank, attribute) in [
+                        (0, *transpose_a, ranks.0, "transpose_a"),
+                        (1, *transpose_b, ranks.1, "transpose_b"),
+                    ] {
+                        if !transposed {
+                            continue;
+                        }
+                        let rank = rank.filter(|rank| *rank >= 2).ok_or_else(|| {
+                            ValidationError::InvalidAttribute {
+                                op: "MatMul".to_string(),
+                                attribute: attribute.to_string(),
+                                reason: "ONNX can only transpose an operand of known rank"
+                                    .to_string(),
+                            }
+                        })?;
+                        let mut perm: Vec<i64> = (0..rank as i64).collect();
+                        perm.swap(rank - 2, rank - 1);
+                        operands[index] = writer.step(
+                            "Transpose",
+                            vec![operands[index].clone()],
+                            attribute,
+                            vec![ints_attribute("perm", &perm)],
+                        );
+                    }
+                    writer.node("MatMul", operands, outputs, vec![]);
+                }
+            }
+            OpKind::Dropout { ratio } => {
+                let ratio = writer.constant("ratio", scalar(DType::F32, *ratio));
+                writer.node("Dropout", vec![inputs[0].clone(), ratio], outputs, vec![]);
+            }
+            OpKind::Softmax { axis } => {
+                writer.node(
+                    "Softmax",
+                    inputs,
+                    outputs,
+                    vec![int_attribute("axis", *axis)],
+                );
+            }
+            OpKind::LayerNorm { axis, epsilon } => {
+                let mut inputs = inputs;
+                if inputs.len() == 1 {
+                    // ONNX requires a scale, so a missing one is written as ones
+                    let dims = input_type(0).and_then(|input_type| {
+                        let shape = input_type.shape();
+                        let axis = shape.normalize_axis(*axis)?;
+                        shape.dims()[axis..].iter().map(Dim::as_static).collect()
+                    });
+                    let dims: Vec<usize> =
+                        dims.ok_or_else(|| ValidationError::InvalidAttribute {
+                            op: "LayerNorm".to_string(),
+                            attribute: "scale".to_string(),
+                            reason: "ONNX needs a scale, which can only be filled in when the \
+                                 normalised dimensions are static"
+                                .to_string(),
+                        })?;
+                    let count = dims.iter().product();
+                    let mut ones = filled(dtype, count, 1.0);
+                    ones.dims = dims.iter().map(|&dim| dim as i64).collect();
+                    inputs.push(writer.constant("scale", ones));
+                }
+                let attributes = vec![
+                    int_attribute("axis", *axis),
+                    float_attribute("epsilon", *epsilon),
+                ];
+                writer.node("LayerNormalization", inputs, outputs, attributes);
+            }
+            OpKind::Rsqrt => {
+                let root = writer.step("Sqrt", inputs, "sqrt", vec![]);
+                let one = writer.constant("one", scalar(dtype, 1.0));
+                writer.node("Div", vec![one, root], outputs, vec![]);
+            }
+            OpKind::Clip { min, max } => {
+                let mut operands = vec![inputs[0].clone()];
+                for (bound, name) in [(min, "min"), (max, "max")] {
+                    operands.push(match bound {
+                        Some(bound) => writer.constant(name, scalar(dtype, *bound)),
+                        None => String::new(),
+                    });
+                }
+                while operands.last().is_some_and(String::is_empty) {
+                    operands.pop();
+                }
+                writer.node("Clip", operands, outputs, vec![]);
+            }
+            OpKind::Cast { to } => {
+                let attributes = vec![int_attribute("to", elem_type(*to).into())];
+                writer.node("Cast", inputs, outputs, attributes);
+            }
+            OpKind::Reduce {
+                kind,
+                axes,
+                keepdims,
+            } => {
+                let mut operands = inputs;
+                if !axes.is_empty() {
+                    operands.push(writer.constant("axes", int64s(axes)));
+                }
+                let attributes = vec![int_attribute("keepdims", *keepdims as i64)];
+                writer.node(kind.op_name(), operands, outputs, attributes);
+            }
+            OpKind::ArgMax { axis, keepdims } => {
+                // ONNX returns INT64 indices, the IR 32-bit ones
+                let attributes = vec![
+                    int_attribute("axis", *axis),
+                    int_attribute("keepdims", *keepdims as i64),
+                ];
+                let indices = writer.step("ArgMax", inputs, "indices", attributes);
+                let to = int_attribute("to", data_type::INT32.into());
+                writer.node("Cast", vec![indices], outputs, vec![to]);
+            }
+            OpKind::Reshape { shape } => {
+                let shape = writer.constant("shape", int64s(shape));
+                writer.node("Reshape", vec![inputs[0].clone(), shape], outputs, vec![]);
+            }
+            OpKind::Transpose { perm } => {
+                let mut attributes = Vec::new();
+                if !perm.is_empty() {
+                    let perm: Vec<i64> = perm.iter().map(|&axis| axis as i64).collect();
+                    attributes.push(ints_attribute("perm", &perm));
+                }
+                writer.node("Transpose", inputs, outputs, attributes);
+            }
+            OpKind::Concat { axis } => {
+                writer.node(
+                    "Concat",
+                    inputs,
+                    outputs,
+                    vec![int_attribute("axis", *axis)],
+                );
+            }
+            OpKind::Split { axis, sizes } => {
+                let sizes: Vec<i64> = sizes.iter().map(|&size| size as i64).collect();
+                let sizes = writer.constant("split", int64s(&sizes));
+                let attributes = vec![int_attribute("axis", *axis)];
+                writer.node("Split", vec![inputs[0].clone(), sizes], outputs, attributes);
+            }
+            OpKind::Slice {
+                starts,
+                ends,
+                axes,
+                steps,
+            } => {
+                let mut operands = vec![
+                    inputs[0].clone(),
+                    writer.constant("starts", int64s(starts)),
+                    writer.constant("ends", int64s(ends)),
+                ];
+                if !axes.is_empty() || !steps.is_empty() {
+                    operands.push(match axes.is_empty() {
+                        true => String::new(),
+                        false => writer.constant("axes", int64s(axes)),
+                    });
+                }
+                if !steps.is_empty() {
+                    operands.push(writer.constant("steps", int64s(steps)));
+                }
+                writer.node("Slice", operands, outputs, vec![]);
+            }
+            OpKind::Gather { axis } => {
+                writer.node(
+                    "Gather",
+                    inputs,
+                    outputs,
+                    vec![int_attribute("axis", *axis)],
+                );
+            }
+            OpKind::Conv2d { window, groups } => {
+                let kernel = input_type(1).and_then(|weights| {
+                    let dims = weights.shape().dims();
+                    dims.get(2..4)?.iter().map(Dim::as_static).collect()
+                });
+                let mut attributes = window_attributes(window, kernel);
+                attributes.push(int_attribute("group", *groups as i64));
+                writer.node("Conv", inputs, outputs, attributes);
+            }
+            OpKind::DepthwiseConv { window } => {
+                let channels = input_type(0)
+                    .and_then(|data| data.shape().dims().get(1)?.as_static())
+                    .ok_or_else(|| ValidationError::InvalidAttribute {
+                        op: "DepthwiseConv".to_string(),
+                        attribute: "group".to_string(),
+                        reason: "ONNX needs one group per channel, so the channel count \
+                                 must be static"
+                            .to_string(),
+                    })?;
+                let mut attributes = window_attributes(window, None);
+                attributes.push(int_attribute("group", channels as i64));
+                writer.node("Conv", inputs, outputs, attributes);
+            }
+            OpKind::MaxPool {
+                kernel_shape,
+                window,
+            } => {
+                let attributes = window_attributes(window, Some(kernel_shape.to_vec()));
+                writer.node("MaxPool", inputs, outputs, attributes);
+            }
+            OpKind::AvgPool {
+                kernel_shape,
+                window,
+                count_include_pad,
+            } => {
+                let mut attributes = window_attributes(window, Some(kernel_shape.to_vec()));
+                // Average pooling takes dilations only from opset 19
+                if window.dilations == Window2d::default().dilations {
+                    attributes.retain(|attribute| attribute.name != "dilations");
+                }
+                attributes.push(int_attribute(
+                    "count_include_pad",
+                    *count_include_pad as i64,
+                ));
+                writer.node("AveragePool", inputs, outputs, attributes);
+            }
+            OpKind::BatchNorm { epsilon } => {
+                let attributes = vec![float_attribute("epsilon", *epsilon)];
+                writer.node("BatchNormalization", inputs, outputs, attributes);
+            }
+            OpKind::Custom { name, attributes } => {
+                writer.nodes.push(NodeProto {
+                    input: inputs,
+                    output: outputs,
+                    name: writer.name.clone(),
+                    op_type: name.clone(),
+                    domain: XYNTRA_DOMAIN.to_string(),
+                    attribute: custom_attributes(attributes),
+                    ..NodeProto::default()
+                });
+            }
+            OpKind::Input { .. } | OpKind::Constant { .. } => {}
+            _ => unreachable!("{} is written as a simple op", node.op().name()),
+        }
+        Ok(())
+    }
+}
+
+/// Appends the nodes written for one IR node, named after it.
+struct NodeWriter<'a> {
+    name: String,
+    nodes: &'a mut Vec<NodeProto>,
+}
+
+impl NodeWriter<'_> {
+    /// The node producing the IR node's results.
+    fn node(
+        &mut self,
+        op_type: &str,
+        inputs: Vec<String>,
+        outputs: Vec<String>,
+        attributes: Vec<AttributeProto>,
+    ) {
+        let name = self.name.clone();
+        self.nodes
+            .push(onnx_node(op_type, name, inputs, outputs, attributes));
+    }
+
+    /// An intermediate node with one result, named like that result after
+    /// the IR node and `suffix`. Returns the result's name.
+    fn step(
+        &mut self,
+        op_type: &str,
+        inputs: Vec<String>,
+        suffix: &str,
+        attributes: Vec<AttributeProto>,
+    ) -> String {
+        let name = format!("{}_{suffix}", self.name);
+        self.nodes.push(onnx_node(
+            op_type,
+            name.clone(),
+            inputs,
+            vec![name.clone()],
+            attributes,
+        ));
+        name
+    }
+
+    /// A `Constant` node holding a parameter ONNX takes as an input.
+    fn constant(&mut self, parameter: &str, tensor: TensorProto) -> String {
+        let value = AttributeProto {
+            name: "value".to_string(),
+            attribute_type: attribute_type::TENSOR,
+            t: Some(tensor),
+            ..AttributeProto::default()
+        };
+        self.step("Constant", vec![], parameter, vec![value])
+    }
+}
+
+fn onnx_node(
+    op_type: &str,
+    name: String,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    attributes: Vec<AttributeProto>,
+) -> NodeProto {
+    NodeProto {
+        input: inputs,
+        output: outputs,
+        name,
+        op_type: op_type.to_string(),
+        attribute: attributes,
+        ..NodeProto::default()
+    }
+}
+
+/// Name of every value: graph inputs and constants keep theirs, a value
+/// exposed as a graph output takes the first output's name, and the rest are
+/// named by [`generated_name`], with a numeric suffix if that is taken.
+fn value_names(graph: &Graph) -> HashMap<ValueRef, String> {
+    let mut names = HashMap::new();
+    for node in graph.nodes() {
+        if let OpKind::Input { name } | OpKind::Constant { name } = node.op() {
+            names.insert(node.id().output(0), name.clone());
+        }
+    }
+    for output in graph.outputs() {
+        names
+            .entry(output.value())
+            .or_insert_with(|| output.name().to_string());
+    }
+
+    let mut taken: HashSet<String> = names.values().cloned().collect();
+    for node in graph.nodes() {
+        for port in 0..output_count(node) {
+            let value = node.id().output(port);
+            if names.contains_key(&value) {
+                continue;
+            }
+            let base = generated_name(node.op().name(), node.id(), port);
+            let mut name = base.clone();
+            let mut suffix = 1;
+            while taken.contains(&name) {
+                name = format!("{base}_{suffix}");
+                suffix += 1;
+            }
+            taken.insert(name.clone());
+            names.insert(value, name);
+        }
+    }
+    names
+}
+
+/// `Relu_3` for output 0 of node 3, a Relu, and `Split_4_1` for output 1 of
+/// a Split, which keeps names readable in viewers.
+fn generated_name(op: &str, node_id: NodeID, port: usize) -> String {
+    match port {
+        0 => format!("{op}_{}", node_id.id()),
+        port => format!("{op}_{}_{port}", node_id.id()),
+    }
+}
+
+/// A group label as an op type: letters, digits and underscores, then the
+/// group's position so every group gets its own function.
+fn function_name(label: &str, index: usize) -> String {
+    let label: String = label
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("{label}_{index}")
+}
+
+/// Number of values a node produces; `Custom` ops declare theirs through
+/// their output types.
+fn output_count(node: &Node) -> usize {
+    match node.op() {
+        OpKind::Custom { .. } => node.output_types().len().max(1),
+        op => op.output_count(),
+    }
+}
+
+fn node_at(graph: &Graph, node_id: NodeID) -> &Node {
+    graph
+        .get_node(node_id)
+        .expect("node IDs come from the graph")
+}
+
+fn is_boundary(node: &Node) -> bool {
+    matches!(node.op(), OpKind::Input { .. } | OpKind::Constant { .. })
+}
+
+/// The lowest default-domain opset that can express `op`.
+fn required_opset(op: &OpKind) -> i64 {
+    match op {
+        OpKind::Gelu => introduced_in("Gelu").expect("Gelu has an opset"),
+        OpKind::AvgPool { window, .. } if window.dilations != Window2d::default().dilations => 19,
+        _ => EXPORT_OPSET,
+    }
+}
+
+fn elem_type(dtype: DType) -> i32 {
+    match dtype {
+        DType::F32 => data_type::FLOAT,
+        DType::F16 => data_type::FLOAT16,
+        DType::BF16 => data_type::BFLOAT16,
+        DType::I32 => data_type::INT32,
+        DType::I8 => data_type::INT8,
+        DType::Bool => data_type::BOOL,
+    }
+}
+
+fn static_dim(dim: &Dim) -> i64 {
+    dim.as_static().expect("constants have static shapes") as i64
+}
+
+fn value_info(name: &str, value_type: &TensorType) -> ValueInfoProto {
+    let dim = value_type
+        .shape()
+        .dims()
+        .iter()
+        .map(|dim| match dim {
+            Dim::Static(value) => Dimension::Value(*value as i64),
+            // A `dim_param` is one name; expressions such as `2*seq + 1` have
+            // no ONNX spelling and are left unknown
+            Dim::Symbolic(expr) => match expr.symbols().collect::<Vec<_>>()[..] {
+                [name] if *expr == DimExpr::symbol(name) => Dimension::Param(name.to_string()),
+                _ => Dimension::Unknown,
+            },
+        })
+        .collect();
+    ValueInfoProto {
+        name: name.to_string(),
+        value_type: Some(TypeProto {
+            tensor_type: Some(TensorTypeProto {
+                elem_type: elem_type(value_type.dtype()),
+                shape: Some(TensorShapeProto { dim }),
+            }),
+            ..TypeProto::default()
+        }),
+        ..ValueInfoProto::default()
+    }
+}
+
+/// A 1D INT64 tensor, the type ONNX uses for shapes, axes and bounds.
+fn int64s(values: &[i64]) -> TensorProto {
+    TensorProto {
+        dims: vec![values.len() as i64],
+        data_type: data_type::INT64,
+        raw_data: values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect(),
+        ..TensorProto::default()
+    }
+}
+
+fn scalar(dtype: DType, value: f32) -> TensorProto {
+    filled(dtype, 1, value)
+}
+
+/// `count` copies of `value` in `dtype`, with no dimensions set.
+fn filled(dtype: DType, count: usize, value: f32) -> TensorProto {
+    let element: Vec<u8> = match dtype {
+        DType::F32 => value.to_le_bytes().to_vec(),
+        DType::F16 => f32_to_f16(value).to_le_bytes().to_vec(),
+        // Truncates; the bounds written this way are small round numbers
+        DType::BF16 => ((value.to_bits() >> 16) as u16).to_le_bytes().to_vec(),
+        DType::I32 => (value as i32).to_le_bytes().to_vec(),
+        DType::I8 => vec![value as i8 as u8],
+        DType::Bool => vec![(value != 0.0) as u8],
+    };
+    TensorProto {
+        data_type: elem_type(dtype),
+        raw_data: element.repeat(count),
+        ..TensorProto::default()
+    }
+}
+
+fn window_attributes(window: &Window2d, kernel_shape: Option<Vec<usize>>) -> Vec<AttributeProto> {
+    let as_ints = |values: &[usize]| values.iter().map(|&value| value as i64).collect::<Vec<_>>();
+    let mut attributes = Vec::new();
+    if let Some(kernel_shape) = kernel_shape {
+        attributes.push(ints_attribute("kernel_shape", &as_ints(&kernel_shape)));
+    }
+    attributes.push(ints_attribute("strides", &as_ints(&window.strides)));
+    attributes.push(ints_attribute("pads", &as_ints(&window.pads)));
+    attributes.push(ints_attribute("dilations", &as_ints(&window.dilations)));
+    attributes
+}
+
+fn custom_attributes(attributes: &Attributes) -> Vec<AttributeProto> {
+    attributes
+        .iter()
+        .map(|(name, value)| match value {
+            AttributeValue::Int(value) => int_attribute(name, *value),
+            AttributeValue::Float(value) => float_attribute(name, *value),
+            AttributeValue::String(value) => AttributeProto {
+                name: name.to_string(),
+                attribute_type: attribute_type::STRING,
+                s: value.as_bytes().to_vec(),
+                ..AttributeProto::default()
+            },
+            AttributeValue::Ints(values) => ints_attribute(name, values),
+            AttributeValue::Floats(values) => AttributeProto {
+                name: name.to_string(),
+                attribute_type: attribute_type::FLOATS,
+                floats: values.clone(),
+                ..AttributeProto::default()
+            },
+            AttributeValue::Strings(values) => AttributeProto {
+                name: name.to_string(),
+                attribute_type: attribute_type::STRINGS,
+                strings: values
+                    .iter()
+                    .map(|value| value.as_bytes().to_vec())
+                    .collect(),
+                ..AttributeProto::default()
+            },
+        })
+        .collect()
+}
+
+fn int_attribute(name: &str, value: i64) -> AttributeProto {
+    AttributeProto {
+        name: name.to_string(),
+        attribute_type: attribute_type::INT,
+        i: value,
+        ..AttributeProto::default()
+    }
+}
+
+fn float_attribute(name: &str, value: f32) -> AttributeProto {
+    AttributeProto {
+        name: name.to_string(),
+        attribute_type: attribute_type::FLOAT,
+        f: value,
+        ..AttributeProto::default()
+    }
+}
+
+fn ints_attribute(name: &str, values: &[i64]) -> AttributeProto {
+    AttributeProto {
+        name: name.to_string(),
+        attribute_type: attribute_type::INTS,
+        ints: values.to_vec(),
+        ..AttributeProto::default()
+    }
+}
+
+fn boundary(name: &str, reason: &str) -> ValidationError {
+    ValidationError::InvalidGraphBoundary {
+        name: name.to_string(),
+        reason: reason.to_string(),
+    }
+}
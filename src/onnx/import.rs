@@ -0,0 +1,1455 @@
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use crate::{
+    ir::{
+        attributes::{AttributeValue, Attributes},
+        errors::{ParsingError, XyntraError},
+        graph::Graph,
+        shape_inference::infer_output_types,
+        symbolic::{Dim, ShapeConstraints},
+        tensor::{Tensor, f16_to_f32},
+        types::{DType, NodeID, OpKind, ReduceKind, TensorShape, TensorType, ValueRef, Window2d},
+        validation::GraphValidator,
+    },
+    onnx::{
+        opset::{OpsetImports, XYNTRA_DOMAIN, introduced_in},
+        proto::{
+            AttributeProto, Dimension, FunctionProto, GraphProto, ModelProto, NodeProto,
+            TensorProto, ValueInfoProto, attribute_type, data_type,
+        },
+    },
+};
+
+/// Reads a `.onnx` file and converts its main graph with [`import_onnx`].
+pub fn load_onnx(path: impl AsRef<Path>) -> Result<Graph, XyntraError> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path).map_err(|error| XyntraError::from_io(path, "read", error))?;
+    import_onnx(&bytes).map_err(XyntraError::Parsing)
+}
+
+/// Decodes a serialised `ModelProto` and converts its main graph with
+/// [`import_model`].
+pub fn import_onnx(bytes: &[u8]) -> Result<Graph, ParsingError> {
+    Ok(import_model(&ModelProto::decode(bytes)?)?.into_graph())
+}
+
+/// A converted model together with the operator sets it was written against.
+pub struct OnnxModel {
+    graph: Graph,
+    opsets: OpsetImports,
+}
+
+impl OnnxModel {
+    pub fn graph(&self) -> &Graph {
+        &self.graph
+    }
+
+    pub fn into_graph(self) -> Graph {
+        self.graph
+    }
+
+    pub fn opsets(&self) -> &OpsetImports {
+        &self.opsets
+    }
+}
+
+/// Converts the main graph of a model into the IR.
+///
+/// Graph inputs keep their declared types, with `dim_param` dimensions
+/// becoming symbols and unnamed unknown dimensions fresh `unk_N` symbols,
+/// skipping any name a `dim_param` already uses.
+/// Initializers and `Constant` nodes become constants when an op reads them as
+/// data; when an op takes them as parameters (a Reshape target shape, Slice
+/// bounds, Split sizes, ...) they are folded into the op's attributes instead,
+/// and initializers nothing reads are dropped. Node output types are taken
+/// from `value_info` and the graph outputs where the importer cannot infer
+/// them.
+///
+/// Each node is read as its op is defined in the model's default-domain
+/// opset, which must lie in [`SUPPORTED_OPSETS`](crate::onnx::opset::SUPPORTED_OPSETS).
+/// Parameters that moved from attributes to inputs (Split sizes, Reduce axes,
+/// Clip bounds, ...) must be where that opset puts them, and defaults that
+/// changed (Softmax's axis) follow the opset, so every version of an op lands
+/// on the same `OpKind`.
+///
+/// Calls to model-local functions, such as the fused groups the exporter
+/// writes, are replaced by the function's nodes. Other nodes of the
+/// [`XYNTRA_DOMAIN`] become `Custom` ops with their attributes.
+///
+/// The IR has no 64-bit integers, so INT64 values become `I32`; INT64 data
+/// outside the i32 range is rejected. Ops outside the supported set are
+/// reported as `UnsupportedOperation` with their domain-qualified type, and
+/// attribute settings the IR cannot express as `InvalidFormat`.
+pub fn import_model(model: &ModelProto) -> Result<OnnxModel, ParsingError> {
+    let graph = model
+        .graph
+        .as_ref()
+        .ok_or_else(|| ParsingError::MissingRequiredField {
+            field: "ModelProto.graph".to_string(),
+        })?;
+
+    let opsets = OpsetImports::from_protos(&model.opset_import)?;
+    let nodes = inline_functions(&graph.node, &model.functions, &opsets)?;
+
+    let mut importer = Importer::new(graph, &opsets)?;
+    for node in nodes.iter() {
+        importer.convert_node(node)?;
+    }
+    Ok(OnnxModel {
+        graph: importer.finish(graph)?,
+        opsets,
+    })
+}
+
+struct Importer<'m> {
+    graph: Graph,
+    opsets: &'m OpsetImports,
+    /// Version of the default domain, which every supported op belongs to.
+    opset: i64,
+    /// IR value of every ONNX value converted so far.
+    values: HashMap<&'m str, ValueRef>,
+    /// Initializers and `Constant` outputs, added to the graph only once
+    /// something reads them as data.
+    constants: HashMap<&'m str, Cow<'m, TensorProto>>,
+    /// ONNX outputs the IR has no counterpart for, such as Dropout's mask,
+    /// with a description for the error when something reads them.
+    unsupported: HashMap<&'m str, String>,
+    /// Declared types of intermediate values and graph outputs.
+    value_info: HashMap<&'m str, &'m ValueInfoProto>,
+    unknown_dims: usize,
+    /// Every `dim_param` the graph declares, which fresh `unk_N` names skip.
+    dim_params: HashSet<&'m str>,
+    /// Equalities found while inferring types, which the import discards.
+    constraints: ShapeConstraints,
+}
+
+impl<'m> Importer<'m> {
+    fn new(graph: &'m GraphProto, opsets: &'m OpsetImports) -> Result<Self, ParsingError> {
+        let mut importer = Importer {
+            graph: Graph::new(),
+            opsets,
+            opset: opsets
+                .onnx_version()
+                .expect("the default domain is imported"),
+            values: HashMap::new(),
+            constants: graph
+                .initializer
+                .iter()
+                .map(|tensor| (tensor.name.as_str(), Cow::Borrowed(tensor)))
+                .collect(),
+            unsupported: HashMap::new(),
+            value_info: graph
+                .value_info
+                .iter()
+                .chain(&graph.output)
+                .map(|info| (info.name.as_str(), info))
+                .collect(),
+            unknown_dims: 0,
+            dim_params: graph
+                .input
+                .iter()
+                .chain(&graph.output)
+                .chain(&graph.value_info)
+                .filter_map(|info| {
+                    info.value_type
+                        .as_ref()?
+                        .tensor_type
+                        .as_ref()?
+                        .shape
+                        .as_ref()
+                })
+                .flat_map(|shape| &shape.dim)
+                .filter_map(|dim| match dim {
+                    Dimension::Param(name) => Some(name.as_str()),
+                    _ => None,
+                })
+                .collect(),
+            constraints: ShapeConstraints::new(),
+        };
+
+        // Before IR version 4 initializers are listed as graph inputs too
+        for input in &graph.input {
+            if importer.constants.contains_key(input.name.as_str()) {
+                continue;
+            }
+            let input_type = importer.tensor_type(input)?.ok_or_else(|| {
+                unsupported(format!(
+                    "graph input '{}' has no tensor type with a known rank",
+                    input.name
+                ))
+            })?;
+            let node_id = importer.graph.add_input(&input.name, input_type);
+            importer.values.insert(&input.name, node_id.into());
+        }
+
+        Ok(importer)
+    }
+
+    fn finish(mut self, graph: &'m GraphProto) -> Result<Graph, ParsingError> {
+        for output in &graph.output {
+            let value = self.value(&output.name, || format!("graph output '{}'", output.name))?;
+            self.graph
+                .add_output(&output.name, value)
+                .map_err(|error| malformed(error.to_string()))?;
+        }
+
+        GraphValidator::new(&self.graph)
+            .validate()
+            .map_err(|errors| {
+                let errors: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+                unsupported(format!("graph is invalid: {}", errors.join("; ")))
+            })?;
+        Ok(self.graph)
+    }
+
+    fn convert_node(&mut self, node: &'m NodeProto) -> Result<(), ParsingError> {
+        let custom = match node.domain.as_str() {
+            "" | "ai.onnx" => false,
+            domain if self.opsets.version(domain).is_none() => {
+                return Err(malformed(format!(
+                    "{} is in domain '{domain}', which the model does not import",
+                    describe(node)
+                )));
+            }
+            XYNTRA_DOMAIN => true,
+            domain => {
+                return Err(ParsingError::UnsupportedOperation {
+                    op_name: format!("{domain}.{}", node.op_type),
+                });
+            }
+        };
+        if !custom
+            && let Some(since) = introduced_in(&node.op_type)
+            && self.opset < since
+        {
+            return Err(malformed(format!(
+                "{} uses an op introduced in opset {since}, but the model imports opset {}",
+                describe(node),
+                self.opset
+            )));
+        }
+
+        let outputs = match node.op_type.as_str() {
+            _ if custom => {
+                let op = OpKind::Custom {
+                    name: node.op_type.clone(),
+                    attributes: custom_attributes(node)?,
+                };
+                let inputs = self.all_inputs(node)?;
+                let node_id = self.add_node(op, inputs);
+                self.declare_types(node, node_id)?;
+                (0..node.output.len())
+                    .map(|port| node_id.output(port))
+                    .collect()
+            }
+            "Constant" => return self.constant_node(node),
+            // Later readers see the input value itself
+            "Identity" => vec![self.input(node, 0)?],
+            "Gemm" => {
+                let node_id = self.gemm(node)?;
+                self.declare_types(node, node_id)?;
+                vec![node_id.into()]
+            }
+            _ => {
+                let (op, inputs) = self.convert_op(node)?;
+                let output_count = op.output_count();
+                let node_id = self.add_node(op, inputs);
+                self.declare_types(node, node_id)?;
+                (0..output_count).map(|port| node_id.output(port)).collect()
+            }
+        };
+
+        for (index, name) in node.output.iter().enumerate() {
+            if name.is_empty() {
+                continue;
+            }
+            match outputs.get(index) {
+                Some(value) => {
+                    self.values.insert(name, *value);
+                }
+                None => {
+                    self.unsupported
+                        .insert(name, format!("output {index} of {}", describe(node)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The op for a node that maps onto a single IR node, with its data inputs.
+    fn convert_op(&mut self, node: &'m NodeProto) -> Result<(OpKind, Vec<ValueRef>), ParsingError> {
+        let attributes = NodeAttributes { node };
+        let simple = match node.op_type.as_str() {
+            "Add" => Some(OpKind::Add),
+            "Sub" => Some(OpKind::Sub),
+            "Mul" => Some(OpKind::Mul),
+            "Div" => Some(OpKind::Div),
+            "Pow" => Some(OpKind::Pow),
+            "Where" => Some(OpKind::Where),
+            "Relu" => Some(OpKind::Relu),
+            "Sigmoid" => Some(OpKind::Sigmoid),
+            "Tanh" => Some(OpKind::Tanh),
+            "Exp" => Some(OpKind::Exp),
+            "Log" => Some(OpKind::Log),
+            "Sqrt" => Some(OpKind::Sqrt),
+            "Erf" => Some(OpKind::Erf),
+            "GlobalAveragePool" => Some(OpKind::GlobalAvgPool),
+            _ => None,
+        };
+        if let Some(op) = simple {
+            return Ok((op, self.all_inputs(node)?));
+        }
+
+        let op = match node.op_type.as_str() {
+            "MatMul" => OpKind::matmul(),
+            "Gelu" => {
+                let approximate = attributes.string("approximate", "none")?;
+                if approximate != "none" {
+                    return Err(unsupported(format!(
+                        "{}: approximate = \"{approximate}\" is not supported",
+                        describe(node)
+                    )));
+                }
+                OpKind::Gelu
+            }
+            "Dropout" => {
+                if self.constant_ints(node, 2)?.is_some_and(|mode| mode != [0]) {
+                    return Err(unsupported(format!(
+                        "{}: training mode is not supported",
+                        describe(node)
+                    )));
+                }
+                let ratio = self.moved_float(node, 1, "ratio", 12)?.unwrap_or(0.5);
+                return Ok((OpKind::Dropout { ratio }, vec![self.input(node, 0)?]));
+            }
+            "Softmax" if self.opset >= 13 => OpKind::Softmax {
+                axis: attributes.int("axis", -1)?,
+            },
+            "Softmax" => {
+                // Older Softmax flattens the input at `axis` and normalises
+                // over every dimension from there on, which is a single-axis
+                // softmax only when `axis` is the last dimension
+                let axis = attributes.int("axis", 1)?;
+                let rank = self.input_rank(node, 0).ok_or_else(|| {
+                    unsupported(format!(
+                        "{}: Softmax before opset 13 needs the input rank to be known",
+                        describe(node)
+                    ))
+                })?;
+                if axis != -1 && axis != rank as i64 - 1 {
+                    return Err(unsupported(format!(
+                        "{}: Softmax before opset 13 normalises over every dimension \
+                         from axis {axis}, which a rank {rank} input cannot do on one axis",
+                        describe(node)
+                    )));
+                }
+                OpKind::Softmax { axis }
+            }
+            "LayerNormalization" => OpKind::LayerNorm {
+                axis: attributes.int("axis", -1)?,
+                epsilon: attributes.float("epsilon", 1e-5)?,
+            },
+            "Clip" => {
+                let min = self.moved_float(node, 1, "min", 11)?;
+                let max = self.moved_float(node, 2, "max", 11)?;
+                return Ok((OpKind::Clip { min, max }, vec![self.input(node, 0)?]));
+            }
+            "Cast" => {
+                let to = attributes.required_int("to")?;
+                OpKind::Cast {
+                    to: dtype(to as i32, &format!("the target of {}", describe(node)))?,
+                }
+            }
+            "ReduceSum" | "ReduceMean" | "ReduceMax" | "ReduceMin" => {
+                let kind =
+                    ReduceKind::from_op_name(&node.op_type).expect("matched a reduction name");
+                let since = if kind == ReduceKind::Sum { 13 } else { 18 };
+                let axes = self.moved_ints(node, 1, "axes", since)?.unwrap_or_default();
+                if axes.is_empty() && attributes.int("noop_with_empty_axes", 0)? != 0 {
+                    return Err(unsupported(format!(
+                        "{}: noop_with_empty_axes = 1 is not supported",
+                        describe(node)
+                    )));
+                }
+                let op = OpKind::Reduce {
+                    kind,
+                    axes,
+                    keepdims: attributes.int("keepdims", 1)? != 0,
+                };
+                return Ok((op, vec![self.input(node, 0)?]));
+            }
+            "ArgMax" => {
+                if attributes.int("select_last_index", 0)? != 0 {
+                    return Err(unsupported(format!(
+                        "{}: select_last_index = 1 is not supported",
+                        describe(node)
+                    )));
+                }
+                OpKind::ArgMax {
+                    axis: attributes.int("axis", 0)?,
+                    keepdims: attributes.int("keepdims", 1)? != 0,
+                }
+            }
+            "Reshape" => {
+                // The shape has been an input since opset 5
+                let shape = self
+                    .constant_ints(node, 1)?
+                    .ok_or_else(|| malformed(format!("{} has no target shape", describe(node))))?;
+                if shape.contains(&0) && attributes.int("allowzero", 0)? != 0 {
+                    return Err(unsupported(format!(
+                        "{}: allowzero = 1 is not supported",
+                        describe(node)
+                    )));
+                }
+                return Ok((OpKind::Reshape { shape }, vec![self.input(node, 0)?]));
+            }
+            "Transpose" => OpKind::Transpose {
+                perm: usizes(node, "perm", attributes.ints("perm")?.unwrap_or_default())?,
+            },
+            "Concat" => OpKind::Concat {
+                axis: attributes.required_int("axis")?,
+            },
+            "Split" => {
+                let axis = attributes.int("axis", 0)?;
+                let data = self.input(node, 0)?;
+                let sizes = match self.moved_ints(node, 1, "split", 13)? {
+                    Some(sizes) => sizes,
+                    None => self.even_split(node, data, axis)?,
+                };
+                let sizes = usizes(node, "split", sizes)?;
+                return Ok((OpKind::Split { axis, sizes }, vec![data]));
+            }
+            "Slice" => {
+                // Steps arrived with the move to inputs, so older slices have none
+                let mut bounds = Vec::new();
+                for (index, name) in ["starts", "ends", "axes", "steps"].into_iter().enumerate() {
+                    bounds.push(
+                        self.moved_ints(node, index + 1, name, 10)?
+                            .unwrap_or_default(),
+                    );
+                }
+                let [starts, ends, axes, steps] =
+                    <[Vec<i64>; 4]>::try_from(bounds).expect("four bounds");
+                let op = OpKind::Slice {
+                    starts,
+                    ends,
+                    axes,
+                    steps,
+                };
+                return Ok((op, vec![self.input(node, 0)?]));
+            }
+            "Gather" => OpKind::Gather {
+                axis: attributes.int("axis", 0)?,
+            },
+            "Conv" => {
+                self.require_2d(node, &attributes)?;
+                OpKind::Conv2d {
+                    window: window(node, &attributes)?,
+                    groups: usizes(node, "group", vec![attributes.int("group", 1)?])?[0],
+                }
+            }
+            "MaxPool" | "AveragePool" => {
+                if attributes.int("ceil_mode", 0)? != 0 {
+                    return Err(unsupported(format!(
+                        "{}: ceil_mode = 1 is not supported",
+                        describe(node)
+                    )));
+                }
+                let kernel_shape = attributes
+                    .ints("kernel_shape")?
+                    .ok_or_else(|| malformed(format!("{} has no kernel_shape", describe(node))))?;
+                let kernel_shape = usizes(node, "kernel_shape", kernel_shape)?
+                    .try_into()
+                    .map_err(|_| only_2d(node))?;
+                let window = window(node, &attributes)?;
+                if node.op_type == "MaxPool" {
+                    OpKind::MaxPool {
+                        kernel_shape,
+                        window,
+                    }
+                } else {
+                    OpKind::AvgPool {
+                        kernel_shape,
+                        window,
+                        count_include_pad: attributes.int("count_include_pad", 0)? != 0,
+                    }
+                }
+            }
+            "BatchNormalization" => {
+                if attributes.int("training_mode", 0)? != 0 {
+                    return Err(unsupported(format!(
+                        "{}: training mode is not supported",
+                        describe(node)
+                    )));
+                }
+                // Opsets 7 and 8 could normalise per element instead of per channel
+                if self.opset < 9 && attributes.int("spatial", 1)? == 0 {
+                    return Err(unsupported(format!(
+                        "{}: spatial = 0 is not supported",
+                        describe(node)
+                    )));
+                }
+                OpKind::BatchNorm {
+                    epsilon: attributes.float("epsilon", 1e-5)?,
+                }
+            }
+            op_type => {
+                return Err(ParsingError::UnsupportedOperation {
+                    op_name: op_type.to_string(),
+                });
+            }
+        };
+
+        Ok((op, self.all_inputs(node)?))
+    }
+
+    /// `alpha * A' * B' + beta * C` as a MatMul plus an Add for the bias.
+    fn gemm(&mut self, node: &'m NodeProto) -> Result<NodeID, ParsingError> {
+        let attributes = NodeAttributes { node };
+        let has_bias = node.input.get(2).is_some_and(|name| !name.is_empty());
+        if attributes.float("alpha", 1.0)? != 1.0
+            || (has_bias && attributes.float("beta", 1.0)? != 1.0)
+        {
+            return Err(unsupported(format!(
+                "{}: alpha and beta other than 1 are not supported",
+                describe(node)
+            )));
+        }
+
+        let op = OpKind::MatMul {
+            transpose_a: attributes.int("transA", 0)? != 0,
+            transpose_b: attributes.int("transB", 0)? != 0,
+        };
+        let inputs = vec![self.input(node, 0)?, self.input(node, 1)?];
+        let bias = self.optional_input(node, 2)?;
+        let matmul = self.add_node(op, inputs);
+        Ok(match bias {
+            Some(bias) => self.add_node(OpKind::Add, vec![matmul.into(), bias]),
+            None => matmul,
+        })
+    }
+
+    fn constant_node(&mut self, node: &'m NodeProto) -> Result<(), ParsingError> {
+        let name = node
+            .output
+            .first()
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| malformed(format!("{} has no output", describe(node))))?;
+        let attributes = NodeAttributes { node };
+
+        let tensor = if let Some(tensor) = attributes.tensor("value")? {
+            Cow::Borrowed(tensor)
+        } else if let Some(value) = attributes.optional_float("value_float")? {
+            Cow::Owned(TensorProto {
+                data_type: data_type::FLOAT,
+                float_data: vec![value],
+                ..TensorProto::default()
+            })
+        } else if let Some(values) = attributes.floats("value_floats")? {
+            Cow::Owned(TensorProto {
+                dims: vec![values.len() as i64],
+                data_type: data_type::FLOAT,
+                float_data: values,
+                ..TensorProto::default()
+            })
+        } else if let Some(value) = attributes.optional_int("value_int")? {
+            Cow::Owned(TensorProto {
+                data_type: data_type::INT64,
+                int64_data: vec![value],
+                ..TensorProto::default()
+            })
+        } else if let Some(values) = attributes.ints("value_ints")? {
+            Cow::Owned(TensorProto {
+                dims: vec![values.len() as i64],
+                data_type: data_type::INT64,
+                int64_data: values,
+                ..TensorProto::default()
+            })
+        } else {
+            return Err(unsupported(format!(
+                "{} has no tensor, float or int value",
+                describe(node)
+            )));
+        };
+
+        self.constants.insert(name, tensor);
+        Ok(())
+    }
+
+    /// Adds a node and infers its output types when its inputs' types are
+    /// known, so later nodes can rely on ranks and dimensions.
+    fn add_node(&mut self, op: OpKind, inputs: Vec<ValueRef>) -> NodeID {
+        let input_types: Option<Vec<TensorType>> = inputs
+            .iter()
+            .map(|input| self.graph.value_type(*input).cloned())
+            .collect();
+        let output_types = input_types.and_then(|input_types| {
+            let input_types: Vec<&TensorType> = input_types.iter().collect();
+            infer_output_types(&op, &input_types, &mut self.constraints).ok()
+        });
+
+        let node_id = self.graph.add_node_with_values(op, inputs);
+        if let Some(output_types) = output_types {
+            let _ = self.graph.set_output_types(node_id, output_types);
+        }
+        node_id
+    }
+
+    /// Sets the output types of a node whose types could not be inferred from
+    /// the declared types of the ONNX outputs, when every output has one.
+    /// `Custom` ops get one type per ONNX output.
+    fn declare_types(&mut self, node: &'m NodeProto, node_id: NodeID) -> Result<(), ParsingError> {
+        let Some(ir_node) = self.graph.get_node(node_id) else {
+            return Ok(());
+        };
+        if !ir_node.output_types().is_empty() {
+            return Ok(());
+        }
+        let output_count = match ir_node.op() {
+            OpKind::Custom { .. } => node.output.len(),
+            op => op.output_count(),
+        };
+        let mut output_types = Vec::new();
+        for port in 0..output_count {
+            let info = node
+                .output
+                .get(port)
+                .and_then(|name| self.value_info.get(name.as_str()).copied());
+            match info {
+                Some(info) => match self.tensor_type(info)? {
+                    Some(output_type) => output_types.push(output_type),
+                    None => return Ok(()),
+                },
+                None => return Ok(()),
+            }
+        }
+
+        self.graph
+            .set_output_types(node_id, output_types)
+            .map_err(|error| malformed(error.to_string()))
+    }
+
+    /// The declared tensor type of a value, or `None` when its type or rank
+    /// is not given.
+    fn tensor_type(&mut self, info: &ValueInfoProto) -> Result<Option<TensorType>, ParsingError> {
+        let Some(tensor_type) = info
+            .value_type
+            .as_ref()
+            .and_then(|value_type| value_type.tensor_type.as_ref())
+        else {
+            return Ok(None);
+        };
+        let Some(shape) = &tensor_type.shape else {
+            return Ok(None);
+        };
+
+        let what = format!("value '{}'", info.name);
+        let dtype = dtype(tensor_type.elem_type, &what)?;
+        let mut dims = Vec::new();
+        for dim in &shape.dim {
+            dims.push(match dim {
+                Dimension::Value(value) => usize::try_from(*value)
+                    .map(Dim::from)
+                    .map_err(|_| malformed(format!("{what} has dimension {value}")))?,
+                Dimension::Param(name) => Dim::symbol(name),
+                Dimension::Unknown => loop {
+                    let name = format!("unk_{}", self.unknown_dims);
+                    self.unknown_dims += 1;
+                    if !self.dim_params.contains(name.as_str()) {
+                        break Dim::symbol(name);
+                    }
+                },
+            });
+        }
+        Ok(Some(TensorType::new(dtype, TensorShape::from_dims(dims))))
+    }
+
+    /// The IR value for an ONNX name, turning a constant into a graph
+    /// constant on first use.
+    fn value(
+        &mut self,
+        name: &'m str,
+        reader: impl Fn() -> String,
+    ) -> Result<ValueRef, ParsingError> {
+        if let Some(value) = self.values.get(name) {
+            return Ok(*value);
+        }
+        if let Some(tensor) = self.constants.get(name) {
+            let tensor = convert_tensor(tensor, &format!("constant '{name}'"))?;
+            let value = self.graph.add_initializer(name, tensor).into();
+            self.values.insert(name, value);
+            return Ok(value);
+        }
+        if let Some(description) = self.unsupported.get(name) {
+            return Err(unsupported(format!(
+                "{} uses '{name}', {description}, which Xyntra does not model",
+                reader()
+            )));
+        }
+        Err(malformed(format!(
+            "{} uses '{name}', which is not defined before it",
+            reader()
+        )))
+    }
+
+    fn input(&mut self, node: &'m NodeProto, index: usize) -> Result<ValueRef, ParsingError> {
+        self.optional_input(node, index)?
+            .ok_or_else(|| malformed(format!("{} is missing input {index}", describe(node))))
+    }
+
+    fn optional_input(
+        &mut self,
+        node: &'m NodeProto,
+        index: usize,
+    ) -> Result<Option<ValueRef>, ParsingError> {
+        match node.input.get(index).filter(|name| !name.is_empty()) {
+            Some(name) => self.value(name, || describe(node)).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Every input given, skipping omitted optional ones.
+    fn all_inputs(&mut self, node: &'m NodeProto) -> Result<Vec<ValueRef>, ParsingError> {
+        let mut inputs = Vec::new();
+        for index in 0..node.input.len() {
+            if let Some(input) = self.optional_input(node, index)? {
+                inputs.push(input);
+            }
+        }
+        Ok(inputs)
+    }
+
+    /// An input the IR holds as an attribute; it must be a constant.
+    fn constant_input(
+        &self,
+        node: &NodeProto,
+        index: usize,
+    ) -> Result<Option<&TensorProto>, ParsingError> {
+        let Some(name) = node.input.get(index).filter(|name| !name.is_empty()) else {
+            return Ok(None);
+        };
+        match self.constants.get(name.as_str()) {
+            Some(tensor) => Ok(Some(tensor)),
+            None => Err(unsupported(format!(
+                "{}: input {index} ('{name}') must be a constant",
+                describe(node)
+            ))),
+        }
+    }
+
+    fn constant_ints(
+        &self,
+        node: &NodeProto,
+        index: usize,
+    ) -> Result<Option<Vec<i64>>, ParsingError> {
+        self.constant_input(node, index)?
+            .map(|tensor| tensor_ints(tensor, &format!("input {index} of {}", describe(node))))
+            .transpose()
+    }
+
+    fn constant_floats(
+        &self,
+        node: &NodeProto,
+        index: usize,
+    ) -> Result<Option<Vec<f32>>, ParsingError> {
+        self.constant_input(node, index)?
+            .map(|tensor| tensor_floats(tensor, &format!("input {index} of {}", describe(node))))
+            .transpose()
+    }
+
+    /// A parameter that moved from attribute `name` to constant input `index`
+    /// in opset `since`, read from where the model's opset keeps it.
+    fn moved_ints(
+        &self,
+        node: &NodeProto,
+        index: usize,
+        name: &str,
+        since: i64,
+    ) -> Result<Option<Vec<i64>>, ParsingError> {
+        if self.opset >= since {
+            self.require_input_form(node, name, since)?;
+            self.constant_ints(node, index)
+        } else {
+            self.require_attribute_form(node, index, name, since)?;
+            NodeAttributes { node }.ints(name)
+        }
+    }
+
+    /// Like [`Importer::moved_ints`] for a single float such as Clip's `min`.
+    fn moved_float(
+        &self,
+        node: &NodeProto,
+        index: usize,
+        name: &str,
+        since: i64,
+    ) -> Result<Option<f32>, ParsingError> {
+        if self.opset >= since {
+            self.require_input_form(node, name, since)?;
+            self.constant_floats(node, index)?
+                .map(|values| scalar(node, name, values))
+                .transpose()
+        } else {
+            self.require_attribute_form(node, index, name, since)?;
+            NodeAttributes { node }.optional_float(name)
+        }
+    }
+
+    fn require_input_form(
+        &self,
+        node: &NodeProto,
+        name: &str,
+        since: i64,
+    ) -> Result<(), ParsingError> {
+        let attributes = NodeAttributes { node };
+        if attributes.contains(name) {
+            return Err(malformed(format!(
+                "{} has a '{name}' attribute, but in opset {} it is an input (since opset {since})",
+                describe(node),
+                self.opset
+            )));
+        }
+        Ok(())
+    }
+
+    fn require_attribute_form(
+        &self,
+        node: &NodeProto,
+        index: usize,
+        name: &str,
+        since: i64,
+    ) -> Result<(), ParsingError> {
+        if node.input.get(index).is_some_and(|input| !input.is_empty()) {
+            return Err(malformed(format!(
+                "{} passes '{name}' as input {index}, but in opset {} it is an attribute \
+                 (an input only since opset {since})",
+                describe(node),
+                self.opset
+            )));
+        }
+        Ok(())
+    }
+
+    /// Rank of an input, when its type is known.
+    fn input_rank(&self, node: &NodeProto, index: usize) -> Option<usize> {
+        node.input
+            .get(index)
+            .and_then(|name| self.values.get(name.as_str()))
+            .and_then(|value| self.graph.value_type(*value))
+            .map(|input_type| input_type.shape().rank())
+    }
+
+    /// Split sizes when none are given: one piece per output, as equal as
+    /// possible with the last one smaller. Needs the input's split dimension.
+    fn even_split(
+        &self,
+        node: &NodeProto,
+        data: ValueRef,
+        axis: i64,
+    ) -> Result<Vec<i64>, ParsingError> {
+        let attributes = NodeAttributes { node };
+        let pieces = node.output.len() as i64;
+        if let Some(num_outputs) = attributes.optional_int("num_outputs")?
+            && num_outputs != pieces
+        {
+            return Err(malformed(format!(
+                "{} has num_outputs {num_outputs} but {pieces} outputs",
+                describe(node)
+            )));
+        }
+        let length = self
+            .graph
+            .value_type(data)
+            .and_then(|data_type| {
+                let shape = data_type.shape();
+                shape
+                    .normalize_axis(axis)
+                    .and_then(|axis| shape.dims()[axis].as_static())
+            })
+            .ok_or_else(|| {
+                unsupported(format!(
+                    "{}: split sizes are needed when the input's dimension is not known",
+                    describe(node)
+                ))
+            })? as i64;
+        if pieces <= 0 {
+            return Err(malformed(format!("{} has no outputs", describe(node))));
+        }
+
+        let piece = (length + pieces - 1) / pieces;
+        Ok((0..pieces)
+            .map(|index| (length - index * piece).clamp(0, piece))
+            .collect())
+    }
+
+    /// Rejects convolutions that are not 2D, judging by the data rank when
+    /// known and by the window attributes otherwise.
+    fn require_2d(
+        &self,
+        node: &NodeProto,
+        attributes: &NodeAttributes,
+    ) -> Result<(), ParsingError> {
+        if self.input_rank(node, 0).is_some_and(|rank| rank != 4) {
+            return Err(only_2d(node));
+        }
+        if let Some(kernel_shape) = attributes.ints("kernel_shape")?
+            && kernel_shape.len() != 2
+        {
+            return Err(only_2d(node));
+        }
+        Ok(())
+    }
+}
+
+/// Typed access to a node's attributes.
+struct NodeAttributes<'m> {
+    node: &'m NodeProto,
+}
+
+impl<'m> NodeAttributes<'m> {
+    fn get(&self, name: &str, expected: i32) -> Result<Option<&'m AttributeProto>, ParsingError> {
+        let Some(attribute) = self
+            .node
+            .attribute
+            .iter()
+            .find(|attribute| attribute.name == name)
+        else {
+            return Ok(None);
+        };
+        // Old exporters leave the type unset
+        if attribute.attribute_type != expected
+            && attribute.attribute_type != attribute_type::UNDEFINED
+        {
+            return Err(malformed(format!(
+                "attribute '{name}' of {} has type {} instead of {expected}",
+                describe(self.node),
+                attribute.attribute_type
+            )));
+        }
+        Ok(Some(attribute))
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        self.node
+            .attribute
+            .iter()
+            .any(|attribute| attribute.name == name)
+    }
+
+    fn optional_int(&self, name: &str) -> Result<Option<i64>, ParsingError> {
+        Ok(self
+            .get(name, attribute_type::INT)?
+            .map(|attribute| attribute.i))
+    }
+
+    fn int(&self, name: &str, default: i64) -> Result<i64, ParsingError> {
+        Ok(self.optional_int(name)?.unwrap_or(default))
+    }
+
+    fn required_int(&self, name: &str) -> Result<i64, ParsingError> {
+        self.optional_int(name)?.ok_or_else(|| {
+            malformed(format!(
+                "{} is missing attribute '{name}'",
+                describe(self.node)
+            ))
+        })
+    }
+
+    fn optional_float(&self, name: &str) -> Result<Option<f32>, ParsingError> {
+        Ok(self
+            .get(name, attribute_type::FLOAT)?
+            .map(|attribute| attribute.f))
+    }
+
+    fn float(&self, name: &str, default: f32) -> Result<f32, ParsingError> {
+        Ok(self.optional_float(name)?.unwrap_or(default))
+    }
+
+    fn ints(&self, name: &str) -> Result<Option<Vec<i64>>, ParsingError> {
+        Ok(self
+            .get(name, attribute_type::INTS)?
+            .map(|attribute| attribute.ints.clone()))
+    }
+
+    fn floats(&self, name: &str) -> Result<Option<Vec<f32>>, ParsingError> {
+        Ok(self
+            .get(name, attribute_type::FLOATS)?
+            .map(|attribute| attribute.floats.clone()))
+    }
+
+    fn string(&self, name: &str, default: &str) -> Result<String, ParsingError> {
+        match self.get(name, attribute_type::STRING)? {
+            Some(attribute) => String::from_utf8(attribute.s.clone()).map_err(|_| {
+                malformed(format!(
+                    "attribute '{name}' of {} is not valid UTF-8",
+                    describe(self.node)
+                ))
+            }),
+            None => Ok(default.to_string()),
+        }
+    }
+
+    fn tensor(&self, name: &str) -> Result<Option<&'m TensorProto>, ParsingError> {
+        Ok(self
+            .get(name, attribute_type::TENSOR)?
+            .and_then(|attribute| attribute.t.as_ref()))
+    }
+}
+
+/// The nodes of a graph with every call to a model-local function replaced
+/// by the function's nodes, recursively. Inside a call, formal inputs and
+/// outputs are renamed to the values the call passes and returns, and other
+/// names are prefixed with the call's name so they stay unique.
+fn inline_functions<'m>(
+    nodes: &'m [NodeProto],
+    functions: &'m [FunctionProto],
+    opsets: &OpsetImports,
+) -> Result<Cow<'m, [NodeProto]>, ParsingError> {
+    if functions.is_empty() {
+        return Ok(Cow::Borrowed(nodes));
+    }
+
+    let mut library = HashMap::new();
+    for function in functions {
+        let qualified = format!("{}.{}", function.domain, function.name);
+        if !function.attribute.is_empty() {
+            return Err(unsupported(format!(
+                "function '{qualified}' takes attributes, which is not supported"
+            )));
+        }
+        for opset in &function.opset_import {
+            if matches!(opset.domain.as_str(), "" | "ai.onnx")
+                && Some(opset.version) != opsets.onnx_version()
+            {
+                return Err(malformed(format!(
+                    "function '{qualified}' imports opset {} of the default domain, \
+                     but the model imports opset {}",
+                    opset.version,
+                    opsets
+                        .onnx_version()
+                        .expect("the default domain is imported")
+                )));
+            }
+        }
+        let key = (function.domain.as_str(), function.name.as_str());
+        if library.insert(key, function).is_some() {
+            return Err(malformed(format!(
+                "function '{qualified}' is defined twice"
+            )));
+        }
+    }
+
+    let mut inlined = Vec::new();
+    for node in nodes {
+        inline_call(node, &library, opsets, &mut Vec::new(), &mut inlined)?;
+    }
+    Ok(Cow::Owned(inlined))
+}
+
+/// Appends `node`, or the nodes of the function it calls. `calls` holds the
+/// functions being expanded, to catch recursion.
+fn inline_call<'m>(
+    node: &NodeProto,
+    library: &HashMap<(&str, &str), &'m FunctionProto>,
+    opsets: &OpsetImports,
+    calls: &mut Vec<&'m FunctionProto>,
+    inlined: &mut Vec<NodeProto>,
+) -> Result<(), ParsingError> {
+    let function = library
+        .get(&(node.domain.as_str(), node.op_type.as_str()))
+        .copied()
+        .filter(|_| opsets.version(&node.domain).is_some());
+    let Some(function) = function else {
+        inlined.push(node.clone());
+        return Ok(());
+    };
+    if calls.iter().any(|call| std::ptr::eq(*call, function)) {
+        return Err(malformed(format!(
+            "function '{}.{}' calls itself",
+            function.domain, function.name
+        )));
+    }
+    if node.input.len() > function.input.len() || node.output.len() > function.output.len() {
+        return Err(malformed(format!(
+            "{} passes {} inputs and takes {} outputs, but the function has {} and {}",
+            describe(node),
+            node.input.len(),
+            node.output.len(),
+            function.input.len(),
+            function.output.len()
+        )));
+    }
+
+    let scope = match node.name.as_str() {
+        "" => node.output.first().cloned().unwrap_or_default(),
+        name => name.to_string(),
+    };
+    let mut renamed: HashMap<&str, String> = HashMap::new();
+    for (index, formal) in function.input.iter().enumerate() {
+        // An input the call leaves out is missing in the body too
+        renamed.insert(formal, node.input.get(index).cloned().unwrap_or_default());
+    }
+    for (index, formal) in function.output.iter().enumerate() {
+        // An output the call leaves out stays internal to the body
+        if let Some(actual) = node.output.get(index).filter(|actual| !actual.is_empty()) {
+            renamed.insert(formal, actual.clone());
+        }
+    }
+    let rename = |name: &String| match renamed.get(name.as_str()) {
+        Some(actual) => actual.clone(),
+        None if name.is_empty() => String::new(),
+        None => format!("{scope}/{name}"),
+    };
+
+    calls.push(function);
+    for body_node in &function.node {
+        let body_node = NodeProto {
+            input: body_node.input.iter().map(&rename).collect(),
+            output: body_node.output.iter().map(&rename).collect(),
+            name: match body_node.name.as_str() {
+                "" => String::new(),
+                name => format!("{scope}/{name}"),
+            },
+            ..body_node.clone()
+        };
+        inline_call(&body_node, library, opsets, calls, inlined)?;
+    }
+    calls.pop();
+    Ok(())
+}
+
+/// The attributes of a node in the Xyntra domain as those of a `Custom` op.
+fn custom_attributes(node: &NodeProto) -> Result<Attributes, ParsingError> {
+    let mut attributes = Attributes::new();
+    for attribute in &node.attribute {
+        let string = |bytes: &[u8]| {
+            String::from_utf8(bytes.to_vec()).map_err(|_| {
+                malformed(format!(
+                    "attribute '{}' of {} is not valid UTF-8",
+                    attribute.name,
+                    describe(node)
+                ))
+            })
+        };
+        let value = match attribute.attribute_type {
+            attribute_type::INT => AttributeValue::Int(attribute.i),
+            attribute_type::FLOAT => AttributeValue::Float(attribute.f),
+            attribute_type::STRING => AttributeValue::String(string(&attribute.s)?),
+            attribute_type::INTS => AttributeValue::Ints(attribute.ints.clone()),
+            attribute_type::FLOATS => AttributeValue::Floats(attribute.floats.clone()),
+            attribute_type::STRINGS => AttributeValue::Strings(
+                attribute
+                    .strings
+                    .iter()
+                    .map(|value| string(value))
+                    .collect::<Result<_, _>>()?,
+            ),
+            other => {
+                return Err(unsupported(format!(
+                    "attribute '{}' of {} has type {other}, which Custom ops cannot hold",
+                    attribute.name,
+                    describe(node)
+                )));
+            }
+        };
+        attributes.insert(&attribute.name, value);
+    }
+    Ok(attributes)
+}
+
+/// Strides, pads and dilations of a 2D convolution or pooling node.
+fn window(node: &NodeProto, attributes: &NodeAttributes) -> Result<Window2d, ParsingError> {
+    let defaults = Window2d::default();
+    let mut window = Window2d {
+        strides: match attributes.ints("strides")? {
+            Some(strides) => usizes(node, "strides", strides)?
+                .try_into()
+                .map_err(|_| only_2d(node))?,
+            None => defaults.strides,
+        },
+        pads: match attributes.ints("pads")? {
+            Some(pads) => usizes(node, "pads", pads)?
+                .try_into()
+                .map_err(|_| only_2d(node))?,
+            None => defaults.pads,
+        },
+        dilations: match attributes.ints("dilations")? {
+            Some(dilations) => usizes(node, "dilations", dilations)?
+                .try_into()
+                .map_err(|_| only_2d(node))?,
+            None => defaults.dilations,
+        },
+    };
+
+    match attributes.string("auto_pad", "NOTSET")?.as_str() {
+        "NOTSET" => {}
+        "VALID" => window.pads = [0; 4],
+        auto_pad => {
+            return Err(unsupported(format!(
+                "{}: auto_pad = \"{auto_pad}\" is not supported",
+                describe(node)
+            )));
+        }
+    }
+    Ok(window)
+}
+
+/// Maps an ONNX element type onto the IR's dtypes.
+fn dtype(elem_type: i32, what: &str) -> Result<DType, ParsingError> {
+    match elem_type {
+        data_type::FLOAT => Ok(DType::F32),
+        data_type::FLOAT16 => Ok(DType::F16),
+        data_type::BFLOAT16 => Ok(DType::BF16),
+        data_type::INT32 | data_type::INT64 => Ok(DType::I32),
+        data_type::INT8 => Ok(DType::I8),
+        data_type::BOOL => Ok(DType::Bool),
+        other => Err(unsupported(format!(
+            "{what} has element type {}, which Xyntra does not support",
+            data_type::name(other)
+        ))),
+    }
+}
+
+/// The elements of a tensor, widened to one representation per family.
+enum Elements {
+    Floats(Vec<f32>),
+    /// FLOAT16 or BFLOAT16 bit patterns.
+    Halves(Vec<u16>),
+    Ints(Vec<i64>),
+}
+
+fn elements(tensor: &TensorProto, what: &str) -> Result<Elements, ParsingError> {
+    if tensor.data_location == 1 {
+        return Err(unsupported(format!(
+            "{what} keeps its data in an external file, which is not supported"
+        )));
+    }
+    let mut count = 1usize;
+    for &dim in &tensor.dims {
+        count = usize::try_from(dim)
+            .ok()
+            .and_then(|dim| count.checked_mul(dim))
+            .ok_or_else(|| malformed(format!("{what} has shape {:?}", tensor.dims)))?;
+    }
+
+    let width = match tensor.data_type {
+        data_type::DOUBLE | data_type::INT64 | data_type::UINT64 => 8,
+        data_type::FLOAT | data_type::INT32 | data_type::UINT32 => 4,
+        data_type::FLOAT16 | data_type::BFLOAT16 | data_type::INT16 | data_type::UINT16 => 2,
+        data_type::INT8 | data_type::UINT8 | data_type::BOOL => 1,
+        other => {
+            return Err(unsupported(format!(
+                "{what} has element type {}, which Xyntra does not support",
+                data_type::name(other)
+            )));
+        }
+    };
+    let size = count
+        .checked_mul(width)
+        .ok_or_else(|| malformed(format!("{what} has shape {:?}", tensor.dims)))?;
+    let raw = &tensor.raw_data;
+    let (raw_len, has_raw) = (raw.len(), !raw.is_empty());
+    if has_raw && raw_len != size {
+        return Err(malformed(format!(
+            "{what} has {raw_len} bytes of data but its shape {:?} needs {size}",
+            tensor.dims
+        )));
+    }
+
+    let elements = match tensor.data_type {
+        data_type::FLOAT if has_raw => Elements::Floats(
+            raw.chunks_exact(4)
+                .map(|bytes| f32::from_le_bytes(bytes.try_into().expect("4 bytes")))
+                .collect(),
+        ),
+        data_type::FLOAT => Elements::Floats(tensor.float_data.clone()),
+        data_type::DOUBLE if has_raw => Elements::Floats(
+            raw.chunks_exact(8)
+                .map(|bytes| f64::from_le_bytes(bytes.try_into().expect("8 bytes")) as f32)
+                .collect(),
+        ),
+        data_type::DOUBLE => Elements::Floats(
+            tensor
+                .double_data
+                .iter()
+                .map(|&value| value as f32)
+                .collect(),
+        ),
+        data_type::FLOAT16 | data_type::BFLOAT16 if has_raw => Elements::Halves(
+            raw.chunks_exact(2)
+                .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+                .collect(),
+        ),
+        data_type::FLOAT16 | data_type::BFLOAT16 => {
+            Elements::Halves(tensor.int32_data.iter().map(|&bits| bits as u16).collect())
+        }
+        data_type::INT64 | data_type::UINT64 if has_raw => Elements::Ints(
+            raw.chunks_exact(8)
+                .map(|bytes| i64::from_le_bytes(bytes.try_into().expect("8 bytes")))
+                .collect(),
+        ),
+        data_type::INT64 => Elements::Ints(tensor.int64_data.clone()),
+        data_type::UINT64 | data_type::UINT32 => Elements::Ints(
+            tensor
+                .uint64_data
+                .iter()
+                .map(|&value| value as i64)
+                .collect(),
+        ),
+        _ if has_raw => Elements::Ints(
+            raw.chunks_exact(width)
+                .map(|bytes| match tensor.data_type {
+                    data_type::INT32 => {
+                        i32::from_le_bytes(bytes.try_into().expect("4 bytes")) as i64
+                    }
+                    data_type::UINT32 => {
+                        u32::from_le_bytes(bytes.try_into().expect("4 bytes")) as i64
+                    }
+                    data_type::INT16 => i16::from_le_bytes([bytes[0], bytes[1]]) as i64,
+                    data_type::UINT16 => u16::from_le_bytes([bytes[0], bytes[1]]) as i64,
+                    data_type::INT8 => bytes[0] as i8 as i64,
+                    _ => bytes[0] as i64,
+                })
+                .collect(),
+        ),
+        _ => Elements::Ints(
+            tensor
+                .int32_data
+                .iter()
+                .map(|&value| value as i64)
+                .collect(),
+        ),
+    };
+
+    let found = match &elements {
+        Elements::Floats(values) => values.len(),
+        Elements::Halves(values) => values.len(),
+        Elements::Ints(values) => values.len(),
+    };
+    if found != count {
+        return Err(malformed(format!(
+            "{what} has {found} values but its shape {:?} holds {count}",
+            tensor.dims
+        )));
+    }
+    Ok(elements)
+}
+
+/// Converts tensor data into an IR constant.
+fn convert_tensor(tensor: &TensorProto, what: &str) -> Result<Tensor, ParsingError> {
+    let dtype = dtype(tensor.data_type, what)?;
+    let dims = tensor
+        .dims
+        .iter()
+        .map(|&dim| {
+            usize::try_from(dim).map_err(|_| malformed(format!("{what} has dimension {dim}")))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let data: Vec<u8> = match (elements(tensor, what)?, dtype) {
+        (Elements::Floats(values), _) => values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect(),
+        (Elements::Halves(values), _) => {
+            values.iter().flat_map(|bits| bits.to_le_bytes()).collect()
+        }
+        (Elements::Ints(values), DType::I32) => {
+            let mut data = Vec::with_capacity(values.len() * 4);
+            for value in values {
+                let narrowed = i32::try_from(value).map_err(|_| {
+                    unsupported(format!(
+                        "{what} holds {value}, which does not fit the 32-bit integers Xyntra uses"
+                    ))
+                })?;
+                data.extend(narrowed.to_le_bytes());
+            }
+            data
+        }
+        (Elements::Ints(values), DType::Bool) => {
+            values.iter().map(|&value| (value != 0) as u8).collect()
+        }
+        (Elements::Ints(values), _) => values.iter().map(|&value| value as i8 as u8).collect(),
+    };
+
+    Tensor::new(TensorType::new(dtype, TensorShape::new(dims)), data)
+        .map_err(|error| malformed(format!("{what}: {error}")))
+}
+
+fn tensor_ints(tensor: &TensorProto, what: &str) -> Result<Vec<i64>, ParsingError> {
+    match elements(tensor, what)? {
+        Elements::Ints(values) => Ok(values),
+        Elements::Floats(_) | Elements::Halves(_) => {
+            Err(malformed(format!("{what} should hold integers")))
+        }
+    }
+}
+
+fn tensor_floats(tensor: &TensorProto, what: &str) -> Result<Vec<f32>, ParsingError> {
+    Ok(match elements(tensor, what)? {
+        Elements::Floats(values) => values,
+        Elements::Halves(values) if tensor.data_type == data_type::BFLOAT16 => values
+            .iter()
+            .map(|&bits| f32::from_bits((bits as u32) << 16))
+            .collect(),
+        Elements::Halves(values) => values.iter().map(|&bits| f16_to_f32(bits)).collect(),
+        Elements::Ints(values) => values.iter().map(|&value| value as f32).collect(),
+    })
+}
+
+/// The single value of a scalar parameter such as Clip's `min`.
+fn scalar(node: &NodeProto, name: &str, values: Vec<f32>) -> Result<f32, ParsingError> {
+    match values.as_slice() {
+        [value] => Ok(*value),
+        _ => Err(malformed(format!(
+            "{name} of {} should be a single value but has {}",
+            describe(node),
+            values.len()
+        ))),
+    }
+}
+
+fn usizes(node: &NodeProto, name: &str, values: Vec<i64>) -> Result<Vec<usize>, ParsingError> {
+    values
+        .into_iter()
+        .map(|value| {
+            usize::try_from(value).map_err(|_| {
+                malformed(format!(
+                    "{name} of {} should not be negative but has {value}",
+                    describe(node)
+                ))
+            })
+        })
+        .collect()
+}
+
+/// Names a node for error messages; many exporters leave node names empty.
+fn describe(node: &NodeProto) -> String {
+    match (node.name.as_str(), node.output.first()) {
+        ("", Some(output)) => format!("{} node producing '{output}'", node.op_type),
+        ("", None) => format!("unnamed {} node", node.op_type),
+        (name, _) => format!("{} node '{name}'", node.op_type),
+    }
+}
+
+fn only_2d(node: &NodeProto) -> ParsingError {
+    unsupported(format!(
+        "{}: only 2D windows over NCHW data are supported",
+        describe(node)
+    ))
+}
+
+fn malformed(details: String) -> ParsingError {
+    ParsingError::MalformedOnnx { details }
+}
+
+/// A well-formed model using something the IR cannot represent.
+fn unsupported(reason: String) -> ParsingError {
+    ParsingError::InvalidFormat {
+        format: "onnx".to_string(),
+        reason,
+    }
+}
@@ -0,0 +1,4 @@
+pub mod export;
+pub mod import;
+pub mod opset;
+pub mod proto;
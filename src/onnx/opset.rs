@@ -0,0 +1,88 @@
+use std::{collections::BTreeMap, ops::RangeInclusive};
+
+use crate::{ir::errors::ParsingError, onnx::proto::OperatorSetIdProto};
+
+/// Versions of the default `ai.onnx` operator set the importer understands.
+/// Opset 7 is where elementwise ops gained multidirectional broadcasting;
+/// 21 is the newest opset the op mapping has been checked against.
+pub const SUPPORTED_OPSETS: RangeInclusive<i64> = 7..=21;
+
+/// Domain of Xyntra's own nodes: fused groups written by the exporter, whose
+/// bodies are model-local functions, and `Custom` ops.
+pub const XYNTRA_DOMAIN: &str = "ai.xyntra";
+
+/// Version the exporter imports [`XYNTRA_DOMAIN`] at.
+pub const XYNTRA_OPSET: i64 = 1;
+
+/// The operator set versions a model imports, by domain. The default domain
+/// is stored as `""`, whether the model spells it that way or `ai.onnx`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpsetImports(BTreeMap<String, i64>);
+
+impl OpsetImports {
+    /// Records a model's `opset_import` list. Each domain may appear once and
+    /// the default domain must be imported at a supported version.
+    pub fn from_protos(opsets: &[OperatorSetIdProto]) -> Result<Self, ParsingError> {
+        let mut imports = BTreeMap::new();
+        for opset in opsets {
+            let domain = canonical_domain(&opset.domain);
+            if imports.insert(domain.to_string(), opset.version).is_some() {
+                return Err(ParsingError::MalformedOnnx {
+                    details: format!("opset of domain '{}' is imported twice", opset.domain),
+                });
+            }
+        }
+
+        let imports = OpsetImports(imports);
+        match imports.onnx_version() {
+            Some(version) if SUPPORTED_OPSETS.contains(&version) => Ok(imports),
+            Some(version) => Err(ParsingError::InvalidFormat {
+                format: "onnx".to_string(),
+                reason: format!(
+                    "opset {version} of the default domain is not supported; \
+                     Xyntra reads opsets {} to {}",
+                    SUPPORTED_OPSETS.start(),
+                    SUPPORTED_OPSETS.end()
+                ),
+            }),
+            None => Err(ParsingError::MissingRequiredField {
+                field: "ModelProto.opset_import".to_string(),
+            }),
+        }
+    }
+
+    /// The imported version of `domain`, if the model imports it.
+    pub fn version(&self, domain: &str) -> Option<i64> {
+        self.0.get(canonical_domain(domain)).copied()
+    }
+
+    /// The imported version of the default `ai.onnx` domain.
+    pub fn onnx_version(&self) -> Option<i64> {
+        self.version("")
+    }
+
+    /// Domains and versions in domain order, the default domain first.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, i64)> {
+        self.0
+            .iter()
+            .map(|(domain, version)| (domain.as_str(), *version))
+    }
+}
+
+/// The opset that introduced a supported op, for ops newer than the oldest
+/// supported opset.
+pub fn introduced_in(op_type: &str) -> Option<i64> {
+    match op_type {
+        "Erf" | "Where" => Some(9),
+        "LayerNormalization" => Some(17),
+        "Gelu" => Some(20),
+        _ => None,
+    }
+}
+
+fn canonical_domain(domain: &str) -> &str {
+    match domain {
+        "ai.onnx" => "",
+        domain => domain,
+    }
+}
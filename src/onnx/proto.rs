@@ -0,0 +1,944 @@
+use crate::ir::errors::ParsingError;
+
+/// The ONNX protobuf messages the importer reads and the exporter writes,
+/// decoded straight from the wire format and encoded back into it. Only the
+/// fields Xyntra uses are kept; everything else is skipped. Field names
+/// follow `onnx.proto`, except `type`, which is `attribute_type` and
+/// `value_type`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ModelProto {
+    pub ir_version: i64,
+    pub opset_import: Vec<OperatorSetIdProto>,
+    pub producer_name: String,
+    pub producer_version: String,
+    pub domain: String,
+    pub model_version: i64,
+    pub doc_string: String,
+    pub graph: Option<GraphProto>,
+    /// Model-local functions, which nodes call by domain and name.
+    pub functions: Vec<FunctionProto>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OperatorSetIdProto {
+    /// Empty for the default `ai.onnx` domain.
+    pub domain: String,
+    pub version: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FunctionProto {
+    pub name: String,
+    /// Formal parameters, bound to a calling node's inputs by position.
+    pub input: Vec<String>,
+    pub output: Vec<String>,
+    /// Names of attribute parameters the body refers to.
+    pub attribute: Vec<String>,
+    pub node: Vec<NodeProto>,
+    pub doc_string: String,
+    pub opset_import: Vec<OperatorSetIdProto>,
+    pub domain: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphProto {
+    pub node: Vec<NodeProto>,
+    pub name: String,
+    pub initializer: Vec<TensorProto>,
+    pub doc_string: String,
+    pub input: Vec<ValueInfoProto>,
+    pub output: Vec<ValueInfoProto>,
+    pub value_info: Vec<ValueInfoProto>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NodeProto {
+    /// Names of the values read; an empty name skips an optional input.
+    pub input: Vec<String>,
+    pub output: Vec<String>,
+    pub name: String,
+    pub op_type: String,
+    pub domain: String,
+    pub attribute: Vec<AttributeProto>,
+    pub doc_string: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AttributeProto {
+    pub name: String,
+    /// One of the `attribute_type` constants.
+    pub attribute_type: i32,
+    pub f: f32,
+    pub i: i64,
+    pub s: Vec<u8>,
+    pub t: Option<TensorProto>,
+    pub g: Option<GraphProto>,
+    pub floats: Vec<f32>,
+    pub ints: Vec<i64>,
+    pub strings: Vec<Vec<u8>>,
+    pub tensors: Vec<TensorProto>,
+    pub graphs: Vec<GraphProto>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TensorProto {
+    pub dims: Vec<i64>,
+    /// One of the `data_type` constants.
+    pub data_type: i32,
+    pub float_data: Vec<f32>,
+    /// Also holds INT8, BOOL, FLOAT16 and BFLOAT16 elements, one per entry.
+    pub int32_data: Vec<i32>,
+    pub string_data: Vec<Vec<u8>>,
+    pub int64_data: Vec<i64>,
+    pub name: String,
+    /// Little-endian element data; when set, the typed fields are empty.
+    pub raw_data: Vec<u8>,
+    pub double_data: Vec<f64>,
+    pub uint64_data: Vec<u64>,
+    pub doc_string: String,
+    /// 1 when the data lives in an external file.
+    pub data_location: i32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValueInfoProto {
+    pub name: String,
+    pub value_type: Option<TypeProto>,
+    pub doc_string: String,
+}
+
+/// Only tensor types are decoded; sequences, maps and the like leave
+/// `tensor_type` unset.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TypeProto {
+    pub tensor_type: Option<TensorTypeProto>,
+    pub denotation: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TensorTypeProto {
+    pub elem_type: i32,
+    /// Unset when even the rank is unknown.
+    pub shape: Option<TensorShapeProto>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TensorShapeProto {
+    pub dim: Vec<Dimension>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Dimension {
+    Value(i64),
+    Param(String),
+    #[default]
+    Unknown,
+}
+
+/// `TensorProto.DataType` values.
+pub mod data_type {
+    pub const UNDEFINED: i32 = 0;
+    pub const FLOAT: i32 = 1;
+    pub const UINT8: i32 = 2;
+    pub const INT8: i32 = 3;
+    pub const UINT16: i32 = 4;
+    pub const INT16: i32 = 5;
+    pub const INT32: i32 = 6;
+    pub const INT64: i32 = 7;
+    pub const STRING: i32 = 8;
+    pub const BOOL: i32 = 9;
+    pub const FLOAT16: i32 = 10;
+    pub const DOUBLE: i32 = 11;
+    pub const UINT32: i32 = 12;
+    pub const UINT64: i32 = 13;
+    pub const COMPLEX64: i32 = 14;
+    pub const COMPLEX128: i32 = 15;
+    pub const BFLOAT16: i32 = 16;
+
+    /// The enum name as written in `onnx.proto`.
+    pub fn name(data_type: i32) -> String {
+        let name = match data_type {
+            UNDEFINED => "UNDEFINED",
+            FLOAT => "FLOAT",
+            UINT8 => "UINT8",
+            INT8 => "INT8",
+            UINT16 => "UINT16",
+            INT16 => "INT16",
+            INT32 => "INT32",
+            INT64 => "INT64",
+            STRING => "STRING",
+            BOOL => "BOOL",
+            FLOAT16 => "FLOAT16",
+            DOUBLE => "DOUBLE",
+            UINT32 => "UINT32",
+            UINT64 => "UINT64",
+            COMPLEX64 => "COMPLEX64",
+            COMPLEX128 => "COMPLEX128",
+            BFLOAT16 => "BFLOAT16",
+            other => return format!("data type {other}"),
+        };
+        name.to_string()
+    }
+}
+
+/// `AttributeProto.AttributeType` values.
+pub mod attribute_type {
+    pub const UNDEFINED: i32 = 0;
+    pub const FLOAT: i32 = 1;
+    pub const INT: i32 = 2;
+    pub const STRING: i32 = 3;
+    pub const TENSOR: i32 = 4;
+    pub const GRAPH: i32 = 5;
+    pub const FLOATS: i32 = 6;
+    pub const INTS: i32 = 7;
+    pub const STRINGS: i32 = 8;
+    pub const TENSORS: i32 = 9;
+    pub const GRAPHS: i32 = 10;
+}
+
+impl ModelProto {
+    pub fn decode(bytes: &[u8]) -> Result<Self, ParsingError> {
+        let mut model = ModelProto::default();
+        for field in Fields::new(bytes, "ModelProto") {
+            let (number, value) = field?;
+            match number {
+                1 => model.ir_version = value.int64()?,
+                2 => model.producer_name = value.string()?,
+                3 => model.producer_version = value.string()?,
+                4 => model.domain = value.string()?,
+                5 => model.model_version = value.int64()?,
+                6 => model.doc_string = value.string()?,
+                7 => model.graph = Some(GraphProto::decode_nested(value.bytes()?, 1)?),
+                8 => model
+                    .opset_import
+                    .push(OperatorSetIdProto::decode(value.bytes()?)?),
+                25 => model.functions.push(FunctionProto::decode(value.bytes()?)?),
+                _ => {}
+            }
+        }
+        Ok(model)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::default();
+        writer.int64(1, self.ir_version);
+        writer.string(2, &self.producer_name);
+        writer.string(3, &self.producer_version);
+        writer.string(4, &self.domain);
+        writer.int64(5, self.model_version);
+        writer.string(6, &self.doc_string);
+        if let Some(graph) = &self.graph {
+            writer.message(7, graph.encode());
+        }
+        for opset in &self.opset_import {
+            writer.message(8, opset.encode());
+        }
+        for function in &self.functions {
+            writer.message(25, function.encode());
+        }
+        writer.bytes
+    }
+}
+
+impl OperatorSetIdProto {
+    pub fn decode(bytes: &[u8]) -> Result<Self, ParsingError> {
+        let mut opset = OperatorSetIdProto::default();
+        for field in Fields::new(bytes, "OperatorSetIdProto") {
+            let (number, value) = field?;
+            match number {
+                1 => opset.domain = value.string()?,
+                2 => opset.version = value.int64()?,
+                _ => {}
+            }
+        }
+        Ok(opset)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::default();
+        writer.string(1, &self.domain);
+        writer.int64(2, self.version);
+        writer.bytes
+    }
+}
+
+impl FunctionProto {
+    pub fn decode(bytes: &[u8]) -> Result<Self, ParsingError> {
+        let mut function = FunctionProto::default();
+        for field in Fields::new(bytes, "FunctionProto") {
+            let (number, value) = field?;
+            match number {
+                1 => function.name = value.string()?,
+                4 => function.input.push(value.string()?),
+                5 => function.output.push(value.string()?),
+                6 => function.attribute.push(value.string()?),
+                7 => function
+                    .node
+                    .push(NodeProto::decode_nested(value.bytes()?, 2)?),
+                8 => function.doc_string = value.string()?,
+                9 => function
+                    .opset_import
+                    .push(OperatorSetIdProto::decode(value.bytes()?)?),
+                10 => function.domain = value.string()?,
+                _ => {}
+            }
+        }
+        Ok(function)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::default();
+        writer.string(1, &self.name);
+        writer.strings(4, &self.input);
+        writer.strings(5, &self.output);
+        writer.strings(6, &self.attribute);
+        for node in &self.node {
+            writer.message(7, node.encode());
+        }
+        writer.string(8, &self.doc_string);
+        for opset in &self.opset_import {
+            writer.message(9, opset.encode());
+        }
+        writer.string(10, &self.domain);
+        writer.bytes
+    }
+}
+
+impl GraphProto {
+    pub fn decode(bytes: &[u8]) -> Result<Self, ParsingError> {
+        GraphProto::decode_nested(bytes, 0)
+    }
+
+    /// Decodes a graph inside `depth` enclosing messages. Subgraphs in
+    /// attributes are the only way messages recurse, so the limit is checked
+    /// here.
+    fn decode_nested(bytes: &[u8], depth: usize) -> Result<Self, ParsingError> {
+        if depth > MAX_DEPTH {
+            return Err(malformed(
+                "GraphProto",
+                format!("messages nest deeper than {MAX_DEPTH} levels"),
+            ));
+        }
+        let mut graph = GraphProto::default();
+        for field in Fields::new(bytes, "GraphProto") {
+            let (number, value) = field?;
+            match number {
+                1 => graph
+                    .node
+                    .push(NodeProto::decode_nested(value.bytes()?, depth + 1)?),
+                2 => graph.name = value.string()?,
+                5 => graph.initializer.push(TensorProto::decode(value.bytes()?)?),
+                10 => graph.doc_string = value.string()?,
+                11 => graph.input.push(ValueInfoProto::decode(value.bytes()?)?),
+                12 => graph.output.push(ValueInfoProto::decode(value.bytes()?)?),
+                13 => graph
+                    .value_info
+                    .push(ValueInfoProto::decode(value.bytes()?)?),
+                _ => {}
+            }
+        }
+        Ok(graph)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::default();
+        for node in &self.node {
+            writer.message(1, node.encode());
+        }
+        writer.string(2, &self.name);
+        for tensor in &self.initializer {
+            writer.message(5, tensor.encode());
+        }
+        writer.string(10, &self.doc_string);
+        for (number, infos) in [
+            (11, &self.input),
+            (12, &self.output),
+            (13, &self.value_info),
+        ] {
+            for info in infos {
+                writer.message(number, info.encode());
+            }
+        }
+        writer.bytes
+    }
+}
+
+impl NodeProto {
+    pub fn decode(bytes: &[u8]) -> Result<Self, ParsingError> {
+        NodeProto::decode_nested(bytes, 0)
+    }
+
+    fn decode_nested(bytes: &[u8], depth: usize) -> Result<Self, ParsingError> {
+        let mut node = NodeProto::default();
+        for field in Fields::new(bytes, "NodeProto") {
+            let (number, value) = field?;
+            match number {
+                1 => node.input.push(value.string()?),
+                2 => node.output.push(value.string()?),
+                3 => node.name = value.string()?,
+                4 => node.op_type = value.string()?,
+                5 => node
+                    .attribute
+                    .push(AttributeProto::decode_nested(value.bytes()?, depth + 1)?),
+                6 => node.doc_string = value.string()?,
+                7 => node.domain = value.string()?,
+                _ => {}
+            }
+        }
+        Ok(node)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::default();
+        writer.strings(1, &self.input);
+        writer.strings(2, &self.output);
+        writer.string(3, &self.name);
+        writer.string(4, &self.op_type);
+        for attribute in &self.attribute {
+            writer.message(5, attribute.encode());
+        }
+        writer.string(6, &self.doc_string);
+        writer.string(7, &self.domain);
+        writer.bytes
+    }
+}
+
+impl AttributeProto {
+    pub fn decode(bytes: &[u8]) -> Result<Self, ParsingError> {
+        AttributeProto::decode_nested(bytes, 0)
+    }
+
+    fn decode_nested(bytes: &[u8], depth: usize) -> Result<Self, ParsingError> {
+        let mut attribute = AttributeProto::default();
+        for field in Fields::new(bytes, "AttributeProto") {
+            let (number, value) = field?;
+            match number {
+                1 => attribute.name = value.string()?,
+                2 => attribute.f = value.float()?,
+                3 => attribute.i = value.int64()?,
+                4 => attribute.s = value.bytes()?.to_vec(),
+                5 => attribute.t = Some(TensorProto::decode(value.bytes()?)?),
+                6 => attribute.g = Some(GraphProto::decode_nested(value.bytes()?, depth + 1)?),
+                7 => value.floats(&mut attribute.floats)?,
+                8 => value.int64s(&mut attribute.ints)?,
+                9 => attribute.strings.push(value.bytes()?.to_vec()),
+                10 => attribute.tensors.push(TensorProto::decode(value.bytes()?)?),
+                11 => attribute
+                    .graphs
+                    .push(GraphProto::decode_nested(value.bytes()?, depth + 1)?),
+                20 => attribute.attribute_type = value.int32()?,
+                _ => {}
+            }
+        }
+        Ok(attribute)
+    }
+
+    /// Writes the value field matching `attribute_type` even when it holds
+    /// the default, since checkers expect exactly one value field.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::default();
+        writer.string(1, &self.name);
+        match self.attribute_type {
+            attribute_type::FLOAT => writer.float_field(2, self.f),
+            attribute_type::INT => writer.varint_field(3, self.i as u64),
+            attribute_type::STRING => writer.bytes_field(4, &self.s),
+            _ => {
+                if self.f != 0.0 {
+                    writer.float_field(2, self.f);
+                }
+                writer.int64(3, self.i);
+                writer.bytes(4, &self.s);
+            }
+        }
+        if let Some(tensor) = &self.t {
+            writer.message(5, tensor.encode());
+        }
+        if let Some(graph) = &self.g {
+            writer.message(6, graph.encode());
+        }
+        writer.packed_fixed(7, self.floats.iter().map(|value| value.to_le_bytes()));
+        writer.packed_varints(8, self.ints.iter().map(|&value| value as u64));
+        for value in &self.strings {
+            writer.bytes_field(9, value);
+        }
+        for tensor in &self.tensors {
+            writer.message(10, tensor.encode());
+        }
+        for graph in &self.graphs {
+            writer.message(11, graph.encode());
+        }
+        writer.int64(20, self.attribute_type.into());
+        writer.bytes
+    }
+}
+
+impl TensorProto {
+    pub fn decode(bytes: &[u8]) -> Result<Self, ParsingError> {
+        let mut tensor = TensorProto::default();
+        for field in Fields::new(bytes, "TensorProto") {
+            let (number, value) = field?;
+            match number {
+                1 => value.int64s(&mut tensor.dims)?,
+                2 => tensor.data_type = value.int32()?,
+                4 => value.floats(&mut tensor.float_data)?,
+                5 => value.int32s(&mut tensor.int32_data)?,
+                6 => tensor.string_data.push(value.bytes()?.to_vec()),
+                7 => value.int64s(&mut tensor.int64_data)?,
+                8 => tensor.name = value.string()?,
+                9 => tensor.raw_data = value.bytes()?.to_vec(),
+                10 => value.doubles(&mut tensor.double_data)?,
+                11 => value.uint64s(&mut tensor.uint64_data)?,
+                12 => tensor.doc_string = value.string()?,
+                14 => tensor.data_location = value.int32()?,
+                _ => {}
+            }
+        }
+        Ok(tensor)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::default();
+        writer.packed_varints(1, self.dims.iter().map(|&dim| dim as u64));
+        writer.int64(2, self.data_type.into());
+        writer.packed_fixed(4, self.float_data.iter().map(|value| value.to_le_bytes()));
+        writer.packed_varints(5, self.int32_data.iter().map(|&value| value as i64 as u64));
+        for value in &self.string_data {
+            writer.bytes_field(6, value);
+        }
+        writer.packed_varints(7, self.int64_data.iter().map(|&value| value as u64));
+        writer.string(8, &self.name);
+        writer.bytes(9, &self.raw_data);
+        writer.packed_fixed(10, self.double_data.iter().map(|value| value.to_le_bytes()));
+        writer.packed_varints(11, self.uint64_data.iter().copied());
+        writer.string(12, &self.doc_string);
+        writer.int64(14, self.data_location.into());
+        writer.bytes
+    }
+}
+
+impl ValueInfoProto {
+    pub fn decode(bytes: &[u8]) -> Result<Self, ParsingError> {
+        let mut info = ValueInfoProto::default();
+        for field in Fields::new(bytes, "ValueInfoProto") {
+            let (number, value) = field?;
+            match number {
+                1 => info.name = value.string()?,
+                2 => info.value_type = Some(TypeProto::decode(value.bytes()?)?),
+                3 => info.doc_string = value.string()?,
+                _ => {}
+            }
+        }
+        Ok(info)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::default();
+        writer.string(1, &self.name);
+        if let Some(value_type) = &self.value_type {
+            writer.message(2, value_type.encode());
+        }
+        writer.string(3, &self.doc_string);
+        writer.bytes
+    }
+}
+
+impl TypeProto {
+    pub fn decode(bytes: &[u8]) -> Result<Self, ParsingError> {
+        let mut type_proto = TypeProto::default();
+        for field in Fields::new(bytes, "TypeProto") {
+            let (number, value) = field?;
+            match number {
+                1 => type_proto.tensor_type = Some(TensorTypeProto::decode(value.bytes()?)?),
+                6 => type_proto.denotation = value.string()?,
+                _ => {}
+            }
+        }
+        Ok(type_proto)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::default();
+        if let Some(tensor_type) = &self.tensor_type {
+            writer.message(1, tensor_type.encode());
+        }
+        writer.string(6, &self.denotation);
+        writer.bytes
+    }
+}
+
+impl TensorTypeProto {
+    pub fn decode(bytes: &[u8]) -> Result<Self, ParsingError> {
+        let mut tensor_type = TensorTypeProto::default();
+        for field in Fields::new(bytes, "TypeProto.Tensor") {
+            let (number, value) = field?;
+            match number {
+                1 => tensor_type.elem_type = value.int32()?,
+                2 => tensor_type.shape = Some(TensorShapeProto::decode(value.bytes()?)?),
+                _ => {}
+            }
+        }
+        Ok(tensor_type)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::default();
+        writer.int64(1, self.elem_type.into());
+        // An empty shape is a scalar, so it is written even without dimensions
+        if let Some(shape) = &self.shape {
+            writer.message(2, shape.encode());
+        }
+        writer.bytes
+    }
+}
+
+impl TensorShapeProto {
+    pub fn decode(bytes: &[u8]) -> Result<Self, ParsingError> {
+        let mut shape = TensorShapeProto::default();
+        for field in Fields::new(bytes, "TensorShapeProto") {
+            let (number, value) = field?;
+            if number == 1 {
+                shape.dim.push(Dimension::decode(value.bytes()?)?);
+            }
+        }
+        Ok(shape)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::default();
+        for dim in &self.dim {
+            writer.message(1, dim.encode());
+        }
+        writer.bytes
+    }
+}
+
+impl Dimension {
+    pub fn decode(bytes: &[u8]) -> Result<Self, ParsingError> {
+        let mut dim = Dimension::Unknown;
+        for field in Fields::new(bytes, "TensorShapeProto.Dimension") {
+            let (number, value) = field?;
+            match number {
+                1 => dim = Dimension::Value(value.int64()?),
+                2 => dim = Dimension::Param(value.string()?),
+                _ => {}
+            }
+        }
+        Ok(dim)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut writer = Writer::default();
+        match self {
+            Dimension::Value(value) => writer.varint_field(1, *value as u64),
+            Dimension::Param(name) => writer.bytes_field(2, name.as_bytes()),
+            Dimension::Unknown => {}
+        }
+        writer.bytes
+    }
+}
+
+/// Deepest message nesting the decoder accepts, protobuf's default recursion
+/// limit. Without it, deeply nested subgraphs overflow the stack.
+const MAX_DEPTH: usize = 100;
+
+fn malformed(message: &str, details: impl std::fmt::Display) -> ParsingError {
+    ParsingError::MalformedOnnx {
+        details: format!("{message}: {details}"),
+    }
+}
+
+/// One field as it appears on the wire.
+enum WireValue<'a> {
+    Varint(u64),
+    Fixed64([u8; 8]),
+    Bytes(&'a [u8]),
+    Fixed32([u8; 4]),
+}
+
+/// A decoded field value plus where it came from, for error messages.
+struct Field<'a> {
+    message: &'static str,
+    number: u32,
+    value: WireValue<'a>,
+}
+
+impl<'a> Field<'a> {
+    fn wrong_type(&self, expected: &str) -> ParsingError {
+        malformed(
+            self.message,
+            format!("field {} should be {expected}", self.number),
+        )
+    }
+
+    fn int64(&self) -> Result<i64, ParsingError> {
+        match self.value {
+            WireValue::Varint(value) => Ok(value as i64),
+            _ => Err(self.wrong_type("a varint")),
+        }
+    }
+
+    fn int32(&self) -> Result<i32, ParsingError> {
+        // Negative int32 values are sign-extended to 64 bits on the wire
+        Ok(self.int64()? as i32)
+    }
+
+    fn float(&self) -> Result<f32, ParsingError> {
+        match self.value {
+            WireValue::Fixed32(bytes) => Ok(f32::from_le_bytes(bytes)),
+            _ => Err(self.wrong_type("a 32-bit float")),
+        }
+    }
+
+    fn bytes(&self) -> Result<&'a [u8], ParsingError> {
+        match self.value {
+            WireValue::Bytes(bytes) => Ok(bytes),
+            _ => Err(self.wrong_type("length-delimited")),
+        }
+    }
+
+    fn string(&self) -> Result<String, ParsingError> {
+        String::from_utf8(self.bytes()?.to_vec()).map_err(|_| {
+            malformed(
+                self.message,
+                format!("field {} is not valid UTF-8", self.number),
+            )
+        })
+    }
+
+    /// Repeated varints, one per field or packed into a single field.
+    fn varints(&self, mut push: impl FnMut(u64)) -> Result<(), ParsingError> {
+        match self.value {
+            WireValue::Varint(value) => push(value),
+            WireValue::Bytes(bytes) => {
+                let mut reader = Reader { bytes, pos: 0 };
+                while !reader.is_done() {
+                    push(reader.varint().map_err(|problem| {
+                        malformed(
+                            self.message,
+                            format!("packed field {}: {problem}", self.number),
+                        )
+                    })?);
+                }
+            }
+            _ => return Err(self.wrong_type("varints")),
+        }
+        Ok(())
+    }
+
+    /// Repeated fixed-width values, one per field or packed.
+    fn fixed<const N: usize>(&self, mut push: impl FnMut([u8; N])) -> Result<(), ParsingError> {
+        match self.value {
+            WireValue::Fixed32(bytes) if N == 4 => push(bytes[..].try_into().expect("N is 4")),
+            WireValue::Fixed64(bytes) if N == 8 => push(bytes[..].try_into().expect("N is 8")),
+            WireValue::Bytes(bytes) if bytes.len() % N == 0 => {
+                for chunk in bytes.chunks_exact(N) {
+                    push(chunk.try_into().expect("chunks are N bytes"));
+                }
+            }
+            _ => return Err(self.wrong_type(&format!("{N}-byte values"))),
+        }
+        Ok(())
+    }
+
+    fn int64s(&self, values: &mut Vec<i64>) -> Result<(), ParsingError> {
+        self.varints(|value| values.push(value as i64))
+    }
+
+    fn int32s(&self, values: &mut Vec<i32>) -> Result<(), ParsingError> {
+        self.varints(|value| values.push(value as i64 as i32))
+    }
+
+    fn uint64s(&self, values: &mut Vec<u64>) -> Result<(), ParsingError> {
+        self.varints(|value| values.push(value))
+    }
+
+    fn floats(&self, values: &mut Vec<f32>) -> Result<(), ParsingError> {
+        self.fixed(|bytes| values.push(f32::from_le_bytes(bytes)))
+    }
+
+    fn doubles(&self, values: &mut Vec<f64>) -> Result<(), ParsingError> {
+        self.fixed(|bytes| values.push(f64::from_le_bytes(bytes)))
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn is_done(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn varint(&mut self) -> Result<u64, String> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self
+                .bytes
+                .get(self.pos)
+                .ok_or_else(|| "varint runs past the end".to_string())?;
+            self.pos += 1;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("varint is longer than 10 bytes".to_string())
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| format!("{len}-byte value runs past the end"))?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+}
+
+/// Builds one message. The plain field writers leave out default values
+/// (zero, empty), which readers treat the same as an absent field; the
+/// `_field` writers always write.
+#[derive(Default)]
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.bytes.push((value as u8 & 0x7f) | 0x80);
+            value >>= 7;
+        }
+        self.bytes.push(value as u8);
+    }
+
+    fn key(&mut self, number: u32, wire_type: u8) {
+        self.varint(u64::from(number) << 3 | u64::from(wire_type));
+    }
+
+    fn varint_field(&mut self, number: u32, value: u64) {
+        self.key(number, 0);
+        self.varint(value);
+    }
+
+    fn float_field(&mut self, number: u32, value: f32) {
+        self.key(number, 5);
+        self.bytes.extend(value.to_le_bytes());
+    }
+
+    fn bytes_field(&mut self, number: u32, bytes: &[u8]) {
+        self.key(number, 2);
+        self.varint(bytes.len() as u64);
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    /// Negative values are sign-extended to 64 bits, as protobuf does.
+    fn int64(&mut self, number: u32, value: i64) {
+        if value != 0 {
+            self.varint_field(number, value as u64);
+        }
+    }
+
+    fn bytes(&mut self, number: u32, bytes: &[u8]) {
+        if !bytes.is_empty() {
+            self.bytes_field(number, bytes);
+        }
+    }
+
+    fn string(&mut self, number: u32, value: &str) {
+        self.bytes(number, value.as_bytes());
+    }
+
+    /// A repeated string field. Every entry is written, since an empty name
+    /// in a node's inputs marks a skipped optional input.
+    fn strings(&mut self, number: u32, values: &[String]) {
+        for value in values {
+            self.bytes_field(number, value.as_bytes());
+        }
+    }
+
+    fn message(&mut self, number: u32, encoded: Vec<u8>) {
+        self.bytes_field(number, &encoded);
+    }
+
+    fn packed_varints(&mut self, number: u32, values: impl Iterator<Item = u64>) {
+        let mut packed = Writer::default();
+        for value in values {
+            packed.varint(value);
+        }
+        self.bytes(number, &packed.bytes);
+    }
+
+    fn packed_fixed<const N: usize>(&mut self, number: u32, values: impl Iterator<Item = [u8; N]>) {
+        let packed: Vec<u8> = values.flatten().collect();
+        self.bytes(number, &packed);
+    }
+}
+
+/// Iterates the fields of one message in wire order.
+struct Fields<'a> {
+    reader: Reader<'a>,
+    message: &'static str,
+    failed: bool,
+}
+
+impl<'a> Fields<'a> {
+    fn new(bytes: &'a [u8], message: &'static str) -> Self {
+        Fields {
+            reader: Reader { bytes, pos: 0 },
+            message,
+            failed: false,
+        }
+    }
+
+    fn next_field(&mut self) -> Result<Field<'a>, String> {
+        let key = self.reader.varint()?;
+        let number = u32::try_from(key >> 3)
+            .ok()
+            .filter(|number| *number > 0)
+            .ok_or_else(|| format!("invalid field number {}", key >> 3))?;
+
+        let value = match key & 7 {
+            0 => WireValue::Varint(self.reader.varint()?),
+            1 => WireValue::Fixed64(self.reader.take(8)?.try_into().expect("took 8 bytes")),
+            2 => {
+                let len = usize::try_from(self.reader.varint()?)
+                    .map_err(|_| "length does not fit in memory".to_string())?;
+                WireValue::Bytes(self.reader.take(len)?)
+            }
+            5 => WireValue::Fixed32(self.reader.take(4)?.try_into().expect("took 4 bytes")),
+            wire_type => {
+                return Err(format!(
+                    "field {number} has unsupported wire type {wire_type}"
+                ));
+            }
+        };
+
+        Ok(Field {
+            message: self.message,
+            number,
+            value,
+        })
+    }
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = Result<(u32, Field<'a>), ParsingError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed || self.reader.is_done() {
+            return None;
+        }
+        match self.next_field() {
+            Ok(field) => Some(Ok((field.number, field))),
+            Err(problem) => {
+                self.failed = true;
+                Some(Err(malformed(self.message, problem)))
+            }
+        }
+    }
+}
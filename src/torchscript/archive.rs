@@ -0,0 +1,263 @@
+use std::borrow::Cow;
+
+use crate::{ir::errors::ParsingError, torchscript::inflate::inflate};
+
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const END_OF_DIRECTORY: u32 = 0x0605_4b50;
+const ZIP64_END_OF_DIRECTORY: u32 = 0x0606_4b50;
+const ZIP64_LOCATOR: u32 = 0x0706_4b50;
+const ZIP64_EXTRA: u16 = 0x0001;
+
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+
+/// A zip archive held in memory, such as the `.pt` files `torch.jit.save`
+/// writes. Entries can be stored or deflated; zip64 sizes and offsets are
+/// understood, encryption and spanning are not.
+pub struct ZipArchive<'a> {
+    bytes: &'a [u8],
+    entries: Vec<ZipEntry>,
+}
+
+/// A file in a zip archive as its central directory describes it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZipEntry {
+    pub name: String,
+    pub method: u16,
+    pub crc32: u32,
+    pub compressed_size: u64,
+    pub size: u64,
+    /// Offset of the entry's local header from the start of the archive.
+    pub header_offset: u64,
+}
+
+impl<'a> ZipArchive<'a> {
+    /// Reads the central directory; entry data is only touched by [`read`](Self::read).
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, ParsingError> {
+        let end = find_end_of_directory(bytes)?;
+        let mut entry_count = read_u16(bytes, end + 10)? as u64;
+        let mut directory_size = read_u32(bytes, end + 12)? as u64;
+        let mut directory_offset = read_u32(bytes, end + 16)? as u64;
+
+        if entry_count == 0xFFFF || directory_size == 0xFFFF_FFFF || directory_offset == 0xFFFF_FFFF
+        {
+            let locator = end
+                .checked_sub(20)
+                .filter(|&locator| read_u32(bytes, locator).ok() == Some(ZIP64_LOCATOR))
+                .ok_or_else(|| invalid("the zip64 end of directory locator is missing"))?;
+            let record = offset(read_u64(bytes, locator + 8)?, bytes)?;
+            if read_u32(bytes, record)? != ZIP64_END_OF_DIRECTORY {
+                return Err(invalid("the zip64 end of directory record is missing"));
+            }
+            entry_count = read_u64(bytes, record + 32)?;
+            directory_size = read_u64(bytes, record + 40)?;
+            directory_offset = read_u64(bytes, record + 48)?;
+        }
+
+        let start = offset(directory_offset, bytes)?;
+        let directory = start
+            .checked_add(offset(directory_size, bytes)?)
+            .and_then(|end| bytes.get(start..end))
+            .ok_or_else(|| invalid("the central directory lies outside the file"))?;
+
+        let mut entries = Vec::new();
+        let mut position = 0;
+        for _ in 0..entry_count {
+            let (entry, length) = parse_central_header(directory, position)?;
+            entries.push(entry);
+            position += length;
+        }
+        Ok(ZipArchive { bytes, entries })
+    }
+
+    pub fn entries(&self) -> &[ZipEntry] {
+        &self.entries
+    }
+
+    pub fn entry(&self, name: &str) -> Option<&ZipEntry> {
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+
+    /// The contents of the named entry, decompressed and checked against its
+    /// CRC-32. Stored entries are borrowed from the archive.
+    pub fn read(&self, name: &str) -> Result<Cow<'a, [u8]>, ParsingError> {
+        let entry = self
+            .entry(name)
+            .ok_or_else(|| invalid(format!("the archive has no entry '{name}'")))?;
+
+        let header = offset(entry.header_offset, self.bytes)?;
+        if read_u32(self.bytes, header)? != LOCAL_HEADER {
+            return Err(invalid(format!("entry '{name}' has no local header")));
+        }
+        // The local header repeats the name but may carry different extra data
+        let name_length = read_u16(self.bytes, header + 26)? as usize;
+        let extra_length = read_u16(self.bytes, header + 28)? as usize;
+        let start = header + 30 + name_length + extra_length;
+        let data = offset(entry.compressed_size, self.bytes)
+            .ok()
+            .and_then(|size| self.bytes.get(start..start.checked_add(size)?))
+            .ok_or_else(|| invalid(format!("entry '{name}' runs past the end of the file")))?;
+
+        let contents = match entry.method {
+            STORED => Cow::Borrowed(data),
+            DEFLATED => Cow::Owned(
+                inflate(data, usize::try_from(entry.size).unwrap_or(usize::MAX))
+                    .map_err(|reason| invalid(format!("entry '{name}': {reason}")))?,
+            ),
+            method => {
+                return Err(invalid(format!(
+                    "entry '{name}' uses compression method {method}; only stored and \
+                     deflated entries are supported"
+                )));
+            }
+        };
+        if contents.len() as u64 != entry.size {
+            return Err(invalid(format!(
+                "entry '{name}' holds {} bytes but its header says {}",
+                contents.len(),
+                entry.size
+            )));
+        }
+        if crc32(&contents) != entry.crc32 {
+            return Err(invalid(format!("entry '{name}' fails its CRC-32 check")));
+        }
+        Ok(contents)
+    }
+}
+
+/// The end of central directory record sits at the end of the file, followed
+/// only by a comment of up to 64 KiB.
+fn find_end_of_directory(bytes: &[u8]) -> Result<usize, ParsingError> {
+    let latest = bytes
+        .len()
+        .checked_sub(22)
+        .ok_or_else(|| invalid("the file is too short to be a zip archive"))?;
+    let earliest = latest.saturating_sub(0xFFFF);
+    (earliest..=latest)
+        .rev()
+        .find(|&position| read_u32(bytes, position).ok() == Some(END_OF_DIRECTORY))
+        .ok_or_else(|| invalid("the file has no zip end of central directory record"))
+}
+
+/// Parses the central directory header at `position`, returning the entry
+/// and the header's length.
+fn parse_central_header(
+    directory: &[u8],
+    position: usize,
+) -> Result<(ZipEntry, usize), ParsingError> {
+    if read_u32(directory, position)? != CENTRAL_HEADER {
+        return Err(invalid("the central directory is truncated or corrupt"));
+    }
+    let flags = read_u16(directory, position + 8)?;
+    let method = read_u16(directory, position + 10)?;
+    let crc32 = read_u32(directory, position + 16)?;
+    let mut compressed_size = read_u32(directory, position + 20)? as u64;
+    let mut size = read_u32(directory, position + 24)? as u64;
+    let name_length = read_u16(directory, position + 28)? as usize;
+    let extra_length = read_u16(directory, position + 30)? as usize;
+    let comment_length = read_u16(directory, position + 32)? as usize;
+    let mut header_offset = read_u32(directory, position + 42)? as u64;
+
+    let name_start = position + 46;
+    let name = directory
+        .get(name_start..name_start + name_length)
+        .ok_or_else(|| invalid("the central directory is truncated"))?;
+    let name = String::from_utf8_lossy(name).into_owned();
+    if flags & 1 != 0 {
+        return Err(invalid(format!("entry '{name}' is encrypted")));
+    }
+
+    // Zip64 extra data holds, in order, whichever of the sizes and offset
+    // did not fit their 32-bit fields
+    let extra_start = name_start + name_length;
+    let extra = directory
+        .get(extra_start..extra_start + extra_length)
+        .ok_or_else(|| invalid("the central directory is truncated"))?;
+    let mut field = 0;
+    while field + 4 <= extra.len() {
+        let id = read_u16(extra, field)?;
+        let length = read_u16(extra, field + 2)? as usize;
+        if id == ZIP64_EXTRA {
+            let mut value = field + 4;
+            for slot in [&mut size, &mut compressed_size, &mut header_offset] {
+                if *slot == 0xFFFF_FFFF {
+                    *slot = read_u64(extra, value)?;
+                    value += 8;
+                }
+            }
+        }
+        field += 4 + length;
+    }
+
+    let entry = ZipEntry {
+        name,
+        method,
+        crc32,
+        compressed_size,
+        size,
+        header_offset,
+    };
+    Ok((entry, 46 + name_length + extra_length + comment_length))
+}
+
+/// CRC-32 with the reflected polynomial zip uses.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    const TABLE: [u32; 256] = {
+        let mut table = [0u32; 256];
+        let mut index = 0;
+        while index < 256 {
+            let mut value = index as u32;
+            let mut bit = 0;
+            while bit < 8 {
+                value = if value & 1 != 0 {
+                    0xEDB8_8320 ^ (value >> 1)
+                } else {
+                    value >> 1
+                };
+                bit += 1;
+            }
+            table[index] = value;
+            index += 1;
+        }
+        table
+    };
+
+    !bytes.iter().fold(!0u32, |crc, &byte| {
+        TABLE[((crc ^ byte as u32) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+fn offset(value: u64, bytes: &[u8]) -> Result<usize, ParsingError> {
+    usize::try_from(value)
+        .ok()
+        .filter(|&value| value <= bytes.len())
+        .ok_or_else(|| invalid(format!("offset {value} lies outside the file")))
+}
+
+fn read_u16(bytes: &[u8], position: usize) -> Result<u16, ParsingError> {
+    read_array(bytes, position).map(u16::from_le_bytes)
+}
+
+fn read_u32(bytes: &[u8], position: usize) -> Result<u32, ParsingError> {
+    read_array(bytes, position).map(u32::from_le_bytes)
+}
+
+fn read_u64(bytes: &[u8], position: usize) -> Result<u64, ParsingError> {
+    read_array(bytes, position).map(u64::from_le_bytes)
+}
+
+fn read_array<const N: usize>(bytes: &[u8], position: usize) -> Result<[u8; N], ParsingError> {
+    position
+        .checked_add(N)
+        .and_then(|end| bytes.get(position..end))
+        .map(|slice| slice.try_into().expect("N bytes"))
+        .ok_or_else(|| invalid("a zip record runs past the end of the file"))
+}
+
+fn invalid(reason: impl Into<String>) -> ParsingError {
+    ParsingError::InvalidFormat {
+        format: "zip".to_string(),
+        reason: reason.into(),
+    }
+}
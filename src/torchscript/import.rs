@@ -0,0 +1,2213 @@
+use std::{collections::HashMap, path::Path, rc::Rc};
+
+use crate::{
+    ir::{
+        errors::{ParsingError, XyntraError},
+        graph::Graph,
+        shape_inference::infer_output_types,
+        symbolic::{Dim, ShapeConstraints},
+        tensor::{Tensor, f32_to_f16},
+        types::{DType, NodeID, OpKind, ReduceKind, TensorShape, TensorType, ValueRef, Window2d},
+        validation::GraphValidator,
+    },
+    torchscript::{
+        archive::ZipArchive,
+        pickle::{Value, unpickle},
+        script::{Expr, FunctionDef, Source, Stmt, UnaryOp, parse_source},
+    },
+};
+
+/// Reads a TorchScript `.pt` file and converts it with [`import_torchscript`].
+pub fn load_torchscript(
+    path: impl AsRef<Path>,
+    input_types: &[TensorType],
+) -> Result<Graph, XyntraError> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path).map_err(|error| XyntraError::from_io(path, "read", error))?;
+    import_torchscript(&bytes, input_types).map_err(XyntraError::Parsing)
+}
+
+/// Converts the `forward` method of a TorchScript archive, as written by
+/// `torch.jit.save`, into the IR without libtorch.
+///
+/// The archive's TorchScript source is run symbolically: module attributes
+/// come from `data.pkl`, `CONSTANTS.cN` from `constants.pkl`, and calls to
+/// submodules and `__torch__` functions are inlined. `forward` takes one graph
+/// input per parameter after `self`, named after it and typed by
+/// `input_types` in order, since the archive does not record shapes. Tensor
+/// attributes become constants named by their attribute path (`fc1.weight`),
+/// and the graph outputs are `output`, or `output_0`, `output_1`, ... when
+/// `forward` returns a tuple.
+///
+/// Arithmetic on plain numbers, `if` statements and `torch.size` of static
+/// dimensions are evaluated while importing; a dynamic `torch.size` may only
+/// reach a reshape that keeps the dimension in place. `torch` ops map onto
+/// `OpKind`s, with a few spelled out (`linear` as MatMul plus Add, `flatten`
+/// as Reshape). Int64 data becomes `I32` as in the ONNX importer.
+///
+/// Every failure, from a damaged zip to an op Xyntra has no counterpart for,
+/// is reported as `InvalidFormat`, with the source file and line once the
+/// code is running.
+pub fn import_torchscript(bytes: &[u8], input_types: &[TensorType]) -> Result<Graph, ParsingError> {
+    let archive = ZipArchive::parse(bytes)?;
+
+    // Everything sits in one top-level directory named after the archive
+    let prefix = archive
+        .entries()
+        .iter()
+        .find_map(|entry| {
+            let prefix = entry.name.strip_suffix("data.pkl")?;
+            (prefix.is_empty() || prefix.find('/') == Some(prefix.len() - 1)).then_some(prefix)
+        })
+        .ok_or_else(|| invalid("the archive has no data.pkl, so it is not a TorchScript file"))?;
+    if !archive
+        .entries()
+        .iter()
+        .any(|entry| entry.name.starts_with(&format!("{prefix}code/")))
+    {
+        return Err(invalid(
+            "the archive has no code/ directory; files written by torch.save hold weights \
+             but no TorchScript",
+        ));
+    }
+    if archive.entry(&format!("{prefix}byteorder")).is_some() {
+        let order = archive.read(&format!("{prefix}byteorder"))?;
+        if order.as_ref() != b"little" {
+            return Err(invalid(format!(
+                "tensor data is stored {}-endian; only little-endian archives are supported",
+                String::from_utf8_lossy(&order)
+            )));
+        }
+    }
+
+    let data = unpickle(&archive.read(&format!("{prefix}data.pkl"))?)?;
+    let constants = match archive.entry(&format!("{prefix}constants.pkl")) {
+        Some(_) => match unpickle(&archive.read(&format!("{prefix}constants.pkl"))?)? {
+            Value::Tuple(items) => items,
+            _ => return Err(invalid("constants.pkl does not hold a tuple")),
+        },
+        None => Vec::new(),
+    };
+
+    let importer = Importer {
+        archive: &archive,
+        prefix,
+        constants: &constants,
+        sources: HashMap::new(),
+        modules: Vec::new(),
+        tensors: HashMap::new(),
+        graph: Graph::new(),
+        constraints: ShapeConstraints::new(),
+        generated: 0,
+        depth: 0,
+        located: false,
+    };
+    importer.import(&data, input_types)
+}
+
+/// How deep calls may nest, which also stops runaway recursion.
+const MAX_CALL_DEPTH: usize = 64;
+
+/// A value while the source runs: tensors are IR values, everything else is
+/// known while importing.
+#[derive(Debug, Clone, PartialEq)]
+enum ScriptValue {
+    Tensor(ValueRef),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Str(String),
+    None,
+    /// Dimension `axis` of `tensor`, whose size is only known at run time.
+    Size {
+        tensor: ValueRef,
+        axis: usize,
+    },
+    List(Vec<ScriptValue>),
+    Tuple(Vec<ScriptValue>),
+    /// A module instance, by index into the importer's modules.
+    Module(usize),
+}
+
+impl ScriptValue {
+    fn kind(&self) -> &'static str {
+        match self {
+            ScriptValue::Tensor(_) => "tensor",
+            ScriptValue::Int(_) => "int",
+            ScriptValue::Float(_) => "float",
+            ScriptValue::Bool(_) => "bool",
+            ScriptValue::Str(_) => "string",
+            ScriptValue::None => "None",
+            ScriptValue::Size { .. } => "dynamic size",
+            ScriptValue::List(_) => "list",
+            ScriptValue::Tuple(_) => "tuple",
+            ScriptValue::Module(_) => "module",
+        }
+    }
+}
+
+/// A module object from `data.pkl` and the attribute path it was reached by.
+struct Instance<'a> {
+    path: String,
+    value: &'a Value,
+}
+
+/// The local variables of a running function and the file it comes from.
+struct Frame {
+    file: Rc<str>,
+    locals: HashMap<String, ScriptValue>,
+}
+
+impl Frame {
+    fn new(file: &Rc<str>) -> Self {
+        Frame {
+            file: file.clone(),
+            locals: HashMap::new(),
+        }
+    }
+}
+
+struct Importer<'a> {
+    archive: &'a ZipArchive<'a>,
+    /// Directory holding the archive's files, with its trailing slash.
+    prefix: &'a str,
+    constants: &'a [Value],
+    /// Parsed `code/` files and their names, by module path.
+    sources: HashMap<String, (Rc<Source>, Rc<str>)>,
+    modules: Vec<Instance<'a>>,
+    /// Graph constants created so far, by name.
+    tensors: HashMap<String, ValueRef>,
+    graph: Graph,
+    /// Equalities found while inferring types, which the import discards.
+    constraints: ShapeConstraints,
+    /// Constants the importer made up, such as scalar operands.
+    generated: usize,
+    depth: usize,
+    /// Whether the error being returned already names its source line.
+    located: bool,
+}
+
+impl<'a> Importer<'a> {
+    fn import(
+        mut self,
+        data: &'a Value,
+        input_types: &[TensorType],
+    ) -> Result<Graph, ParsingError> {
+        let root = self.module(String::new(), data);
+        let (source, file, class) = self.class_of(root)?;
+        let forward = source
+            .class(&class)
+            .and_then(|class| class.method("forward"))
+            .ok_or_else(|| invalid(format!("{file}: class {class} has no forward method")))?;
+
+        let params = forward.params.get(1..).unwrap_or_default();
+        if params.len() != input_types.len() {
+            let names: Vec<&str> = params.iter().map(|param| param.name.as_str()).collect();
+            return Err(invalid(format!(
+                "forward takes {} inputs ({}) but {} input types were given",
+                params.len(),
+                names.join(", "),
+                input_types.len()
+            )));
+        }
+        let mut args = Vec::new();
+        for (param, input_type) in params.iter().zip(input_types) {
+            let node_id = self.graph.add_input(&param.name, input_type.clone());
+            args.push(ScriptValue::Tensor(node_id.into()));
+        }
+
+        let outputs = match self.invoke(&file, forward, Some(root), args, Vec::new())? {
+            ScriptValue::Tensor(value) => vec![("output".to_string(), value)],
+            ScriptValue::Tuple(items) | ScriptValue::List(items) => items
+                .into_iter()
+                .enumerate()
+                .map(|(index, item)| match item {
+                    ScriptValue::Tensor(value) => Ok((format!("output_{index}"), value)),
+                    other => Err(invalid(format!(
+                        "forward returns a {} at position {index}; only tensors can be graph outputs",
+                        other.kind()
+                    ))),
+                })
+                .collect::<Result<_, _>>()?,
+            other => {
+                return Err(invalid(format!(
+                    "forward returns a {}; only tensors can be graph outputs",
+                    other.kind()
+                )));
+            }
+        };
+        for (name, value) in outputs {
+            self.graph
+                .add_output(name, value)
+                .map_err(|error| invalid(error.to_string()))?;
+        }
+
+        GraphValidator::new(&self.graph)
+            .validate()
+            .map_err(|errors| {
+                let errors: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+                invalid(format!("graph is invalid: {}", errors.join("; ")))
+            })?;
+        Ok(self.graph)
+    }
+
+    /// Runs a function or method; `receiver` is bound to its first parameter.
+    fn invoke(
+        &mut self,
+        file: &Rc<str>,
+        function: &FunctionDef,
+        receiver: Option<usize>,
+        mut args: Vec<ScriptValue>,
+        mut keywords: Vec<(String, ScriptValue)>,
+    ) -> Result<ScriptValue, ParsingError> {
+        if self.depth == MAX_CALL_DEPTH {
+            return Err(invalid(format!(
+                "calls nest more than {MAX_CALL_DEPTH} deep at {}()",
+                function.name
+            )));
+        }
+        if let Some(module) = receiver {
+            args.insert(0, ScriptValue::Module(module));
+        }
+        if args.len() > function.params.len() {
+            return Err(invalid(format!(
+                "{}() takes {} arguments but {} were given",
+                function.name,
+                function.params.len(),
+                args.len()
+            )));
+        }
+
+        let mut frame = Frame::new(file);
+        let mut args = args.into_iter();
+        for param in &function.params {
+            let value = match args.next() {
+                Some(value) => value,
+                None => match keywords.iter().position(|(name, _)| *name == param.name) {
+                    Some(index) => keywords.swap_remove(index).1,
+                    None => match &param.default {
+                        Some(default) => self.eval(default, &mut Frame::new(file))?,
+                        None => {
+                            return Err(invalid(format!(
+                                "{}() is missing argument '{}'",
+                                function.name, param.name
+                            )));
+                        }
+                    },
+                },
+            };
+            frame.locals.insert(param.name.clone(), value);
+        }
+        if let Some((name, _)) = keywords.first() {
+            return Err(invalid(format!(
+                "{}() has no parameter '{name}'",
+                function.name
+            )));
+        }
+
+        self.depth += 1;
+        let result = self.block(&function.body, &mut frame)?;
+        self.depth -= 1;
+        Ok(result.unwrap_or(ScriptValue::None))
+    }
+
+    /// Runs statements until one returns.
+    fn block(
+        &mut self,
+        body: &[Stmt],
+        frame: &mut Frame,
+    ) -> Result<Option<ScriptValue>, ParsingError> {
+        for statement in body {
+            match self.statement(statement, frame) {
+                Ok(None) => {}
+                Ok(returned) => return Ok(returned),
+                Err(error) => return Err(self.locate(error, &frame.file, statement)),
+            }
+        }
+        Ok(None)
+    }
+
+    fn statement(
+        &mut self,
+        statement: &Stmt,
+        frame: &mut Frame,
+    ) -> Result<Option<ScriptValue>, ParsingError> {
+        match statement {
+            Stmt::Assign {
+                targets,
+                unpack,
+                value,
+                ..
+            } => {
+                let value = self.eval(value, frame)?;
+                if !unpack {
+                    frame.locals.insert(targets[0].clone(), value);
+                    return Ok(None);
+                }
+                let items = match value {
+                    ScriptValue::Tuple(items) | ScriptValue::List(items) => items,
+                    other => {
+                        return Err(invalid(format!(
+                            "a {} cannot be unpacked into {} names",
+                            other.kind(),
+                            targets.len()
+                        )));
+                    }
+                };
+                if items.len() != targets.len() {
+                    return Err(invalid(format!(
+                        "{} values cannot be unpacked into {} names",
+                        items.len(),
+                        targets.len()
+                    )));
+                }
+                for (target, item) in targets.iter().zip(items) {
+                    frame.locals.insert(target.clone(), item);
+                }
+                Ok(None)
+            }
+            Stmt::Expr { value, .. } => {
+                self.eval(value, frame)?;
+                Ok(None)
+            }
+            Stmt::Return { value, .. } => Ok(Some(match value {
+                Some(value) => self.eval(value, frame)?,
+                None => ScriptValue::None,
+            })),
+            Stmt::If {
+                condition,
+                then_body,
+                else_body,
+                ..
+            } => {
+                let condition = self.eval(condition, frame)?;
+                if truthy(&condition)? {
+                    self.block(then_body, frame)
+                } else {
+                    self.block(else_body, frame)
+                }
+            }
+            Stmt::Pass => Ok(None),
+            Stmt::Unsupported { description, .. } => {
+                Err(invalid(format!("{description} are not supported")))
+            }
+        }
+    }
+
+    /// Prefixes the innermost statement's location to an error on its way out.
+    fn locate(&mut self, error: ParsingError, file: &str, statement: &Stmt) -> ParsingError {
+        let line = match statement {
+            Stmt::Assign { line, .. }
+            | Stmt::Expr { line, .. }
+            | Stmt::Return { line, .. }
+            | Stmt::If { line, .. }
+            | Stmt::Unsupported { line, .. } => *line,
+            Stmt::Pass => return error,
+        };
+        if self.located {
+            return error;
+        }
+        self.located = true;
+        match error {
+            ParsingError::InvalidFormat { format, reason } => ParsingError::InvalidFormat {
+                format,
+                reason: format!("{file}:{line}: {reason}"),
+            },
+            other => other,
+        }
+    }
+
+    fn eval(&mut self, expr: &Expr, frame: &mut Frame) -> Result<ScriptValue, ParsingError> {
+        Ok(match expr {
+            Expr::Name(name) => frame
+                .locals
+                .get(name)
+                .cloned()
+                .ok_or_else(|| invalid(format!("'{name}' is used before it is assigned")))?,
+            Expr::Int(value) => ScriptValue::Int(*value),
+            Expr::Float(value) => ScriptValue::Float(*value),
+            Expr::Str(value) => ScriptValue::Str(value.clone()),
+            Expr::Bool(value) => ScriptValue::Bool(*value),
+            Expr::None => ScriptValue::None,
+            Expr::Attribute { value, name } => {
+                if matches!(value.as_ref(), Expr::Name(base) if base == "CONSTANTS") {
+                    return self.archive_constant(name);
+                }
+                match self.eval(value, frame)? {
+                    ScriptValue::Module(module) => self.attribute(module, name)?,
+                    other => {
+                        return Err(invalid(format!(
+                            "attribute '{name}' of a {} cannot be read",
+                            other.kind()
+                        )));
+                    }
+                }
+            }
+            Expr::Call {
+                function,
+                args,
+                keywords,
+            } => self.call(function, args, keywords, frame)?,
+            Expr::Subscript { value, index } => {
+                let value = self.eval(value, frame)?;
+                let index = self.eval(index, frame)?;
+                match (value, index) {
+                    (
+                        ScriptValue::List(items) | ScriptValue::Tuple(items),
+                        ScriptValue::Int(index),
+                    ) => {
+                        let position = if index < 0 {
+                            items.len() as i64 + index
+                        } else {
+                            index
+                        };
+                        usize::try_from(position)
+                            .ok()
+                            .and_then(|position| items.into_iter().nth(position))
+                            .ok_or_else(|| invalid(format!("index {index} is out of range")))?
+                    }
+                    (value, index) => {
+                        return Err(invalid(format!(
+                            "a {} cannot be indexed by a {}",
+                            value.kind(),
+                            index.kind()
+                        )));
+                    }
+                }
+            }
+            Expr::List(items) => ScriptValue::List(self.eval_all(items, frame)?),
+            Expr::Tuple(items) => ScriptValue::Tuple(self.eval_all(items, frame)?),
+            Expr::Unary { op, operand } => {
+                let operand = self.eval(operand, frame)?;
+                match op {
+                    UnaryOp::Neg => self.torch("neg", vec![operand], Vec::new())?,
+                    UnaryOp::Not => ScriptValue::Bool(!truthy(&operand)?),
+                }
+            }
+            Expr::Binary { op, left, right } => {
+                let left = self.eval(left, frame)?;
+                let right = self.eval(right, frame)?;
+                self.torch(op, vec![left, right], Vec::new())?
+            }
+            Expr::Logical { and, left, right } => {
+                let left = truthy(&self.eval(left, frame)?)?;
+                // `and` only looks further while true, `or` while false
+                if left == *and {
+                    ScriptValue::Bool(truthy(&self.eval(right, frame)?)?)
+                } else {
+                    ScriptValue::Bool(left)
+                }
+            }
+        })
+    }
+
+    fn eval_all(
+        &mut self,
+        exprs: &[Expr],
+        frame: &mut Frame,
+    ) -> Result<Vec<ScriptValue>, ParsingError> {
+        exprs.iter().map(|expr| self.eval(expr, frame)).collect()
+    }
+
+    fn call(
+        &mut self,
+        function: &Expr,
+        args: &[Expr],
+        keywords: &[(String, Expr)],
+        frame: &mut Frame,
+    ) -> Result<ScriptValue, ParsingError> {
+        let dotted = function.dotted_name();
+
+        // Builtins whose first argument is a type rather than a value
+        match (dotted.as_deref(), args) {
+            (Some("annotate" | "unchecked_cast"), [_, value]) => return self.eval(value, frame),
+            (Some("uninitialized"), [_]) => return Ok(ScriptValue::None),
+            _ => {}
+        }
+
+        let positional = self.eval_all(args, frame)?;
+        let mut named = Vec::new();
+        for (name, value) in keywords {
+            named.push((name.clone(), self.eval(value, frame)?));
+        }
+
+        if let Some(dotted) = &dotted {
+            if let Some(name) = dotted
+                .strip_prefix("torch.")
+                .or_else(|| dotted.strip_prefix("ops.aten."))
+            {
+                // In-place variants hand their result back through the
+                // variable they were given
+                if let Some(base) = name
+                    .strip_suffix('_')
+                    .filter(|base| !base.starts_with('_') && !base.ends_with('_'))
+                {
+                    let result = self.torch(base, positional, named)?;
+                    if let Some(Expr::Name(target)) = args.first() {
+                        frame.locals.insert(target.clone(), result.clone());
+                    }
+                    return Ok(result);
+                }
+                return self.torch(name, positional, named);
+            }
+            if let Some(name) = dotted.strip_prefix("ops.prim.") {
+                return prim(name, positional);
+            }
+            if let Some((module, name)) = dotted
+                .strip_prefix("__torch__.")
+                .and_then(|_| dotted.rsplit_once('.'))
+            {
+                let (source, file) = self.source(module)?;
+                let function = source
+                    .function(name)
+                    .ok_or_else(|| invalid(format!("{file} defines no function '{name}'")))?;
+                return self.invoke(&file, function, None, positional, named);
+            }
+            if let Expr::Name(name) = function {
+                return self.builtin(name, positional);
+            }
+        }
+
+        if let Expr::Attribute { value, name } = function {
+            return match self.eval(value, frame)? {
+                ScriptValue::Module(module) => self.call_method(module, name, positional, named),
+                other => Err(invalid(format!(
+                    "method '{name}' of a {} cannot be called",
+                    other.kind()
+                ))),
+            };
+        }
+        Err(invalid(format!(
+            "calls to {} are not supported",
+            dotted.as_deref().unwrap_or("computed functions")
+        )))
+    }
+
+    fn builtin(&mut self, name: &str, args: Vec<ScriptValue>) -> Result<ScriptValue, ParsingError> {
+        Ok(match (name, args.as_slice()) {
+            ("getattr", [ScriptValue::Module(module), ScriptValue::Str(attribute)]) => {
+                self.attribute(*module, attribute)?
+            }
+            ("int", [ScriptValue::Float(value)]) => ScriptValue::Int(value.trunc() as i64),
+            ("int", [value @ (ScriptValue::Int(_) | ScriptValue::Size { .. })]) => value.clone(),
+            ("int", [ScriptValue::Bool(value)]) => ScriptValue::Int(*value as i64),
+            ("float", [ScriptValue::Int(value)]) => ScriptValue::Float(*value as f64),
+            ("float", [ScriptValue::Float(value)]) => ScriptValue::Float(*value),
+            ("float", [ScriptValue::Str(text)]) => ScriptValue::Float(
+                text.parse()
+                    .map_err(|_| invalid(format!("float(\"{text}\") is not a number")))?,
+            ),
+            ("bool", [value]) if !matches!(value, ScriptValue::Tensor(_)) => {
+                ScriptValue::Bool(truthy(value)?)
+            }
+            ("len", [ScriptValue::List(items) | ScriptValue::Tuple(items)]) => {
+                ScriptValue::Int(items.len() as i64)
+            }
+            ("print", _) => ScriptValue::None,
+            (_, args) if args.iter().any(|arg| matches!(arg, ScriptValue::Tensor(_))) => {
+                return Err(invalid(format!(
+                    "{name}() of a tensor needs its values, which are not known while importing"
+                )));
+            }
+            _ => return Err(invalid(format!("calls to {name}() are not supported"))),
+        })
+    }
+
+    fn call_method(
+        &mut self,
+        module: usize,
+        name: &str,
+        args: Vec<ScriptValue>,
+        keywords: Vec<(String, ScriptValue)>,
+    ) -> Result<ScriptValue, ParsingError> {
+        let (source, file, class) = self.class_of(module)?;
+        let method = source
+            .class(&class)
+            .and_then(|class| class.method(name))
+            .ok_or_else(|| {
+                invalid(format!(
+                    "{} ({class}) has no method '{name}'",
+                    self.describe_module(module)
+                ))
+            })?;
+        self.invoke(&file, method, Some(module), args, keywords)
+    }
+
+    /// The parsed source of a module's class, the file it is in and the
+    /// class name.
+    fn class_of(&mut self, module: usize) -> Result<(Rc<Source>, Rc<str>, String), ParsingError> {
+        let Value::Object {
+            module: class_module,
+            name,
+            ..
+        } = self.modules[module].value
+        else {
+            unreachable!("only objects are registered as modules");
+        };
+        let (source, file) = self.source(class_module)?;
+        if source.class(name).is_none() {
+            return Err(invalid(format!("{file} defines no class '{name}'")));
+        }
+        Ok((source, file, name.clone()))
+    }
+
+    /// Parses the `code/` file for a module path such as
+    /// `__torch__.torch.nn.modules.linear`, once.
+    fn source(&mut self, module: &str) -> Result<(Rc<Source>, Rc<str>), ParsingError> {
+        if let Some((source, file)) = self.sources.get(module) {
+            return Ok((source.clone(), file.clone()));
+        }
+        let file = format!("code/{}.py", module.replace('.', "/"));
+        let bytes = self.archive.read(&format!("{}{file}", self.prefix))?;
+        let text = std::str::from_utf8(&bytes)
+            .map_err(|_| invalid(format!("{file} is not valid UTF-8")))?;
+        let source = Rc::new(parse_source(text, &file)?);
+        let file: Rc<str> = file.into();
+        self.sources
+            .insert(module.to_string(), (source.clone(), file.clone()));
+        Ok((source, file))
+    }
+
+    fn module(&mut self, path: String, value: &'a Value) -> usize {
+        if let Some(index) = self.modules.iter().position(|module| module.path == path) {
+            return index;
+        }
+        self.modules.push(Instance { path, value });
+        self.modules.len() - 1
+    }
+
+    fn describe_module(&self, module: usize) -> String {
+        match self.modules[module].path.as_str() {
+            "" => "the root module".to_string(),
+            path => format!("module '{path}'"),
+        }
+    }
+
+    /// Reads `module.name`: a pickled attribute, or failing that a constant
+    /// in the class body.
+    fn attribute(&mut self, module: usize, name: &str) -> Result<ScriptValue, ParsingError> {
+        let instance = &self.modules[module];
+        let path = match instance.path.as_str() {
+            "" => name.to_string(),
+            parent => format!("{parent}.{name}"),
+        };
+        let state = match instance.value {
+            Value::Object {
+                state: Some(state), ..
+            } => Some(state.as_ref()),
+            _ => None,
+        };
+        if let Some(state) = state
+            && !matches!(state, Value::Dict(_))
+        {
+            return Err(invalid(format!(
+                "{} stores its state through __getstate__, which is not supported",
+                self.describe_module(module)
+            )));
+        }
+        if let Some(value) = state.and_then(|state| state.get(name)) {
+            return self.convert(value, path, "data");
+        }
+
+        let (source, file, class) = self.class_of(module)?;
+        let constant = source.class(&class).and_then(|class| {
+            class
+                .constants
+                .iter()
+                .find(|(constant, _)| constant == name)
+        });
+        match constant {
+            Some((_, value)) => self.eval(value, &mut Frame::new(&file)),
+            None => Err(invalid(format!(
+                "{} has no attribute '{name}'",
+                self.describe_module(module)
+            ))),
+        }
+    }
+
+    /// `CONSTANTS.cN`, entry N of `constants.pkl`.
+    fn archive_constant(&mut self, name: &str) -> Result<ScriptValue, ParsingError> {
+        let constants = self.constants;
+        let value = name
+            .strip_prefix('c')
+            .and_then(|index| index.parse::<usize>().ok())
+            .and_then(|index| constants.get(index))
+            .ok_or_else(|| invalid(format!("CONSTANTS.{name} is not in constants.pkl")))?;
+        self.convert(value, format!("CONSTANTS.{name}"), "constants")
+    }
+
+    /// Turns a pickled value into a script value; tensors become graph
+    /// constants named `path`, with their data in `directory`.
+    fn convert(
+        &mut self,
+        value: &'a Value,
+        path: String,
+        directory: &str,
+    ) -> Result<ScriptValue, ParsingError> {
+        Ok(match value {
+            Value::None => ScriptValue::None,
+            Value::Bool(value) => ScriptValue::Bool(*value),
+            Value::Int(value) => ScriptValue::Int(*value),
+            Value::Float(value) => ScriptValue::Float(*value),
+            Value::String(value) => ScriptValue::Str(value.clone()),
+            Value::Tuple(items) | Value::List(items) => {
+                let mut converted = Vec::new();
+                for (index, item) in items.iter().enumerate() {
+                    converted.push(self.convert(item, format!("{path}.{index}"), directory)?);
+                }
+                if matches!(value, Value::Tuple(_)) {
+                    ScriptValue::Tuple(converted)
+                } else {
+                    ScriptValue::List(converted)
+                }
+            }
+            Value::Object { module, .. } if module.starts_with("__torch__") => {
+                ScriptValue::Module(self.module(path, value))
+            }
+            Value::Object { .. } => {
+                if let Some(constant) = self.tensors.get(&path) {
+                    return Ok(ScriptValue::Tensor(*constant));
+                }
+                let tensor = self.read_tensor(value, &path, directory)?;
+                let constant: ValueRef = self.graph.add_initializer(&path, tensor).into();
+                self.tensors.insert(path, constant);
+                ScriptValue::Tensor(constant)
+            }
+            Value::Bytes(_) | Value::Dict(_) | Value::Global { .. } | Value::PersistentId(_) => {
+                return Err(invalid(format!(
+                    "'{path}' holds a pickled value Xyntra cannot read"
+                )));
+            }
+        })
+    }
+
+    /// Rebuilds a pickled tensor from its storage in `directory`.
+    fn read_tensor(
+        &self,
+        value: &Value,
+        what: &str,
+        directory: &str,
+    ) -> Result<Tensor, ParsingError> {
+        let Value::Object {
+            module, name, args, ..
+        } = value
+        else {
+            return Err(invalid(format!("'{what}' is not a tensor")));
+        };
+        match (module.as_str(), name.as_str(), args.first()) {
+            ("torch._utils", "_rebuild_parameter", Some(tensor)) => {
+                return self.read_tensor(tensor, what, directory);
+            }
+            ("torch._utils", "_rebuild_tensor_v2" | "_rebuild_tensor", _) => {}
+            _ => {
+                return Err(invalid(format!(
+                    "'{what}' holds a {module}.{name} object, which Xyntra cannot read"
+                )));
+            }
+        }
+
+        let (
+            Some(Value::PersistentId(storage)),
+            Some(Value::Int(offset)),
+            Some(Value::Tuple(size)),
+            Some(Value::Tuple(stride)),
+        ) = (args.first(), args.get(1), args.get(2), args.get(3))
+        else {
+            return Err(invalid(format!("'{what}' has malformed tensor arguments")));
+        };
+        let Value::Tuple(storage) = storage.as_ref() else {
+            return Err(invalid(format!("'{what}' has a malformed storage id")));
+        };
+        let [
+            Value::String(kind),
+            Value::Global {
+                name: storage_type, ..
+            },
+            Value::String(key),
+            _,
+            _,
+        ] = storage.as_slice()
+        else {
+            return Err(invalid(format!("'{what}' has a malformed storage id")));
+        };
+        if kind != "storage" {
+            return Err(invalid(format!(
+                "'{what}' refers to a {kind}, not a storage"
+            )));
+        }
+        let (dtype, width) = storage_dtype(storage_type).ok_or_else(|| {
+            invalid(format!(
+                "'{what}' is a {storage_type}, which Xyntra does not support"
+            ))
+        })?;
+
+        let dims = non_negative(size, what)?;
+        let strides = non_negative(stride, what)?;
+        let offset = usize::try_from(*offset)
+            .map_err(|_| invalid(format!("'{what}' has storage offset {offset}")))?;
+        if strides.len() != dims.len() {
+            return Err(invalid(format!(
+                "'{what}' has {} sizes but {} strides",
+                dims.len(),
+                strides.len()
+            )));
+        }
+
+        let bytes = self
+            .archive
+            .read(&format!("{}{directory}/{key}", self.prefix))?;
+        let available = bytes.len() / width;
+        let too_large = || {
+            invalid(format!(
+                "'{what}' has sizes {dims:?} and strides {strides:?}"
+            ))
+        };
+        let count = dims
+            .iter()
+            .try_fold(1usize, |count, &dim| count.checked_mul(dim))
+            .ok_or_else(too_large)?;
+
+        // Every position visited is at most the one of the last element, so
+        // checking that one bounds all reads before anything is allocated
+        if count > 0 {
+            let last = dims
+                .iter()
+                .zip(&strides)
+                .try_fold(offset, |position, (dim, stride)| {
+                    (dim - 1)
+                        .checked_mul(*stride)
+                        .and_then(|step| position.checked_add(step))
+                });
+            if last.is_none_or(|last| last >= available) {
+                return Err(invalid(format!(
+                    "'{what}' reads past the end of its storage"
+                )));
+            }
+        }
+        let mut data = Vec::new();
+        count
+            .checked_mul(dtype.size_in_bytes())
+            .and_then(|size| data.try_reserve_exact(size).ok())
+            .ok_or_else(too_large)?;
+
+        // Visit the elements in row-major order, following the strides
+        let mut index = vec![0usize; dims.len()];
+        for _ in 0..count {
+            let position = offset
+                + index
+                    .iter()
+                    .zip(&strides)
+                    .map(|(index, stride)| index * stride)
+                    .sum::<usize>();
+            let element = &bytes[position * width..(position + 1) * width];
+            if width == 8 {
+                // int64 storage narrowed to the IR's 32-bit integers
+                let value = i64::from_le_bytes(element.try_into().expect("8 bytes"));
+                let narrowed = i32::try_from(value).map_err(|_| {
+                    invalid(format!(
+                        "'{what}' holds {value}, which does not fit the 32-bit integers Xyntra uses"
+                    ))
+                })?;
+                data.extend(narrowed.to_le_bytes());
+            } else {
+                data.extend_from_slice(element);
+            }
+            for axis in (0..dims.len()).rev() {
+                index[axis] += 1;
+                if index[axis] < dims[axis] {
+                    break;
+                }
+                index[axis] = 0;
+            }
+        }
+
+        Tensor::new(TensorType::new(dtype, TensorShape::new(dims)), data)
+            .map_err(|error| invalid(format!("'{what}': {error}")))
+    }
+
+    /// Evaluates `torch.<name>(...)`.
+    fn torch(
+        &mut self,
+        name: &str,
+        positional: Vec<ScriptValue>,
+        keywords: Vec<(String, ScriptValue)>,
+    ) -> Result<ScriptValue, ParsingError> {
+        if keywords.is_empty()
+            && let Some(value) = scalar_op(name, &positional)?
+        {
+            return Ok(value);
+        }
+        let op = format!("torch.{name}");
+
+        let unary = match name {
+            "relu" => Some(OpKind::Relu),
+            "sigmoid" => Some(OpKind::Sigmoid),
+            "tanh" => Some(OpKind::Tanh),
+            "exp" => Some(OpKind::Exp),
+            "log" => Some(OpKind::Log),
+            "sqrt" => Some(OpKind::Sqrt),
+            "rsqrt" => Some(OpKind::Rsqrt),
+            "erf" => Some(OpKind::Erf),
+            _ => None,
+        };
+        if let Some(kind) = unary {
+            let args = bind(&op, &["input"], positional, keywords)?;
+            let input = self.tensor(&args, 0)?;
+            return Ok(self.node(kind, vec![input]));
+        }
+
+        let binary = match name {
+            "add" => Some(OpKind::Add),
+            "sub" | "rsub" => Some(OpKind::Sub),
+            "mul" => Some(OpKind::Mul),
+            "div" => Some(OpKind::Div),
+            "pow" => Some(OpKind::Pow),
+            _ => None,
+        };
+        if let Some(kind) = binary {
+            let names: &'static [&'static str] = if name == "div" {
+                &["input", "other", "rounding_mode"]
+            } else {
+                &["input", "other", "alpha"]
+            };
+            let args = bind(&op, names, positional, keywords)?;
+            if name == "div" && args.get(2).is_some() {
+                return Err(invalid(format!("{op}: rounding modes are not supported")));
+            }
+            if name != "div" && args.float(2, 1.0)? != 1.0 {
+                return Err(invalid(format!(
+                    "{op}: alpha other than 1 is not supported"
+                )));
+            }
+            let (mut left, mut right) = self.operands(&args, 0, 1)?;
+            if name == "rsub" {
+                std::mem::swap(&mut left, &mut right);
+            }
+            return Ok(self.node(kind, vec![left, right]));
+        }
+
+        match name {
+            "dim" => {
+                let args = bind(&op, &["input"], positional, keywords)?;
+                let input = self.tensor(&args, 0)?;
+                Ok(ScriptValue::Int(self.rank(input, &op)? as i64))
+            }
+            "size" => {
+                let args = bind(&op, &["input", "dim"], positional, keywords)?;
+                let input = self.tensor(&args, 0)?;
+                let rank = self.rank(input, &op)?;
+                match args.optional_int(1)? {
+                    Some(dim) => {
+                        let axis = normalize(dim, rank, &op)?;
+                        Ok(self.size(input, axis))
+                    }
+                    None => Ok(ScriptValue::List(
+                        (0..rank).map(|axis| self.size(input, axis)).collect(),
+                    )),
+                }
+            }
+            "neg" => {
+                let args = bind(&op, &["input"], positional, keywords)?;
+                let input = self.tensor(&args, 0)?;
+                let minus_one = self.scalar(-1.0, self.dtype(input).unwrap_or(DType::F32))?;
+                Ok(self.node(OpKind::Mul, vec![input, minus_one]))
+            }
+            "contiguous" | "clone" | "detach" => {
+                let args = bind(&op, &["input", "memory_format"], positional, keywords)?;
+                Ok(ScriptValue::Tensor(self.tensor(&args, 0)?))
+            }
+            "gelu" => {
+                let args = bind(&op, &["input", "approximate"], positional, keywords)?;
+                let approximate = args.string(1, "none")?;
+                if approximate != "none" {
+                    return Err(invalid(format!(
+                        "{op}: approximate = \"{approximate}\" is not supported"
+                    )));
+                }
+                let input = self.tensor(&args, 0)?;
+                Ok(self.node(OpKind::Gelu, vec![input]))
+            }
+            "dropout" => {
+                let args = bind(&op, &["input", "p", "train"], positional, keywords)?;
+                if args.bool(2, false)? {
+                    return Err(invalid(format!("{op}: training mode is not supported")));
+                }
+                let ratio = args.float(1, 0.5)? as f32;
+                let input = self.tensor(&args, 0)?;
+                Ok(self.node(OpKind::Dropout { ratio }, vec![input]))
+            }
+            "softmax" => {
+                let args = bind(&op, &["input", "dim", "dtype"], positional, keywords)?;
+                if args.get(2).is_some() {
+                    return Err(invalid(format!("{op}: 'dtype' is not supported")));
+                }
+                let axis = args.required_int(1)?;
+                let input = self.tensor(&args, 0)?;
+                Ok(self.node(OpKind::Softmax { axis }, vec![input]))
+            }
+            "clamp" | "clamp_min" | "clamp_max" | "hardtanh" | "relu6" => {
+                let names: &'static [&'static str] = match name {
+                    "clamp" => &["input", "min", "max"],
+                    "clamp_min" => &["input", "min"],
+                    "clamp_max" => &["input", "max"],
+                    "hardtanh" => &["input", "min_val", "max_val"],
+                    _ => &["input"],
+                };
+                let args = bind(&op, names, positional, keywords)?;
+                let (min, max) = match name {
+                    "clamp" => (args.optional_float(1)?, args.optional_float(2)?),
+                    "clamp_min" => (args.optional_float(1)?, None),
+                    "clamp_max" => (None, args.optional_float(1)?),
+                    "hardtanh" => (Some(args.float(1, -1.0)?), Some(args.float(2, 1.0)?)),
+                    _ => (Some(0.0), Some(6.0)),
+                };
+                let input = self.tensor(&args, 0)?;
+                let op = OpKind::Clip {
+                    min: min.map(|min| min as f32),
+                    max: max.map(|max| max as f32),
+                };
+                Ok(self.node(op, vec![input]))
+            }
+            "to" | "type_as" => self.cast(&op, positional, keywords),
+            "where" => {
+                let args = bind(&op, &["condition", "input", "other"], positional, keywords)?;
+                let condition = self.tensor(&args, 0)?;
+                let (input, other) = self.operands(&args, 1, 2)?;
+                Ok(self.node(OpKind::Where, vec![condition, input, other]))
+            }
+
+            "matmul" | "mm" | "bmm" => {
+                let args = bind(&op, &["input", "other"], positional, keywords)?;
+                let inputs = vec![self.tensor(&args, 0)?, self.tensor(&args, 1)?];
+                Ok(self.node(OpKind::matmul(), inputs))
+            }
+            "linear" => {
+                let args = bind(&op, &["input", "weight", "bias"], positional, keywords)?;
+                let inputs = vec![self.tensor(&args, 0)?, self.tensor(&args, 1)?];
+                let bias = self.optional_tensor(&args, 2)?;
+                let product = OpKind::MatMul {
+                    transpose_a: false,
+                    transpose_b: true,
+                };
+                Ok(self.with_bias(product, inputs, bias))
+            }
+            "addmm" => {
+                let args = bind(
+                    &op,
+                    &["input", "mat1", "mat2", "beta", "alpha"],
+                    positional,
+                    keywords,
+                )?;
+                if args.float(3, 1.0)? != 1.0 || args.float(4, 1.0)? != 1.0 {
+                    return Err(invalid(format!(
+                        "{op}: alpha and beta other than 1 are not supported"
+                    )));
+                }
+                let bias = self.tensor(&args, 0)?;
+                let inputs = vec![self.tensor(&args, 1)?, self.tensor(&args, 2)?];
+                Ok(self.with_bias(OpKind::matmul(), inputs, Some(bias)))
+            }
+
+            "layer_norm" => {
+                let args = bind(
+                    &op,
+                    &[
+                        "input",
+                        "normalized_shape",
+                        "weight",
+                        "bias",
+                        "eps",
+                        "cudnn_enable",
+                    ],
+                    positional,
+                    keywords,
+                )?;
+                let input = self.tensor(&args, 0)?;
+                let shape = non_negative_ints(&args, 1)?;
+                let op = OpKind::LayerNorm {
+                    axis: -(shape.len() as i64),
+                    epsilon: args.float(4, 1e-5)? as f32,
+                };
+                let mut inputs = vec![input];
+                match (
+                    self.optional_tensor(&args, 2)?,
+                    self.optional_tensor(&args, 3)?,
+                ) {
+                    (Some(weight), bias) => {
+                        inputs.extend([Some(weight), bias].into_iter().flatten())
+                    }
+                    // The IR takes the bias after the scale, so fill in the scale
+                    (None, Some(bias)) => {
+                        let dtype = self.dtype(input).unwrap_or(DType::F32);
+                        inputs.extend([self.filled(1.0, shape, dtype)?, bias]);
+                    }
+                    (None, None) => {}
+                }
+                Ok(self.node(op, inputs))
+            }
+            "batch_norm" => {
+                let args = bind(
+                    &op,
+                    &[
+                        "input",
+                        "weight",
+                        "bias",
+                        "running_mean",
+                        "running_var",
+                        "training",
+                        "momentum",
+                        "eps",
+                        "cudnn_enabled",
+                    ],
+                    positional,
+                    keywords,
+                )?;
+                if args.bool(5, false)? {
+                    return Err(invalid(format!("{op}: training mode is not supported")));
+                }
+                let input = self.tensor(&args, 0)?;
+                let (Some(mean), Some(variance)) = (
+                    self.optional_tensor(&args, 3)?,
+                    self.optional_tensor(&args, 4)?,
+                ) else {
+                    return Err(invalid(format!("{op}: running statistics are required")));
+                };
+                let channels = self
+                    .graph
+                    .value_type(mean)
+                    .and_then(|mean_type| mean_type.shape().static_dims())
+                    .ok_or_else(|| {
+                        invalid(format!("{op}: the running mean has no static shape"))
+                    })?;
+                let dtype = self.dtype(input).unwrap_or(DType::F32);
+                let weight = match self.optional_tensor(&args, 1)? {
+                    Some(weight) => weight,
+                    None => self.filled(1.0, channels.clone(), dtype)?,
+                };
+                let bias = match self.optional_tensor(&args, 2)? {
+                    Some(bias) => bias,
+                    None => self.filled(0.0, channels, dtype)?,
+                };
+                let op = OpKind::BatchNorm {
+                    epsilon: args.float(7, 1e-5)? as f32,
+                };
+                Ok(self.node(op, vec![input, weight, bias, mean, variance]))
+            }
+
+            "sum" | "mean" | "amax" | "amin" => {
+                let names: &'static [&'static str] = if name == "sum" || name == "mean" {
+                    &["input", "dim", "keepdim", "dtype"]
+                } else {
+                    &["input", "dim", "keepdim"]
+                };
+                let args = bind(&op, names, positional, keywords)?;
+                if args.get(3).is_some() {
+                    return Err(invalid(format!("{op}: 'dtype' is not supported")));
+                }
+                let kind = match name {
+                    "sum" => ReduceKind::Sum,
+                    "mean" => ReduceKind::Mean,
+                    "amax" => ReduceKind::Max,
+                    _ => ReduceKind::Min,
+                };
+                let op = OpKind::Reduce {
+                    kind,
+                    axes: args.ints(1)?.unwrap_or_default(),
+                    keepdims: args.bool(2, false)?,
+                };
+                let input = self.tensor(&args, 0)?;
+                Ok(self.node(op, vec![input]))
+            }
+            "argmax" => {
+                let args = bind(&op, &["input", "dim", "keepdim"], positional, keywords)?;
+                let axis = args.optional_int(1)?.ok_or_else(|| {
+                    invalid(format!(
+                        "{op}: the index into the flattened tensor is not supported"
+                    ))
+                })?;
+                let op = OpKind::ArgMax {
+                    axis,
+                    keepdims: args.bool(2, false)?,
+                };
+                let input = self.tensor(&args, 0)?;
+                Ok(self.node(op, vec![input]))
+            }
+
+            "reshape" | "view" => {
+                let names: &'static [&'static str] = if name == "view" {
+                    &["input", "size"]
+                } else {
+                    &["input", "shape"]
+                };
+                let args = bind(&op, names, positional, keywords)?;
+                let input = self.tensor(&args, 0)?;
+                let shape = self.target_shape(&args, 1, input)?;
+                Ok(self.node(OpKind::Reshape { shape }, vec![input]))
+            }
+            "flatten" => {
+                let args = bind(
+                    &op,
+                    &["input", "start_dim", "end_dim"],
+                    positional,
+                    keywords,
+                )?;
+                let input = self.tensor(&args, 0)?;
+                let rank = self.rank(input, &op)?;
+                let start = normalize(args.int(1, 0)?, rank.max(1), &op)?;
+                let end = normalize(args.int(2, -1)?, rank.max(1), &op)?;
+                if start >= end {
+                    return Ok(ScriptValue::Tensor(input));
+                }
+                // Leading dimensions are copied in place; trailing ones have
+                // moved, so they must be spelled out
+                let mut shape = vec![0; start];
+                shape.push(-1);
+                for axis in end + 1..rank {
+                    let dim = self.static_dim(input, axis).ok_or_else(|| {
+                        invalid(format!(
+                            "{op}: dimension {axis} after 'end_dim' has no static size"
+                        ))
+                    })?;
+                    shape.push(dim as i64);
+                }
+                Ok(self.node(OpKind::Reshape { shape }, vec![input]))
+            }
+            "permute" => {
+                let args = bind(&op, &["input", "dims"], positional, keywords)?;
+                let input = self.tensor(&args, 0)?;
+                let dims = args
+                    .ints(1)?
+                    .ok_or_else(|| invalid(format!("{op} is missing 'dims'")))?;
+                let rank = self.rank(input, &op)?;
+                let perm = dims
+                    .iter()
+                    .map(|&dim| normalize(dim, rank, &op))
+                    .collect::<Result<_, _>>()?;
+                Ok(self.node(OpKind::Transpose { perm }, vec![input]))
+            }
+            "transpose" | "t" => {
+                let names: &'static [&'static str] = if name == "t" {
+                    &["input"]
+                } else {
+                    &["input", "dim0", "dim1"]
+                };
+                let args = bind(&op, names, positional, keywords)?;
+                let input = self.tensor(&args, 0)?;
+                let rank = self.rank(input, &op)?;
+                let (first, second) = if name == "t" {
+                    if rank < 2 {
+                        return Ok(ScriptValue::Tensor(input));
+                    }
+                    (0, 1)
+                } else {
+                    (
+                        normalize(args.required_int(1)?, rank, &op)?,
+                        normalize(args.required_int(2)?, rank, &op)?,
+                    )
+                };
+                let mut perm: Vec<usize> = (0..rank).collect();
+                perm.swap(first, second);
+                Ok(self.node(OpKind::Transpose { perm }, vec![input]))
+            }
+            "cat" | "concat" | "concatenate" => {
+                let args = bind(&op, &["tensors", "dim"], positional, keywords)?;
+                let tensors = match args.get(0) {
+                    Some(ScriptValue::List(items) | ScriptValue::Tuple(items)) => items
+                        .iter()
+                        .map(|item| match item {
+                            ScriptValue::Tensor(value) => Ok(*value),
+                            other => Err(invalid(format!(
+                                "{op}: 'tensors' should hold tensors, not a {}",
+                                other.kind()
+                            ))),
+                        })
+                        .collect::<Result<Vec<_>, _>>()?,
+                    _ => return Err(args.mismatch(0, "a list of tensors")),
+                };
+                let axis = args.int(1, 0)?;
+                Ok(self.node(OpKind::Concat { axis }, tensors))
+            }
+            "split" | "split_with_sizes" | "chunk" => {
+                let second = match name {
+                    "split" => "split_size",
+                    "split_with_sizes" => "split_sizes",
+                    _ => "chunks",
+                };
+                let names: &'static [&'static str] = match second {
+                    "split_size" => &["input", "split_size", "dim"],
+                    "split_sizes" => &["input", "split_sizes", "dim"],
+                    _ => &["input", "chunks", "dim"],
+                };
+                let args = bind(&op, names, positional, keywords)?;
+                let input = self.tensor(&args, 0)?;
+                let axis = args.int(2, 0)?;
+                let sizes = match (name, args.get(1)) {
+                    ("chunk", _) | (_, Some(ScriptValue::Int(_))) => {
+                        let rank = self.rank(input, &op)?;
+                        let length = self
+                            .static_dim(input, normalize(axis, rank, &op)?)
+                            .ok_or_else(|| {
+                                invalid(format!("{op}: dimension {axis} has no static size"))
+                            })?;
+                        let count = usize::try_from(args.required_int(1)?)
+                            .ok()
+                            .filter(|&count| count > 0)
+                            .ok_or_else(|| args.mismatch(1, "a positive int"))?;
+                        match name {
+                            // ATen keeps every requested chunk of an empty dimension
+                            "chunk" if length == 0 => vec![0; count],
+                            "chunk" => even_pieces(length, length.div_ceil(count)),
+                            _ => even_pieces(length, count),
+                        }
+                    }
+                    _ => non_negative_ints(&args, 1)?,
+                };
+                let count = sizes.len();
+                let node_id = self.add_node(OpKind::Split { axis, sizes }, vec![input]);
+                Ok(ScriptValue::List(
+                    (0..count)
+                        .map(|port| ScriptValue::Tensor(node_id.output(port)))
+                        .collect(),
+                ))
+            }
+            "slice" => {
+                let args = bind(
+                    &op,
+                    &["input", "dim", "start", "end", "step"],
+                    positional,
+                    keywords,
+                )?;
+                let input = self.tensor(&args, 0)?;
+                let step = args.int(4, 1)?;
+                if step <= 0 {
+                    return Err(invalid(format!("{op}: step {step} is not supported")));
+                }
+                let op = OpKind::Slice {
+                    starts: vec![args.optional_int(2)?.unwrap_or(0)],
+                    ends: vec![args.optional_int(3)?.unwrap_or(i64::MAX)],
+                    axes: vec![args.int(1, 0)?],
+                    steps: if step == 1 { Vec::new() } else { vec![step] },
+                };
+                Ok(self.node(op, vec![input]))
+            }
+            "select" => {
+                let args = bind(&op, &["input", "dim", "index"], positional, keywords)?;
+                let input = self.tensor(&args, 0)?;
+                let axis = args.required_int(1)?;
+                let index = args.required_int(2)?;
+                let index = self.scalar(index as f64, DType::I32)?;
+                Ok(self.node(OpKind::Gather { axis }, vec![input, index]))
+            }
+            "index_select" => {
+                let args = bind(&op, &["input", "dim", "index"], positional, keywords)?;
+                let inputs = vec![self.tensor(&args, 0)?, self.tensor(&args, 2)?];
+                let axis = args.required_int(1)?;
+                Ok(self.node(OpKind::Gather { axis }, inputs))
+            }
+            "embedding" => {
+                let args = bind(
+                    &op,
+                    &[
+                        "weight",
+                        "indices",
+                        "padding_idx",
+                        "scale_grad_by_freq",
+                        "sparse",
+                    ],
+                    positional,
+                    keywords,
+                )?;
+                let inputs = vec![self.tensor(&args, 0)?, self.tensor(&args, 1)?];
+                Ok(self.node(OpKind::Gather { axis: 0 }, inputs))
+            }
+
+            "conv2d" | "_convolution" => {
+                let names: &'static [&'static str] = if name == "conv2d" {
+                    &[
+                        "input", "weight", "bias", "stride", "padding", "dilation", "groups",
+                    ]
+                } else {
+                    &[
+                        "input",
+                        "weight",
+                        "bias",
+                        "stride",
+                        "padding",
+                        "dilation",
+                        "transposed",
+                        "output_padding",
+                        "groups",
+                        "benchmark",
+                        "deterministic",
+                        "cudnn_enabled",
+                        "allow_tf32",
+                    ]
+                };
+                let args = bind(&op, names, positional, keywords)?;
+                let groups_index = if name == "conv2d" { 6 } else { 8 };
+                if name == "_convolution" && args.bool(6, false)? {
+                    return Err(invalid(format!(
+                        "{op}: transposed convolutions are not supported"
+                    )));
+                }
+                let input = self.tensor(&args, 0)?;
+                let weight = self.tensor(&args, 1)?;
+                let kernel = match self.graph.value_type(weight).map(|weight| weight.shape()) {
+                    Some(shape) if shape.rank() == 4 => shape
+                        .dims()
+                        .get(2..)
+                        .and_then(|dims| Some([dims[0].as_static()?, dims[1].as_static()?])),
+                    _ => {
+                        return Err(invalid(format!("{op}: only 2D convolutions are supported")));
+                    }
+                };
+                let strides = pair(&args, 3, [1, 1])?;
+                let dilations = pair(&args, 5, [1, 1])?;
+                let pads = match args.get(4) {
+                    Some(ScriptValue::Str(mode)) if mode == "valid" => [0; 4],
+                    Some(ScriptValue::Str(mode)) if mode == "same" => {
+                        let kernel = kernel.ok_or_else(|| {
+                            invalid(format!("{op}: padding \"same\" needs a static kernel size"))
+                        })?;
+                        if kernel.contains(&0) {
+                            return Err(invalid(format!(
+                                "{op}: the weight has an empty kernel {kernel:?}"
+                            )));
+                        }
+                        same_padding(kernel, dilations)
+                    }
+                    _ => {
+                        let [height, width] = pair(&args, 4, [0, 0])?;
+                        [height, width, height, width]
+                    }
+                };
+                let groups = usize::try_from(args.int(groups_index, 1)?)
+                    .ok()
+                    .filter(|&groups| groups > 0)
+                    .ok_or_else(|| args.mismatch(groups_index, "a positive int"))?;
+                let op = OpKind::Conv2d {
+                    window: Window2d {
+                        strides,
+                        pads,
+                        dilations,
+                    },
+                    groups,
+                };
+                let mut inputs = vec![input, weight];
+                inputs.extend(self.optional_tensor(&args, 2)?);
+                Ok(self.node(op, inputs))
+            }
+            "max_pool2d" | "avg_pool2d" => {
+                let names: &'static [&'static str] = if name == "max_pool2d" {
+                    &[
+                        "input",
+                        "kernel_size",
+                        "stride",
+                        "padding",
+                        "dilation",
+                        "ceil_mode",
+                    ]
+                } else {
+                    &[
+                        "input",
+                        "kernel_size",
+                        "stride",
+                        "padding",
+                        "ceil_mode",
+                        "count_include_pad",
+                        "divisor_override",
+                    ]
+                };
+                let args = bind(&op, names, positional, keywords)?;
+                let ceil_mode = if name == "max_pool2d" { 5 } else { 4 };
+                if args.bool(ceil_mode, false)? {
+                    return Err(invalid(format!("{op}: ceil_mode = True is not supported")));
+                }
+                if name == "avg_pool2d" && args.get(6).is_some() {
+                    return Err(invalid(format!(
+                        "{op}: 'divisor_override' is not supported"
+                    )));
+                }
+                let input = self.tensor(&args, 0)?;
+                let kernel_shape = pair(&args, 1, [1, 1])?;
+                // An empty stride means the kernel size
+                let strides = match args.ints(2)? {
+                    Some(stride) if !stride.is_empty() => pair(&args, 2, kernel_shape)?,
+                    _ => kernel_shape,
+                };
+                let [height, width] = pair(&args, 3, [0, 0])?;
+                let window = Window2d {
+                    strides,
+                    pads: [height, width, height, width],
+                    dilations: if name == "max_pool2d" {
+                        pair(&args, 4, [1, 1])?
+                    } else {
+                        [1, 1]
+                    },
+                };
+                let op = if name == "max_pool2d" {
+                    OpKind::MaxPool {
+                        kernel_shape,
+                        window,
+                    }
+                } else {
+                    OpKind::AvgPool {
+                        kernel_shape,
+                        window,
+                        count_include_pad: args.bool(5, true)?,
+                    }
+                };
+                Ok(self.node(op, vec![input]))
+            }
+            "adaptive_avg_pool2d" => {
+                let args = bind(&op, &["input", "output_size"], positional, keywords)?;
+                if pair(&args, 1, [0, 0])? != [1, 1] {
+                    return Err(invalid(format!(
+                        "{op}: only an output size of 1x1 is supported"
+                    )));
+                }
+                let input = self.tensor(&args, 0)?;
+                Ok(self.node(OpKind::GlobalAvgPool, vec![input]))
+            }
+
+            _ => Err(invalid(format!("{op} is not supported"))),
+        }
+    }
+
+    /// `torch.to` in its dtype, device and tensor forms, and `torch.type_as`.
+    fn cast(
+        &mut self,
+        op: &str,
+        positional: Vec<ScriptValue>,
+        mut keywords: Vec<(String, ScriptValue)>,
+    ) -> Result<ScriptValue, ParsingError> {
+        let input = match positional.first() {
+            Some(ScriptValue::Tensor(input)) => *input,
+            _ => return Err(invalid(format!("{op} needs a tensor input"))),
+        };
+        let target = match positional.get(1) {
+            // `to(input, device, dtype, ...)`
+            Some(ScriptValue::Str(_)) => positional.get(2).cloned(),
+            target => target.cloned(),
+        };
+        let target = match keywords.iter().position(|(name, _)| name == "dtype") {
+            Some(index) => Some(keywords.swap_remove(index).1),
+            None => target,
+        };
+        let to = match target {
+            None | Some(ScriptValue::None) => return Ok(ScriptValue::Tensor(input)),
+            Some(ScriptValue::Int(code)) => scalar_type(code)
+                .ok_or_else(|| invalid(format!("{op}: dtype {code} is not supported")))?,
+            Some(ScriptValue::Tensor(other)) => self
+                .dtype(other)
+                .ok_or_else(|| invalid(format!("{op}: the target tensor's dtype is unknown")))?,
+            Some(other) => {
+                return Err(invalid(format!(
+                    "{op}: a {} target is not supported",
+                    other.kind()
+                )));
+            }
+        };
+        Ok(self.node(OpKind::Cast { to }, vec![input]))
+    }
+
+    /// A reshape target whose dynamic entries keep their input dimension:
+    /// they must equal the input's dimension at the same position, possibly
+    /// read from another tensor sharing its symbol.
+    fn target_shape(
+        &self,
+        args: &Args,
+        index: usize,
+        input: ValueRef,
+    ) -> Result<Vec<i64>, ParsingError> {
+        let items = match args.get(index) {
+            Some(ScriptValue::List(items) | ScriptValue::Tuple(items)) => items,
+            _ => return Err(args.mismatch(index, "a list of sizes")),
+        };
+        let mut shape = Vec::new();
+        for (position, item) in items.iter().enumerate() {
+            shape.push(match item {
+                // The IR reads 0 as "copy the input dimension"
+                ScriptValue::Int(0) => {
+                    return Err(invalid(format!(
+                        "{}: dimensions of size 0 are not supported",
+                        args.op
+                    )));
+                }
+                ScriptValue::Int(size) => *size,
+                ScriptValue::Size { tensor, axis }
+                    if self.dim(*tensor, *axis).is_some()
+                        && self.dim(*tensor, *axis) == self.dim(input, position) =>
+                {
+                    0
+                }
+                ScriptValue::Size { .. } => {
+                    return Err(invalid(format!(
+                        "{}: a dynamic size is only supported where it keeps the input \
+                         dimension in place",
+                        args.op
+                    )));
+                }
+                other => {
+                    return Err(invalid(format!(
+                        "{}: sizes should be ints, not a {}",
+                        args.op,
+                        other.kind()
+                    )));
+                }
+            });
+        }
+        Ok(shape)
+    }
+
+    fn tensor(&self, args: &Args, index: usize) -> Result<ValueRef, ParsingError> {
+        self.optional_tensor(args, index)?
+            .ok_or_else(|| args.mismatch(index, "a tensor"))
+    }
+
+    fn optional_tensor(&self, args: &Args, index: usize) -> Result<Option<ValueRef>, ParsingError> {
+        match args.get(index) {
+            Some(ScriptValue::Tensor(value)) => Ok(Some(*value)),
+            None => Ok(None),
+            Some(_) => Err(args.mismatch(index, "a tensor")),
+        }
+    }
+
+    /// Two operands of an elementwise op, either of which may be a number;
+    /// numbers become constants of the other operand's dtype.
+    fn operands(
+        &mut self,
+        args: &Args,
+        first: usize,
+        second: usize,
+    ) -> Result<(ValueRef, ValueRef), ParsingError> {
+        let like = [first, second]
+            .iter()
+            .find_map(|&index| match args.get(index) {
+                Some(ScriptValue::Tensor(value)) => self.dtype(*value),
+                _ => None,
+            });
+        Ok((
+            self.operand(args, first, like)?,
+            self.operand(args, second, like)?,
+        ))
+    }
+
+    fn operand(
+        &mut self,
+        args: &Args,
+        index: usize,
+        like: Option<DType>,
+    ) -> Result<ValueRef, ParsingError> {
+        match args.get(index) {
+            Some(ScriptValue::Tensor(value)) => Ok(*value),
+            Some(ScriptValue::Int(value)) => self.scalar(*value as f64, like.unwrap_or(DType::I32)),
+            Some(ScriptValue::Bool(value)) => {
+                self.scalar(*value as i64 as f64, like.unwrap_or(DType::Bool))
+            }
+            Some(ScriptValue::Float(value)) => self.scalar(*value, like.unwrap_or(DType::F32)),
+            _ => Err(args.mismatch(index, "a tensor or a number")),
+        }
+    }
+
+    fn scalar(&mut self, value: f64, dtype: DType) -> Result<ValueRef, ParsingError> {
+        self.filled(value, Vec::new(), dtype)
+    }
+
+    /// A made-up constant holding `value` everywhere.
+    fn filled(
+        &mut self,
+        value: f64,
+        dims: Vec<usize>,
+        dtype: DType,
+    ) -> Result<ValueRef, ParsingError> {
+        let element = element_bytes(value, dtype);
+        let count: usize = dims.iter().product();
+        let tensor_type = TensorType::new(dtype, TensorShape::new(dims));
+        let tensor = Tensor::new(tensor_type, element.repeat(count))
+            .map_err(|error| invalid(error.to_string()))?;
+        let name = format!("const_{}", self.generated);
+        self.generated += 1;
+        Ok(self.graph.add_initializer(name, tensor).into())
+    }
+
+    /// A product plus an optional bias, as `linear` and `addmm` compute.
+    fn with_bias(
+        &mut self,
+        product: OpKind,
+        inputs: Vec<ValueRef>,
+        bias: Option<ValueRef>,
+    ) -> ScriptValue {
+        let product = self.add_node(product, inputs);
+        match bias {
+            Some(bias) => self.node(OpKind::Add, vec![product.into(), bias]),
+            None => ScriptValue::Tensor(product.into()),
+        }
+    }
+
+    fn node(&mut self, op: OpKind, inputs: Vec<ValueRef>) -> ScriptValue {
+        ScriptValue::Tensor(self.add_node(op, inputs).into())
+    }
+
+    /// Adds a node and infers its output types when its inputs' types are
+    /// known, so later ops can rely on ranks and dimensions.
+    fn add_node(&mut self, op: OpKind, inputs: Vec<ValueRef>) -> NodeID {
+        let input_types: Option<Vec<TensorType>> = inputs
+            .iter()
+            .map(|input| self.graph.value_type(*input).cloned())
+            .collect();
+        let output_types = input_types.and_then(|input_types| {
+            let input_types: Vec<&TensorType> = input_types.iter().collect();
+            infer_output_types(&op, &input_types, &mut self.constraints).ok()
+        });
+
+        let node_id = self.graph.add_node_with_values(op, inputs);
+        if let Some(output_types) = output_types {
+            let _ = self.graph.set_output_types(node_id, output_types);
+        }
+        node_id
+    }
+
+    fn dtype(&self, value: ValueRef) -> Option<DType> {
+        self.graph
+            .value_type(value)
+            .map(|value_type| value_type.dtype())
+    }
+
+    fn rank(&self, value: ValueRef, op: &str) -> Result<usize, ParsingError> {
+        self.graph
+            .value_type(value)
+            .map(|value_type| value_type.shape().rank())
+            .ok_or_else(|| {
+                invalid(format!(
+                    "{op} needs the rank of its input, which is unknown"
+                ))
+            })
+    }
+
+    fn dim(&self, value: ValueRef, axis: usize) -> Option<&Dim> {
+        self.graph.value_type(value)?.shape().dims().get(axis)
+    }
+
+    fn static_dim(&self, value: ValueRef, axis: usize) -> Option<usize> {
+        self.dim(value, axis)?.as_static()
+    }
+
+    /// `torch.size` of one dimension: a number when static.
+    fn size(&self, tensor: ValueRef, axis: usize) -> ScriptValue {
+        match self.static_dim(tensor, axis) {
+            Some(size) => ScriptValue::Int(size as i64),
+            None => ScriptValue::Size { tensor, axis },
+        }
+    }
+}
+
+/// The arguments of a `torch` call matched to the function's parameters;
+/// arguments left out read as `None`.
+struct Args {
+    op: String,
+    names: &'static [&'static str],
+    values: Vec<ScriptValue>,
+}
+
+fn bind(
+    op: &str,
+    names: &'static [&'static str],
+    positional: Vec<ScriptValue>,
+    keywords: Vec<(String, ScriptValue)>,
+) -> Result<Args, ParsingError> {
+    if positional.len() > names.len() {
+        return Err(invalid(format!(
+            "{op} takes at most {} arguments but {} were given",
+            names.len(),
+            positional.len()
+        )));
+    }
+    let mut values = positional;
+    values.resize(names.len(), ScriptValue::None);
+    for (name, value) in keywords {
+        let index = names
+            .iter()
+            .position(|param| *param == name)
+            .ok_or_else(|| invalid(format!("{op} has no parameter '{name}'")))?;
+        values[index] = value;
+    }
+    Ok(Args {
+        op: op.to_string(),
+        names,
+        values,
+    })
+}
+
+impl Args {
+    /// The argument at `index`, unless it is `None`.
+    fn get(&self, index: usize) -> Option<&ScriptValue> {
+        self.values
+            .get(index)
+            .filter(|value| **value != ScriptValue::None)
+    }
+
+    fn optional_int(&self, index: usize) -> Result<Option<i64>, ParsingError> {
+        match self.get(index) {
+            None => Ok(None),
+            Some(ScriptValue::Int(value)) => Ok(Some(*value)),
+            Some(_) => Err(self.mismatch(index, "an int")),
+        }
+    }
+
+    fn int(&self, index: usize, default: i64) -> Result<i64, ParsingError> {
+        Ok(self.optional_int(index)?.unwrap_or(default))
+    }
+
+    fn required_int(&self, index: usize) -> Result<i64, ParsingError> {
+        self.optional_int(index)?
+            .ok_or_else(|| invalid(format!("{} is missing '{}'", self.op, self.names[index])))
+    }
+
+    fn optional_float(&self, index: usize) -> Result<Option<f64>, ParsingError> {
+        match self.get(index) {
+            None => Ok(None),
+            Some(ScriptValue::Float(value)) => Ok(Some(*value)),
+            Some(ScriptValue::Int(value)) => Ok(Some(*value as f64)),
+            Some(_) => Err(self.mismatch(index, "a number")),
+        }
+    }
+
+    fn float(&self, index: usize, default: f64) -> Result<f64, ParsingError> {
+        Ok(self.optional_float(index)?.unwrap_or(default))
+    }
+
+    fn bool(&self, index: usize, default: bool) -> Result<bool, ParsingError> {
+        match self.get(index) {
+            None => Ok(default),
+            Some(ScriptValue::Bool(value)) => Ok(*value),
+            Some(_) => Err(self.mismatch(index, "a bool")),
+        }
+    }
+
+    fn string(&self, index: usize, default: &str) -> Result<String, ParsingError> {
+        match self.get(index) {
+            None => Ok(default.to_string()),
+            Some(ScriptValue::Str(value)) => Ok(value.clone()),
+            Some(_) => Err(self.mismatch(index, "a string")),
+        }
+    }
+
+    /// A list of ints, or a single int standing for a one-element list.
+    fn ints(&self, index: usize) -> Result<Option<Vec<i64>>, ParsingError> {
+        match self.get(index) {
+            None => Ok(None),
+            Some(ScriptValue::Int(value)) => Ok(Some(vec![*value])),
+            Some(ScriptValue::List(items) | ScriptValue::Tuple(items)) => items
+                .iter()
+                .map(|item| match item {
+                    ScriptValue::Int(value) => Ok(*value),
+                    _ => Err(self.mismatch(index, "a list of ints")),
+                })
+                .collect::<Result<_, _>>()
+                .map(Some),
+            Some(_) => Err(self.mismatch(index, "a list of ints")),
+        }
+    }
+
+    fn mismatch(&self, index: usize, expected: &str) -> ParsingError {
+        let found = self.values.get(index).map_or("nothing", ScriptValue::kind);
+        invalid(format!(
+            "{}: '{}' should be {expected}, not {found}",
+            self.op, self.names[index]
+        ))
+    }
+}
+
+/// `ops.prim` calls, which only shuffle values around.
+fn prim(name: &str, args: Vec<ScriptValue>) -> Result<ScriptValue, ParsingError> {
+    match (name, args.as_slice()) {
+        ("unchecked_unwrap_optional" | "NumToTensor", [value]) => Ok(value.clone()),
+        ("RaiseException", [ScriptValue::Str(message), ..]) => Err(invalid(format!(
+            "the module raises an exception: {message}"
+        ))),
+        _ => Err(invalid(format!("ops.prim.{name} is not supported"))),
+    }
+}
+
+/// Evaluates `torch` calls on numbers, strings and `None`, which never reach
+/// the graph. Returns `None` when the arguments include tensors.
+fn scalar_op(name: &str, args: &[ScriptValue]) -> Result<Option<ScriptValue>, ParsingError> {
+    use ScriptValue::{Bool, Int, List, Str, Tuple};
+
+    let arithmetic = matches!(name, "add" | "sub" | "mul" | "div" | "floordiv" | "neg");
+    if arithmetic
+        && args
+            .iter()
+            .any(|arg| matches!(arg, ScriptValue::Size { .. }))
+    {
+        return Err(invalid(format!(
+            "torch.{name} on a dynamic size is not supported"
+        )));
+    }
+
+    Ok(Some(match (name, args) {
+        ("__is__", [left, right]) => Bool(same(left, right)),
+        ("__isnot__", [left, right]) => Bool(!same(left, right)),
+        ("__not__", [Bool(value)]) => Bool(!value),
+        ("__and__", [Bool(left), Bool(right)]) => Bool(*left && *right),
+        ("__or__", [Bool(left), Bool(right)]) => Bool(*left || *right),
+        ("len", [List(items) | Tuple(items)]) => Int(items.len() as i64),
+        ("format", [Str(template), rest @ ..]) => Str(format_string(template, rest)),
+        ("device", [Str(device)]) => Str(device.clone()),
+        ("warn", _) => ScriptValue::None,
+        ("eq" | "ne", [Str(left), Str(right)]) => Bool((left == right) == (name == "eq")),
+        ("eq" | "ne" | "lt" | "le" | "gt" | "ge", [left, right]) => {
+            let (Some(left), Some(right)) = (number(left), number(right)) else {
+                return Ok(None);
+            };
+            let ordering = match (left, right) {
+                (Number::Int(left), Number::Int(right)) => left.partial_cmp(&right),
+                (left, right) => left.as_f64().partial_cmp(&right.as_f64()),
+            };
+            let result = match name {
+                "eq" => ordering.is_some_and(|ordering| ordering.is_eq()),
+                "ne" => !ordering.is_some_and(|ordering| ordering.is_eq()),
+                "lt" => ordering.is_some_and(|ordering| ordering.is_lt()),
+                "le" => ordering.is_some_and(|ordering| ordering.is_le()),
+                "gt" => ordering.is_some_and(|ordering| ordering.is_gt()),
+                _ => ordering.is_some_and(|ordering| ordering.is_ge()),
+            };
+            Bool(result)
+        }
+        ("neg", [value]) => match number(value) {
+            Some(Number::Int(value)) => Int(value
+                .checked_neg()
+                .ok_or_else(|| invalid("integer overflow"))?),
+            Some(Number::Float(value)) => ScriptValue::Float(-value),
+            None => return Ok(None),
+        },
+        (_, [left, right]) if arithmetic => {
+            let (Some(left), Some(right)) = (number(left), number(right)) else {
+                return Ok(None);
+            };
+            arithmetic_op(name, left, right)?
+        }
+        _ => return Ok(None),
+    }))
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Number {
+    Int(i64),
+    Float(f64),
+}
+
+impl Number {
+    fn as_f64(self) -> f64 {
+        match self {
+            Number::Int(value) => value as f64,
+            Number::Float(value) => value,
+        }
+    }
+}
+
+fn number(value: &ScriptValue) -> Option<Number> {
+    match value {
+        ScriptValue::Int(value) => Some(Number::Int(*value)),
+        ScriptValue::Bool(value) => Some(Number::Int(*value as i64)),
+        ScriptValue::Float(value) => Some(Number::Float(*value)),
+        _ => None,
+    }
+}
+
+/// Python arithmetic: ints stay ints except under `/`, and `//` floors.
+fn arithmetic_op(name: &str, left: Number, right: Number) -> Result<ScriptValue, ParsingError> {
+    if let (Number::Int(left), Number::Int(right)) = (left, right) {
+        let result = match name {
+            "add" => left.checked_add(right),
+            "sub" => left.checked_sub(right),
+            "mul" => left.checked_mul(right),
+            "floordiv" if right == 0 => return Err(invalid("integer division by zero")),
+            "floordiv" => {
+                let quotient = left / right;
+                Some(if left % right != 0 && (left < 0) != (right < 0) {
+                    quotient - 1
+                } else {
+                    quotient
+                })
+            }
+            _ => return Ok(ScriptValue::Float(left as f64 / right as f64)),
+        };
+        return result
+            .map(ScriptValue::Int)
+            .ok_or_else(|| invalid(format!("torch.{name} overflows 64-bit integers")));
+    }
+    let (left, right) = (left.as_f64(), right.as_f64());
+    Ok(ScriptValue::Float(match name {
+        "add" => left + right,
+        "sub" => left - right,
+        "mul" => left * right,
+        "floordiv" => (left / right).floor(),
+        _ => left / right,
+    }))
+}
+
+/// Python's `is` for the values TorchScript compares that way.
+fn same(left: &ScriptValue, right: &ScriptValue) -> bool {
+    match (left, right) {
+        (ScriptValue::None, ScriptValue::None) => true,
+        (ScriptValue::None, _) | (_, ScriptValue::None) => false,
+        (left, right) => left == right,
+    }
+}
+
+/// Fills the `{}` placeholders of `torch.format`.
+fn format_string(template: &str, args: &[ScriptValue]) -> String {
+    let mut args = args.iter();
+    let mut pieces = template.split("{}");
+    let mut text = pieces.next().unwrap_or_default().to_string();
+    for piece in pieces {
+        match args.next() {
+            Some(ScriptValue::Int(value)) => text.push_str(&value.to_string()),
+            Some(ScriptValue::Float(value)) => text.push_str(&value.to_string()),
+            Some(ScriptValue::Bool(value)) => text.push_str(if *value { "True" } else { "False" }),
+            Some(ScriptValue::Str(value)) => text.push_str(value),
+            Some(other) => text.push_str(&format!("<{}>", other.kind())),
+            None => text.push_str("{}"),
+        }
+        text.push_str(piece);
+    }
+    text
+}
+
+fn truthy(value: &ScriptValue) -> Result<bool, ParsingError> {
+    match value {
+        ScriptValue::Bool(value) => Ok(*value),
+        ScriptValue::Int(value) => Ok(*value != 0),
+        ScriptValue::Float(value) => Ok(*value != 0.0),
+        ScriptValue::Str(value) => Ok(!value.is_empty()),
+        ScriptValue::None => Ok(false),
+        ScriptValue::List(items) | ScriptValue::Tuple(items) => Ok(!items.is_empty()),
+        ScriptValue::Module(_) => Ok(true),
+        ScriptValue::Tensor(_) | ScriptValue::Size { .. } => Err(invalid(
+            "a condition depends on tensor values, which are not known while importing",
+        )),
+    }
+}
+
+fn normalize(dim: i64, rank: usize, op: &str) -> Result<usize, ParsingError> {
+    let axis = if dim < 0 { dim + rank as i64 } else { dim };
+    usize::try_from(axis)
+        .ok()
+        .filter(|&axis| axis < rank)
+        .ok_or_else(|| {
+            invalid(format!(
+                "{op}: dimension {dim} is out of range for rank {rank}"
+            ))
+        })
+}
+
+fn non_negative_ints(args: &Args, index: usize) -> Result<Vec<usize>, ParsingError> {
+    args.ints(index)?
+        .unwrap_or_default()
+        .into_iter()
+        .map(|value| usize::try_from(value).map_err(|_| args.mismatch(index, "non-negative ints")))
+        .collect()
+}
+
+/// A height and width parameter, given as one value for both or two.
+fn pair(args: &Args, index: usize, default: [usize; 2]) -> Result<[usize; 2], ParsingError> {
+    if args.get(index).is_none() {
+        return Ok(default);
+    }
+    match non_negative_ints(args, index)?.as_slice() {
+        [both] => Ok([*both, *both]),
+        [height, width] => Ok([*height, *width]),
+        _ => Err(args.mismatch(index, "one or two ints")),
+    }
+}
+
+/// Padding that keeps a stride-1 convolution's output the input's size,
+/// with any odd pixel at the bottom and right as PyTorch does.
+fn same_padding(kernel: [usize; 2], dilations: [usize; 2]) -> [usize; 4] {
+    let total = [
+        dilations[0] * (kernel[0] - 1),
+        dilations[1] * (kernel[1] - 1),
+    ];
+    let before = [total[0] / 2, total[1] / 2];
+    [
+        before[0],
+        before[1],
+        total[0] - before[0],
+        total[1] - before[1],
+    ]
+}
+
+/// Pieces of `piece` elements covering `length`, the last one shorter. An
+/// empty dimension gives one empty piece, as `torch.split` does.
+fn even_pieces(length: usize, piece: usize) -> Vec<usize> {
+    if length == 0 {
+        return vec![0];
+    }
+    let mut sizes = vec![piece; length / piece];
+    if !length.is_multiple_of(piece) {
+        sizes.push(length % piece);
+    }
+    sizes
+}
+
+fn non_negative(values: &[Value], what: &str) -> Result<Vec<usize>, ParsingError> {
+    values
+        .iter()
+        .map(|value| match value {
+            Value::Int(value) => usize::try_from(*value).ok(),
+            _ => None,
+        })
+        .collect::<Option<_>>()
+        .ok_or_else(|| invalid(format!("'{what}' has malformed sizes or strides")))
+}
+
+/// The IR dtype and element width of a pickled storage class.
+fn storage_dtype(storage_type: &str) -> Option<(DType, usize)> {
+    Some(match storage_type {
+        "FloatStorage" => (DType::F32, 4),
+        "HalfStorage" => (DType::F16, 2),
+        "BFloat16Storage" => (DType::BF16, 2),
+        "IntStorage" => (DType::I32, 4),
+        // Narrowed to 32 bits on reading
+        "LongStorage" => (DType::I32, 8),
+        "CharStorage" => (DType::I8, 1),
+        "BoolStorage" => (DType::Bool, 1),
+        _ => return None,
+    })
+}
+
+/// Maps a `torch.dtype` code, as TorchScript prints dtypes, onto the IR.
+fn scalar_type(code: i64) -> Option<DType> {
+    Some(match code {
+        1 => DType::I8,
+        3 | 4 => DType::I32,
+        5 => DType::F16,
+        6 => DType::F32,
+        11 => DType::Bool,
+        15 => DType::BF16,
+        _ => return None,
+    })
+}
+
+/// One element of `dtype` holding `value`.
+fn element_bytes(value: f64, dtype: DType) -> Vec<u8> {
+    match dtype {
+        DType::F32 => (value as f32).to_le_bytes().to_vec(),
+        DType::F16 => f32_to_f16(value as f32).to_le_bytes().to_vec(),
+        DType::BF16 => (((value as f32).to_bits() >> 16) as u16)
+            .to_le_bytes()
+            .to_vec(),
+        DType::I32 => (value as i32).to_le_bytes().to_vec(),
+        DType::I8 => vec![value as i8 as u8],
+        DType::Bool => vec![(value != 0.0) as u8],
+    }
+}
+
+fn invalid(reason: impl Into<String>) -> ParsingError {
+    ParsingError::InvalidFormat {
+        format: "torchscript".to_string(),
+        reason: reason.into(),
+    }
+}
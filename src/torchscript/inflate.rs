@@ -0,0 +1,297 @@
+/// Decompresses a raw DEFLATE stream (RFC 1951), as stored in zip entries
+/// with compression method 8. `limit` is the expected output length;
+/// decompression stops as soon as the output would grow past it, so a small
+/// stream cannot expand without bound.
+///
+/// Returns a description of the problem when the stream is malformed.
+pub fn inflate(data: &[u8], limit: usize) -> Result<Vec<u8>, String> {
+    // Cap the preallocation so a lying header cannot exhaust memory; DEFLATE
+    // expands at most about 1032:1
+    let capacity = limit.min(data.len().saturating_mul(1032));
+    let mut inflater = Inflater {
+        bits: BitReader::new(data),
+        output: Vec::with_capacity(capacity),
+        limit,
+    };
+
+    loop {
+        let last = inflater.bits.bits(1)? == 1;
+        match inflater.bits.bits(2)? {
+            0 => inflater.stored_block()?,
+            1 => inflater
+                .compressed_block(&Huffman::fixed_literals(), &Huffman::fixed_distances())?,
+            2 => {
+                let (literals, distances) = inflater.dynamic_tables()?;
+                inflater.compressed_block(&literals, &distances)?;
+            }
+            _ => return Err("block type 3 is reserved".to_string()),
+        }
+        if last {
+            return Ok(inflater.output);
+        }
+    }
+}
+
+const MAX_BITS: usize = 15;
+
+/// Base lengths and extra bits for length codes 257 to 285.
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+/// Base distances and extra bits for distance codes 0 to 29.
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// Order in which a dynamic block lists the code lengths of the code-length
+/// alphabet.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Reads bits least-significant first, as DEFLATE packs them.
+struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    buffer: u32,
+    count: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        BitReader {
+            data,
+            position: 0,
+            buffer: 0,
+            count: 0,
+        }
+    }
+
+    fn bits(&mut self, count: u32) -> Result<u32, String> {
+        while self.count < count {
+            let byte = *self
+                .data
+                .get(self.position)
+                .ok_or_else(|| "stream ends early".to_string())?;
+            self.position += 1;
+            self.buffer |= (byte as u32) << self.count;
+            self.count += 8;
+        }
+        let value = self.buffer & ((1u32 << count) - 1);
+        self.buffer >>= count;
+        self.count -= count;
+        Ok(value)
+    }
+
+    /// Drops the bits left in the current byte.
+    fn align(&mut self) {
+        self.buffer = 0;
+        self.count = 0;
+    }
+
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .position
+            .checked_add(count)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| "stream ends early".to_string())?;
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+}
+
+/// A canonical Huffman code: how many codes have each length, and the symbols
+/// in code order.
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    /// Builds the code for per-symbol code lengths, zero meaning unused.
+    /// Incomplete codes are allowed, since a block may use a single distance.
+    fn new(lengths: &[u8]) -> Result<Self, String> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left = left * 2 - count as i32;
+            if left < 0 {
+                return Err("a Huffman code is oversubscribed".to_string());
+            }
+        }
+
+        let mut offsets = [0u16; MAX_BITS + 1];
+        for length in 1..MAX_BITS {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Ok(Huffman { counts, symbols })
+    }
+
+    fn fixed_literals() -> Self {
+        let mut lengths = [8u8; 288];
+        lengths[144..256].fill(9);
+        lengths[256..280].fill(7);
+        Huffman::new(&lengths).expect("the fixed literal code is complete")
+    }
+
+    fn fixed_distances() -> Self {
+        Huffman::new(&[5; 30]).expect("the fixed distance code is complete")
+    }
+
+    fn decode(&self, bits: &mut BitReader) -> Result<u16, String> {
+        // Canonical codes of one length are consecutive, so walk the lengths
+        // keeping the first code and symbol index of each
+        let mut code = 0i32;
+        let mut first = 0i32;
+        let mut index = 0i32;
+        for length in 1..=MAX_BITS {
+            code |= bits.bits(1)? as i32;
+            let count = self.counts[length] as i32;
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("a Huffman code is not in the table".to_string())
+    }
+}
+
+struct Inflater<'a> {
+    bits: BitReader<'a>,
+    output: Vec<u8>,
+    limit: usize,
+}
+
+impl Inflater<'_> {
+    /// Fails when `extra` more bytes would take the output past the limit.
+    fn make_room(&self, extra: usize) -> Result<(), String> {
+        if extra > self.limit - self.output.len() {
+            return Err(format!(
+                "the data inflates to more than {} bytes",
+                self.limit
+            ));
+        }
+        Ok(())
+    }
+
+    fn stored_block(&mut self) -> Result<(), String> {
+        self.bits.align();
+        let header = self.bits.bytes(4)?;
+        let length = u16::from_le_bytes([header[0], header[1]]);
+        let complement = u16::from_le_bytes([header[2], header[3]]);
+        if length != !complement {
+            return Err("a stored block's length does not match its complement".to_string());
+        }
+        let bytes = self.bits.bytes(length as usize)?;
+        self.make_room(bytes.len())?;
+        self.output.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    fn dynamic_tables(&mut self) -> Result<(Huffman, Huffman), String> {
+        let literal_count = self.bits.bits(5)? as usize + 257;
+        let distance_count = self.bits.bits(5)? as usize + 1;
+        let code_length_count = self.bits.bits(4)? as usize + 4;
+        if literal_count > 286 || distance_count > 30 {
+            return Err("a dynamic block declares too many codes".to_string());
+        }
+
+        let mut code_lengths = [0u8; 19];
+        for &symbol in &CODE_LENGTH_ORDER[..code_length_count] {
+            code_lengths[symbol] = self.bits.bits(3)? as u8;
+        }
+        let code_length_code = Huffman::new(&code_lengths)?;
+
+        let mut lengths = Vec::with_capacity(literal_count + distance_count);
+        while lengths.len() < literal_count + distance_count {
+            let symbol = code_length_code.decode(&mut self.bits)?;
+            let (value, repeat) = match symbol {
+                0..=15 => (symbol as u8, 1),
+                16 => {
+                    let previous = *lengths
+                        .last()
+                        .ok_or_else(|| "a length repeat has nothing to repeat".to_string())?;
+                    (previous, 3 + self.bits.bits(2)? as usize)
+                }
+                17 => (0, 3 + self.bits.bits(3)? as usize),
+                _ => (0, 11 + self.bits.bits(7)? as usize),
+            };
+            if lengths.len() + repeat > literal_count + distance_count {
+                return Err("code lengths overrun the table".to_string());
+            }
+            lengths.extend(std::iter::repeat_n(value, repeat));
+        }
+        if lengths[256] == 0 {
+            return Err("a dynamic block has no end-of-block code".to_string());
+        }
+
+        let literals = Huffman::new(&lengths[..literal_count])?;
+        let distances = Huffman::new(&lengths[literal_count..])?;
+        Ok((literals, distances))
+    }
+
+    fn compressed_block(&mut self, literals: &Huffman, distances: &Huffman) -> Result<(), String> {
+        loop {
+            let symbol = literals.decode(&mut self.bits)? as usize;
+            match symbol {
+                0..=255 => {
+                    self.make_room(1)?;
+                    self.output.push(symbol as u8);
+                }
+                256 => return Ok(()),
+                _ => {
+                    let index = symbol - 257;
+                    if index >= LENGTH_BASE.len() {
+                        return Err(format!("length code {symbol} is invalid"));
+                    }
+                    let length = LENGTH_BASE[index] as usize
+                        + self.bits.bits(LENGTH_EXTRA[index] as u32)? as usize;
+
+                    let index = distances.decode(&mut self.bits)? as usize;
+                    if index >= DISTANCE_BASE.len() {
+                        return Err(format!("distance code {index} is invalid"));
+                    }
+                    let distance = DISTANCE_BASE[index] as usize
+                        + self.bits.bits(DISTANCE_EXTRA[index] as u32)? as usize;
+                    if distance > self.output.len() {
+                        return Err("a back-reference reaches before the start".to_string());
+                    }
+
+                    self.make_room(length)?;
+
+                    // Copies may overlap their own output, so go byte by byte
+                    let start = self.output.len() - distance;
+                    for offset in 0..length {
+                        let byte = self.output[start + offset];
+                        self.output.push(byte);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,5 @@
+pub mod archive;
+pub mod import;
+pub mod inflate;
+pub mod pickle;
+pub mod script;
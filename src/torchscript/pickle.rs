@@ -0,0 +1,498 @@
+use std::collections::HashMap;
+
+use crate::ir::errors::ParsingError;
+
+/// A value read from a pickle. Calls the pickle asks for are not run; they
+/// are kept as [`Value::Object`]s for the reader to interpret, and persistent
+/// ids are kept as [`Value::PersistentId`] for it to resolve.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    None,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    Bytes(Vec<u8>),
+    Tuple(Vec<Value>),
+    List(Vec<Value>),
+    /// Entries in insertion order.
+    Dict(Vec<(Value, Value)>),
+    /// A class or function named by module and qualified name.
+    Global {
+        module: String,
+        name: String,
+    },
+    /// A global called with `args` (`REDUCE`) or instantiated (`NEWOBJ`),
+    /// plus the state `BUILD` gave it.
+    Object {
+        module: String,
+        name: String,
+        args: Vec<Value>,
+        state: Option<Box<Value>>,
+    },
+    PersistentId(Box<Value>),
+}
+
+impl Value {
+    /// The value stored under a string key, when this is a dict.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Dict(entries) => entries
+                .iter()
+                .find(|(entry, _)| matches!(entry, Value::String(name) if name == key))
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// Whether this is an object of the class `module.name`.
+    pub fn is_object(&self, class_module: &str, class_name: &str) -> bool {
+        matches!(
+            self,
+            Value::Object { module, name, .. } if module == class_module && name == class_name
+        )
+    }
+}
+
+/// Reads a pickle up to its `STOP` opcode.
+///
+/// Protocols 2 to 5 are understood, minus the opcodes for out-of-band buffers,
+/// sets, extension registries and the text-based protocol 0 forms. Memoised
+/// values are copied when fetched, which matches how TorchScript memoises:
+/// only once a value is complete.
+pub fn unpickle(bytes: &[u8]) -> Result<Value, ParsingError> {
+    let mut reader = Unpickler {
+        bytes,
+        position: 0,
+        stack: Vec::new(),
+        marks: Vec::new(),
+        memo: HashMap::new(),
+    };
+    reader.run()
+}
+
+mod opcode {
+    pub const MARK: u8 = b'(';
+    pub const STOP: u8 = b'.';
+    pub const POP: u8 = b'0';
+    pub const POP_MARK: u8 = b'1';
+    pub const DUP: u8 = b'2';
+    pub const BINFLOAT: u8 = b'G';
+    pub const BININT: u8 = b'J';
+    pub const BININT1: u8 = b'K';
+    pub const BININT2: u8 = b'M';
+    pub const NONE: u8 = b'N';
+    pub const BINPERSID: u8 = b'Q';
+    pub const REDUCE: u8 = b'R';
+    pub const BINUNICODE: u8 = b'X';
+    pub const EMPTY_LIST: u8 = b']';
+    pub const APPEND: u8 = b'a';
+    pub const BUILD: u8 = b'b';
+    pub const GLOBAL: u8 = b'c';
+    pub const DICT: u8 = b'd';
+    pub const EMPTY_DICT: u8 = b'}';
+    pub const APPENDS: u8 = b'e';
+    pub const BINGET: u8 = b'h';
+    pub const LONG_BINGET: u8 = b'j';
+    pub const LIST: u8 = b'l';
+    pub const BINPUT: u8 = b'q';
+    pub const LONG_BINPUT: u8 = b'r';
+    pub const SETITEM: u8 = b's';
+    pub const TUPLE: u8 = b't';
+    pub const EMPTY_TUPLE: u8 = b')';
+    pub const SETITEMS: u8 = b'u';
+    pub const BINBYTES: u8 = b'B';
+    pub const SHORT_BINBYTES: u8 = b'C';
+    pub const PROTO: u8 = 0x80;
+    pub const NEWOBJ: u8 = 0x81;
+    pub const TUPLE1: u8 = 0x85;
+    pub const TUPLE2: u8 = 0x86;
+    pub const TUPLE3: u8 = 0x87;
+    pub const NEWTRUE: u8 = 0x88;
+    pub const NEWFALSE: u8 = 0x89;
+    pub const LONG1: u8 = 0x8a;
+    pub const LONG4: u8 = 0x8b;
+    pub const SHORT_BINUNICODE: u8 = 0x8c;
+    pub const BINUNICODE8: u8 = 0x8d;
+    pub const BINBYTES8: u8 = 0x8e;
+    pub const STACK_GLOBAL: u8 = 0x93;
+    pub const MEMOIZE: u8 = 0x94;
+    pub const FRAME: u8 = 0x95;
+}
+
+struct Unpickler<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    stack: Vec<Value>,
+    /// Stack lengths at each open `MARK`.
+    marks: Vec<usize>,
+    memo: HashMap<u32, Value>,
+}
+
+impl<'a> Unpickler<'a> {
+    fn run(&mut self) -> Result<Value, ParsingError> {
+        loop {
+            let at = self.position;
+            let code = self.byte()?;
+            match code {
+                opcode::PROTO => {
+                    let version = self.byte()?;
+                    if !(2..=5).contains(&version) {
+                        return Err(invalid(format!("protocol {version} is not supported")));
+                    }
+                }
+                // Frames only group opcodes for buffered reading
+                opcode::FRAME => {
+                    self.take(8)?;
+                }
+                opcode::STOP => {
+                    let value = self.pop()?;
+                    if !self.stack.is_empty() || !self.marks.is_empty() {
+                        return Err(invalid("values are left on the stack at STOP"));
+                    }
+                    return Ok(value);
+                }
+
+                opcode::MARK => self.marks.push(self.stack.len()),
+                opcode::POP => {
+                    self.pop()?;
+                }
+                opcode::POP_MARK => {
+                    self.pop_mark()?;
+                }
+                opcode::DUP => {
+                    let top = self.top()?.clone();
+                    self.stack.push(top);
+                }
+
+                opcode::NONE => self.stack.push(Value::None),
+                opcode::NEWTRUE => self.stack.push(Value::Bool(true)),
+                opcode::NEWFALSE => self.stack.push(Value::Bool(false)),
+                opcode::BININT => {
+                    let value = i32::from_le_bytes(self.array()?);
+                    self.stack.push(Value::Int(value as i64));
+                }
+                opcode::BININT1 => {
+                    let value = self.byte()?;
+                    self.stack.push(Value::Int(value as i64));
+                }
+                opcode::BININT2 => {
+                    let value = u16::from_le_bytes(self.array()?);
+                    self.stack.push(Value::Int(value as i64));
+                }
+                opcode::LONG1 | opcode::LONG4 => {
+                    let length = if code == opcode::LONG1 {
+                        self.byte()? as usize
+                    } else {
+                        let length = u32::from_le_bytes(self.array()?);
+                        self.length(length as u64)?
+                    };
+                    let value = long(self.take(length)?)?;
+                    self.stack.push(Value::Int(value));
+                }
+                opcode::BINFLOAT => {
+                    // The only big-endian field in the format
+                    let value = f64::from_be_bytes(self.array()?);
+                    self.stack.push(Value::Float(value));
+                }
+
+                opcode::SHORT_BINUNICODE | opcode::BINUNICODE | opcode::BINUNICODE8 => {
+                    let length = match code {
+                        opcode::SHORT_BINUNICODE => self.byte()? as usize,
+                        opcode::BINUNICODE => {
+                            let length = u32::from_le_bytes(self.array()?);
+                            self.length(length as u64)?
+                        }
+                        _ => {
+                            let length = u64::from_le_bytes(self.array()?);
+                            self.length(length)?
+                        }
+                    };
+                    let text = std::str::from_utf8(self.take(length)?)
+                        .map_err(|_| invalid(format!("the string at byte {at} is not UTF-8")))?;
+                    self.stack.push(Value::String(text.to_string()));
+                }
+                opcode::SHORT_BINBYTES | opcode::BINBYTES | opcode::BINBYTES8 => {
+                    let length = match code {
+                        opcode::SHORT_BINBYTES => self.byte()? as usize,
+                        opcode::BINBYTES => {
+                            let length = u32::from_le_bytes(self.array()?);
+                            self.length(length as u64)?
+                        }
+                        _ => {
+                            let length = u64::from_le_bytes(self.array()?);
+                            self.length(length)?
+                        }
+                    };
+                    let bytes = self.take(length)?.to_vec();
+                    self.stack.push(Value::Bytes(bytes));
+                }
+
+                opcode::EMPTY_TUPLE => self.stack.push(Value::Tuple(Vec::new())),
+                opcode::TUPLE => {
+                    let items = self.pop_mark()?;
+                    self.stack.push(Value::Tuple(items));
+                }
+                opcode::TUPLE1 | opcode::TUPLE2 | opcode::TUPLE3 => {
+                    let count = (code - opcode::TUPLE1 + 1) as usize;
+                    let start = self.stack.len().checked_sub(count).ok_or_else(|| {
+                        invalid(format!("TUPLE{count} at byte {at} underflows the stack"))
+                    })?;
+                    let items = self.stack.split_off(start);
+                    self.stack.push(Value::Tuple(items));
+                }
+                opcode::EMPTY_LIST => self.stack.push(Value::List(Vec::new())),
+                opcode::LIST => {
+                    let items = self.pop_mark()?;
+                    self.stack.push(Value::List(items));
+                }
+                opcode::APPEND => {
+                    let item = self.pop()?;
+                    self.append(vec![item], at)?;
+                }
+                opcode::APPENDS => {
+                    let items = self.pop_mark()?;
+                    self.append(items, at)?;
+                }
+                opcode::EMPTY_DICT => self.stack.push(Value::Dict(Vec::new())),
+                opcode::DICT => {
+                    let items = self.pop_mark()?;
+                    let entries = pairs(items, at)?;
+                    self.stack.push(Value::Dict(entries));
+                }
+                opcode::SETITEM => {
+                    let value = self.pop()?;
+                    let key = self.pop()?;
+                    self.set_items(vec![(key, value)], at)?;
+                }
+                opcode::SETITEMS => {
+                    let items = self.pop_mark()?;
+                    let entries = pairs(items, at)?;
+                    self.set_items(entries, at)?;
+                }
+
+                opcode::GLOBAL => {
+                    let module = self.line()?;
+                    let name = self.line()?;
+                    self.stack.push(Value::Global { module, name });
+                }
+                opcode::STACK_GLOBAL => {
+                    let name = self.pop()?;
+                    let module = self.pop()?;
+                    let (Value::String(module), Value::String(name)) = (module, name) else {
+                        return Err(invalid(format!(
+                            "STACK_GLOBAL at byte {at} needs two strings"
+                        )));
+                    };
+                    self.stack.push(Value::Global { module, name });
+                }
+                opcode::REDUCE | opcode::NEWOBJ => {
+                    let args = self.pop()?;
+                    let callable = self.pop()?;
+                    let (Value::Global { module, name }, Value::Tuple(args)) = (callable, args)
+                    else {
+                        return Err(invalid(format!(
+                            "the call at byte {at} needs a global and an argument tuple"
+                        )));
+                    };
+                    self.stack.push(Value::Object {
+                        module,
+                        name,
+                        args,
+                        state: None,
+                    });
+                }
+                opcode::BUILD => {
+                    let new_state = self.pop()?;
+                    match self.top_mut()? {
+                        Value::Object { state, .. } => *state = Some(Box::new(new_state)),
+                        _ => {
+                            return Err(invalid(format!(
+                                "BUILD at byte {at} needs an object below its state"
+                            )));
+                        }
+                    }
+                }
+                opcode::BINPERSID => {
+                    let id = self.pop()?;
+                    self.stack.push(Value::PersistentId(Box::new(id)));
+                }
+
+                opcode::BINPUT => {
+                    let index = self.byte()? as u32;
+                    self.put(index)?;
+                }
+                opcode::LONG_BINPUT => {
+                    let index = u32::from_le_bytes(self.array()?);
+                    self.put(index)?;
+                }
+                opcode::MEMOIZE => {
+                    let index = self.memo.len() as u32;
+                    self.put(index)?;
+                }
+                opcode::BINGET | opcode::LONG_BINGET => {
+                    let index = if code == opcode::BINGET {
+                        self.byte()? as u32
+                    } else {
+                        u32::from_le_bytes(self.array()?)
+                    };
+                    let value = self
+                        .memo
+                        .get(&index)
+                        .ok_or_else(|| {
+                            invalid(format!("memo entry {index} is read before it is set"))
+                        })?
+                        .clone();
+                    self.stack.push(value);
+                }
+
+                code => {
+                    return Err(invalid(format!(
+                        "opcode 0x{code:02x} at byte {at} is not supported"
+                    )));
+                }
+            }
+        }
+    }
+
+    fn byte(&mut self) -> Result<u8, ParsingError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], ParsingError> {
+        Ok(self.take(N)?.try_into().expect("N bytes"))
+    }
+
+    fn take(&mut self, count: usize) -> Result<&'a [u8], ParsingError> {
+        let end = self
+            .position
+            .checked_add(count)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| invalid("the pickle ends before its STOP opcode"))?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    /// A newline-terminated argument, as `GLOBAL` takes its names.
+    fn line(&mut self) -> Result<String, ParsingError> {
+        let rest = &self.bytes[self.position..];
+        let length = rest
+            .iter()
+            .position(|&byte| byte == b'\n')
+            .ok_or_else(|| invalid("the pickle ends inside a GLOBAL name"))?;
+        let text = String::from_utf8_lossy(&rest[..length]).into_owned();
+        self.position += length + 1;
+        Ok(text)
+    }
+
+    fn length(&self, length: u64) -> Result<usize, ParsingError> {
+        usize::try_from(length)
+            .ok()
+            .filter(|&length| length <= self.bytes.len() - self.position)
+            .ok_or_else(|| invalid("the pickle ends before its STOP opcode"))
+    }
+
+    fn pop(&mut self) -> Result<Value, ParsingError> {
+        if self.marks.last() == Some(&self.stack.len()) {
+            return Err(invalid("an opcode pops past a MARK"));
+        }
+        self.stack
+            .pop()
+            .ok_or_else(|| invalid("an opcode pops an empty stack"))
+    }
+
+    fn top(&self) -> Result<&Value, ParsingError> {
+        self.stack
+            .last()
+            .ok_or_else(|| invalid("an opcode reads an empty stack"))
+    }
+
+    fn top_mut(&mut self) -> Result<&mut Value, ParsingError> {
+        self.stack
+            .last_mut()
+            .ok_or_else(|| invalid("an opcode reads an empty stack"))
+    }
+
+    /// Everything pushed since the last `MARK`.
+    fn pop_mark(&mut self) -> Result<Vec<Value>, ParsingError> {
+        let start = self
+            .marks
+            .pop()
+            .ok_or_else(|| invalid("an opcode needs a MARK but none is open"))?;
+        Ok(self.stack.split_off(start))
+    }
+
+    fn put(&mut self, index: u32) -> Result<(), ParsingError> {
+        let value = self.top()?.clone();
+        self.memo.insert(index, value);
+        Ok(())
+    }
+
+    fn append(&mut self, items: Vec<Value>, at: usize) -> Result<(), ParsingError> {
+        match self.top_mut()? {
+            Value::List(list) => {
+                list.extend(items);
+                Ok(())
+            }
+            _ => Err(invalid(format!("the append at byte {at} needs a list"))),
+        }
+    }
+
+    fn set_items(&mut self, entries: Vec<(Value, Value)>, at: usize) -> Result<(), ParsingError> {
+        match self.top_mut()? {
+            Value::Dict(dict) => {
+                for (key, value) in entries {
+                    match dict.iter_mut().find(|(existing, _)| *existing == key) {
+                        Some((_, slot)) => *slot = value,
+                        None => dict.push((key, value)),
+                    }
+                }
+                Ok(())
+            }
+            _ => Err(invalid(format!(
+                "the item assignment at byte {at} needs a dict"
+            ))),
+        }
+    }
+}
+
+/// Pairs up alternating keys and values.
+fn pairs(items: Vec<Value>, at: usize) -> Result<Vec<(Value, Value)>, ParsingError> {
+    if !items.len().is_multiple_of(2) {
+        return Err(invalid(format!(
+            "the dict items at byte {at} have a key without a value"
+        )));
+    }
+    let mut items = items.into_iter();
+    let mut entries = Vec::new();
+    while let (Some(key), Some(value)) = (items.next(), items.next()) {
+        entries.push((key, value));
+    }
+    Ok(entries)
+}
+
+/// A little-endian two's complement integer, as `LONG1` and `LONG4` store it.
+fn long(bytes: &[u8]) -> Result<i64, ParsingError> {
+    if bytes.len() > 8 {
+        return Err(invalid(format!(
+            "a {}-byte integer does not fit 64 bits",
+            bytes.len()
+        )));
+    }
+    let fill = if bytes.last().is_some_and(|&byte| byte & 0x80 != 0) {
+        0xFF
+    } else {
+        0
+    };
+    let mut buffer = [fill; 8];
+    buffer[..bytes.len()].copy_from_slice(bytes);
+    Ok(i64::from_le_bytes(buffer))
+}
+
+fn invalid(reason: impl Into<String>) -> ParsingError {
+    ParsingError::InvalidFormat {
+        format: "pickle".to_string(),
+        reason: reason.into(),
+    }
+}
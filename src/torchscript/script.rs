@@ -0,0 +1,938 @@
+use crate::ir::errors::ParsingError;
+
+/// One file of TorchScript source from an archive's `code/` directory: the
+/// Python subset `torch.jit.save` prints, parsed into classes and free
+/// functions. Imports and module-level assignments are skipped.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Source {
+    pub classes: Vec<ClassDef>,
+    pub functions: Vec<FunctionDef>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassDef {
+    pub name: String,
+    /// Class-level assignments, such as `in_features : Final[int] = 4`.
+    pub constants: Vec<(String, Expr)>,
+    pub methods: Vec<FunctionDef>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionDef {
+    pub name: String,
+    pub params: Vec<Param>,
+    pub body: Vec<Stmt>,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    pub name: String,
+    pub default: Option<Expr>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stmt {
+    /// `name = value`, or `a, b, = value` to unpack a tuple or list.
+    Assign {
+        targets: Vec<String>,
+        unpack: bool,
+        value: Expr,
+        line: usize,
+    },
+    Expr {
+        value: Expr,
+        line: usize,
+    },
+    Return {
+        value: Option<Expr>,
+        line: usize,
+    },
+    If {
+        condition: Expr,
+        then_body: Vec<Stmt>,
+        else_body: Vec<Stmt>,
+        line: usize,
+    },
+    Pass,
+    /// A statement the reader does not execute, such as a loop, kept so the
+    /// error only comes up if it is reached.
+    Unsupported {
+        description: String,
+        line: usize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Name(String),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Bool(bool),
+    None,
+    Attribute {
+        value: Box<Expr>,
+        name: String,
+    },
+    Call {
+        function: Box<Expr>,
+        args: Vec<Expr>,
+        keywords: Vec<(String, Expr)>,
+    },
+    Subscript {
+        value: Box<Expr>,
+        index: Box<Expr>,
+    },
+    List(Vec<Expr>),
+    Tuple(Vec<Expr>),
+    Unary {
+        op: UnaryOp,
+        operand: Box<Expr>,
+    },
+    /// Arithmetic and comparisons, named after the `torch` functions they
+    /// stand for (`add`, `eq`, ...), plus `is` and `is not`.
+    Binary {
+        op: &'static str,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    /// `and` / `or`, which only evaluate `right` when they need it.
+    Logical {
+        and: bool,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+}
+
+impl Expr {
+    /// The dotted name an expression spells, such as `torch.nn.functional`.
+    pub fn dotted_name(&self) -> Option<String> {
+        match self {
+            Expr::Name(name) => Some(name.clone()),
+            Expr::Attribute { value, name } => Some(format!("{}.{name}", value.dotted_name()?)),
+            _ => None,
+        }
+    }
+}
+
+impl Source {
+    pub fn class(&self, name: &str) -> Option<&ClassDef> {
+        self.classes.iter().find(|class| class.name == name)
+    }
+
+    pub fn function(&self, name: &str) -> Option<&FunctionDef> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+}
+
+impl ClassDef {
+    pub fn method(&self, name: &str) -> Option<&FunctionDef> {
+        self.methods.iter().find(|method| method.name == name)
+    }
+}
+
+/// Parses one source file; `file` names it in error messages.
+pub fn parse_source(text: &str, file: &str) -> Result<Source, ParsingError> {
+    let tokens = tokenize(text).map_err(|(line, reason)| invalid(file, line, reason))?;
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+    };
+    parser
+        .source()
+        .map_err(|(line, reason)| invalid(file, line, reason))
+}
+
+/// A syntax problem and the line it is on.
+type Failure = (usize, String);
+
+/// Positional and keyword arguments of a call.
+type Arguments = (Vec<Expr>, Vec<(String, Expr)>);
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Name(String),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    /// Punctuation and operators.
+    Op(&'static str),
+    Newline,
+    Indent,
+    Dedent,
+    End,
+}
+
+const OPERATORS: [&str; 26] = [
+    "->", "**", "//", "==", "!=", "<=", ">=", "+=", "-=", "(", ")", "[", "]", "{", "}", ",", ":",
+    ".", "=", "+", "-", "*", "/", "<", ">", "@",
+];
+
+/// Splits source into tokens, turning indentation into `Indent` / `Dedent`
+/// and ending logical lines with `Newline`. Lines continue while brackets
+/// are open.
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, Failure> {
+    let mut tokens = Vec::new();
+    let mut indents = vec![0usize];
+    let mut depth = 0usize;
+    let mut joined = false;
+
+    for (index, line) in text.lines().enumerate() {
+        let number = index + 1;
+        let mut chars = line.char_indices().peekable();
+
+        if depth == 0 && !joined {
+            let indent =
+                line.chars()
+                    .take_while(|c| *c == ' ' || *c == '\t')
+                    .fold(0, |column, c| {
+                        if c == '\t' {
+                            column / 8 * 8 + 8
+                        } else {
+                            column + 1
+                        }
+                    });
+            let rest = line.trim_start();
+            if rest.is_empty() || rest.starts_with('#') {
+                continue;
+            }
+            let current = *indents.last().expect("the base indent stays");
+            if indent > current {
+                indents.push(indent);
+                tokens.push((Token::Indent, number));
+            }
+            while indent < *indents.last().expect("the base indent stays") {
+                indents.pop();
+                tokens.push((Token::Dedent, number));
+            }
+            if indent != *indents.last().expect("the base indent stays") {
+                return Err((
+                    number,
+                    "indentation does not match any outer block".to_string(),
+                ));
+            }
+        }
+
+        while let Some(&(start, c)) = chars.peek() {
+            if c.is_whitespace() {
+                chars.next();
+            } else if c == '#' {
+                break;
+            } else if c == '\\' && line[start..].trim_end() == "\\" {
+                // Explicit line continuation
+                break;
+            } else if c.is_ascii_alphabetic() || c == '_' {
+                let mut end = start;
+                while let Some(&(position, c)) = chars.peek() {
+                    if c.is_ascii_alphanumeric() || c == '_' {
+                        end = position + c.len_utf8();
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push((Token::Name(line[start..end].to_string()), number));
+            } else if c.is_ascii_digit()
+                || (c == '.' && line[start + 1..].starts_with(|c: char| c.is_ascii_digit()))
+            {
+                let mut end = start;
+                let mut previous = ' ';
+                while let Some(&(position, c)) = chars.peek() {
+                    let exponent_sign = (c == '-' || c == '+') && matches!(previous, 'e' | 'E');
+                    if c.is_ascii_alphanumeric() || c == '.' || exponent_sign {
+                        end = position + 1;
+                        previous = c;
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push((number_token(&line[start..end], number)?, number));
+            } else if c == '"' || c == '\'' {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    let Some((_, next)) = chars.next() else {
+                        return Err((number, "a string is not closed on its line".to_string()));
+                    };
+                    match next {
+                        '\\' => {
+                            let Some((_, escaped)) = chars.next() else {
+                                return Err((number, "a string ends in an escape".to_string()));
+                            };
+                            value.push(match escaped {
+                                'n' => '\n',
+                                't' => '\t',
+                                'r' => '\r',
+                                '0' => '\0',
+                                other => other,
+                            });
+                        }
+                        quote if quote == c => break,
+                        other => value.push(other),
+                    }
+                }
+                tokens.push((Token::Str(value), number));
+            } else {
+                let rest = &line[start..];
+                let op = OPERATORS
+                    .iter()
+                    .find(|op| rest.starts_with(**op))
+                    .ok_or_else(|| (number, format!("unexpected character '{c}'")))?;
+                match *op {
+                    "(" | "[" | "{" => depth += 1,
+                    ")" | "]" | "}" => {
+                        depth = depth
+                            .checked_sub(1)
+                            .ok_or_else(|| (number, format!("unmatched '{op}'")))?;
+                    }
+                    _ => {}
+                }
+                for _ in 0..op.len() {
+                    chars.next();
+                }
+                tokens.push((Token::Op(op), number));
+            }
+        }
+
+        joined = line.trim_end().ends_with('\\');
+        if depth == 0
+            && !joined
+            && tokens
+                .last()
+                .is_some_and(|(token, _)| *token != Token::Newline)
+        {
+            tokens.push((Token::Newline, number));
+        }
+    }
+
+    let last = text.lines().count().max(1);
+    if depth != 0 {
+        return Err((last, "a bracket is not closed".to_string()));
+    }
+    for _ in 1..indents.len() {
+        tokens.push((Token::Dedent, last));
+    }
+    tokens.push((Token::End, last));
+    Ok(tokens)
+}
+
+fn number_token(text: &str, line: usize) -> Result<Token, Failure> {
+    let is_float = !text.starts_with("0x") && text.contains(['.', 'e', 'E']);
+    if is_float {
+        text.parse()
+            .map(Token::Float)
+            .map_err(|_| (line, format!("'{text}' is not a number")))
+    } else if let Some(hex) = text.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16)
+            .map(Token::Int)
+            .map_err(|_| (line, format!("'{text}' is not a 64-bit integer")))
+    } else {
+        text.parse()
+            .map(Token::Int)
+            .map_err(|_| (line, format!("'{text}' is not a 64-bit integer")))
+    }
+}
+
+/// Statement keywords whose statements are kept as [`Stmt::Unsupported`].
+const UNSUPPORTED_STATEMENTS: [&str; 8] = [
+    "for", "while", "with", "raise", "assert", "break", "continue", "del",
+];
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+}
+
+impl Parser {
+    fn source(&mut self) -> Result<Source, Failure> {
+        let mut source = Source::default();
+        loop {
+            match self.peek() {
+                Token::End => return Ok(source),
+                Token::Newline => self.advance(),
+                Token::Name(keyword) if keyword == "class" => source.classes.push(self.class()?),
+                Token::Name(keyword) if keyword == "def" => source.functions.push(self.function()?),
+                Token::Op("@") => self.skip_line(),
+                Token::Name(keyword) if keyword == "import" || keyword == "from" => {
+                    self.skip_line()
+                }
+                // Module-level assignments such as `op_version_set = 1`
+                _ => {
+                    self.expression_list()?;
+                    if self.eat_op("=") {
+                        self.expression_list()?;
+                    }
+                    self.expect_newline()?;
+                }
+            }
+        }
+    }
+
+    fn class(&mut self) -> Result<ClassDef, Failure> {
+        self.advance();
+        let name = self.name()?;
+        if self.eat_op("(") && !self.eat_op(")") {
+            self.expression_list()?;
+            self.expect_op(")")?;
+        }
+        self.expect_op(":")?;
+        self.expect(Token::Newline, "a new line after the class header")?;
+        self.expect(Token::Indent, "an indented class body")?;
+
+        let mut class = ClassDef {
+            name,
+            constants: Vec::new(),
+            methods: Vec::new(),
+        };
+        loop {
+            match self.peek() {
+                Token::Dedent => {
+                    self.advance();
+                    return Ok(class);
+                }
+                Token::Name(keyword) if keyword == "def" => class.methods.push(self.function()?),
+                Token::Name(keyword) if keyword == "pass" => self.skip_line(),
+                _ if self.peek_op("@") => self.skip_line(),
+                _ => {
+                    // `name : Type [= value]` or `name = value`; assignments to
+                    // anything but a plain name, such as `__annotations__["x"]`,
+                    // only describe types
+                    let target = self.expression()?;
+                    if self.eat_op(":") {
+                        self.expression()?;
+                    }
+                    if self.eat_op("=") {
+                        let value = self.expression_list()?;
+                        if let Expr::Name(name) = target {
+                            class.constants.push((name, value));
+                        }
+                    }
+                    self.expect_newline()?;
+                }
+            }
+        }
+    }
+
+    fn function(&mut self) -> Result<FunctionDef, Failure> {
+        let line = self.line();
+        self.advance();
+        let name = self.name()?;
+        self.expect_op("(")?;
+        let mut params = Vec::new();
+        while !self.eat_op(")") {
+            // A bare `*` starts the keyword-only parameters
+            if !self.eat_op("*") {
+                let name = self.name()?;
+                if self.eat_op(":") {
+                    self.expression()?;
+                }
+                let default = if self.eat_op("=") {
+                    Some(self.expression()?)
+                } else {
+                    None
+                };
+                params.push(Param { name, default });
+            }
+            if !self.eat_op(",") {
+                self.expect_op(")")?;
+                break;
+            }
+        }
+        if self.eat_op("->") {
+            self.expression()?;
+        }
+        self.expect_op(":")?;
+        let body = self.block()?;
+        Ok(FunctionDef {
+            name,
+            params,
+            body,
+            line,
+        })
+    }
+
+    /// The statements after a `:`, either indented on the following lines
+    /// or a single one on the same line.
+    fn block(&mut self) -> Result<Vec<Stmt>, Failure> {
+        if *self.peek() != Token::Newline {
+            return Ok(vec![self.simple_statement()?]);
+        }
+        self.advance();
+        self.expect(Token::Indent, "an indented block")?;
+        let mut body = Vec::new();
+        while *self.peek() != Token::Dedent {
+            body.push(self.statement()?);
+        }
+        self.advance();
+        Ok(body)
+    }
+
+    fn statement(&mut self) -> Result<Stmt, Failure> {
+        let line = self.line();
+        match self.peek().clone() {
+            Token::Name(keyword) if keyword == "if" => {
+                self.advance();
+                self.if_statement(line)
+            }
+            Token::Name(keyword) if UNSUPPORTED_STATEMENTS.contains(&keyword.as_str()) => {
+                self.skip_statement();
+                Ok(Stmt::Unsupported {
+                    description: format!("'{keyword}' statements"),
+                    line,
+                })
+            }
+            _ => self.simple_statement(),
+        }
+    }
+
+    fn if_statement(&mut self, line: usize) -> Result<Stmt, Failure> {
+        let condition = self.expression()?;
+        self.expect_op(":")?;
+        let then_body = self.block()?;
+        let else_body = match self.peek() {
+            Token::Name(keyword) if keyword == "elif" => {
+                let line = self.line();
+                self.advance();
+                vec![self.if_statement(line)?]
+            }
+            Token::Name(keyword) if keyword == "else" => {
+                self.advance();
+                self.expect_op(":")?;
+                self.block()?
+            }
+            _ => Vec::new(),
+        };
+        Ok(Stmt::If {
+            condition,
+            then_body,
+            else_body,
+            line,
+        })
+    }
+
+    fn simple_statement(&mut self) -> Result<Stmt, Failure> {
+        let line = self.line();
+        let statement = match self.peek().clone() {
+            Token::Name(keyword) if keyword == "pass" => {
+                self.advance();
+                Stmt::Pass
+            }
+            Token::Name(keyword) if keyword == "return" => {
+                self.advance();
+                let value = if *self.peek() == Token::Newline {
+                    None
+                } else {
+                    Some(self.expression_list()?)
+                };
+                Stmt::Return { value, line }
+            }
+            _ => {
+                let target = self.expression_list()?;
+                if self.eat_op("=") {
+                    let value = self.expression_list()?;
+                    assignment(target, value, line)
+                } else if self.eat_op("+=") || self.eat_op("-=") {
+                    self.expression_list()?;
+                    Stmt::Unsupported {
+                        description: "augmented assignments".to_string(),
+                        line,
+                    }
+                } else {
+                    Stmt::Expr {
+                        value: target,
+                        line,
+                    }
+                }
+            }
+        };
+        self.expect_newline()?;
+        Ok(statement)
+    }
+
+    /// Expressions separated by commas; more than one, or a trailing comma,
+    /// makes a tuple.
+    fn expression_list(&mut self) -> Result<Expr, Failure> {
+        let first = self.expression()?;
+        if !self.peek_op(",") {
+            return Ok(first);
+        }
+        let mut items = vec![first];
+        while self.eat_op(",") {
+            if self.at_expression_end() {
+                break;
+            }
+            items.push(self.expression()?);
+        }
+        Ok(Expr::Tuple(items))
+    }
+
+    fn expression(&mut self) -> Result<Expr, Failure> {
+        let mut left = self.and_expression()?;
+        while self.eat_keyword("or") {
+            let right = self.and_expression()?;
+            left = Expr::Logical {
+                and: false,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+        }
+        Ok(left)
+    }
+
+    fn and_expression(&mut self) -> Result<Expr, Failure> {
+        let mut left = self.not_expression()?;
+        while self.eat_keyword("and") {
+            let right = self.not_expression()?;
+            left = Expr::Logical {
+                and: true,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+        }
+        Ok(left)
+    }
+
+    fn not_expression(&mut self) -> Result<Expr, Failure> {
+        if self.eat_keyword("not") {
+            let operand = self.not_expression()?;
+            return Ok(Expr::Unary {
+                op: UnaryOp::Not,
+                operand: Box::new(operand),
+            });
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, Failure> {
+        let left = self.binary(0)?;
+        let op = if self.eat_keyword("is") {
+            if self.eat_keyword("not") {
+                "__isnot__"
+            } else {
+                "__is__"
+            }
+        } else {
+            let op = match self.peek() {
+                Token::Op("==") => "eq",
+                Token::Op("!=") => "ne",
+                Token::Op("<") => "lt",
+                Token::Op("<=") => "le",
+                Token::Op(">") => "gt",
+                Token::Op(">=") => "ge",
+                _ => return Ok(left),
+            };
+            self.advance();
+            op
+        };
+        let right = self.binary(0)?;
+        Ok(Expr::Binary {
+            op,
+            left: Box::new(left),
+            right: Box::new(right),
+        })
+    }
+
+    /// Arithmetic by precedence level: `+ -`, then `* / //`.
+    fn binary(&mut self, level: usize) -> Result<Expr, Failure> {
+        const LEVELS: [&[(&str, &str)]; 2] = [
+            &[("+", "add"), ("-", "sub")],
+            &[("*", "mul"), ("/", "div"), ("//", "floordiv")],
+        ];
+        if level == LEVELS.len() {
+            return self.unary();
+        }
+        let mut left = self.binary(level + 1)?;
+        while let Some(&(_, op)) = LEVELS[level]
+            .iter()
+            .find(|(symbol, _)| self.peek_op(symbol))
+        {
+            self.advance();
+            let right = self.binary(level + 1)?;
+            left = Expr::Binary {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, Failure> {
+        if self.eat_op("-") {
+            let operand = self.unary()?;
+            return Ok(match operand {
+                Expr::Int(value) => Expr::Int(value.wrapping_neg()),
+                Expr::Float(value) => Expr::Float(-value),
+                operand => Expr::Unary {
+                    op: UnaryOp::Neg,
+                    operand: Box::new(operand),
+                },
+            });
+        }
+        let mut value = self.atom()?;
+        loop {
+            if self.eat_op(".") {
+                let name = self.name()?;
+                value = Expr::Attribute {
+                    value: Box::new(value),
+                    name,
+                };
+            } else if self.eat_op("(") {
+                let (args, keywords) = self.arguments()?;
+                value = Expr::Call {
+                    function: Box::new(value),
+                    args,
+                    keywords,
+                };
+            } else if self.eat_op("[") {
+                let index = self.expression_list()?;
+                self.expect_op("]")?;
+                value = Expr::Subscript {
+                    value: Box::new(value),
+                    index: Box::new(index),
+                };
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    fn arguments(&mut self) -> Result<Arguments, Failure> {
+        let mut args = Vec::new();
+        let mut keywords = Vec::new();
+        while !self.eat_op(")") {
+            let is_keyword = matches!(self.peek(), Token::Name(_))
+                && matches!(
+                    self.tokens.get(self.position + 1),
+                    Some((Token::Op("="), _))
+                );
+            if is_keyword {
+                let name = self.name()?;
+                self.advance();
+                keywords.push((name, self.expression()?));
+            } else if !keywords.is_empty() {
+                return Err((
+                    self.line(),
+                    "a positional argument follows keyword arguments".to_string(),
+                ));
+            } else {
+                args.push(self.expression()?);
+            }
+            if !self.eat_op(",") {
+                self.expect_op(")")?;
+                break;
+            }
+        }
+        Ok((args, keywords))
+    }
+
+    fn atom(&mut self) -> Result<Expr, Failure> {
+        let line = self.line();
+        let token = self.peek().clone();
+        self.advance();
+        Ok(match token {
+            Token::Name(name) => match name.as_str() {
+                "True" => Expr::Bool(true),
+                "False" => Expr::Bool(false),
+                "None" => Expr::None,
+                _ => Expr::Name(name),
+            },
+            Token::Int(value) => Expr::Int(value),
+            Token::Float(value) => Expr::Float(value),
+            Token::Str(mut value) => {
+                // Adjacent literals concatenate
+                while let Token::Str(next) = self.peek() {
+                    value.push_str(next);
+                    self.advance();
+                }
+                Expr::Str(value)
+            }
+            Token::Op("(") => {
+                if self.eat_op(")") {
+                    return Ok(Expr::Tuple(Vec::new()));
+                }
+                let value = self.expression_list()?;
+                self.expect_op(")")?;
+                value
+            }
+            Token::Op("[") => {
+                let mut items = Vec::new();
+                while !self.eat_op("]") {
+                    items.push(self.expression()?);
+                    if !self.eat_op(",") {
+                        self.expect_op("]")?;
+                        break;
+                    }
+                }
+                Expr::List(items)
+            }
+            other => return Err((line, format!("unexpected {}", describe(&other)))),
+        })
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.position].0
+    }
+
+    fn peek_op(&self, op: &str) -> bool {
+        matches!(self.peek(), Token::Op(found) if *found == op)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens[self.position].1
+    }
+
+    fn advance(&mut self) {
+        if self.position + 1 < self.tokens.len() {
+            self.position += 1;
+        }
+    }
+
+    fn eat_op(&mut self, op: &str) -> bool {
+        let found = self.peek_op(op);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = matches!(self.peek(), Token::Name(name) if name == keyword);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    fn at_expression_end(&self) -> bool {
+        matches!(
+            self.peek(),
+            Token::Newline | Token::End | Token::Op("=" | ")" | "]" | ":")
+        )
+    }
+
+    fn name(&mut self) -> Result<String, Failure> {
+        match self.peek().clone() {
+            Token::Name(name) => {
+                self.advance();
+                Ok(name)
+            }
+            other => Err((
+                self.line(),
+                format!("expected a name, found {}", describe(&other)),
+            )),
+        }
+    }
+
+    fn expect(&mut self, token: Token, what: &str) -> Result<(), Failure> {
+        if *self.peek() != token {
+            return Err((
+                self.line(),
+                format!("expected {what}, found {}", describe(self.peek())),
+            ));
+        }
+        self.advance();
+        Ok(())
+    }
+
+    fn expect_op(&mut self, op: &'static str) -> Result<(), Failure> {
+        self.expect(Token::Op(op), &format!("'{op}'"))
+    }
+
+    fn expect_newline(&mut self) -> Result<(), Failure> {
+        if *self.peek() == Token::End {
+            return Ok(());
+        }
+        self.expect(Token::Newline, "the end of the line")
+    }
+
+    /// Skips to the start of the next logical line.
+    fn skip_line(&mut self) {
+        while !matches!(self.peek(), Token::Newline | Token::End) {
+            self.advance();
+        }
+        self.advance();
+    }
+
+    /// Skips a statement along with any block it opens, and an `else` block
+    /// after it.
+    fn skip_statement(&mut self) {
+        self.skip_line();
+        loop {
+            if *self.peek() == Token::Indent {
+                let mut depth = 0;
+                loop {
+                    match self.peek() {
+                        Token::Indent => depth += 1,
+                        Token::Dedent => depth -= 1,
+                        Token::End => return,
+                        _ => {}
+                    }
+                    self.advance();
+                    if depth == 0 {
+                        break;
+                    }
+                }
+            }
+            if !self.eat_keyword("else") {
+                return;
+            }
+            self.skip_line();
+        }
+    }
+}
+
+/// Turns `target = value` into an assignment to plain names.
+fn assignment(target: Expr, value: Expr, line: usize) -> Stmt {
+    let (names, unpack) = match target {
+        Expr::Tuple(items) => (items, true),
+        target => (vec![target], false),
+    };
+    let mut targets = Vec::new();
+    for name in names {
+        match name {
+            Expr::Name(name) => targets.push(name),
+            _ => {
+                return Stmt::Unsupported {
+                    description: "assignments to attributes or items".to_string(),
+                    line,
+                };
+            }
+        }
+    }
+    Stmt::Assign {
+        targets,
+        unpack,
+        value,
+        line,
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Name(name) => format!("'{name}'"),
+        Token::Int(value) => format!("'{value}'"),
+        Token::Float(value) => format!("'{value}'"),
+        Token::Str(_) => "a string".to_string(),
+        Token::Op(op) => format!("'{op}'"),
+        Token::Newline => "the end of the line".to_string(),
+        Token::Indent => "an indented block".to_string(),
+        Token::Dedent => "the end of a block".to_string(),
+        Token::End => "the end of the file".to_string(),
+    }
+}
+
+fn invalid(file: &str, line: usize, reason: String) -> ParsingError {
+    ParsingError::InvalidFormat {
+        format: "torchscript".to_string(),
+        reason: format!("{file}:{line}: {reason}"),
+    }
+}
@@ -1,7 +1,13 @@
+// Each test binary uses a different subset of these helpers
+#![allow(dead_code)]
+
 use xyntra::ir::{
+    errors::{ParsingError, ValidationError},
     graph::Graph,
-    ops::Node,
-    types::{NodeID, OpKind, TensorShape},
+    shape_inference::infer_output_type,
+    symbolic::ShapeConstraints,
+    tensor::Tensor,
+    types::{DType, NodeID, OpKind, ReduceKind, TensorShape, TensorType, Window2d},
 };
 
 /// Creates a test NodeID with a known value for consistent testing
@@ -24,22 +30,62 @@ pub fn create_scalar_tensor_shape() -> TensorShape {
     TensorShape::new(vec![])
 }
 
-/// Builds a simple 3-node graph: input → matmul → output
+/// Creates a TensorType from a dtype and dimensions
+pub fn create_test_tensor_type(dtype: DType, dims: Vec<usize>) -> TensorType {
+    TensorType::new(dtype, TensorShape::new(dims))
+}
+
+/// Creates an F32 TensorType from dimensions
+pub fn f32_type(dims: Vec<usize>) -> TensorType {
+    create_test_tensor_type(DType::F32, dims)
+}
+
+/// Infers the single output type of `op` on `inputs` with fresh constraints
+pub fn infer(op: OpKind, inputs: Vec<TensorType>) -> Result<TensorType, ValidationError> {
+    let refs: Vec<&TensorType> = inputs.iter().collect();
+    infer_output_type(&op, &refs, &mut ShapeConstraints::new())
+}
+
+/// Path of a file under `tests/data/<dir>`, e.g. an ONNX or TorchScript model
+pub fn fixture_path(dir: &str, name: &str) -> String {
+    format!("{}/tests/data/{dir}/{name}", env!("CARGO_MANIFEST_DIR"))
+}
+
+/// Reads a file under `tests/data/<dir>`
+pub fn fixture(dir: &str, name: &str) -> Vec<u8> {
+    std::fs::read(fixture_path(dir, name)).unwrap()
+}
+
+/// The error of an import that is expected to fail
+pub fn import_error<T>(result: Result<T, ParsingError>) -> ParsingError {
+    match result {
+        Err(error) => error,
+        Ok(_) => panic!("expected the import to fail"),
+    }
+}
+
+/// Op names of the graph's nodes in node order
+pub fn ops(graph: &Graph) -> Vec<String> {
+    graph
+        .nodes()
+        .map(|node| node.op().name().to_string())
+        .collect()
+}
+
+/// Builds a simple 3-node graph: input, weights → matmul, with the matmul
+/// result as the graph output
 pub fn build_simple_graph() -> Graph {
     let mut graph = Graph::new();
 
-    // Add input node (no inputs, one output)
-    let input_id = graph.add_node(OpKind::Custom("Input".to_string()), vec![], vec![]);
+    let input_id = graph.add_input("input", create_test_tensor_type(DType::F32, vec![2, 4]));
+    let weights = Tensor::from_f32(TensorShape::new(vec![4, 3]), &[0.5; 12])
+        .expect("weights fill their shape");
+    let weights_id = graph.add_initializer("weights", weights);
 
-    // Add matmul node (one input, one output)
-    let matmul_id = graph.add_node(OpKind::MatMul, vec![input_id], vec![]);
-
-    // Add output node (one input, no outputs)
-    let _output_id = graph.add_node(
-        OpKind::Custom("Output".to_string()),
-        vec![matmul_id],
-        vec![],
-    );
+    let matmul_id = graph.add_node(OpKind::matmul(), vec![input_id, weights_id]);
+    graph
+        .add_output("output", matmul_id)
+        .expect("matmul node exists");
 
     graph
 }
@@ -49,18 +95,16 @@ pub fn build_complex_graph() -> Graph {
     let mut graph = Graph::new();
 
     // Create a matmul → gelu → dropout chain
-    let input1_id = graph.add_node(OpKind::Custom("Input1".to_string()), vec![], vec![]);
-    let input2_id = graph.add_node(OpKind::Custom("Input2".to_string()), vec![], vec![]);
+    let input1_id = graph.add_input("input1", create_test_tensor_type(DType::F32, vec![8, 16]));
+    let input2_id = graph.add_input("input2", create_test_tensor_type(DType::F32, vec![16, 32]));
 
-    let matmul_id = graph.add_node(OpKind::MatMul, vec![input1_id, input2_id], vec![]);
-    let gelu_id = graph.add_node(OpKind::Gelu, vec![matmul_id], vec![]);
-    let dropout_id = graph.add_node(OpKind::Dropout, vec![gelu_id], vec![]);
+    let matmul_id = graph.add_node(OpKind::matmul(), vec![input1_id, input2_id]);
+    let gelu_id = graph.add_node(OpKind::Gelu, vec![matmul_id]);
+    let dropout_id = graph.add_node(OpKind::dropout(), vec![gelu_id]);
 
-    let _output_id = graph.add_node(
-        OpKind::Custom("Output".to_string()),
-        vec![dropout_id],
-        vec![],
-    );
+    graph
+        .add_output("output", dropout_id)
+        .expect("dropout node exists");
 
     graph
 }
@@ -85,18 +129,48 @@ pub fn assert_node_ids_equal(expected: NodeID, actual: NodeID) {
 
 /// Helper to create a test OpKind for consistent testing
 pub fn create_test_op_kind() -> OpKind {
-    OpKind::MatMul
+    OpKind::matmul()
 }
 
 /// Helper to create various OpKind variants for testing
 pub fn create_all_op_kinds() -> Vec<OpKind> {
     vec![
-        OpKind::MatMul,
+        OpKind::matmul(),
         OpKind::Add,
         OpKind::Gelu,
-        OpKind::Dropout,
-        OpKind::Softmax,
-        OpKind::LayerNorm,
-        OpKind::Custom("TestOp".to_string()),
+        OpKind::dropout(),
+        OpKind::softmax(),
+        OpKind::layer_norm(),
+        OpKind::Relu,
+        OpKind::Sigmoid,
+        OpKind::Tanh,
+        OpKind::Exp,
+        OpKind::Log,
+        OpKind::Sqrt,
+        OpKind::Rsqrt,
+        OpKind::Erf,
+        OpKind::Clip {
+            min: Some(0.0),
+            max: Some(6.0),
+        },
+        OpKind::Cast { to: DType::F16 },
+        OpKind::Mul,
+        OpKind::Sub,
+        OpKind::Div,
+        OpKind::Pow,
+        OpKind::Where,
+        OpKind::reduce(ReduceKind::Mean, vec![-1]),
+        OpKind::ArgMax {
+            axis: 0,
+            keepdims: false,
+        },
+        OpKind::Reshape { shape: vec![0, -1] },
+        OpKind::Gather { axis: 0 },
+        OpKind::Conv2d {
+            window: Window2d::default(),
+            groups: 1,
+        },
+        OpKind::GlobalAvgPool,
+        OpKind::custom("TestOp"),
     ]
 }
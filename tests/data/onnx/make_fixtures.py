@@ -0,0 +1,315 @@
+#!/usr/bin/env python3
+"""Writes the small .onnx models the ONNX import tests read.
+
+The models are encoded by hand so regenerating them needs nothing beyond the
+Python standard library: run `python3 make_fixtures.py` in this directory.
+"""
+
+import struct
+
+FLOAT, INT32, INT64, BOOL = 1, 6, 7, 9
+ATTR_FLOAT, ATTR_INT, ATTR_STRING, ATTR_TENSOR, ATTR_INTS = 1, 2, 3, 4, 7
+
+
+def varint(value):
+    value &= (1 << 64) - 1
+    out = bytearray()
+    while True:
+        byte = value & 0x7F
+        value >>= 7
+        if value:
+            out.append(byte | 0x80)
+        else:
+            out.append(byte)
+            return bytes(out)
+
+
+def key(number, wire_type):
+    return varint(number << 3 | wire_type)
+
+
+def int_field(number, value):
+    return key(number, 0) + varint(value)
+
+
+def bytes_field(number, data):
+    if isinstance(data, str):
+        data = data.encode()
+    return key(number, 2) + varint(len(data)) + data
+
+
+def float_field(number, value):
+    return key(number, 5) + struct.pack("<f", value)
+
+
+def packed_ints(number, values):
+    return bytes_field(number, b"".join(varint(value) for value in values))
+
+
+def tensor(name, data_type, dims, values):
+    body = packed_ints(1, dims) + int_field(2, data_type) + bytes_field(8, name)
+    if data_type == FLOAT:
+        raw = struct.pack(f"<{len(values)}f", *values)
+    elif data_type == INT64:
+        raw = struct.pack(f"<{len(values)}q", *values)
+    else:
+        raw = struct.pack(f"<{len(values)}i", *values)
+    return body + bytes_field(9, raw)
+
+
+def attribute(name, value):
+    body = bytes_field(1, name)
+    if isinstance(value, float):
+        return body + float_field(2, value) + int_field(20, ATTR_FLOAT)
+    if isinstance(value, int):
+        return body + int_field(3, value) + int_field(20, ATTR_INT)
+    if isinstance(value, str):
+        return body + bytes_field(4, value) + int_field(20, ATTR_STRING)
+    if isinstance(value, list):
+        return body + packed_ints(8, value) + int_field(20, ATTR_INTS)
+    # A pre-encoded TensorProto
+    return body + bytes_field(5, value) + int_field(20, ATTR_TENSOR)
+
+
+def node(op_type, inputs, outputs, name="", domain="", **attributes):
+    body = b"".join(bytes_field(1, value) for value in inputs)
+    body += b"".join(bytes_field(2, value) for value in outputs)
+    if name:
+        body += bytes_field(3, name)
+    body += bytes_field(4, op_type)
+    body += b"".join(
+        bytes_field(5, attribute(attr_name, value))
+        for attr_name, value in attributes.items()
+    )
+    if domain:
+        body += bytes_field(7, domain)
+    return body
+
+
+def value_info(name, elem_type, dims):
+    shape = b""
+    for dim in dims:
+        if isinstance(dim, str):
+            shape += bytes_field(1, bytes_field(2, dim))
+        elif dim is None:
+            shape += bytes_field(1, b"")
+        else:
+            shape += bytes_field(1, int_field(1, dim))
+    tensor_type = int_field(1, elem_type) + bytes_field(2, shape)
+    return bytes_field(1, name) + bytes_field(2, bytes_field(1, tensor_type))
+
+
+def model(
+    name, nodes, inputs, outputs, initializers=(), value_infos=(), opset=13, domains=()
+):
+    graph = b"".join(bytes_field(1, value) for value in nodes)
+    graph += bytes_field(2, name)
+    graph += b"".join(bytes_field(5, value) for value in initializers)
+    graph += b"".join(bytes_field(11, value) for value in inputs)
+    graph += b"".join(bytes_field(12, value) for value in outputs)
+    graph += b"".join(bytes_field(13, value) for value in value_infos)
+    opset_imports = b"".join(
+        bytes_field(8, bytes_field(1, domain) + int_field(2, version))
+        for domain, version in [("", opset), *domains]
+    )
+    return (
+        int_field(1, 8)
+        + bytes_field(2, "xyntra-fixtures")
+        + bytes_field(7, graph)
+        + opset_imports
+    )
+
+
+def mlp():
+    """Gemm with bias, Relu, MatMul and Softmax over a symbolic batch."""
+    return model(
+        "mlp",
+        [
+            node("Gemm", ["x", "w1", "b1"], ["hidden"], name="fc1"),
+            node("Relu", ["hidden"], ["activated"], name="relu"),
+            node("MatMul", ["activated", "w2"], ["logits"], name="fc2"),
+            node("Softmax", ["logits"], ["y"], name="softmax", axis=-1),
+        ],
+        [value_info("x", FLOAT, ["batch", 4])],
+        [value_info("y", FLOAT, ["batch", 2])],
+        initializers=[
+            tensor("w1", FLOAT, [4, 8], [0.125 * i for i in range(32)]),
+            tensor("b1", FLOAT, [8], [0.5] * 8),
+            tensor("w2", FLOAT, [8, 2], [1.0, -1.0] * 8),
+            # Never read, so the importer drops it
+            tensor("unused", FLOAT, [2], [0.0, 0.0]),
+        ],
+        value_infos=[value_info("activated", FLOAT, ["batch", 8])],
+    )
+
+
+def attention_prep():
+    """Parameters passed as constant inputs and Constant nodes, plus outputs
+    the IR does not model (Dropout's mask) that nothing reads."""
+    int64_max = (1 << 63) - 1
+    return model(
+        "attention_prep",
+        [
+            node("LayerNormalization", ["x", "gamma", "beta"], ["normed"], axis=-1, epsilon=1e-6),
+            node(
+                "Constant",
+                [],
+                ["target_shape"],
+                value=tensor("", INT64, [4], [0, 0, 2, 4]),
+            ),
+            node("Reshape", ["normed", "target_shape"], ["heads"]),
+            node("Transpose", ["heads"], ["transposed"], perm=[0, 2, 1, 3]),
+            node("Split", ["transposed", "split_sizes"], ["q", "k"], axis=3),
+            node("Slice", ["q", "starts", "ends", "axes"], ["q_tail"]),
+            node("Dropout", ["q_tail", "ratio"], ["dropped", "mask"]),
+            node("Identity", ["dropped"], ["alias"]),
+            node("Cast", ["alias"], ["y"], to=FLOAT),
+            node("ReduceMean", ["k"], ["k_mean"], axes=[-1], keepdims=0),
+        ],
+        [value_info("x", FLOAT, [1, "seq", 8])],
+        [value_info("y", FLOAT, [1, 2, "seq", None]), value_info("k_mean", FLOAT, [1, 2, "seq"])],
+        initializers=[
+            tensor("gamma", FLOAT, [8], [1.0] * 8),
+            tensor("beta", FLOAT, [8], [0.0] * 8),
+            tensor("split_sizes", INT64, [2], [3, 1]),
+            tensor("starts", INT64, [1], [1]),
+            tensor("ends", INT64, [1], [int64_max]),
+            tensor("axes", INT64, [1], [-1]),
+            tensor("ratio", FLOAT, [], [0.1]),
+        ],
+        opset=17,
+    )
+
+
+def conv_block():
+    """Conv, batch norm, pooling and the INT64 shape of a final Reshape."""
+    return model(
+        "conv_block",
+        [
+            node(
+                "Conv",
+                ["image", "kernel", "bias"],
+                ["conv"],
+                name="conv",
+                kernel_shape=[3, 3],
+                strides=[2, 2],
+                pads=[1, 1, 1, 1],
+            ),
+            node(
+                "BatchNormalization",
+                ["conv", "scale", "shift", "mean", "var"],
+                ["normed"],
+                name="bn",
+                epsilon=1e-3,
+            ),
+            node("Relu", ["normed"], ["activated"]),
+            node("MaxPool", ["activated"], ["pooled"], kernel_shape=[2, 2], strides=[2, 2]),
+            node("GlobalAveragePool", ["pooled"], ["gap"]),
+            node("Reshape", ["gap", "flat"], ["features"]),
+        ],
+        [value_info("image", FLOAT, ["n", 3, 16, 16])],
+        [value_info("features", FLOAT, ["n", 4])],
+        initializers=[
+            tensor("kernel", FLOAT, [4, 3, 3, 3], [0.01] * 108),
+            tensor("bias", FLOAT, [4], [0.0] * 4),
+            tensor("scale", FLOAT, [4], [1.0] * 4),
+            tensor("shift", FLOAT, [4], [0.0] * 4),
+            tensor("mean", FLOAT, [4], [0.0] * 4),
+            tensor("var", FLOAT, [4], [1.0] * 4),
+            tensor("flat", INT64, [2], [0, -1]),
+        ],
+    )
+
+
+def unknown_op():
+    """A standard op the IR has no counterpart for."""
+    return model(
+        "unknown_op",
+        [
+            node("Relu", ["x"], ["activated"]),
+            node("Einsum", ["activated", "activated"], ["y"], name="contract", equation="ij,ij->i"),
+        ],
+        [value_info("x", FLOAT, [2, 3])],
+        [value_info("y", FLOAT, [2])],
+    )
+
+
+def custom_domain():
+    """An op from a vendor domain."""
+    return model(
+        "custom_domain",
+        [node("FusedGelu", ["x"], ["y"], domain="com.microsoft")],
+        [value_info("x", FLOAT, [2, 3])],
+        [value_info("y", FLOAT, [2, 3])],
+        domains=[("com.microsoft", 1)],
+    )
+
+
+def mask_output():
+    """Reads Dropout's mask, which the IR does not model."""
+    return model(
+        "mask_output",
+        [node("Dropout", ["x"], ["y", "mask"], name="drop")],
+        [value_info("x", FLOAT, [2, 3])],
+        [value_info("mask", BOOL, [2, 3])],
+    )
+
+
+def opset_variants(opset):
+    """The same computation written for opset 9, where Split sizes, Slice
+    bounds, Clip bounds, Dropout's ratio and Reduce axes are attributes and
+    Softmax defaults to axis 1, and for opset 18, where they are inputs."""
+    legacy = opset < 10
+    nodes = [
+        node("Softmax", ["x"], ["probs"]) if legacy else node("Softmax", ["x"], ["probs"], axis=1),
+        node("Split", ["probs"], ["head", "tail"], axis=1, split=[4, 2])
+        if legacy
+        else node("Split", ["probs", "sizes"], ["head", "tail"], axis=1),
+        node("Slice", ["head"], ["window"], starts=[1], ends=[3], axes=[1])
+        if legacy
+        else node("Slice", ["head", "starts", "ends", "axes"], ["window"]),
+        node("Clip", ["window"], ["clipped"], min=0.0, max=6.0)
+        if legacy
+        else node("Clip", ["window", "low", "high"], ["clipped"]),
+        node("Dropout", ["clipped"], ["y"], ratio=0.25)
+        if legacy
+        else node("Dropout", ["clipped", "ratio"], ["y"]),
+        node("ReduceSum", ["tail"], ["total"], axes=[1], keepdims=1)
+        if legacy
+        else node("ReduceSum", ["tail", "reduce_axes"], ["total"], keepdims=1),
+    ]
+    initializers = [
+        tensor("sizes", INT64, [2], [4, 2]),
+        tensor("starts", INT64, [1], [1]),
+        tensor("ends", INT64, [1], [3]),
+        tensor("axes", INT64, [1], [1]),
+        tensor("low", FLOAT, [], [0.0]),
+        tensor("high", FLOAT, [], [6.0]),
+        tensor("ratio", FLOAT, [], [0.25]),
+        tensor("reduce_axes", INT64, [1], [1]),
+    ]
+    return model(
+        f"opset{opset}",
+        nodes,
+        [value_info("x", FLOAT, ["batch", 6])],
+        [value_info("y", FLOAT, ["batch", 2]), value_info("total", FLOAT, ["batch", 1])],
+        initializers=[] if legacy else initializers,
+        opset=opset,
+    )
+
+
+FIXTURES = {
+    "mlp.onnx": mlp,
+    "attention_prep.onnx": attention_prep,
+    "conv_block.onnx": conv_block,
+    "unknown_op.onnx": unknown_op,
+    "custom_domain.onnx": custom_domain,
+    "mask_output.onnx": mask_output,
+    "legacy_opset9.onnx": lambda: opset_variants(9),
+    "modern_opset18.onnx": lambda: opset_variants(18),
+}
+
+if __name__ == "__main__":
+    for file_name, build in FIXTURES.items():
+        with open(file_name, "wb") as file:
+            file.write(build())
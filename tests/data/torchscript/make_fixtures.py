@@ -0,0 +1,436 @@
+#!/usr/bin/env python3
+"""Writes the small TorchScript archives the TorchScript import tests read.
+
+The pickles are encoded by hand the way libtorch's pickler writes them, and
+the source is what `torch.jit.save` prints, so regenerating the archives needs
+nothing beyond the Python standard library: run `python3 make_fixtures.py` in
+this directory.
+"""
+
+import struct
+import zipfile
+
+
+class Global:
+    def __init__(self, module, name):
+        self.module, self.name = module, name
+
+
+class Module:
+    """A scripted module instance: `__torch__` class plus attribute dict."""
+
+    def __init__(self, qualified_name, attributes):
+        self.module, _, self.name = qualified_name.rpartition(".")
+        self.attributes = attributes
+
+
+class TensorRef:
+    """A tensor whose data is storage `key` in the archive."""
+
+    def __init__(self, key, storage, size, stride, offset=0, numel=None):
+        self.key, self.storage = key, storage
+        self.size, self.stride, self.offset = size, stride, offset
+        self.numel = numel
+
+
+class Pickler:
+    def __init__(self):
+        self.out = bytearray(b"\x80\x02")
+        self.memo = 0
+
+    def memoize(self):
+        self.out += b"q" + bytes([self.memo])
+        self.memo += 1
+
+    def dump(self, value):
+        if value is None:
+            self.out += b"N"
+        elif isinstance(value, bool):
+            self.out += b"\x88" if value else b"\x89"
+        elif isinstance(value, int):
+            if 0 <= value < 256:
+                self.out += b"K" + bytes([value])
+            elif 0 <= value < 65536:
+                self.out += b"M" + struct.pack("<H", value)
+            elif -(2**31) <= value < 2**31:
+                self.out += b"J" + struct.pack("<i", value)
+            else:
+                self.out += b"\x8a\x08" + struct.pack("<q", value)
+        elif isinstance(value, float):
+            self.out += b"G" + struct.pack(">d", value)
+        elif isinstance(value, str):
+            data = value.encode()
+            self.out += b"X" + struct.pack("<I", len(data)) + data
+            self.memoize()
+        elif isinstance(value, tuple):
+            if not value:
+                self.out += b")"
+                return
+            self.out += b"("
+            for item in value:
+                self.dump(item)
+            self.out += b"t"
+        elif isinstance(value, list):
+            self.out += b"]("
+            for item in value:
+                self.dump(item)
+            self.out += b"e"
+        elif isinstance(value, dict):
+            self.out += b"}("
+            for key, item in value.items():
+                self.dump(key)
+                self.dump(item)
+            self.out += b"u"
+        elif isinstance(value, Global):
+            self.out += f"c{value.module}\n{value.name}\n".encode()
+            self.memoize()
+        elif isinstance(value, Module):
+            self.dump(Global(value.module, value.name))
+            self.out += b")\x81"
+            self.dump(value.attributes)
+            self.out += b"b"
+        elif isinstance(value, TensorRef):
+            self.dump(Global("torch._utils", "_rebuild_tensor_v2"))
+            self.out += b"("
+            numel = value.numel
+            if numel is None:
+                numel = 1
+                for dim in value.size:
+                    numel *= dim
+            self.dump(("storage", Global("torch", value.storage), value.key, "cpu", numel))
+            self.out += b"Q"
+            self.dump(value.offset)
+            self.dump(tuple(value.size))
+            self.dump(tuple(value.stride))
+            self.dump(False)
+            self.dump(Global("collections", "OrderedDict"))
+            self.out += b")R"
+            self.out += b"tR"
+        else:
+            raise TypeError(value)
+
+    def finish(self):
+        return bytes(self.out + b".")
+
+
+def pickle(value):
+    pickler = Pickler()
+    pickler.dump(value)
+    return pickler.finish()
+
+
+def floats(values):
+    return struct.pack(f"<{len(values)}f", *values)
+
+
+def longs(values):
+    return struct.pack(f"<{len(values)}q", *values)
+
+
+def contiguous(size):
+    stride, step = [], 1
+    for dim in reversed(size):
+        stride.insert(0, step)
+        step *= dim
+    return stride
+
+
+def archive(path, name, root, code, data=None, constants=(), constant_data=None,
+            compression=zipfile.ZIP_STORED):
+    """Writes the layout of `torch.jit.save`: everything under one directory
+    named after the archive."""
+    files = {"data.pkl": pickle(root), "constants.pkl": pickle(tuple(constants))}
+    for module, text in code.items():
+        files["code/" + module.replace(".", "/") + ".py"] = text.encode()
+    for key, raw in (data or {}).items():
+        files[f"data/{key}"] = raw
+    for key, raw in (constant_data or {}).items():
+        files[f"constants/{key}"] = raw
+    files["version"] = b"3\n"
+    files["byteorder"] = b"little"
+
+    with zipfile.ZipFile(path, "w") as zip_file:
+        for file_name, contents in files.items():
+            info = zipfile.ZipInfo(f"{name}/{file_name}", date_time=(1980, 1, 1, 0, 0, 0))
+            info.compress_type = compression
+            zip_file.writestr(info, contents)
+
+
+LINEAR = """\
+class Linear(Module):
+  __parameters__ = ["weight", "bias", ]
+  __buffers__ = []
+  weight : Tensor
+  bias : Tensor
+  training : bool
+  _is_full_backward_hook : Optional[bool]
+  in_features : Final[int] = 4
+  def forward(self: __torch__.torch.nn.modules.linear.Linear,
+    input: Tensor) -> Tensor:
+    weight = self.weight
+    bias = self.bias
+    return torch.linear(input, weight, bias)
+"""
+
+
+def mlp(path, compression=zipfile.ZIP_STORED, fc1_bias_view=None):
+    """Two scripted nn.Linear submodules, ReLU and softmax. fc2's weight is
+    stored transposed, as `w.t()` assigned to a parameter leaves it.
+    `fc1_bias_view` replaces the size and stride of fc1's bias."""
+    code = {
+        "__torch__": """\
+class MLP(Module):
+  __parameters__ = []
+  __buffers__ = []
+  training : bool
+  _is_full_backward_hook : Optional[bool]
+  fc1 : __torch__.torch.nn.modules.linear.Linear
+  fc2 : __torch__.torch.nn.modules.linear.Linear
+  def forward(self: __torch__.MLP,
+    x: Tensor) -> Tensor:
+    fc1 = self.fc1
+    h = torch.relu((fc1).forward(x, ))
+    fc2 = self.fc2
+    _0 = torch.softmax((fc2).forward(h, ), -1)
+    return _0
+""",
+        "__torch__.torch.nn.modules.linear": LINEAR,
+    }
+
+    def linear(weight_key, bias_key, out_features, in_features, transposed=False):
+        stride = [1, out_features] if transposed else [in_features, 1]
+        return Module("__torch__.torch.nn.modules.linear.Linear", {
+            "training": False,
+            "_is_full_backward_hook": None,
+            "weight": TensorRef(weight_key, "FloatStorage", [out_features, in_features], stride),
+            "bias": TensorRef(bias_key, "FloatStorage", [out_features], [1]),
+        })
+
+    root = Module("__torch__.MLP", {
+        "training": False,
+        "_is_full_backward_hook": None,
+        "fc1": linear("0", "1", 8, 4),
+        "fc2": linear("2", "3", 2, 8, transposed=True),
+    })
+    if fc1_bias_view is not None:
+        size, stride = fc1_bias_view
+        root.attributes["fc1"].attributes["bias"] = TensorRef(
+            "1", "FloatStorage", size, stride, numel=8)
+    # fc2.weight[i][j] = i + 10 * j, laid out column by column
+    data = {
+        "0": floats([(i % 5 - 2) * 0.25 for i in range(32)]),
+        "1": floats([0.1] * 8),
+        "2": floats([i + 10.0 * j for j in range(8) for i in range(2)]),
+        "3": floats([0.0, 1.0]),
+    }
+    archive(path, "mlp", root, code, data, compression=compression)
+
+
+def conv_block(path):
+    """Traced-style code: a Sequential of Conv2d and BatchNorm2d reached
+    through getattr, then an in-place ReLU, pooling and a dynamic reshape."""
+    code = {
+        "__torch__": """\
+class ConvBlock(Module):
+  __parameters__ = []
+  __buffers__ = []
+  training : bool
+  features : __torch__.torch.nn.modules.container.Sequential
+  def forward(self: __torch__.ConvBlock,
+    x: Tensor) -> Tensor:
+    features = self.features
+    _0 = ops.prim.NumToTensor(torch.size(x, 0))
+    input = torch.relu_((features).forward(x, ))
+    input0 = torch.max_pool2d(input, [2, 2], annotate(List[int], []), [0, 0], [1, 1], False)
+    _1 = torch.adaptive_avg_pool2d(input0, [1, 1])
+    return torch.view(_1, [int(_0), -1])
+""",
+        "__torch__.torch.nn.modules.container": """\
+class Sequential(Module):
+  __parameters__ = []
+  __buffers__ = []
+  training : bool
+  def forward(self: __torch__.torch.nn.modules.container.Sequential,
+    x: Tensor) -> Tensor:
+    _0 = getattr(self, "0")
+    _1 = getattr(self, "1")
+    return (_1).forward((_0).forward(x, ), )
+""",
+        "__torch__.torch.nn.modules.conv": """\
+class Conv2d(Module):
+  __parameters__ = ["weight", "bias", ]
+  __buffers__ = []
+  weight : Tensor
+  bias : Tensor
+  training : bool
+  def forward(self: __torch__.torch.nn.modules.conv.Conv2d,
+    x: Tensor) -> Tensor:
+    bias = self.bias
+    weight = self.weight
+    input = torch._convolution(x, weight, bias, [2, 2], [1, 1], [1, 1], False, [0, 0], 1, False, False, True, True)
+    return input
+""",
+        "__torch__.torch.nn.modules.batchnorm": """\
+class BatchNorm2d(Module):
+  __parameters__ = ["weight", "bias", ]
+  __buffers__ = ["running_mean", "running_var", "num_batches_tracked", ]
+  weight : Tensor
+  bias : Tensor
+  running_mean : Tensor
+  running_var : Tensor
+  num_batches_tracked : Tensor
+  training : bool
+  def forward(self: __torch__.torch.nn.modules.batchnorm.BatchNorm2d,
+    x: Tensor) -> Tensor:
+    running_var = self.running_var
+    running_mean = self.running_mean
+    bias = self.bias
+    weight = self.weight
+    _0 = torch.batch_norm(x, weight, bias, running_mean, running_var, False, 0.10000000000000001, 1.0000000000000001e-05, True)
+    return _0
+""",
+    }
+    conv = Module("__torch__.torch.nn.modules.conv.Conv2d", {
+        "training": False,
+        "weight": TensorRef("0", "FloatStorage", [4, 3, 3, 3], contiguous([4, 3, 3, 3])),
+        "bias": TensorRef("1", "FloatStorage", [4], [1]),
+    })
+    norm = Module("__torch__.torch.nn.modules.batchnorm.BatchNorm2d", {
+        "training": False,
+        "weight": TensorRef("2", "FloatStorage", [4], [1]),
+        "bias": TensorRef("3", "FloatStorage", [4], [1]),
+        "running_mean": TensorRef("4", "FloatStorage", [4], [1]),
+        "running_var": TensorRef("5", "FloatStorage", [4], [1]),
+        "num_batches_tracked": TensorRef("6", "LongStorage", [], []),
+    })
+    features = Module("__torch__.torch.nn.modules.container.Sequential", {
+        "training": False,
+        "0": conv,
+        "1": norm,
+    })
+    root = Module("__torch__.ConvBlock", {"training": False, "features": features})
+    data = {
+        "0": floats([((i * 7) % 11 - 5) * 0.1 for i in range(108)]),
+        "1": floats([0.0] * 4),
+        "2": floats([1.0] * 4),
+        "3": floats([0.0] * 4),
+        "4": floats([0.0] * 4),
+        "5": floats([1.0] * 4),
+        "6": longs([100]),
+    }
+    archive(path, "conv_block", root, code, data)
+
+
+def gated(path):
+    """Scripted control flow on module attributes, a tensor from
+    constants.pkl, an exception branch and a free function."""
+    code = {
+        "__torch__": """\
+class Gate(Module):
+  __parameters__ = []
+  __buffers__ = []
+  training : bool
+  use_gate : bool
+  scale : float
+  def forward(self: __torch__.Gate,
+    x: Tensor,
+    mask: Tensor) -> Tuple[Tensor, Tensor]:
+    if self.use_gate:
+      y = torch.mul(x, self.scale)
+    else:
+      y = x
+    if torch.eq(torch.dim(x), 2):
+      pass
+    else:
+      ops.prim.RaiseException("Expected a 2D input", "builtins.ValueError")
+    z = torch.index_select(y, 1, CONSTANTS.c0)
+    w = torch.where(mask, z, 0.)
+    _0 = __torch__.helpers.double(w, )
+    _1 = torch.sum(y, [1], True, dtype=None)
+    return (_0, _1)
+""",
+        "__torch__.helpers": """\
+def double(x: Tensor) -> Tensor:
+  return torch.add(x, x)
+""",
+    }
+    root = Module("__torch__.Gate", {"training": False, "use_gate": True, "scale": 0.5})
+    constants = (TensorRef("0", "LongStorage", [2], [1]),)
+    archive(path, "gated", root, code, constants=constants,
+            constant_data={"0": longs([2, 0])})
+
+
+def unknown_op(path):
+    """Calls an aten op Xyntra has no counterpart for."""
+    code = {
+        "__torch__": """\
+class Cumulative(Module):
+  __parameters__ = []
+  __buffers__ = []
+  training : bool
+  def forward(self: __torch__.Cumulative,
+    x: Tensor) -> Tensor:
+    y = torch.relu(x)
+    return torch.cumsum(y, 0)
+""",
+    }
+    archive(path, "unknown_op", Module("__torch__.Cumulative", {"training": False}), code)
+
+
+def chunked(path):
+    """Chunks and splits its input along the first dimension, which the
+    tests make empty."""
+    code = {
+        "__torch__": """\
+class Chunked(Module):
+  __parameters__ = []
+  __buffers__ = []
+  training : bool
+  def forward(self: __torch__.Chunked,
+    x: Tensor) -> Tuple[Tensor, Tensor]:
+    _0 = torch.chunk(x, 3, 0)
+    _1 = torch.split(x, 2, 0)
+    return (_0[2], _1[0])
+""",
+    }
+    archive(path, "chunked", Module("__torch__.Chunked", {"training": False}), code)
+
+
+def empty_kernel(path):
+    """A "same"-padded convolution whose weight has a zero-size kernel."""
+    code = {
+        "__torch__": """\
+class EmptyKernel(Module):
+  __parameters__ = ["weight", ]
+  __buffers__ = []
+  weight : Tensor
+  training : bool
+  def forward(self: __torch__.EmptyKernel,
+    x: Tensor) -> Tensor:
+    weight = self.weight
+    return torch.conv2d(x, weight, None, [1, 1], "same", [1, 1], 1)
+""",
+    }
+    root = Module("__torch__.EmptyKernel", {
+        "training": False,
+        "weight": TensorRef("0", "FloatStorage", [2, 3, 0, 3], contiguous([2, 3, 0, 3])),
+    })
+    archive(path, "empty_kernel", root, code, {"0": b""})
+
+
+FIXTURES = {
+    "mlp.pt": mlp,
+    "mlp_deflated.pt": lambda path: mlp(path, zipfile.ZIP_DEFLATED),
+    # Views whose element count or last position overflows
+    "mlp_huge_view.pt": lambda path: mlp(path, fc1_bias_view=([1 << 40, 1 << 40], [0, 0])),
+    "mlp_huge_stride.pt": lambda path: mlp(path, fc1_bias_view=([8], [1 << 62])),
+    "conv_block.pt": conv_block,
+    "gated.pt": gated,
+    "unknown_op.pt": unknown_op,
+    "chunked.pt": chunked,
+    "empty_kernel.pt": empty_kernel,
+}
+
+if __name__ == "__main__":
+    for file_name, build in FIXTURES.items():
+        build(file_name)
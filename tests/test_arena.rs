@@ -0,0 +1,118 @@
+mod common;
+
+use common::create_test_tensor_type;
+use xyntra::ir::{
+    arena::NodeArena,
+    errors::ValidationError,
+    graph::Graph,
+    ops::Node,
+    types::{DType, NodeID, OpKind},
+    validation::GraphValidator,
+};
+
+#[test]
+fn test_arena_reuses_slots_with_new_generation() {
+    let mut arena = NodeArena::new();
+    let first = arena.insert_with(|id| Node::new(id, OpKind::Relu, vec![], vec![]));
+    let second = arena.insert_with(|id| Node::new(id, OpKind::Exp, vec![], vec![]));
+
+    assert_eq!(first, NodeID::new(0));
+    assert_eq!(second, NodeID::new(1));
+
+    let removed = arena.remove(first).unwrap();
+    assert_eq!(removed.op(), &OpKind::Relu);
+    assert!(arena.get(first).is_none());
+    assert!(arena.remove(first).is_none());
+
+    let third = arena.insert_with(|id| Node::new(id, OpKind::Tanh, vec![], vec![]));
+    assert_eq!(third.id(), first.id());
+    assert_eq!(third.generation(), 1);
+    assert_ne!(third, first);
+
+    // The old handle does not reach the node now living in its slot
+    assert!(arena.get(first).is_none());
+    assert!(!arena.contains(first));
+    assert_eq!(arena[third].op(), &OpKind::Tanh);
+    assert_eq!(arena.ids(), vec![second, third]);
+    assert_eq!(arena.len(), 2);
+}
+
+#[test]
+fn test_stale_handle_reads_nothing() {
+    let mut graph = Graph::new();
+    let x = graph.add_input("x", create_test_tensor_type(DType::F32, vec![4]));
+    let dead = graph.add_node(OpKind::Relu, vec![x]);
+    graph.remove_node(dead).unwrap();
+    let live = graph.add_node(OpKind::Exp, vec![x]);
+
+    assert_eq!(live.id(), dead.id());
+    assert!(graph.get_node(dead).is_none());
+    assert!(!graph.contains_node(dead));
+    assert_eq!(graph.get_node(live).unwrap().op(), &OpKind::Exp);
+    assert!(graph.users(dead).is_empty());
+    assert_eq!(graph.users(x), &[live]);
+    assert_eq!(graph.node_ids(), vec![x, live]);
+}
+
+#[test]
+fn test_stale_handles_are_rejected_as_missing() {
+    let mut graph = Graph::new();
+    let x = graph.add_input("x", create_test_tensor_type(DType::F32, vec![4]));
+    let dead = graph.add_node(OpKind::Relu, vec![x]);
+    graph.remove_node(dead).unwrap();
+    let live = graph.add_node(OpKind::Exp, vec![x]);
+    let id = dead.id();
+
+    assert!(matches!(
+        graph.set_inputs(live, vec![dead.output(0)]),
+        Err(ValidationError::MissingNode { node_id }) if node_id == id
+    ));
+    assert!(matches!(
+        graph.add_output("y", dead),
+        Err(ValidationError::MissingNode { node_id }) if node_id == id
+    ));
+    assert!(matches!(
+        graph.remove_node(dead),
+        Err(ValidationError::MissingNode { node_id }) if node_id == id
+    ));
+    assert!(matches!(
+        graph.replace_op(dead, OpKind::Tanh),
+        Err(ValidationError::MissingNode { .. })
+    ));
+    assert_eq!(graph.get_node(live).unwrap().op(), &OpKind::Exp);
+}
+
+#[test]
+fn test_validator_reports_stale_inputs() {
+    let mut graph = Graph::new();
+    let x = graph.add_input("x", create_test_tensor_type(DType::F32, vec![4]));
+    let dead = graph.add_node(OpKind::Relu, vec![x]);
+    graph.remove_node(dead).unwrap();
+    graph.add_node(OpKind::Exp, vec![x]);
+    graph.add_node(OpKind::Tanh, vec![dead]);
+
+    let errors = GraphValidator::new(&graph)
+        .validate_node_references()
+        .unwrap_err();
+
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(
+        errors[0],
+        ValidationError::MissingNode { node_id } if node_id == dead.id()
+    ));
+}
+
+#[test]
+fn test_orders_after_slot_reuse() {
+    let mut graph = Graph::new();
+    let x = graph.add_input("x", create_test_tensor_type(DType::F32, vec![4]));
+    let dead = graph.add_node(OpKind::Relu, vec![x]);
+    let exp = graph.add_node(OpKind::Exp, vec![x]);
+    graph.remove_node(dead).unwrap();
+    // Reuses slot 1 but reads node 2, so it must come after it
+    let tanh = graph.add_node(OpKind::Tanh, vec![exp]);
+
+    assert_eq!(graph.node_ids(), vec![x, exp, tanh]);
+    assert_eq!(graph.topological_order().unwrap(), vec![x, exp, tanh]);
+    assert!(GraphValidator::new(&graph).validate().is_ok());
+}
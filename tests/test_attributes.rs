@@ -0,0 +1,177 @@
+mod common;
+
+use common::{create_all_op_kinds, f32_type, infer};
+use xyntra::ir::{
+    attributes::{AttributeValue, Attributes},
+    errors::{ParsingError, ValidationError},
+    graph::Graph,
+    types::OpKind,
+    validation::GraphValidator,
+};
+
+#[test]
+fn test_every_op_round_trips_through_attributes() {
+    let mut ops = create_all_op_kinds();
+    ops.push(OpKind::MatMul {
+        transpose_a: true,
+        transpose_b: false,
+    });
+    ops.push(OpKind::Softmax { axis: 1 });
+    ops.push(OpKind::LayerNorm {
+        axis: -2,
+        epsilon: 1e-6,
+    });
+    ops.push(OpKind::Dropout { ratio: 0.1 });
+
+    for op in ops {
+        let rebuilt = OpKind::from_parts(op.name(), &op.attributes()).unwrap();
+        assert_eq!(rebuilt, op);
+    }
+}
+
+#[test]
+fn test_missing_attributes_take_defaults() {
+    let empty = Attributes::new();
+
+    assert_eq!(
+        OpKind::from_parts("MatMul", &empty).unwrap(),
+        OpKind::matmul()
+    );
+    assert_eq!(
+        OpKind::from_parts("Softmax", &empty).unwrap(),
+        OpKind::Softmax { axis: -1 }
+    );
+    assert_eq!(
+        OpKind::from_parts("LayerNorm", &empty).unwrap(),
+        OpKind::LayerNorm {
+            axis: -1,
+            epsilon: 1e-5
+        }
+    );
+    assert_eq!(
+        OpKind::from_parts("Dropout", &empty).unwrap(),
+        OpKind::Dropout { ratio: 0.5 }
+    );
+}
+
+#[test]
+fn test_bad_attributes_are_rejected() {
+    let unknown = Attributes::new().with("alpha", AttributeValue::Float(1.0));
+    match OpKind::from_parts("Softmax", &unknown) {
+        Err(ParsingError::InvalidFormat { reason, .. }) => assert!(reason.contains("alpha")),
+        other => panic!("Expected InvalidFormat, got {other:?}"),
+    }
+
+    let wrong_kind = Attributes::new().with("axis", AttributeValue::String("last".to_string()));
+    assert!(OpKind::from_parts("Softmax", &wrong_kind).is_err());
+}
+
+#[test]
+fn test_custom_ops_keep_their_attribute_map() {
+    let attributes = Attributes::new()
+        .with("mode", AttributeValue::String("nearest".to_string()))
+        .with("scales", AttributeValue::Floats(vec![1.0, 2.0]))
+        .with("pads", AttributeValue::Ints(vec![0, 1]));
+
+    let op = OpKind::from_parts("Resize", &attributes).unwrap();
+
+    match &op {
+        OpKind::Custom { name, attributes } => {
+            assert_eq!(name, "Resize");
+            assert_eq!(attributes.len(), 3);
+            assert_eq!(
+                attributes.get("pads"),
+                Some(&AttributeValue::Ints(vec![0, 1]))
+            );
+        }
+        other => panic!("Expected Custom, got {other:?}"),
+    }
+    assert_eq!(op.attributes(), attributes);
+
+    // Sorted by name regardless of insertion order
+    let names: Vec<&str> = attributes.iter().map(|(name, _)| name).collect();
+    assert_eq!(names, vec!["mode", "pads", "scales"]);
+}
+
+#[test]
+fn test_attribute_value_display() {
+    assert_eq!(AttributeValue::Int(3).to_string(), "3");
+    assert_eq!(AttributeValue::Float(1.0).to_string(), "1.0");
+    assert_eq!(AttributeValue::String("a".to_string()).to_string(), "\"a\"");
+    assert_eq!(AttributeValue::Ints(vec![1, -1]).to_string(), "[1, -1]");
+    assert_eq!(AttributeValue::Floats(vec![0.5]).to_string(), "[0.5]");
+}
+
+#[test]
+fn test_validator_checks_attribute_ranges() {
+    let mut graph = Graph::new();
+    let x = graph.add_node(OpKind::custom("Input"), vec![]);
+    graph.add_node(OpKind::Dropout { ratio: 1.5 }, vec![x]);
+    graph.add_node(
+        OpKind::LayerNorm {
+            axis: -1,
+            epsilon: 0.0,
+        },
+        vec![x],
+    );
+    graph.add_node(OpKind::Dropout { ratio: 0.0 }, vec![x]);
+
+    let errors = GraphValidator::new(&graph)
+        .validate_operation_constraints()
+        .unwrap_err();
+
+    let attributes: Vec<(&str, &str)> = errors
+        .iter()
+        .map(|error| match error {
+            ValidationError::InvalidAttribute { op, attribute, .. } => {
+                (op.as_str(), attribute.as_str())
+            }
+            other => panic!("Expected InvalidAttribute, got {other:?}"),
+        })
+        .collect();
+    assert_eq!(
+        attributes,
+        vec![("Dropout", "ratio"), ("LayerNorm", "epsilon")]
+    );
+}
+
+#[test]
+fn test_matmul_transpose_flags_in_shape_inference() {
+    let a = f32_type(vec![3, 2]);
+    let b = f32_type(vec![4, 3]);
+
+    let op = OpKind::MatMul {
+        transpose_a: true,
+        transpose_b: true,
+    };
+    assert_eq!(
+        infer(op, vec![a.clone(), b.clone()]).unwrap(),
+        f32_type(vec![2, 4])
+    );
+
+    // Without the flags the contraction dimensions do not line up
+    assert!(infer(OpKind::matmul(), vec![a, b]).is_err());
+}
+
+#[test]
+fn test_axis_attributes_in_shape_inference() {
+    let x = f32_type(vec![2, 8, 16]);
+
+    assert!(infer(OpKind::Softmax { axis: 1 }, vec![x.clone()]).is_ok());
+    assert!(infer(OpKind::Softmax { axis: -3 }, vec![x.clone()]).is_ok());
+    match infer(OpKind::Softmax { axis: 3 }, vec![x.clone()]) {
+        Err(ValidationError::InvalidAttribute { attribute, .. }) => assert_eq!(attribute, "axis"),
+        other => panic!("Expected InvalidAttribute, got {other:?}"),
+    }
+
+    // LayerNorm over the last two dims takes a [8, 16] scale
+    let over_two = OpKind::LayerNorm {
+        axis: 1,
+        epsilon: 1e-5,
+    };
+    assert_eq!(
+        infer(over_two.clone(), vec![x.clone(), f32_type(vec![8, 16])]).unwrap(),
+        x
+    );
+    assert!(infer(OpKind::layer_norm(), vec![x.clone(), f32_type(vec![8, 16])]).is_err());
+}
@@ -0,0 +1,187 @@
+mod common;
+
+use common::{build_complex_graph, build_simple_graph, create_test_tensor_type};
+use xyntra::ir::{
+    errors::ValidationError,
+    graph::Graph,
+    shape_inference::ShapeInference,
+    tensor::{Tensor, f16_to_f32},
+    types::{DType, NodeID, OpCategory, OpKind, TensorShape},
+    validation::GraphValidator,
+};
+
+#[test]
+fn test_inputs_and_outputs() {
+    let mut graph = Graph::new();
+    let tokens = graph.add_input("tokens", create_test_tensor_type(DType::I32, vec![4]));
+    let mask = graph.add_input("mask", create_test_tensor_type(DType::Bool, vec![4]));
+    let cast = graph.add_node(OpKind::Cast { to: DType::F32 }, vec![tokens]);
+    graph.add_output("features", cast).unwrap();
+
+    assert_eq!(graph.inputs(), &[tokens, mask]);
+    assert_eq!(
+        graph.get_node(tokens).unwrap().op(),
+        &OpKind::Input {
+            name: "tokens".to_string()
+        }
+    );
+    assert_eq!(
+        graph.output_type(mask),
+        Some(&create_test_tensor_type(DType::Bool, vec![4]))
+    );
+    assert_eq!(graph.outputs()[0].name(), "features");
+    assert_eq!(graph.outputs()[0].node(), cast);
+    assert_eq!(graph.find_value("mask"), Some(mask));
+    assert_eq!(graph.find_value("features"), None);
+
+    assert!(matches!(
+        graph.add_output("missing", NodeID::new(99)),
+        Err(ValidationError::MissingNode { node_id: 99 })
+    ));
+}
+
+#[test]
+fn test_initializers_hold_data() {
+    let mut graph = Graph::new();
+    let bias = Tensor::from_f32(TensorShape::new(vec![3]), &[0.5, -1.0, 2.0]).unwrap();
+    let bias_id = graph.add_initializer("bias", bias.clone());
+
+    assert_eq!(graph.initializer(bias_id), Some(&bias));
+    assert_eq!(graph.output_type(bias_id), Some(bias.tensor_type()));
+    assert_eq!(graph.initializers(), vec![(bias_id, &bias)]);
+    assert_eq!(graph.find_value("bias"), Some(bias_id));
+    assert_eq!(
+        graph.get_node(bias_id).unwrap().op().category(),
+        OpCategory::Source
+    );
+    assert!(graph.inputs().is_empty());
+}
+
+#[test]
+fn test_tensor_data_checks() {
+    let shape = TensorShape::new(vec![2, 2]);
+    assert!(Tensor::from_f32(shape.clone(), &[1.0, 2.0, 3.0]).is_err());
+    assert!(Tensor::from_i32(shape.clone(), &[1, 2, 3, 4]).is_ok());
+
+    let symbolic = TensorShape::from_dims(vec![xyntra::ir::symbolic::Dim::symbol("n")]);
+    assert!(Tensor::from_f32(symbolic, &[]).is_err());
+
+    let raw = Tensor::new(
+        create_test_tensor_type(DType::I8, vec![3]),
+        vec![1, 0xff, 7],
+    )
+    .unwrap();
+    assert_eq!(raw.len(), 3);
+    assert_eq!(raw.to_i64_vec(), Some(vec![1, -1, 7]));
+    assert_eq!(raw.to_f32_vec(), vec![1.0, -1.0, 7.0]);
+    assert_eq!(Tensor::scalar_f32(2.5).to_f32_vec(), vec![2.5]);
+    assert_eq!(Tensor::scalar_f32(2.5).to_i64_vec(), None);
+}
+
+#[test]
+fn test_half_precision_decoding() {
+    assert_eq!(f16_to_f32(0x3c00), 1.0);
+    assert_eq!(f16_to_f32(0xc000), -2.0);
+    assert_eq!(f16_to_f32(0x7bff), 65504.0);
+    assert_eq!(f16_to_f32(0x0001), 2f32.powi(-24));
+    assert!(f16_to_f32(0x7c00).is_infinite());
+    assert!(f16_to_f32(0x7e00).is_nan());
+
+    // 1.0 in f16 followed by -3.0 in bf16
+    let halves = Tensor::new(
+        create_test_tensor_type(DType::F16, vec![1]),
+        vec![0x00, 0x3c],
+    )
+    .unwrap();
+    assert_eq!(halves.to_f32_vec(), vec![1.0]);
+    let brain = Tensor::new(
+        create_test_tensor_type(DType::BF16, vec![1]),
+        vec![0x40, 0xc0],
+    )
+    .unwrap();
+    assert_eq!(brain.to_f32_vec(), vec![-3.0]);
+}
+
+#[test]
+fn test_helper_graphs_are_valid() {
+    for graph in [build_simple_graph(), build_complex_graph()] {
+        assert!(GraphValidator::new(&graph).validate().is_ok());
+    }
+
+    // Constants and inputs feed shape inference
+    let mut graph = build_simple_graph();
+    ShapeInference::new(&mut graph).run().unwrap();
+    let output = graph.outputs()[0].node();
+    assert_eq!(
+        graph.output_type(output),
+        Some(&create_test_tensor_type(DType::F32, vec![2, 3]))
+    );
+}
+
+#[test]
+fn test_boundary_validation() {
+    let mut graph = Graph::new();
+    let x = graph.add_input("x", create_test_tensor_type(DType::F32, vec![2]));
+    graph.add_input("x", create_test_tensor_type(DType::F32, vec![2]));
+    // Source nodes added behind the graph's back
+    graph.add_node(
+        OpKind::Input {
+            name: "stray".to_string(),
+        },
+        vec![],
+    );
+    graph.add_node(
+        OpKind::Constant {
+            name: "empty".to_string(),
+        },
+        vec![],
+    );
+    let weights = graph.add_initializer("w", Tensor::scalar_f32(1.0));
+    graph
+        .set_output_type(weights, create_test_tensor_type(DType::F32, vec![2]))
+        .unwrap();
+    graph.add_output("y", x).unwrap();
+    graph.add_output("y", weights).unwrap();
+
+    let errors = GraphValidator::new(&graph)
+        .validate_boundaries()
+        .unwrap_err();
+    let reasons: Vec<(String, String)> = errors
+        .iter()
+        .map(|error| match error {
+            ValidationError::InvalidGraphBoundary { name, reason } => {
+                (name.clone(), reason.clone())
+            }
+            other => panic!("unexpected error {other}"),
+        })
+        .collect();
+
+    assert_eq!(reasons.len(), 6);
+    assert_eq!(reasons[0].0, "x");
+    assert!(reasons[0].1.contains("another input"));
+    assert_eq!(reasons[1].0, "stray");
+    assert!(reasons[1].1.contains("not a declared"));
+    assert_eq!(reasons[2].0, "stray");
+    assert!(reasons[2].1.contains("no declared type"));
+    assert_eq!(
+        reasons[3],
+        ("empty".to_string(), "constant has no data".to_string())
+    );
+    assert_eq!(reasons[4].0, "w");
+    assert_eq!(reasons[5].0, "y");
+    assert_eq!(
+        errors[5].to_string(),
+        "Invalid graph input, output or constant 'y': name is used by another output."
+    );
+}
+
+#[test]
+fn test_source_attributes_round_trip() {
+    let op = OpKind::Constant {
+        name: "embedding.weight".to_string(),
+    };
+    assert_eq!(
+        OpKind::from_parts("Constant", &op.attributes()).unwrap(),
+        op
+    );
+}
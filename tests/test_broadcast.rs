@@ -0,0 +1,96 @@
+mod common;
+
+use common::create_test_tensor_shape as shape;
+use xyntra::ir::{errors::ValidationError, types::TensorShape};
+
+#[test]
+fn test_broadcast_two_shapes() {
+    assert_eq!(
+        shape(vec![4, 1, 3])
+            .broadcast_with(&shape(vec![5, 1]))
+            .unwrap(),
+        shape(vec![4, 5, 3])
+    );
+    assert_eq!(
+        shape(vec![2, 3])
+            .broadcast_with(&shape(vec![2, 3]))
+            .unwrap(),
+        shape(vec![2, 3])
+    );
+    assert_eq!(
+        shape(vec![]).broadcast_with(&shape(vec![7])).unwrap(),
+        shape(vec![7])
+    );
+}
+
+#[test]
+fn test_broadcast_many_shapes() {
+    let a = shape(vec![8, 1, 6, 1]);
+    let b = shape(vec![7, 1, 5]);
+    let c = shape(vec![1, 6, 1]);
+
+    assert_eq!(
+        TensorShape::broadcast(&[&a, &b, &c]).unwrap(),
+        shape(vec![8, 7, 6, 5])
+    );
+
+    // No shapes broadcast to a scalar
+    assert_eq!(TensorShape::broadcast(&[]).unwrap(), shape(vec![]));
+}
+
+#[test]
+fn test_broadcast_incompatible_shapes() {
+    let a = shape(vec![2, 3]);
+    let b = shape(vec![4]);
+
+    match TensorShape::broadcast(&[&a, &b]) {
+        Err(ValidationError::IncompatibleShapes { op, shapes }) => {
+            assert_eq!(op, "Broadcast");
+            assert_eq!(shapes, vec!["[2, 3]", "[4]"]);
+        }
+        other => panic!("Expected IncompatibleShapes, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_expanded_dims() {
+    let target = shape(vec![4, 5, 3]);
+
+    assert_eq!(shape(vec![5, 1]).expanded_dims(&target), Some(vec![0, 2]));
+    assert_eq!(shape(vec![4, 5, 3]).expanded_dims(&target), Some(vec![]));
+    assert_eq!(shape(vec![]).expanded_dims(&target), Some(vec![0, 1, 2]));
+
+    // Broadcasting is one-directional: the target never shrinks
+    assert_eq!(shape(vec![4, 5, 3]).expanded_dims(&shape(vec![5, 3])), None);
+    assert_eq!(shape(vec![2, 3]).expanded_dims(&target), None);
+    assert!(!shape(vec![2, 3]).can_broadcast_to(&target));
+    assert!(shape(vec![1, 3]).can_broadcast_to(&target));
+}
+
+#[test]
+fn test_broadcast_map_index_mapping() {
+    let map = shape(vec![5, 1])
+        .broadcast_map(&shape(vec![4, 5, 3]))
+        .unwrap();
+
+    assert!(!map.is_identity());
+    assert_eq!(map.expanded_dims(), &[0, 2]);
+    assert_eq!(map.map_index(&[3, 2, 1]), vec![2, 0]);
+    assert_eq!(map.map_index(&[0, 4, 2]), vec![4, 0]);
+    assert_eq!(map.source_strides(), Some(vec![0, 1, 0]));
+}
+
+#[test]
+fn test_broadcast_map_strides_row_major() {
+    let map = shape(vec![2, 1, 3])
+        .broadcast_map(&shape(vec![2, 4, 3]))
+        .unwrap();
+
+    assert_eq!(map.source_strides(), Some(vec![3, 0, 1]));
+    assert_eq!(map.map_index(&[1, 3, 2]), vec![1, 0, 2]);
+
+    let identity = shape(vec![2, 3]).broadcast_map(&shape(vec![2, 3])).unwrap();
+    assert!(identity.is_identity());
+    assert_eq!(identity.source_strides(), Some(vec![3, 1]));
+    assert_eq!(identity.map_index(&[1, 2]), vec![1, 2]);
+}
@@ -0,0 +1,210 @@
+mod common;
+
+use common::{create_all_op_kinds, create_test_tensor_type, infer};
+use xyntra::ir::{
+    elementwise::erf,
+    errors::ValidationError,
+    graph::Graph,
+    types::{Arity, DType, OpCategory, OpKind},
+    validation::GraphValidator,
+};
+
+fn assert_close(actual: f32, expected: f32) {
+    assert!(
+        (actual - expected).abs() < 1e-5,
+        "expected {expected}, got {actual}"
+    );
+}
+
+#[test]
+fn test_elementwise_arity() {
+    for op in [
+        OpKind::Relu,
+        OpKind::Sigmoid,
+        OpKind::Tanh,
+        OpKind::Exp,
+        OpKind::Log,
+        OpKind::Sqrt,
+        OpKind::Rsqrt,
+        OpKind::Erf,
+        OpKind::Clip {
+            min: None,
+            max: None,
+        },
+        OpKind::Cast { to: DType::I32 },
+    ] {
+        assert_eq!(op.arity(), Arity::Exact(1), "{}", op.name());
+    }
+
+    for op in [OpKind::Mul, OpKind::Sub, OpKind::Div, OpKind::Pow] {
+        assert_eq!(op.arity(), Arity::Exact(2), "{}", op.name());
+    }
+
+    assert_eq!(OpKind::Where.arity(), Arity::Exact(3));
+}
+
+#[test]
+fn test_op_categories() {
+    for op in create_all_op_kinds() {
+        let expected = match op.name() {
+            "MatMul" | "Conv2d" => OpCategory::Contraction,
+            "Softmax" | "LayerNorm" => OpCategory::Normalization,
+            "ReduceMean" | "ArgMax" | "GlobalAvgPool" => OpCategory::Reduction,
+            "Reshape" | "Gather" => OpCategory::Movement,
+            "TestOp" => OpCategory::Opaque,
+            _ => OpCategory::Elementwise,
+        };
+        assert_eq!(op.category(), expected, "{}", op.name());
+    }
+}
+
+#[test]
+fn test_validator_checks_elementwise_arity() {
+    let mut graph = Graph::new();
+    let x = graph.add_node(OpKind::custom("Input"), vec![]);
+    graph.add_node(OpKind::Relu, vec![x, x]);
+    graph.add_node(OpKind::Mul, vec![x]);
+    graph.add_node(OpKind::Where, vec![x, x]);
+    graph.add_node(
+        OpKind::Clip {
+            min: Some(1.0),
+            max: Some(0.0),
+        },
+        vec![x],
+    );
+
+    let errors = GraphValidator::new(&graph)
+        .validate_operation_constraints()
+        .unwrap_err();
+
+    assert_eq!(errors.len(), 4);
+    assert!(matches!(
+        &errors[3],
+        ValidationError::InvalidAttribute { attribute, .. } if attribute == "min"
+    ));
+}
+
+#[test]
+fn test_elementwise_shape_inference() {
+    let x = create_test_tensor_type(DType::F32, vec![4, 1, 8]);
+    let y = create_test_tensor_type(DType::F32, vec![3, 1]);
+    let broadcast = create_test_tensor_type(DType::F32, vec![4, 3, 8]);
+
+    for op in [OpKind::Mul, OpKind::Sub, OpKind::Div, OpKind::Pow] {
+        assert_eq!(infer(op, vec![x.clone(), y.clone()]).unwrap(), broadcast);
+    }
+
+    for op in [OpKind::Relu, OpKind::Tanh, OpKind::Rsqrt, OpKind::Erf] {
+        assert_eq!(infer(op, vec![x.clone()]).unwrap(), x);
+    }
+
+    let condition = create_test_tensor_type(DType::Bool, vec![8]);
+    assert_eq!(
+        infer(OpKind::Where, vec![condition, x.clone(), y.clone()]).unwrap(),
+        broadcast
+    );
+}
+
+#[test]
+fn test_elementwise_dtype_rules() {
+    let ints = create_test_tensor_type(DType::I32, vec![4]);
+    let floats = create_test_tensor_type(DType::F32, vec![4]);
+
+    // Integer-friendly ops accept ints, transcendental ones do not
+    assert!(infer(OpKind::Relu, vec![ints.clone()]).is_ok());
+    assert!(infer(OpKind::Mul, vec![ints.clone(), ints.clone()]).is_ok());
+    assert!(infer(OpKind::Exp, vec![ints.clone()]).is_err());
+    assert!(infer(OpKind::Sigmoid, vec![ints.clone()]).is_err());
+
+    // Where needs a boolean condition
+    assert!(
+        infer(
+            OpKind::Where,
+            vec![floats.clone(), floats.clone(), floats.clone()]
+        )
+        .is_err()
+    );
+
+    // Cast changes only the element type
+    assert_eq!(
+        infer(OpKind::Cast { to: DType::BF16 }, vec![ints]).unwrap(),
+        create_test_tensor_type(DType::BF16, vec![4])
+    );
+}
+
+#[test]
+fn test_scalar_semantics_unary() {
+    assert_close(OpKind::Relu.evaluate_scalar(&[-2.0]).unwrap(), 0.0);
+    assert_close(OpKind::Relu.evaluate_scalar(&[3.0]).unwrap(), 3.0);
+    assert_close(OpKind::Sigmoid.evaluate_scalar(&[0.0]).unwrap(), 0.5);
+    assert_close(OpKind::Tanh.evaluate_scalar(&[0.5]).unwrap(), 0.5f32.tanh());
+    assert_close(
+        OpKind::Exp.evaluate_scalar(&[1.0]).unwrap(),
+        std::f32::consts::E,
+    );
+    assert_close(OpKind::Log.evaluate_scalar(&[1.0]).unwrap(), 0.0);
+    assert_close(OpKind::Sqrt.evaluate_scalar(&[9.0]).unwrap(), 3.0);
+    assert_close(OpKind::Rsqrt.evaluate_scalar(&[4.0]).unwrap(), 0.5);
+    assert_close(OpKind::Gelu.evaluate_scalar(&[1.0]).unwrap(), 0.841_344_7);
+    assert_close(OpKind::dropout().evaluate_scalar(&[1.25]).unwrap(), 1.25);
+
+    let relu6 = OpKind::Clip {
+        min: Some(0.0),
+        max: Some(6.0),
+    };
+    assert_close(relu6.evaluate_scalar(&[-1.0]).unwrap(), 0.0);
+    assert_close(relu6.evaluate_scalar(&[7.5]).unwrap(), 6.0);
+    let floor_only = OpKind::Clip {
+        min: Some(1.0),
+        max: None,
+    };
+    assert_close(floor_only.evaluate_scalar(&[100.0]).unwrap(), 100.0);
+
+    assert_close(
+        OpKind::Cast { to: DType::I32 }
+            .evaluate_scalar(&[-2.7])
+            .unwrap(),
+        -2.0,
+    );
+    assert_close(
+        OpKind::Cast { to: DType::Bool }
+            .evaluate_scalar(&[0.3])
+            .unwrap(),
+        1.0,
+    );
+}
+
+#[test]
+fn test_scalar_semantics_binary_and_where() {
+    assert_close(OpKind::Add.evaluate_scalar(&[2.0, 3.0]).unwrap(), 5.0);
+    assert_close(OpKind::Sub.evaluate_scalar(&[2.0, 3.0]).unwrap(), -1.0);
+    assert_close(OpKind::Mul.evaluate_scalar(&[2.0, 3.0]).unwrap(), 6.0);
+    assert_close(OpKind::Div.evaluate_scalar(&[3.0, 2.0]).unwrap(), 1.5);
+    assert_close(OpKind::Pow.evaluate_scalar(&[2.0, 3.0]).unwrap(), 8.0);
+    assert_close(
+        OpKind::Where.evaluate_scalar(&[1.0, 4.0, 5.0]).unwrap(),
+        4.0,
+    );
+    assert_close(
+        OpKind::Where.evaluate_scalar(&[0.0, 4.0, 5.0]).unwrap(),
+        5.0,
+    );
+
+    // Not elementwise, or the wrong number of operands
+    assert!(OpKind::matmul().evaluate_scalar(&[1.0, 2.0]).is_none());
+    assert!(OpKind::Add.evaluate_scalar(&[1.0]).is_none());
+}
+
+#[test]
+fn test_erf_accuracy() {
+    let reference = [
+        (0.0, 0.0),
+        (0.5, 0.520_499_9),
+        (1.0, 0.842_700_8),
+        (2.0, 0.995_322_3),
+        (-1.0, -0.842_700_8),
+    ];
+    for (x, expected) in reference {
+        assert_close(erf(x), expected);
+    }
+}
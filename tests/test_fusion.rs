@@ -0,0 +1,213 @@
+mod common;
+
+use common::create_test_tensor_type;
+use xyntra::{
+    fusion::candidates::{
+        FusionPattern, find_candidates, find_conv_bn_relu, find_reduce_elementwise,
+    },
+    ir::{
+        graph::Graph,
+        shape_inference::ShapeInference,
+        types::{DType, OpKind, ReduceKind, Window2d},
+    },
+};
+
+#[test]
+fn test_reduce_elementwise_chain() {
+    // Variance epilogue of a layer norm: mean(x^2) + eps -> rsqrt
+    let mut graph = Graph::new();
+    let x = graph.add_node(OpKind::custom("Input"), vec![]);
+    let square = graph.add_node(OpKind::Mul, vec![x, x]);
+    let mean = graph.add_node(OpKind::reduce(ReduceKind::Mean, vec![-1]), vec![square]);
+    let eps = graph.add_node(OpKind::custom("Constant"), vec![]);
+    let shifted = graph.add_node(OpKind::Add, vec![mean, eps]);
+    let rsqrt = graph.add_node(OpKind::Rsqrt, vec![shifted]);
+
+    let candidates = find_reduce_elementwise(&graph);
+
+    assert_eq!(candidates.len(), 1);
+    let candidate = &candidates[0];
+    assert_eq!(candidate.pattern(), FusionPattern::ReduceElementwise);
+    assert_eq!(candidate.nodes(), &[mean, shifted, rsqrt]);
+    assert_eq!(candidate.root(), mean);
+    assert_eq!(candidate.output(), rsqrt);
+    assert!(!candidate.contains(square));
+}
+
+#[test]
+fn test_reduction_without_elementwise_consumer() {
+    let mut graph = Graph::new();
+    let x = graph.add_node(OpKind::custom("Input"), vec![]);
+    let sum = graph.add_node(OpKind::reduce(ReduceKind::Sum, vec![0]), vec![x]);
+    graph.add_node(OpKind::matmul(), vec![sum, x]);
+
+    assert!(find_candidates(&graph).is_empty());
+}
+
+#[test]
+fn test_chain_stops_at_shared_value() {
+    let mut graph = Graph::new();
+    let x = graph.add_node(OpKind::custom("Input"), vec![]);
+    let max = graph.add_node(OpKind::reduce(ReduceKind::Max, vec![1]), vec![x]);
+    let exp = graph.add_node(OpKind::Exp, vec![max]);
+    // `exp` is needed by two consumers, so it must be written out
+    graph.add_node(OpKind::Relu, vec![exp]);
+    graph.add_node(OpKind::Tanh, vec![exp]);
+
+    let candidates = find_candidates(&graph);
+
+    assert_eq!(candidates.len(), 1);
+    assert_eq!(candidates[0].nodes(), &[max, exp]);
+}
+
+#[test]
+fn test_chain_stops_at_graph_output() {
+    // A graph output must be written out even with a single consumer
+    let mut graph = Graph::new();
+    let x = graph.add_node(OpKind::custom("Input"), vec![]);
+    let sum = graph.add_node(OpKind::reduce(ReduceKind::Sum, vec![0]), vec![x]);
+    let exp = graph.add_node(OpKind::Exp, vec![sum]);
+    let tanh = graph.add_node(OpKind::Tanh, vec![exp]);
+    graph.add_output("sum", sum).unwrap();
+    graph.add_output("tanh", tanh).unwrap();
+
+    assert!(find_candidates(&graph).is_empty());
+
+    let mut graph = Graph::new();
+    let image = graph.add_node(OpKind::custom("Input"), vec![]);
+    let conv = graph.add_node(
+        OpKind::Conv2d {
+            window: Window2d::default(),
+            groups: 1,
+        },
+        vec![image, image],
+    );
+    let relu = graph.add_node(OpKind::Relu, vec![conv]);
+    graph.add_output("features", conv).unwrap();
+    graph.add_output("activations", relu).unwrap();
+
+    assert!(find_candidates(&graph).is_empty());
+}
+
+#[test]
+fn test_chain_stops_at_broadcast_back() {
+    // x - max(x) runs over the full input, not the reduced values
+    let mut graph = Graph::new();
+    let x = graph.add_node_with_type(
+        OpKind::custom("Input"),
+        vec![],
+        create_test_tensor_type(DType::F32, vec![4, 16]),
+    );
+    let max = graph.add_node(OpKind::reduce(ReduceKind::Max, vec![-1]), vec![x]);
+    let centered = graph.add_node(OpKind::Sub, vec![x, max]);
+    graph.add_node(OpKind::Exp, vec![centered]);
+    ShapeInference::new(&mut graph).run().unwrap();
+
+    assert!(find_candidates(&graph).is_empty());
+
+    // Without shape information the chain is taken as is
+    let mut untyped = Graph::new();
+    let x = untyped.add_node(OpKind::custom("Input"), vec![]);
+    let max = untyped.add_node(OpKind::reduce(ReduceKind::Max, vec![-1]), vec![x]);
+    let centered = untyped.add_node(OpKind::Sub, vec![x, max]);
+    assert_eq!(find_candidates(&untyped)[0].nodes(), &[max, centered]);
+}
+
+#[test]
+fn test_candidates_are_deterministic() {
+    let mut graph = Graph::new();
+    let x = graph.add_node(OpKind::custom("Input"), vec![]);
+    let mut roots = Vec::new();
+    for kind in [ReduceKind::Sum, ReduceKind::Min, ReduceKind::Mean] {
+        let reduce = graph.add_node(OpKind::reduce(kind, vec![0]), vec![x]);
+        graph.add_node(OpKind::Sigmoid, vec![reduce]);
+        roots.push(reduce);
+    }
+    let argmax = graph.add_node(
+        OpKind::ArgMax {
+            axis: 0,
+            keepdims: false,
+        },
+        vec![x],
+    );
+    graph.add_node(OpKind::Cast { to: DType::F32 }, vec![argmax]);
+    roots.push(argmax);
+
+    let first: Vec<_> = find_candidates(&graph).iter().map(|c| c.root()).collect();
+    let second: Vec<_> = find_candidates(&graph).iter().map(|c| c.root()).collect();
+
+    assert_eq!(first, roots);
+    assert_eq!(first, second);
+}
+
+#[test]
+fn test_conv_bn_relu_chain() {
+    let mut graph = Graph::new();
+    let image = graph.add_node(OpKind::custom("Input"), vec![]);
+    let weights = graph.add_node(OpKind::custom("Weights"), vec![]);
+    let conv = graph.add_node(
+        OpKind::Conv2d {
+            window: Window2d::default(),
+            groups: 1,
+        },
+        vec![image, weights],
+    );
+    let stats = graph.add_node(OpKind::custom("Stats"), vec![]);
+    let bn = graph.add_node(
+        OpKind::BatchNorm { epsilon: 1e-5 },
+        vec![conv, stats, stats, stats, stats],
+    );
+    let relu = graph.add_node(OpKind::Relu, vec![bn]);
+    let pool = graph.add_node(OpKind::GlobalAvgPool, vec![relu]);
+
+    let candidates = find_conv_bn_relu(&graph);
+    assert_eq!(candidates.len(), 1);
+    assert_eq!(candidates[0].pattern(), FusionPattern::ConvBnRelu);
+    assert_eq!(candidates[0].nodes(), &[conv, bn, relu]);
+    assert!(!candidates[0].contains(pool));
+}
+
+#[test]
+fn test_conv_partial_chains() {
+    let mut graph = Graph::new();
+    let x = graph.add_node(OpKind::custom("Input"), vec![]);
+    let depthwise = OpKind::DepthwiseConv {
+        window: Window2d::default(),
+    };
+
+    // Conv -> Clip (ReLU6) without a batch norm
+    let conv_a = graph.add_node(depthwise.clone(), vec![x, x]);
+    let relu6 = graph.add_node(
+        OpKind::Clip {
+            min: Some(0.0),
+            max: Some(6.0),
+        },
+        vec![conv_a],
+    );
+
+    // Conv -> BatchNorm whose output is shared, so the ReLU stays separate
+    let conv_b = graph.add_node(depthwise.clone(), vec![x, x]);
+    let bn = graph.add_node(
+        OpKind::BatchNorm { epsilon: 1e-5 },
+        vec![conv_b, x, x, x, x],
+    );
+    graph.add_node(OpKind::Relu, vec![bn]);
+    graph.add_node(OpKind::Add, vec![bn, x]);
+
+    // A convolution used as a batch norm parameter is not normalised by it
+    let conv_c = graph.add_node(depthwise.clone(), vec![x, x]);
+    graph.add_node(
+        OpKind::BatchNorm { epsilon: 1e-5 },
+        vec![x, conv_c, x, x, x],
+    );
+
+    // A bare convolution is not a candidate
+    let conv_d = graph.add_node(depthwise, vec![x, x]);
+    graph.add_node(OpKind::Sigmoid, vec![conv_d]);
+
+    let chains: Vec<Vec<_>> = find_candidates(&graph)
+        .iter()
+        .map(|candidate| candidate.nodes().to_vec())
+        .collect();
+    assert_eq!(chains, vec![vec![conv_a, relu6], vec![conv_b, bn]]);
+}
@@ -0,0 +1,138 @@
+mod common;
+
+use common::{create_test_tensor_shape as shape, create_test_tensor_type};
+use xyntra::ir::{
+    errors::ValidationError,
+    layout::{MemoryFormat, TensorLayout, column_major_strides, row_major_strides},
+    symbolic::Dim,
+    types::{DType, TensorShape},
+};
+
+#[test]
+fn test_stride_computation() {
+    assert_eq!(row_major_strides(&[2, 3, 4]), vec![12, 4, 1]);
+    assert_eq!(column_major_strides(&[2, 3, 4]), vec![1, 2, 6]);
+    assert_eq!(row_major_strides(&[]), Vec::<usize>::new());
+}
+
+#[test]
+fn test_layouts_by_format() {
+    let nchw = shape(vec![2, 3, 4, 5]);
+
+    let row_major = TensorLayout::contiguous(&nchw).unwrap();
+    assert_eq!(row_major.strides(), &[60, 20, 5, 1]);
+    assert_eq!(row_major.memory_format(&nchw), MemoryFormat::RowMajor);
+    assert!(row_major.is_contiguous(&nchw));
+
+    let column_major = TensorLayout::with_format(&nchw, MemoryFormat::ColumnMajor).unwrap();
+    assert_eq!(column_major.strides(), &[1, 2, 6, 24]);
+    assert_eq!(column_major.memory_format(&nchw), MemoryFormat::ColumnMajor);
+    assert!(!column_major.is_contiguous(&nchw));
+
+    let nhwc = TensorLayout::with_format(&nchw, MemoryFormat::ChannelsLast).unwrap();
+    assert_eq!(nhwc.strides(), &[60, 1, 15, 3]);
+    assert_eq!(nhwc.memory_format(&nchw), MemoryFormat::ChannelsLast);
+}
+
+#[test]
+fn test_invalid_layout_requests() {
+    // Channels-last only makes sense for rank-4 tensors
+    assert!(matches!(
+        TensorLayout::with_format(&shape(vec![2, 3]), MemoryFormat::ChannelsLast),
+        Err(ValidationError::InvalidTensorShape { .. })
+    ));
+
+    // Strided layouts cannot be derived from the shape alone
+    assert!(matches!(
+        TensorLayout::with_format(&shape(vec![2, 3]), MemoryFormat::Strided),
+        Err(ValidationError::InvalidTensorShape { .. })
+    ));
+
+    // Symbolic shapes have no concrete strides yet
+    let dynamic = TensorShape::from_dims(vec![Dim::symbol("batch"), Dim::Static(4)]);
+    assert!(TensorLayout::contiguous(&dynamic).is_err());
+}
+
+#[test]
+fn test_transpose_is_a_view() {
+    let matrix = shape(vec![3, 4]);
+    let layout = TensorLayout::contiguous(&matrix).unwrap();
+
+    let transposed = layout.transpose(&[1, 0]).unwrap();
+    let transposed_shape = matrix.permute(&[1, 0]).unwrap();
+
+    assert_eq!(transposed_shape, shape(vec![4, 3]));
+    assert_eq!(transposed.strides(), &[1, 4]);
+    assert_eq!(transposed.offset(), layout.offset());
+    assert!(!transposed.is_contiguous(&transposed_shape));
+    assert_eq!(
+        transposed.memory_format(&transposed_shape),
+        MemoryFormat::ColumnMajor
+    );
+
+    // Element (i, j) of the view is element (j, i) of the original
+    assert_eq!(
+        transposed.element_offset(&[2, 1]),
+        layout.element_offset(&[1, 2])
+    );
+
+    // NCHW -> NHWC permutation of a channels-last tensor is contiguous
+    let nchw = shape(vec![1, 8, 2, 2]);
+    let nhwc = TensorLayout::with_format(&nchw, MemoryFormat::ChannelsLast).unwrap();
+    let permuted = nhwc.transpose(&[0, 2, 3, 1]).unwrap();
+    assert!(permuted.is_contiguous(&nchw.permute(&[0, 2, 3, 1]).unwrap()));
+}
+
+#[test]
+fn test_invalid_permutation() {
+    let layout = TensorLayout::contiguous(&shape(vec![2, 3, 4])).unwrap();
+
+    assert!(layout.transpose(&[0, 1]).is_err());
+    assert!(layout.transpose(&[0, 0, 1]).is_err());
+    assert!(layout.transpose(&[0, 1, 3]).is_err());
+    assert!(shape(vec![2, 3]).permute(&[1, 1]).is_err());
+}
+
+#[test]
+fn test_odd_strides_and_offsets() {
+    // Every other column of a 4x6 buffer, starting at column 1
+    let view_shape = shape(vec![4, 3]);
+    let layout = TensorLayout::strided(vec![6, 2], 1);
+
+    assert_eq!(layout.memory_format(&view_shape), MemoryFormat::Strided);
+    assert!(!layout.is_contiguous(&view_shape));
+    assert_eq!(layout.element_offset(&[0, 0]), 1);
+    assert_eq!(layout.element_offset(&[3, 2]), 1 + 18 + 4);
+    assert_eq!(layout.storage_span(&view_shape).unwrap(), 24);
+
+    // Expanded dimensions have stride 0
+    let expanded = TensorLayout::strided(vec![0, 1], 0);
+    assert_eq!(expanded.element_offset(&[5, 2]), 2);
+    assert_eq!(expanded.storage_span(&shape(vec![8, 3])).unwrap(), 3);
+
+    // Size-1 dimensions never affect contiguity
+    let unit = TensorLayout::strided(vec![99, 3, 1], 0);
+    assert!(unit.is_contiguous(&shape(vec![1, 2, 3])));
+}
+
+#[test]
+fn test_tensor_type_layout() {
+    let plain = create_test_tensor_type(DType::F32, vec![3, 4]);
+    assert!(plain.layout().is_none());
+    assert!(plain.is_contiguous());
+    assert_eq!(plain.effective_layout().unwrap().strides(), &[4, 1]);
+
+    let transposed = create_test_tensor_type(DType::F32, vec![3, 4])
+        .with_layout(TensorLayout::strided(vec![1, 3], 0))
+        .unwrap();
+    assert!(!transposed.is_contiguous());
+    assert_eq!(transposed.layout().unwrap().strides(), &[1, 3]);
+    assert_ne!(plain, transposed);
+
+    // One stride per dimension is required
+    assert!(
+        create_test_tensor_type(DType::F32, vec![3, 4])
+            .with_layout(TensorLayout::strided(vec![1], 0))
+            .is_err()
+    );
+}
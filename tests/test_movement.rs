@@ -0,0 +1,408 @@
+mod common;
+
+use common::{create_test_tensor_type, f32_type, infer};
+use xyntra::ir::{
+    attributes::Attributes,
+    errors::ValidationError,
+    graph::Graph,
+    layout::{MemoryFormat, TensorLayout},
+    movement::slice_range,
+    shape_inference::{ShapeInference, infer_output_type, infer_output_types},
+    symbolic::{Dim, ShapeConstraints},
+    types::{DType, OpCategory, OpKind, TensorShape, TensorType},
+    validation::GraphValidator,
+};
+
+fn symbolic_type(dims: Vec<Dim>) -> TensorType {
+    TensorType::new(DType::F32, TensorShape::from_dims(dims))
+}
+
+fn slice(starts: Vec<i64>, ends: Vec<i64>, axes: Vec<i64>, steps: Vec<i64>) -> OpKind {
+    OpKind::Slice {
+        starts,
+        ends,
+        axes,
+        steps,
+    }
+}
+
+#[test]
+fn test_reshape_inference() {
+    let input = f32_type(vec![2, 3, 4]);
+
+    let reshape = |shape: Vec<i64>| OpKind::Reshape { shape };
+    assert_eq!(
+        infer(reshape(vec![0, -1]), vec![input.clone()]).unwrap(),
+        f32_type(vec![2, 12])
+    );
+    assert_eq!(
+        infer(reshape(vec![4, 3, 2]), vec![input.clone()]).unwrap(),
+        f32_type(vec![4, 3, 2])
+    );
+    assert!(infer(reshape(vec![5, -1]), vec![input.clone()]).is_err());
+    assert!(infer(reshape(vec![4, 4]), vec![input.clone()]).is_err());
+    assert!(infer(reshape(vec![0, 0, 0, 0]), vec![input]).is_err());
+}
+
+#[test]
+fn test_reshape_symbolic_element_counts() {
+    let reshape = |shape: Vec<i64>| OpKind::Reshape { shape };
+    let hidden = symbolic_type(vec![Dim::symbol("batch"), Dim::Static(768)]);
+
+    // batch * 768 == 5 has no solution
+    assert!(matches!(
+        infer(reshape(vec![5]), vec![hidden.clone()]),
+        Err(ValidationError::IncompatibleShapes { .. })
+    ));
+
+    // A fixed target records what the symbol must be
+    let mut constraints = ShapeConstraints::new();
+    infer_output_type(&reshape(vec![1536]), &[&hidden], &mut constraints).unwrap();
+    assert_eq!(constraints.value_of("batch"), Some(2));
+
+    // A product of symbols cannot be compared with a fixed count
+    let grid = symbolic_type(vec![Dim::symbol("h"), Dim::symbol("w")]);
+    assert!(matches!(
+        infer(reshape(vec![64]), vec![grid]),
+        Err(ValidationError::IncompatibleShapes { .. })
+    ));
+}
+
+#[test]
+fn test_reshape_symbolic_heads() {
+    // Splitting the hidden dimension into attention heads and back
+    let hidden = symbolic_type(vec![
+        Dim::symbol("batch"),
+        Dim::symbol("seq"),
+        Dim::Static(768),
+    ]);
+
+    let heads = infer(
+        OpKind::Reshape {
+            shape: vec![0, 0, 12, 64],
+        },
+        vec![hidden],
+    )
+    .unwrap();
+    assert_eq!(
+        heads,
+        symbolic_type(vec![
+            Dim::symbol("batch"),
+            Dim::symbol("seq"),
+            Dim::Static(12),
+            Dim::Static(64),
+        ])
+    );
+
+    let merged = infer(
+        OpKind::Reshape {
+            shape: vec![0, 0, 768],
+        },
+        vec![heads.clone()],
+    )
+    .unwrap();
+    assert_eq!(merged.shape().to_string(), "[batch, seq, 768]");
+
+    // batch*seq is not an affine expression, so it cannot be inferred
+    assert!(
+        infer(
+            OpKind::Reshape {
+                shape: vec![-1, 64]
+            },
+            vec![heads]
+        )
+        .is_err()
+    );
+}
+
+#[test]
+fn test_transpose_inference() {
+    let input = f32_type(vec![2, 3, 4]);
+
+    assert_eq!(
+        infer(
+            OpKind::Transpose {
+                perm: vec![0, 2, 1]
+            },
+            vec![input.clone()]
+        )
+        .unwrap(),
+        f32_type(vec![2, 4, 3])
+    );
+    assert_eq!(
+        infer(OpKind::Transpose { perm: vec![] }, vec![input.clone()]).unwrap(),
+        f32_type(vec![4, 3, 2])
+    );
+    assert!(matches!(
+        infer(OpKind::Transpose { perm: vec![0, 0, 1] }, vec![input]),
+        Err(ValidationError::InvalidAttribute { ref attribute, .. }) if attribute == "perm"
+    ));
+}
+
+#[test]
+fn test_concat_inference() {
+    let op = OpKind::Concat { axis: -1 };
+
+    assert_eq!(
+        infer(
+            op.clone(),
+            vec![
+                f32_type(vec![2, 3]),
+                f32_type(vec![2, 5]),
+                f32_type(vec![2, 1])
+            ]
+        )
+        .unwrap(),
+        f32_type(vec![2, 9])
+    );
+    assert!(infer(op.clone(), vec![f32_type(vec![2, 3]), f32_type(vec![3, 3])]).is_err());
+    assert!(infer(op.clone(), vec![f32_type(vec![2, 3]), f32_type(vec![3])]).is_err());
+
+    let seq = symbolic_type(vec![Dim::symbol("seq"), Dim::Static(8)]);
+    let joined = infer(
+        OpKind::Concat { axis: 0 },
+        vec![seq.clone(), seq, f32_type(vec![1, 8])],
+    )
+    .unwrap();
+    assert_eq!(joined.shape().to_string(), "[2*seq + 1, 8]");
+
+    let mut graph = Graph::new();
+    graph.add_node(op, vec![]);
+    let errors = GraphValidator::new(&graph)
+        .validate_operation_constraints()
+        .unwrap_err();
+    assert!(matches!(
+        errors[0],
+        ValidationError::InvalidOpInputCount {
+            expected: 1,
+            found: 0,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn test_split_produces_one_type_per_output() {
+    let op = OpKind::Split {
+        axis: 1,
+        sizes: vec![2, 4, 2],
+    };
+    let input = f32_type(vec![3, 8]);
+
+    let outputs = infer_output_types(&op, &[&input], &mut ShapeConstraints::new()).unwrap();
+    assert_eq!(op.output_count(), 3);
+    assert_eq!(
+        outputs,
+        vec![
+            f32_type(vec![3, 2]),
+            f32_type(vec![3, 4]),
+            f32_type(vec![3, 2])
+        ]
+    );
+
+    assert!(infer(op.clone(), vec![f32_type(vec![3, 7])]).is_err());
+
+    // The split size pins down a symbolic dimension
+    let mut graph = Graph::new();
+    let x = graph.add_node_with_type(
+        OpKind::custom("Input"),
+        vec![],
+        symbolic_type(vec![Dim::Static(3), Dim::symbol("hidden")]),
+    );
+    let split = graph.add_node(op, vec![x]);
+    let mut pass = ShapeInference::new(&mut graph);
+    pass.run().unwrap();
+    assert_eq!(pass.constraints().value_of("hidden"), Some(8));
+    assert_eq!(graph.get_node(split).unwrap().output_types().len(), 3);
+}
+
+#[test]
+fn test_slice_inference() {
+    let input = f32_type(vec![10, 6]);
+
+    assert_eq!(
+        infer(slice(vec![2], vec![8], vec![], vec![]), vec![input.clone()]).unwrap(),
+        f32_type(vec![6, 6])
+    );
+    assert_eq!(
+        infer(
+            slice(vec![0, 1], vec![i64::MAX, -1], vec![], vec![3, 2]),
+            vec![input.clone()]
+        )
+        .unwrap(),
+        f32_type(vec![4, 2])
+    );
+    // Reversing with a negative step
+    assert_eq!(
+        infer(
+            slice(vec![-1], vec![i64::MIN], vec![1], vec![-1]),
+            vec![input.clone()]
+        )
+        .unwrap(),
+        f32_type(vec![10, 6])
+    );
+    assert!(infer(slice(vec![0], vec![1], vec![2], vec![]), vec![input]).is_err());
+
+    let tokens = symbolic_type(vec![Dim::symbol("seq"), Dim::Static(4)]);
+    let rest = infer(
+        slice(vec![1], vec![i64::MAX], vec![0], vec![]),
+        vec![tokens.clone()],
+    );
+    assert_eq!(rest.unwrap().shape().to_string(), "[seq - 1, 4]");
+    assert!(infer(slice(vec![0], vec![4], vec![0], vec![]), vec![tokens]).is_err());
+}
+
+#[test]
+fn test_slice_range_clamps_like_onnx() {
+    assert_eq!(slice_range(10, 2, 8, 1), (2, 6));
+    assert_eq!(slice_range(10, -3, i64::MAX, 1), (7, 3));
+    assert_eq!(slice_range(10, 0, 10, 4), (0, 3));
+    assert_eq!(slice_range(10, 20, 30, 1), (10, 0));
+    assert_eq!(slice_range(10, 8, 2, -2), (8, 3));
+    assert_eq!(slice_range(0, 0, 5, -1), (0, 0));
+}
+
+#[test]
+fn test_gather_inference() {
+    let table = f32_type(vec![1000, 64]);
+    let ids = create_test_tensor_type(DType::I32, vec![2, 16]);
+
+    assert_eq!(
+        infer(OpKind::Gather { axis: 0 }, vec![table.clone(), ids]).unwrap(),
+        f32_type(vec![2, 16, 64])
+    );
+
+    let float_ids = f32_type(vec![2]);
+    assert!(infer(OpKind::Gather { axis: 0 }, vec![table, float_ids]).is_err());
+}
+
+#[test]
+fn test_view_and_copy_classification() {
+    for op in [
+        OpKind::Reshape { shape: vec![-1] },
+        OpKind::Transpose { perm: vec![] },
+        OpKind::Split {
+            axis: 0,
+            sizes: vec![1],
+        },
+        slice(vec![0], vec![1], vec![], vec![]),
+    ] {
+        assert!(op.is_view(), "{}", op.name());
+        assert_eq!(op.category(), OpCategory::Movement);
+    }
+    for op in [
+        OpKind::Concat { axis: 0 },
+        OpKind::Gather { axis: 0 },
+        OpKind::Add,
+    ] {
+        assert!(!op.is_view(), "{}", op.name());
+    }
+}
+
+#[test]
+fn test_view_layouts() {
+    let input = f32_type(vec![4, 6]);
+
+    let transposed = OpKind::Transpose { perm: vec![1, 0] }
+        .view_layouts(&input)
+        .unwrap();
+    assert_eq!(transposed, vec![TensorLayout::strided(vec![1, 6], 0)]);
+    let transposed_type = f32_type(vec![6, 4])
+        .with_layout(transposed[0].clone())
+        .unwrap();
+    assert_eq!(
+        transposed[0].memory_format(transposed_type.shape()),
+        MemoryFormat::ColumnMajor
+    );
+
+    let sliced = slice(vec![1, 0], vec![3, 6], vec![], vec![1, 2])
+        .view_layouts(&input)
+        .unwrap();
+    assert_eq!(sliced, vec![TensorLayout::strided(vec![6, 2], 6)]);
+
+    let pieces = OpKind::Split {
+        axis: 0,
+        sizes: vec![1, 3],
+    }
+    .view_layouts(&input)
+    .unwrap();
+    assert_eq!(
+        pieces,
+        vec![
+            TensorLayout::strided(vec![6, 1], 0),
+            TensorLayout::strided(vec![6, 1], 6)
+        ]
+    );
+
+    // Sizes that miss or overrun the split dimension have no views
+    for sizes in [vec![1, 2], vec![3, 3], vec![usize::MAX, 5]] {
+        assert!(
+            OpKind::Split { axis: 0, sizes }
+                .view_layouts(&input)
+                .is_none()
+        );
+    }
+
+    let reshape = OpKind::Reshape { shape: vec![2, 12] };
+    assert_eq!(
+        reshape.view_layouts(&input).unwrap(),
+        vec![TensorLayout::strided(vec![12, 1], 0)]
+    );
+
+    // Reshaping a transposed buffer or reading backwards needs a copy
+    assert!(reshape.view_layouts(&transposed_type).is_none());
+    assert!(
+        slice(vec![-1], vec![i64::MIN], vec![], vec![-1])
+            .view_layouts(&input)
+            .is_none()
+    );
+    assert!(OpKind::Concat { axis: 0 }.view_layouts(&input).is_none());
+}
+
+#[test]
+fn test_movement_attributes() {
+    let ops = [
+        OpKind::Reshape { shape: vec![0, -1] },
+        OpKind::Transpose {
+            perm: vec![2, 0, 1],
+        },
+        OpKind::Concat { axis: 1 },
+        OpKind::Split {
+            axis: -1,
+            sizes: vec![3, 3],
+        },
+        slice(vec![0], vec![-1], vec![1], vec![2]),
+        OpKind::Gather { axis: 1 },
+    ];
+    for op in ops {
+        assert_eq!(OpKind::from_parts(op.name(), &op.attributes()).unwrap(), op);
+    }
+
+    let negative_perm = OpKind::Transpose { perm: vec![1, 0] }.attributes().with(
+        "perm",
+        xyntra::ir::attributes::AttributeValue::Ints(vec![-1, 0]),
+    );
+    assert!(OpKind::from_parts("Transpose", &negative_perm).is_err());
+    assert_eq!(
+        OpKind::from_parts("Transpose", &Attributes::new()).unwrap(),
+        OpKind::Transpose { perm: vec![] }
+    );
+
+    let mut graph = Graph::new();
+    let x = graph.add_node(OpKind::custom("Input"), vec![]);
+    graph.add_node(
+        OpKind::Reshape {
+            shape: vec![-1, -1],
+        },
+        vec![x],
+    );
+    graph.add_node(slice(vec![0, 0], vec![1], vec![], vec![]), vec![x]);
+    graph.add_node(slice(vec![0], vec![1], vec![], vec![0]), vec![x]);
+    assert_eq!(
+        GraphValidator::new(&graph)
+            .validate_operation_constraints()
+            .unwrap_err()
+            .len(),
+        3
+    );
+}
@@ -0,0 +1,209 @@
+mod common;
+
+use common::{build_complex_graph, create_test_tensor_type};
+use xyntra::ir::{
+    errors::ValidationError,
+    graph::Graph,
+    types::{DType, NodeID, OpKind},
+    validation::GraphValidator,
+};
+
+/// input -> relu -> exp, with exp as the graph output.
+fn chain_graph() -> (Graph, NodeID, NodeID, NodeID) {
+    let mut graph = Graph::new();
+    let input = graph.add_input("x", create_test_tensor_type(DType::F32, vec![4]));
+    let relu = graph.add_node(OpKind::Relu, vec![input]);
+    let exp = graph.add_node(OpKind::Exp, vec![relu]);
+    graph.add_output("y", exp).unwrap();
+    (graph, input, relu, exp)
+}
+
+#[test]
+fn test_remove_unused_node() {
+    let (mut graph, input, _, _) = chain_graph();
+    let dead = graph.add_node(OpKind::Sigmoid, vec![input]);
+
+    let removed = graph.remove_node(dead).unwrap();
+
+    assert_eq!(removed.op(), &OpKind::Sigmoid);
+    assert!(!graph.contains_node(dead));
+    assert_eq!(graph.len(), 3);
+    assert!(!graph.get_node(input).unwrap().outputs().contains(&dead));
+    assert!(GraphValidator::new(&graph).validate().is_ok());
+}
+
+#[test]
+fn test_remove_rejects_dangling_references() {
+    let (mut graph, input, relu, exp) = chain_graph();
+
+    assert!(matches!(
+        graph.remove_node(relu),
+        Err(ValidationError::InvalidNodeConnection { from, to, .. })
+            if from == relu.id() && to == exp.id()
+    ));
+    assert!(matches!(
+        graph.remove_node(exp),
+        Err(ValidationError::InvalidGraphBoundary { name, .. }) if name == "y"
+    ));
+    assert!(matches!(
+        graph.remove_node(NodeID::new(99)),
+        Err(ValidationError::MissingNode { node_id: 99 })
+    ));
+    assert!(graph.contains_node(input));
+    assert_eq!(graph.len(), 3);
+}
+
+#[test]
+fn test_replace_all_uses_with() {
+    let (mut graph, input, relu, exp) = chain_graph();
+    let sigmoid = graph.add_node(OpKind::Sigmoid, vec![input]);
+    let add = graph.add_node(OpKind::Add, vec![relu, relu]);
+
+    let rewritten = graph
+        .replace_all_uses_with(relu.output(0), sigmoid.output(0))
+        .unwrap();
+
+    assert_eq!(rewritten, 3);
+    assert_eq!(
+        graph.get_node(exp).unwrap().inputs(),
+        &vec![sigmoid.output(0)]
+    );
+    assert_eq!(
+        graph.get_node(add).unwrap().inputs(),
+        &vec![sigmoid.output(0), sigmoid.output(0)]
+    );
+    assert!(graph.get_node(relu).unwrap().outputs().is_empty());
+
+    // The old node is now unused and can go
+    graph.remove_node(relu).unwrap();
+    assert!(GraphValidator::new(&graph).validate().is_ok());
+}
+
+#[test]
+fn test_replace_all_uses_updates_graph_outputs() {
+    let (mut graph, _, relu, exp) = chain_graph();
+
+    assert_eq!(
+        graph
+            .replace_all_uses_with(exp.output(0), relu.output(0))
+            .unwrap(),
+        1
+    );
+    assert_eq!(graph.outputs()[0].node(), relu);
+
+    graph.remove_node(exp).unwrap();
+    assert!(GraphValidator::new(&graph).validate().is_ok());
+}
+
+#[test]
+fn test_replace_all_uses_with_function_of_old() {
+    let (mut graph, _, relu, exp) = chain_graph();
+    let sigmoid = graph.add_node(OpKind::Sigmoid, vec![relu]);
+
+    // Replacing relu with exp(relu) must leave exp reading relu
+    assert_eq!(
+        graph
+            .replace_all_uses_with(relu.output(0), exp.output(0))
+            .unwrap(),
+        1
+    );
+    assert_eq!(graph.get_node(exp).unwrap().inputs(), &vec![relu.output(0)]);
+    assert_eq!(
+        graph.get_node(sigmoid).unwrap().inputs(),
+        &vec![exp.output(0)]
+    );
+    assert!(GraphValidator::new(&graph).validate().is_ok());
+}
+
+#[test]
+fn test_insert_between() {
+    let (mut graph, input, relu, exp) = chain_graph();
+
+    let cast = graph
+        .insert_between(relu.output(0), exp, OpKind::Cast { to: DType::F16 })
+        .unwrap();
+
+    assert_eq!(
+        graph.get_node(cast).unwrap().inputs(),
+        &vec![relu.output(0)]
+    );
+    assert_eq!(graph.get_node(exp).unwrap().inputs(), &vec![cast.output(0)]);
+    assert_eq!(graph.get_node(relu).unwrap().outputs(), &vec![cast]);
+    assert_eq!(graph.get_node(cast).unwrap().outputs(), &vec![exp]);
+    assert_eq!(
+        graph.topological_order().unwrap(),
+        vec![input, relu, cast, exp]
+    );
+    assert!(GraphValidator::new(&graph).validate().is_ok());
+
+    assert!(matches!(
+        graph.insert_between(input.output(0), exp, OpKind::Relu),
+        Err(ValidationError::InvalidNodeConnection { .. })
+    ));
+}
+
+#[test]
+fn test_set_inputs() {
+    let (mut graph, input, relu, exp) = chain_graph();
+
+    graph.set_inputs(exp, vec![input.output(0)]).unwrap();
+
+    assert_eq!(
+        graph.get_node(exp).unwrap().inputs(),
+        &vec![input.output(0)]
+    );
+    assert!(graph.get_node(input).unwrap().outputs().contains(&exp));
+    assert!(!graph.get_node(relu).unwrap().outputs().contains(&exp));
+
+    assert!(matches!(
+        graph.set_inputs(exp, vec![NodeID::new(42).output(0)]),
+        Err(ValidationError::MissingNode { node_id: 42 })
+    ));
+    // A failed call leaves the node untouched
+    assert_eq!(
+        graph.get_node(exp).unwrap().inputs(),
+        &vec![input.output(0)]
+    );
+}
+
+#[test]
+fn test_set_inputs_cycle_is_reported_by_validator() {
+    let (mut graph, _, relu, exp) = chain_graph();
+
+    graph.set_inputs(relu, vec![exp.output(0)]).unwrap();
+
+    assert!(matches!(
+        GraphValidator::new(&graph)
+            .detect_cycles()
+            .unwrap_err()
+            .as_slice(),
+        [ValidationError::CyclicGraph { .. }]
+    ));
+}
+
+#[test]
+fn test_replace_op() {
+    let mut graph = build_complex_graph();
+    let gelu = NodeID::new(3);
+
+    let old = graph.replace_op(gelu, OpKind::Relu).unwrap();
+
+    assert_eq!(old, OpKind::Gelu);
+    assert_eq!(graph.get_node(gelu).unwrap().op(), &OpKind::Relu);
+    assert!(graph.get_node(gelu).unwrap().output_types().is_empty());
+    assert!(GraphValidator::new(&graph).validate().is_ok());
+
+    assert!(matches!(
+        graph.replace_op(NodeID::new(0), OpKind::Relu),
+        Err(ValidationError::InvalidGraphBoundary { .. })
+    ));
+    assert!(matches!(
+        graph.replace_op(
+            gelu,
+            OpKind::Constant {
+                name: "c".to_string()
+            }
+        ),
+        Err(ValidationError::InvalidGraphBoundary { .. })
+    ));
+}
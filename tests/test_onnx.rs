@@ -0,0 +1,488 @@
+mod common;
+
+use common::{fixture, fixture_path, import_error, ops};
+use xyntra::{
+    ir::{
+        errors::{ParsingError, SystemError, XyntraError},
+        graph::Graph,
+        shape_inference::ShapeInference,
+        symbolic::Dim,
+        text::print_graph,
+        types::{DType, OpKind, ReduceKind, TensorShape, TensorType, Window2d},
+        validation::GraphValidator,
+    },
+    onnx::{
+        import::{import_model, import_onnx, load_onnx},
+        opset::SUPPORTED_OPSETS,
+        proto::{
+            AttributeProto, Dimension, GraphProto, ModelProto, NodeProto, attribute_type, data_type,
+        },
+    },
+};
+
+fn with_opset(name: &str, version: i64) -> ModelProto {
+    let mut model = ModelProto::decode(&fixture("onnx", name)).unwrap();
+    model.opset_import[0].version = version;
+    model
+}
+
+#[test]
+fn test_decode_model_proto() {
+    let model = ModelProto::decode(&fixture("onnx", "mlp.onnx")).unwrap();
+
+    assert_eq!(model.ir_version, 8);
+    assert_eq!(model.producer_name, "xyntra-fixtures");
+    assert_eq!(model.opset_import.len(), 1);
+    assert_eq!(model.opset_import[0].version, 13);
+
+    let graph = model.graph.unwrap();
+    assert_eq!(graph.name, "mlp");
+    assert_eq!(graph.node.len(), 4);
+    assert_eq!(graph.node[0].op_type, "Gemm");
+    assert_eq!(graph.node[0].input, ["x", "w1", "b1"]);
+    assert_eq!(graph.node[3].attribute[0].name, "axis");
+    assert_eq!(graph.node[3].attribute[0].i, -1);
+
+    let w1 = &graph.initializer[0];
+    assert_eq!(w1.dims, [4, 8]);
+    assert_eq!(w1.data_type, data_type::FLOAT);
+    assert_eq!(w1.raw_data.len(), 4 * 8 * 4);
+}
+
+#[test]
+fn test_import_mlp() {
+    let graph = load_onnx(fixture_path("onnx", "mlp.onnx")).unwrap();
+
+    // Gemm becomes MatMul plus Add; the unused initializer is dropped
+    assert_eq!(
+        ops(&graph),
+        [
+            "Input", "Constant", "Constant", "MatMul", "Add", "Relu", "Constant", "MatMul",
+            "Softmax"
+        ]
+    );
+    assert_eq!(graph.initializers().len(), 3);
+    assert!(graph.find_value("unused").is_none());
+
+    let x = graph.inputs()[0];
+    assert_eq!(
+        graph.output_type(x),
+        Some(&TensorType::new(
+            DType::F32,
+            TensorShape::from_dims(vec![Dim::symbol("batch"), Dim::from(4)])
+        ))
+    );
+    let w2 = graph.initializer(graph.find_value("w2").unwrap()).unwrap();
+    assert_eq!(w2.to_f32_vec()[..4], [1.0, -1.0, 1.0, -1.0]);
+
+    let output = graph.get_node(graph.outputs()[0].node()).unwrap();
+    assert_eq!(graph.outputs()[0].name(), "y");
+    assert_eq!(output.op(), &OpKind::Softmax { axis: -1 });
+    assert_eq!(
+        output.output_type().unwrap().to_string(),
+        "f32[batch, 2]".to_string()
+    );
+    assert!(GraphValidator::new(&graph).validate().is_ok());
+}
+
+#[test]
+fn test_constant_inputs_fold_into_attributes() {
+    let mut graph = import_onnx(&fixture("onnx", "attention_prep.onnx")).unwrap();
+
+    let op_of = |graph: &Graph, name: &str| {
+        graph
+            .nodes()
+            .find(|node| node.op().name() == name)
+            .unwrap()
+            .op()
+            .clone()
+    };
+    assert_eq!(
+        op_of(&graph, "Reshape"),
+        OpKind::Reshape {
+            shape: vec![0, 0, 2, 4]
+        }
+    );
+    assert_eq!(
+        op_of(&graph, "Split"),
+        OpKind::Split {
+            axis: 3,
+            sizes: vec![3, 1]
+        }
+    );
+    // Slice bounds keep their INT64 range
+    assert_eq!(
+        op_of(&graph, "Slice"),
+        OpKind::Slice {
+            starts: vec![1],
+            ends: vec![i64::MAX],
+            axes: vec![-1],
+            steps: vec![],
+        }
+    );
+    assert_eq!(op_of(&graph, "Dropout"), OpKind::Dropout { ratio: 0.1 });
+    assert_eq!(
+        op_of(&graph, "ReduceMean"),
+        OpKind::Reduce {
+            kind: ReduceKind::Mean,
+            axes: vec![-1],
+            keepdims: false
+        }
+    );
+
+    // Only the LayerNorm parameters are read as data
+    let constants: Vec<String> = graph
+        .nodes()
+        .filter_map(|node| match node.op() {
+            OpKind::Constant { name } => Some(name.clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(constants, ["gamma", "beta"]);
+
+    // Identity is bypassed and the second Split output feeds the reduction
+    let cast = graph.get_node(graph.outputs()[0].node()).unwrap();
+    assert_eq!(cast.op(), &OpKind::Cast { to: DType::F32 });
+    let reduce = graph.get_node(graph.outputs()[1].node()).unwrap();
+    assert_eq!(reduce.inputs()[0].port(), 1);
+
+    ShapeInference::new(&mut graph).run().unwrap();
+    let text = print_graph(&graph);
+    assert!(text.contains(
+        "Slice(%6) {axes = [-1], ends = [9223372036854775807], starts = [1], steps = []} \
+         : f32[1, 2, seq, 2]"
+    ));
+}
+
+#[test]
+fn test_import_conv_block() {
+    let mut graph = import_onnx(&fixture("onnx", "conv_block.onnx")).unwrap();
+
+    let conv = graph
+        .nodes()
+        .find(|node| node.op().name() == "Conv2d")
+        .unwrap();
+    assert_eq!(
+        conv.op(),
+        &OpKind::Conv2d {
+            window: Window2d {
+                strides: [2, 2],
+                pads: [1, 1, 1, 1],
+                dilations: [1, 1],
+            },
+            groups: 1,
+        }
+    );
+    assert_eq!(conv.inputs().len(), 3);
+    assert!(ops(&graph).ends_with(&[
+        "BatchNorm".to_string(),
+        "Relu".to_string(),
+        "MaxPool".to_string(),
+        "GlobalAvgPool".to_string(),
+        "Reshape".to_string(),
+    ]));
+
+    ShapeInference::new(&mut graph).run().unwrap();
+    let pooled = graph
+        .nodes()
+        .find(|node| node.op().name() == "MaxPool")
+        .unwrap();
+    assert_eq!(pooled.output_type().unwrap().to_string(), "f32[n, 4, 4, 4]");
+}
+
+#[test]
+fn test_unknown_ops_are_reported() {
+    match import_error(import_onnx(&fixture("onnx", "unknown_op.onnx"))) {
+        ParsingError::UnsupportedOperation { op_name } => assert_eq!(op_name, "Einsum"),
+        other => panic!("expected UnsupportedOperation, got {other:?}"),
+    }
+    match import_error(import_onnx(&fixture("onnx", "custom_domain.onnx"))) {
+        ParsingError::UnsupportedOperation { op_name } => {
+            assert_eq!(op_name, "com.microsoft.FusedGelu")
+        }
+        other => panic!("expected UnsupportedOperation, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_unmodelled_outputs_fail_only_when_used() {
+    // Dropout's mask is declared but unread in attention_prep.onnx
+    assert!(import_onnx(&fixture("onnx", "attention_prep.onnx")).is_ok());
+
+    match import_error(import_onnx(&fixture("onnx", "mask_output.onnx"))) {
+        ParsingError::InvalidFormat { format, reason } => {
+            assert_eq!(format, "onnx");
+            assert_eq!(
+                reason,
+                "graph output 'mask' uses 'mask', output 1 of Dropout node 'drop', \
+                 which Xyntra does not model"
+            );
+        }
+        other => panic!("expected InvalidFormat, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_malformed_files() {
+    let bytes = fixture("onnx", "mlp.onnx");
+    match import_error(import_onnx(&bytes[..bytes.len() - 3])) {
+        ParsingError::MalformedOnnx { details } => {
+            assert!(details.starts_with("ModelProto: "), "{details}")
+        }
+        other => panic!("expected MalformedOnnx, got {other:?}"),
+    }
+
+    match import_error(import_onnx(&[])) {
+        ParsingError::MissingRequiredField { field } => assert_eq!(field, "ModelProto.graph"),
+        other => panic!("expected MissingRequiredField, got {other:?}"),
+    }
+
+    // Shapes whose byte size does not fit in memory
+    let mut model = ModelProto::decode(&bytes).unwrap();
+    let w1 = &mut model.graph.as_mut().unwrap().initializer[0];
+    w1.dims = vec![1 << 62];
+    w1.raw_data = vec![0];
+    match import_error(import_model(&model)) {
+        ParsingError::MalformedOnnx { details } => {
+            assert!(
+                details.ends_with("has shape [4611686018427387904]"),
+                "{details}"
+            )
+        }
+        other => panic!("expected MalformedOnnx, got {other:?}"),
+    }
+
+    // Subgraphs nested past protobuf's recursion limit
+    let nested = |depth: usize| {
+        let mut graph = GraphProto::default();
+        for _ in 0..depth {
+            graph = GraphProto {
+                node: vec![NodeProto {
+                    attribute: vec![AttributeProto {
+                        attribute_type: attribute_type::GRAPH,
+                        g: Some(graph),
+                        ..AttributeProto::default()
+                    }],
+                    ..NodeProto::default()
+                }],
+                ..GraphProto::default()
+            };
+        }
+        ModelProto {
+            graph: Some(graph),
+            ..ModelProto::default()
+        }
+        .encode()
+    };
+    assert!(ModelProto::decode(&nested(30)).is_ok());
+    match ModelProto::decode(&nested(40)) {
+        Err(ParsingError::MalformedOnnx { details }) => {
+            assert_eq!(details, "GraphProto: messages nest deeper than 100 levels")
+        }
+        other => panic!("expected MalformedOnnx, got {other:?}"),
+    }
+
+    match load_onnx("tests/data/onnx/missing.onnx") {
+        Err(XyntraError::System(SystemError::FileNotFound { path })) => {
+            assert!(path.ends_with("missing.onnx"))
+        }
+        other => panic!("expected FileNotFound, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_opset_versions_import_to_the_same_graph() {
+    let legacy = import_onnx(&fixture("onnx", "legacy_opset9.onnx")).unwrap();
+    let modern =
+        import_model(&ModelProto::decode(&fixture("onnx", "modern_opset18.onnx")).unwrap())
+            .unwrap();
+
+    assert_eq!(print_graph(&legacy), print_graph(modern.graph()));
+    let text = print_graph(&legacy);
+    // Softmax defaults to axis 1 before opset 13, the last axis of this input
+    assert!(text.contains("Softmax(%0) {axis = 1}"), "{text}");
+    assert!(text.contains("Clip(%3) {max = 6.0, min = 0.0}"), "{text}");
+    assert!(text.contains("Dropout(%4) {ratio = 0.25}"), "{text}");
+    assert!(
+        text.contains("ReduceSum(%2#1) {axes = [1], keepdims = 1}"),
+        "{text}"
+    );
+
+    assert_eq!(modern.opsets().onnx_version(), Some(18));
+    assert_eq!(modern.opsets().iter().collect::<Vec<_>>(), [("", 18)]);
+}
+
+#[test]
+fn test_unsupported_opsets_are_rejected() {
+    for version in [*SUPPORTED_OPSETS.start() - 1, *SUPPORTED_OPSETS.end() + 1] {
+        match import_error(import_model(&with_opset("mlp.onnx", version))) {
+            ParsingError::InvalidFormat { format, reason } => {
+                assert_eq!(format, "onnx");
+                assert_eq!(
+                    reason,
+                    format!(
+                        "opset {version} of the default domain is not supported; \
+                         Xyntra reads opsets 7 to 21"
+                    )
+                );
+            }
+            other => panic!("expected InvalidFormat, got {other:?}"),
+        }
+    }
+
+    let mut model = with_opset("mlp.onnx", 13);
+    model.opset_import.clear();
+    match import_error(import_model(&model)) {
+        ParsingError::MissingRequiredField { field } => {
+            assert_eq!(field, "ModelProto.opset_import")
+        }
+        other => panic!("expected MissingRequiredField, got {other:?}"),
+    }
+
+    let mut model = with_opset("mlp.onnx", 13);
+    model.opset_import.push(model.opset_import[0].clone());
+    model.opset_import[1].domain = "ai.onnx".to_string();
+    assert!(matches!(
+        import_error(import_model(&model)),
+        ParsingError::MalformedOnnx { .. }
+    ));
+
+    // Ops newer than the imported opset and domains that are not imported
+    match import_error(import_model(&with_opset("attention_prep.onnx", 16))) {
+        ParsingError::MalformedOnnx { details } => assert_eq!(
+            details,
+            "LayerNormalization node producing 'normed' uses an op introduced in opset 17, \
+             but the model imports opset 16"
+        ),
+        other => panic!("expected MalformedOnnx, got {other:?}"),
+    }
+    let mut model = with_opset("custom_domain.onnx", 13);
+    model.opset_import.truncate(1);
+    match import_error(import_model(&model)) {
+        ParsingError::MalformedOnnx { details } => assert_eq!(
+            details,
+            "FusedGelu node producing 'y' is in domain 'com.microsoft', \
+             which the model does not import"
+        ),
+        other => panic!("expected MalformedOnnx, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_parameters_must_match_the_opset() {
+    // Split sizes became an input in opset 13
+    match import_error(import_model(&with_opset("modern_opset18.onnx", 12))) {
+        ParsingError::MalformedOnnx { details } => assert_eq!(
+            details,
+            "Split node producing 'head' passes 'split' as input 1, but in opset 12 it is \
+             an attribute (an input only since opset 13)"
+        ),
+        other => panic!("expected MalformedOnnx, got {other:?}"),
+    }
+    match import_error(import_model(&with_opset("legacy_opset9.onnx", 12))) {
+        ParsingError::MalformedOnnx { details } => assert_eq!(
+            details,
+            "Slice node producing 'window' has a 'starts' attribute, but in opset 12 it is \
+             an input (since opset 10)"
+        ),
+        other => panic!("expected MalformedOnnx, got {other:?}"),
+    }
+
+    // ReduceMean kept its axes attribute until opset 18
+    assert!(import_model(&with_opset("attention_prep.onnx", 17)).is_ok());
+    assert!(matches!(
+        import_error(import_model(&with_opset("attention_prep.onnx", 18))),
+        ParsingError::MalformedOnnx { .. }
+    ));
+}
+
+#[test]
+fn test_split_num_outputs_must_match_the_outputs() {
+    let split_with = |num_outputs: i64| {
+        let mut model = ModelProto::decode(&fixture("onnx", "modern_opset18.onnx")).unwrap();
+        let graph = model.graph.as_mut().unwrap();
+        let split = graph
+            .node
+            .iter_mut()
+            .find(|node| node.op_type == "Split")
+            .unwrap();
+        split.input.truncate(1);
+        split.attribute.push(AttributeProto {
+            name: "num_outputs".to_string(),
+            attribute_type: attribute_type::INT,
+            i: num_outputs,
+            ..AttributeProto::default()
+        });
+        model
+    };
+
+    let model = import_model(&split_with(2)).unwrap();
+    let split = model
+        .graph()
+        .nodes()
+        .find(|node| node.op().name() == "Split")
+        .unwrap();
+    assert_eq!(
+        split.op(),
+        &OpKind::Split {
+            axis: 1,
+            sizes: vec![3, 3]
+        }
+    );
+
+    for num_outputs in [3, i64::MAX] {
+        match import_error(import_model(&split_with(num_outputs))) {
+            ParsingError::MalformedOnnx { details } => assert_eq!(
+                details,
+                format!("Split node producing 'head' has num_outputs {num_outputs} but 2 outputs")
+            ),
+            other => panic!("expected MalformedOnnx, got {other:?}"),
+        }
+    }
+}
+
+#[test]
+fn test_unknown_dims_do_not_reuse_declared_names() {
+    let mut model = ModelProto::decode(&fixture("onnx", "modern_opset18.onnx")).unwrap();
+    let graph = model.graph.as_mut().unwrap();
+    for (info, dim) in [
+        (&mut graph.input[0], Dimension::Unknown),
+        (&mut graph.output[0], Dimension::Param("unk_0".to_string())),
+    ] {
+        let shape = info
+            .value_type
+            .as_mut()
+            .and_then(|value_type| value_type.tensor_type.as_mut())
+            .and_then(|tensor_type| tensor_type.shape.as_mut())
+            .unwrap();
+        shape.dim[0] = dim;
+    }
+
+    let imported = import_model(&model).unwrap();
+    let x = imported.graph().find_value("x").unwrap();
+    assert_eq!(
+        imported.graph().output_type(x).unwrap().shape().dims()[0],
+        Dim::symbol("unk_1")
+    );
+}
+
+#[test]
+fn test_legacy_softmax_over_several_axes_is_rejected() {
+    let mut model = ModelProto::decode(&fixture("onnx", "legacy_opset9.onnx")).unwrap();
+    let graph = model.graph.as_mut().unwrap();
+    graph.node[0].attribute.push(AttributeProto {
+        name: "axis".to_string(),
+        attribute_type: attribute_type::INT,
+        i: 0,
+        ..AttributeProto::default()
+    });
+
+    match import_error(import_model(&model)) {
+        ParsingError::InvalidFormat { reason, .. } => assert_eq!(
+            reason,
+            "Softmax node producing 'probs': Softmax before opset 13 normalises over every \
+             dimension from axis 0, which a rank 2 input cannot do on one axis"
+        ),
+        other => panic!("expected InvalidFormat, got {other:?}"),
+    }
+}
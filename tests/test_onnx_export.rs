@@ -0,0 +1,357 @@
+mod common;
+
+use common::{f32_type, fixture};
+use xyntra::{
+    fusion::candidates::find_candidates,
+    ir::{
+        attributes::{AttributeValue, Attributes},
+        errors::ValidationError,
+        graph::Graph,
+        symbolic::Dim,
+        text::print_graph,
+        types::{DType, OpKind, ReduceKind, TensorShape, TensorType, Window2d},
+        visualize::NodeGroup,
+    },
+    onnx::{
+        export::{export_model, export_onnx, save_onnx},
+        import::{import_onnx, load_onnx},
+        opset::XYNTRA_DOMAIN,
+        proto::{Dimension, ModelProto},
+    },
+};
+
+fn export_error(graph: &Graph, groups: &[NodeGroup]) -> ValidationError {
+    match export_model(graph, groups) {
+        Err(error) => error,
+        Ok(_) => panic!("expected the export to fail"),
+    }
+}
+
+fn op_types(model: &ModelProto) -> Vec<&str> {
+    let graph = model.graph.as_ref().unwrap();
+    graph
+        .node
+        .iter()
+        .map(|node| node.op_type.as_str())
+        .collect()
+}
+
+#[test]
+fn test_encoding_round_trips() {
+    for name in ["mlp.onnx", "attention_prep.onnx", "conv_block.onnx"] {
+        let model = ModelProto::decode(&fixture("onnx", name)).unwrap();
+        assert_eq!(
+            ModelProto::decode(&model.encode()).unwrap(),
+            model,
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn test_exported_models_import_to_the_same_graph() {
+    for name in [
+        "attention_prep.onnx",
+        "conv_block.onnx",
+        "legacy_opset9.onnx",
+    ] {
+        let graph = import_onnx(&fixture("onnx", name)).unwrap();
+        let bytes = export_onnx(&graph, &[]).unwrap();
+        let reimported = import_onnx(&bytes).unwrap();
+        assert_eq!(print_graph(&reimported), print_graph(&graph), "{name}");
+    }
+}
+
+#[test]
+fn test_symbolic_dims_round_trip_only_as_names() {
+    let mut graph = Graph::new();
+    let padded = Dim::symbol("seq") * 2 + Dim::from(1);
+    let x = graph.add_input(
+        "x",
+        TensorType::new(
+            DType::F32,
+            TensorShape::from_dims(vec![Dim::symbol("batch"), padded]),
+        ),
+    );
+    let relu = graph.add_node(OpKind::Relu, vec![x]);
+    graph.add_output("y", relu).unwrap();
+
+    let model = export_model(&graph, &[]).unwrap();
+    let input = &model.graph.as_ref().unwrap().input[0];
+    let shape = input
+        .value_type
+        .as_ref()
+        .and_then(|value_type| value_type.tensor_type.as_ref())
+        .and_then(|tensor_type| tensor_type.shape.as_ref())
+        .unwrap();
+    assert_eq!(
+        shape.dim,
+        [Dimension::Param("batch".to_string()), Dimension::Unknown]
+    );
+
+    // The expression comes back as a fresh symbol, not one named "2*seq + 1"
+    let reimported = import_onnx(&model.encode()).unwrap();
+    let x = reimported.find_value("x").unwrap();
+    let dims = reimported.output_type(x).unwrap().shape().dims().to_vec();
+    assert_eq!(dims[0], Dim::symbol("batch"));
+    assert_ne!(dims[1], Dim::symbol("2*seq + 1"));
+    assert!(dims[1].to_expr().symbols().all(|name| name != "seq"));
+}
+
+#[test]
+fn test_export_mlp() {
+    let graph = import_onnx(&fixture("onnx", "mlp.onnx")).unwrap();
+    let model = export_model(&graph, &[]).unwrap();
+
+    assert_eq!(model.opset_import.len(), 1);
+    assert_eq!(model.opset_import[0].version, 18);
+    assert!(model.functions.is_empty());
+    assert_eq!(
+        op_types(&model),
+        ["MatMul", "Add", "Relu", "MatMul", "Softmax"]
+    );
+
+    let main = model.graph.unwrap();
+    assert_eq!(main.input.len(), 1);
+    assert_eq!(main.input[0].name, "x");
+    let initializers: Vec<&str> = main.initializer.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(initializers, ["w1", "b1", "w2"]);
+    assert_eq!(main.output[0].name, "y");
+    assert_eq!(main.node[4].output, ["y"]);
+    // Intermediate values are named after their node and declared
+    assert_eq!(main.node[0].name, "MatMul_3");
+    assert_eq!(main.node[1].input, ["MatMul_3", "b1"]);
+    assert!(main.value_info.iter().any(|info| info.name == "Relu_5"));
+
+    // Importing Gemm reads its bias before adding the MatMul, so only the
+    // second round trip numbers the nodes like the first
+    let once = import_onnx(&export_onnx(&graph, &[]).unwrap()).unwrap();
+    let twice = import_onnx(&export_onnx(&once, &[]).unwrap()).unwrap();
+    assert_eq!(print_graph(&twice), print_graph(&once));
+}
+
+#[test]
+fn test_fused_groups_become_functions() {
+    let graph = import_onnx(&fixture("onnx", "conv_block.onnx")).unwrap();
+    let groups: Vec<NodeGroup> = find_candidates(&graph)
+        .iter()
+        .map(NodeGroup::from)
+        .collect();
+    assert_eq!(groups.len(), 1);
+
+    let model = export_model(&graph, &groups).unwrap();
+    assert!(
+        model
+            .opset_import
+            .iter()
+            .any(|opset| opset.domain == XYNTRA_DOMAIN)
+    );
+    assert_eq!(
+        op_types(&model),
+        [
+            "ConvBnRelu_0",
+            "MaxPool",
+            "GlobalAveragePool",
+            "Constant",
+            "Reshape"
+        ]
+    );
+
+    let call = &model.graph.as_ref().unwrap().node[0];
+    assert_eq!(call.domain, XYNTRA_DOMAIN);
+    assert_eq!(
+        call.input,
+        ["image", "kernel", "bias", "scale", "shift", "mean", "var"]
+    );
+
+    assert_eq!(model.functions.len(), 1);
+    let function = &model.functions[0];
+    assert_eq!(function.name, "ConvBnRelu_0");
+    assert_eq!(function.domain, XYNTRA_DOMAIN);
+    assert_eq!(function.input, call.input);
+    assert_eq!(function.output, call.output);
+    let body: Vec<&str> = function
+        .node
+        .iter()
+        .map(|node| node.op_type.as_str())
+        .collect();
+    assert_eq!(body, ["Conv", "BatchNormalization", "Relu"]);
+
+    // Re-importing inlines the function back into the original nodes
+    let reimported = import_onnx(&model.encode()).unwrap();
+    assert_eq!(print_graph(&reimported), print_graph(&graph));
+}
+
+#[test]
+fn test_ops_without_onnx_counterpart() {
+    let mut graph = Graph::new();
+    let x = graph.add_input("x", f32_type(vec![2, 3, 4]));
+    let w = graph.add_input("w", f32_type(vec![2, 5, 4]));
+    let rsqrt = graph.add_node(OpKind::Rsqrt, vec![x]);
+    let matmul = graph.add_node(
+        OpKind::MatMul {
+            transpose_a: false,
+            transpose_b: true,
+        },
+        vec![rsqrt, w],
+    );
+    let argmax = graph.add_node(
+        OpKind::ArgMax {
+            axis: -1,
+            keepdims: false,
+        },
+        vec![matmul],
+    );
+    let scale = graph.add_node(
+        OpKind::Custom {
+            name: "Scale".to_string(),
+            attributes: Attributes::new().with("factor", AttributeValue::Float(2.0)),
+        },
+        vec![matmul],
+    );
+    graph
+        .set_output_types(scale, vec![f32_type(vec![2, 3, 5])])
+        .unwrap();
+    graph.add_output("indices", argmax).unwrap();
+    graph.add_output("scaled", scale).unwrap();
+
+    let model = export_model(&graph, &[]).unwrap();
+    assert_eq!(
+        op_types(&model),
+        [
+            "Sqrt",
+            "Constant",
+            "Div",
+            "Transpose",
+            "MatMul",
+            "ArgMax",
+            "Cast",
+            "Scale"
+        ]
+    );
+    let main = model.graph.as_ref().unwrap();
+    assert_eq!(main.node[3].attribute[0].ints, [0, 2, 1]);
+    assert_eq!(main.node[7].domain, XYNTRA_DOMAIN);
+
+    let reimported = import_onnx(&model.encode()).unwrap();
+    let ops: Vec<&str> = reimported.nodes().map(|node| node.op().name()).collect();
+    assert_eq!(
+        ops,
+        [
+            "Input",
+            "Input",
+            "Sqrt",
+            "Constant",
+            "Div",
+            "Transpose",
+            "MatMul",
+            "ArgMax",
+            "Cast",
+            "Scale"
+        ]
+    );
+    let scale = reimported.nodes().last().unwrap();
+    let OpKind::Custom { attributes, .. } = scale.op() else {
+        panic!("expected a custom op");
+    };
+    assert_eq!(attributes.get("factor"), Some(&AttributeValue::Float(2.0)));
+    assert_eq!(scale.output_types(), &[f32_type(vec![2, 3, 5])]);
+}
+
+#[test]
+fn test_invalid_groups_are_rejected() {
+    let mut graph = Graph::new();
+    let x = graph.add_input("x", f32_type(vec![4, 8]));
+    let mean = graph.add_node(OpKind::reduce(ReduceKind::Mean, vec![-1]), vec![x]);
+    let centred = graph.add_node(OpKind::Sub, vec![x, mean]);
+    let square = graph.add_node(OpKind::Mul, vec![centred, centred]);
+    let y = graph.add_node(OpKind::Add, vec![mean, square]);
+    graph.add_output("y", y).unwrap();
+
+    let error = export_error(&graph, &[NodeGroup::new("broken", vec![mean, y])]);
+    assert!(
+        matches!(
+            &error,
+            ValidationError::InvalidGraphBoundary { name, reason }
+                if name == "broken" && reason.contains("its own results")
+        ),
+        "{error}"
+    );
+
+    let error = export_error(
+        &graph,
+        &[
+            NodeGroup::new("first", vec![mean, centred]),
+            NodeGroup::new("second", vec![centred, square]),
+        ],
+    );
+    assert!(
+        matches!(
+            &error,
+            ValidationError::InvalidGraphBoundary { name, reason }
+                if name == "second" && reason.contains("with group 'first'")
+        ),
+        "{error}"
+    );
+
+    let error = export_error(&graph, &[NodeGroup::new("source", vec![x, mean])]);
+    assert!(
+        matches!(&error, ValidationError::InvalidGraphBoundary { name, .. } if name == "source"),
+        "{error}"
+    );
+
+    // The whole chain is a valid group
+    let model = export_model(
+        &graph,
+        &[NodeGroup::new("norm", vec![mean, centred, square, y])],
+    )
+    .unwrap();
+    assert_eq!(op_types(&model), ["norm_0"]);
+    assert_eq!(model.functions[0].output, ["y"]);
+}
+
+#[test]
+fn test_ops_onnx_cannot_express() {
+    let mut graph = Graph::new();
+    let x = graph.add_input("x", f32_type(vec![1, 3, 8, 8]));
+    let w = graph.add_input("w", f32_type(vec![3, 1, 3, 3]));
+    let data = graph.add_node(OpKind::custom("Opaque"), vec![x]);
+    let conv = graph.add_node(
+        OpKind::DepthwiseConv {
+            window: Window2d::default(),
+        },
+        vec![data, w],
+    );
+    graph.add_output("y", conv).unwrap();
+
+    // Without the Custom op's output type the channel count is unknown
+    let error = export_error(&graph, &[]);
+    assert!(
+        matches!(&error, ValidationError::InvalidAttribute { op, .. } if op == "DepthwiseConv"),
+        "{error}"
+    );
+
+    graph
+        .set_output_types(data, vec![f32_type(vec![1, 3, 8, 8])])
+        .unwrap();
+    let model = export_model(&graph, &[]).unwrap();
+    let conv = &model.graph.unwrap().node[1];
+    assert_eq!(conv.op_type, "Conv");
+    let group = conv.attribute.iter().find(|a| a.name == "group").unwrap();
+    assert_eq!(group.i, 3);
+}
+
+#[test]
+fn test_save_and_load() {
+    let graph = import_onnx(&fixture("onnx", "conv_block.onnx")).unwrap();
+    let groups: Vec<NodeGroup> = find_candidates(&graph)
+        .iter()
+        .map(NodeGroup::from)
+        .collect();
+    let path = std::env::temp_dir().join(format!("xyntra_fused_{}.onnx", std::process::id()));
+
+    save_onnx(&path, &graph, &groups).unwrap();
+    let loaded = load_onnx(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(print_graph(&loaded), print_graph(&graph));
+}
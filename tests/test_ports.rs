@@ -0,0 +1,171 @@
+mod common;
+
+use common::create_test_tensor_type;
+use xyntra::ir::{
+    errors::ValidationError,
+    graph::Graph,
+    shape_inference::ShapeInference,
+    types::{DType, NodeID, OpKind, TensorType, ValueRef},
+    validation::GraphValidator,
+};
+
+fn split_graph() -> (Graph, NodeID) {
+    let mut graph = Graph::new();
+    let input = graph.add_input("x", create_test_tensor_type(DType::F32, vec![2, 6]));
+    let split = graph.add_node(
+        OpKind::Split {
+            axis: 1,
+            sizes: vec![2, 4],
+        },
+        vec![input],
+    );
+    (graph, split)
+}
+
+#[test]
+fn test_value_ref_handles() {
+    let node = NodeID::new(3);
+    let value = node.output(1);
+
+    assert_eq!(value.node(), node);
+    assert_eq!(value.port(), 1);
+    assert_eq!(ValueRef::from(node), node.output(0));
+    assert_ne!(value, node.output(0));
+}
+
+#[test]
+fn test_consumers_read_split_ports() {
+    let (mut graph, split) = split_graph();
+    let relu = graph.add_node_with_values(OpKind::Relu, vec![split.output(1)]);
+    let exp = graph.add_node(OpKind::Exp, vec![split]);
+
+    assert_eq!(
+        graph.get_node(relu).unwrap().inputs(),
+        &vec![split.output(1)]
+    );
+    assert_eq!(
+        graph.get_node(exp).unwrap().inputs(),
+        &vec![split.output(0)]
+    );
+
+    ShapeInference::new(&mut graph).run().unwrap();
+
+    assert_eq!(
+        graph.value_type(split.output(1)),
+        Some(&create_test_tensor_type(DType::F32, vec![2, 4]))
+    );
+    assert_eq!(
+        graph.output_type(relu),
+        Some(&create_test_tensor_type(DType::F32, vec![2, 4]))
+    );
+    assert_eq!(
+        graph.output_type(exp),
+        Some(&create_test_tensor_type(DType::F32, vec![2, 2]))
+    );
+    assert_eq!(graph.value_type(split.output(2)), None);
+}
+
+#[test]
+fn test_graph_outputs_on_ports() {
+    let (mut graph, split) = split_graph();
+    graph.add_output("left", split).unwrap();
+    graph.add_output("right", split.output(1)).unwrap();
+
+    assert_eq!(graph.outputs()[0].value(), split.output(0));
+    assert_eq!(graph.outputs()[1].value(), split.output(1));
+    assert_eq!(graph.outputs()[1].node(), split);
+    assert!(GraphValidator::new(&graph).validate().is_ok());
+
+    assert!(matches!(
+        graph.add_output("missing", NodeID::new(42).output(1)),
+        Err(ValidationError::MissingNode { node_id: 42 })
+    ));
+}
+
+#[test]
+fn test_validator_rejects_missing_ports() {
+    let (mut graph, split) = split_graph();
+    let relu = graph.add_node_with_values(OpKind::Relu, vec![split.output(2)]);
+    let gelu = graph.add_node_with_values(OpKind::Gelu, vec![relu.output(1)]);
+
+    let errors = GraphValidator::new(&graph)
+        .validate_node_references()
+        .unwrap_err();
+
+    assert_eq!(errors.len(), 2);
+    assert!(matches!(
+        &errors[0],
+        ValidationError::InvalidNodeConnection { from, to, .. }
+            if *from == split.id() && *to == relu.id()
+    ));
+    assert!(matches!(
+        &errors[1],
+        ValidationError::InvalidNodeConnection { from, to, .. }
+            if *from == relu.id() && *to == gelu.id()
+    ));
+}
+
+#[test]
+fn test_custom_op_ports_follow_declared_types() {
+    let mut graph = Graph::new();
+    let input = graph.add_input("x", create_test_tensor_type(DType::F32, vec![4]));
+    let custom = graph.add_node(OpKind::custom("TopK"), vec![input]);
+
+    // Untyped custom ops cannot be checked
+    let indices = graph.add_node_with_values(OpKind::Relu, vec![custom.output(1)]);
+    assert!(
+        GraphValidator::new(&graph)
+            .validate_node_references()
+            .is_ok()
+    );
+
+    graph
+        .set_output_types(custom, vec![create_test_tensor_type(DType::F32, vec![2])])
+        .unwrap();
+    assert!(matches!(
+        GraphValidator::new(&graph)
+            .validate_node_references()
+            .unwrap_err()
+            .as_slice(),
+        [ValidationError::InvalidNodeConnection { from, to, .. }]
+            if *from == custom.id() && *to == indices.id()
+    ));
+
+    graph
+        .set_output_types(
+            custom,
+            vec![
+                create_test_tensor_type(DType::F32, vec![2]),
+                create_test_tensor_type(DType::I32, vec![2]),
+            ],
+        )
+        .unwrap();
+    assert!(
+        GraphValidator::new(&graph)
+            .validate_node_references()
+            .is_ok()
+    );
+}
+
+#[test]
+fn test_layer_norm_statistics_outputs() {
+    let mut graph = Graph::new();
+    let input = graph.add_input("x", create_test_tensor_type(DType::F32, vec![2, 3, 8]));
+    let norm = graph.add_node(OpKind::layer_norm(), vec![input]);
+    let mean = graph.add_node_with_values(OpKind::Exp, vec![norm.output(1)]);
+
+    ShapeInference::new(&mut graph).run().unwrap();
+
+    let statistics = create_test_tensor_type(DType::F32, vec![2, 3, 1]);
+    let types: Vec<TensorType> = graph.get_node(norm).unwrap().output_types().to_vec();
+    assert_eq!(
+        types,
+        vec![
+            create_test_tensor_type(DType::F32, vec![2, 3, 8]),
+            statistics.clone(),
+            statistics.clone(),
+        ]
+    );
+    assert_eq!(graph.output_type(mean), Some(&statistics));
+    assert!(GraphValidator::new(&graph).validate().is_ok());
+}
@@ -0,0 +1,171 @@
+mod common;
+
+use common::{create_test_tensor_type, infer};
+use xyntra::ir::{
+    attributes::{AttributeValue, Attributes},
+    errors::ValidationError,
+    reduction::argmax,
+    symbolic::Dim,
+    types::{DType, OpCategory, OpKind, ReduceKind, TensorShape, TensorType},
+};
+
+#[test]
+fn test_reduction_names_and_category() {
+    let kinds = [
+        (ReduceKind::Sum, "ReduceSum"),
+        (ReduceKind::Mean, "ReduceMean"),
+        (ReduceKind::Max, "ReduceMax"),
+        (ReduceKind::Min, "ReduceMin"),
+    ];
+    for (kind, name) in kinds {
+        let op = OpKind::reduce(kind, vec![0]);
+        assert_eq!(op.name(), name);
+        assert_eq!(ReduceKind::from_op_name(name), Some(kind));
+        assert_eq!(op.category(), OpCategory::Reduction);
+        assert!(op.is_reduction());
+        assert!(!op.is_elementwise());
+    }
+
+    assert_eq!(OpKind::softmax().category(), OpCategory::Normalization);
+    assert!(ReduceKind::from_op_name("ReduceProd").is_none());
+}
+
+#[test]
+fn test_reduce_shape_inference_keepdims() {
+    let input = create_test_tensor_type(DType::F32, vec![2, 3, 4]);
+
+    let kept = infer(
+        OpKind::reduce(ReduceKind::Sum, vec![1, -1]),
+        vec![input.clone()],
+    )
+    .unwrap();
+    assert_eq!(kept, create_test_tensor_type(DType::F32, vec![2, 1, 1]));
+
+    let dropped = OpKind::Reduce {
+        kind: ReduceKind::Max,
+        axes: vec![-1, 0],
+        keepdims: false,
+    };
+    assert_eq!(
+        infer(dropped, vec![input.clone()]).unwrap(),
+        create_test_tensor_type(DType::F32, vec![3])
+    );
+}
+
+#[test]
+fn test_reduce_over_all_axes() {
+    let input = create_test_tensor_type(DType::I32, vec![2, 3]);
+
+    let scalar = OpKind::Reduce {
+        kind: ReduceKind::Sum,
+        axes: vec![],
+        keepdims: false,
+    };
+    assert_eq!(
+        infer(scalar, vec![input.clone()]).unwrap(),
+        create_test_tensor_type(DType::I32, vec![])
+    );
+    assert_eq!(
+        infer(OpKind::reduce(ReduceKind::Min, vec![]), vec![input]).unwrap(),
+        create_test_tensor_type(DType::I32, vec![1, 1])
+    );
+}
+
+#[test]
+fn test_reduce_rejects_bad_axes_and_bool() {
+    let input = create_test_tensor_type(DType::F32, vec![2, 3, 4]);
+
+    for axes in [vec![3], vec![1, -2], vec![0, 0]] {
+        let result = infer(OpKind::reduce(ReduceKind::Mean, axes), vec![input.clone()]);
+        assert!(matches!(
+            result,
+            Err(ValidationError::InvalidAttribute { ref attribute, .. }) if attribute == "axes"
+        ));
+    }
+
+    let flags = create_test_tensor_type(DType::Bool, vec![4]);
+    assert!(infer(OpKind::reduce(ReduceKind::Sum, vec![0]), vec![flags]).is_err());
+}
+
+#[test]
+fn test_argmax_shape_and_dtype() {
+    let input = create_test_tensor_type(DType::F16, vec![8, 10]);
+
+    let op = OpKind::ArgMax {
+        axis: -1,
+        keepdims: false,
+    };
+    assert_eq!(
+        infer(op, vec![input.clone()]).unwrap(),
+        create_test_tensor_type(DType::I32, vec![8])
+    );
+
+    let out_of_range = OpKind::ArgMax {
+        axis: 2,
+        keepdims: true,
+    };
+    assert!(infer(out_of_range, vec![input]).is_err());
+}
+
+#[test]
+fn test_reduce_keeps_symbolic_dims() {
+    let input = TensorType::new(
+        DType::F32,
+        TensorShape::from_dims(vec![
+            Dim::symbol("batch"),
+            Dim::symbol("seq"),
+            Dim::Static(64),
+        ]),
+    );
+
+    let output = infer(OpKind::reduce(ReduceKind::Mean, vec![1]), vec![input]).unwrap();
+    assert_eq!(
+        output.shape(),
+        &TensorShape::from_dims(vec![Dim::symbol("batch"), Dim::Static(1), Dim::Static(64)])
+    );
+}
+
+#[test]
+fn test_reduction_attributes_round_trip() {
+    let ops = [
+        OpKind::Reduce {
+            kind: ReduceKind::Min,
+            axes: vec![0, -1],
+            keepdims: false,
+        },
+        OpKind::ArgMax {
+            axis: 1,
+            keepdims: true,
+        },
+    ];
+    for op in ops {
+        assert_eq!(OpKind::from_parts(op.name(), &op.attributes()).unwrap(), op);
+    }
+
+    // ONNX defaults: every axis, dimensions kept
+    assert_eq!(
+        OpKind::from_parts("ReduceSum", &Attributes::new()).unwrap(),
+        OpKind::reduce(ReduceKind::Sum, vec![])
+    );
+
+    let single_axis = Attributes::new().with("axes", AttributeValue::Int(2));
+    assert_eq!(
+        OpKind::from_parts("ReduceMax", &single_axis).unwrap(),
+        OpKind::reduce(ReduceKind::Max, vec![2])
+    );
+}
+
+#[test]
+fn test_reduction_reference_semantics() {
+    let values = [3.0, -1.0, 4.0, 1.0, 4.0];
+
+    assert_eq!(ReduceKind::Sum.evaluate(&values), Some(11.0));
+    assert_eq!(ReduceKind::Mean.evaluate(&values), Some(2.2));
+    assert_eq!(ReduceKind::Max.evaluate(&values), Some(4.0));
+    assert_eq!(ReduceKind::Min.evaluate(&values), Some(-1.0));
+    assert_eq!(ReduceKind::Mean.evaluate(&[]), None);
+
+    // First occurrence wins on ties
+    assert_eq!(argmax(&values), Some(2));
+    assert_eq!(argmax(&[]), None);
+}
@@ -0,0 +1,288 @@
+mod common;
+
+use common::{build_complex_graph, build_simple_graph, create_test_tensor_type};
+use xyntra::ir::{
+    attributes::{AttributeValue, Attributes},
+    errors::ParsingError,
+    graph::Graph,
+    json::JsonValue,
+    layout::TensorLayout,
+    ops::Node,
+    serialization::{JSON_VERSION, JsonSerialize, export_json, import_json},
+    shape_inference::ShapeInference,
+    symbolic::{Dim, DimExpr},
+    tensor::Tensor,
+    text::print_graph,
+    types::{DType, NodeID, OpKind, TensorShape, TensorType},
+};
+
+fn assert_round_trips(graph: &Graph) -> String {
+    let json = export_json(graph);
+    let imported = import_json(&json).unwrap();
+    assert_eq!(print_graph(&imported), print_graph(graph));
+    assert_eq!(export_json(&imported), json);
+    json
+}
+
+fn invalid_reason(result: Result<Graph, ParsingError>) -> String {
+    match result {
+        Err(ParsingError::InvalidFormat { format, reason }) => {
+            assert_eq!(format, "json");
+            reason
+        }
+        Err(other) => panic!("expected InvalidFormat, got {other:?}"),
+        Ok(_) => panic!("expected the document to be rejected"),
+    }
+}
+
+#[test]
+fn test_json_values_parse_and_print() {
+    let value =
+        JsonValue::parse(r#"{"a": [1, -2.5e3, true, null], "b": {"c": "q\"é😀\n"}, "d": []}"#)
+            .unwrap();
+
+    assert_eq!(value.get("a").unwrap().as_array().unwrap().len(), 4);
+    assert_eq!(
+        value.get("a").unwrap().as_array().unwrap()[1].as_f32(),
+        Some(-2500.0)
+    );
+    assert_eq!(
+        value.get("b").unwrap().get("c").unwrap().as_str(),
+        Some("q\"é😀\n")
+    );
+    assert_eq!(JsonValue::parse(&value.to_string()).unwrap(), value);
+    assert_eq!(JsonValue::parse(&value.to_pretty_string()).unwrap(), value);
+
+    // Wide integers keep every digit
+    let wide = JsonValue::parse("9007199254740993").unwrap();
+    assert_eq!(wide.as_i64(), Some(9_007_199_254_740_993));
+
+    for (source, expected) in [
+        ("{\"a\": 1,}", "line 1, column 9: expected a string key"),
+        ("[1, 2", "line 1, column 6: expected ',' or ']'"),
+        ("{\n  \"a\": 01\n}", "line 2, column 9: expected ',' or '}'"),
+        (
+            "{\"a\": 1, \"a\": 2}",
+            "line 1, column 10: duplicate key \"a\"",
+        ),
+        (
+            "[] []",
+            "line 1, column 4: unexpected content after the document",
+        ),
+    ] {
+        match JsonValue::parse(source) {
+            Err(ParsingError::InvalidFormat { reason, .. }) => {
+                assert_eq!(reason, expected, "{source:?}")
+            }
+            other => panic!("expected {source:?} to be rejected, got {other:?}"),
+        }
+    }
+
+    // Deep nesting is an error, not a stack overflow
+    let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+    assert!(JsonValue::parse(&nested(128)).is_ok());
+    match JsonValue::parse(&format!("{{\"a\": {}", nested(200_000))) {
+        Err(ParsingError::InvalidFormat { reason, .. }) => {
+            assert_eq!(reason, "line 1, column 134: nesting deeper than 128 levels")
+        }
+        other => panic!("expected deep nesting to be rejected, got {other:?}"),
+    }
+    assert!(import_json(&nested(200_000)).is_err());
+}
+
+#[test]
+fn test_round_trip_common_graphs() {
+    let json = assert_round_trips(&build_simple_graph());
+    assert!(json.starts_with("{\n  \"format\": \"xyntra-ir\",\n  \"version\": 1,"));
+
+    let mut graph = build_complex_graph();
+    ShapeInference::new(&mut graph).run().unwrap();
+    let json = assert_round_trips(&graph);
+    assert!(json.contains("\"attributes\": {\"ratio\": {\"float\": 0.5}}"));
+}
+
+#[test]
+fn test_round_trip_types_ports_and_custom_ops() {
+    let mut graph = Graph::new();
+    let seq = Dim::from_expr(DimExpr::symbol("seq").scale(2) + DimExpr::constant(1));
+    let x = graph.add_input(
+        "x",
+        TensorType::new(
+            DType::F32,
+            TensorShape::from_dims(vec![Dim::symbol("batch"), seq]),
+        ),
+    );
+    let strided = TensorType::new(DType::BF16, TensorShape::new(vec![2, 3]))
+        .with_layout(TensorLayout::strided(vec![1, 2], 4))
+        .unwrap();
+    let y = graph.add_input("y", strided);
+    let half = graph.add_initializer(
+        "half",
+        Tensor::new(
+            TensorType::new(DType::F16, TensorShape::new(vec![2])),
+            vec![0x00, 0x3c, 0x00, 0xc0],
+        )
+        .unwrap(),
+    );
+    let split = graph.add_node(
+        OpKind::Split {
+            axis: 0,
+            sizes: vec![1, 1],
+        },
+        vec![half],
+    );
+    let attributes = Attributes::new()
+        .with("nan", AttributeValue::Float(f32::NAN))
+        .with(
+            "inf",
+            AttributeValue::Floats(vec![f32::INFINITY, -0.0, 1e-8]),
+        )
+        .with("tags", AttributeValue::Strings(vec!["a\tb".to_string()]))
+        .with("none", AttributeValue::Ints(vec![]));
+    // Named like a built-in, but stays custom
+    let custom = graph.add_node_with_values(
+        OpKind::Custom {
+            name: "Relu".to_string(),
+            attributes,
+        },
+        vec![split.output(1), x.output(0)],
+    );
+    graph.add_output("custom", custom).unwrap();
+    graph.add_output("head", split.output(1)).unwrap();
+    graph.add_output("y", y).unwrap();
+
+    let json = assert_round_trips(&graph);
+    assert!(json.contains("\"shape\": [\"batch\", \"2*seq + 1\"]"));
+    assert!(json.contains("\"layout\": {\"strides\": [1, 2], \"offset\": 4}"));
+    assert!(json.contains("\"data\": \"0x003c00c0\""));
+    assert!(json.contains("\"float\": \"NaN\""));
+    assert!(json.contains("\"floats\": [\"inf\", -0.0, 1e-8]"));
+    assert!(json.contains("\"custom\": true"));
+
+    let imported = import_json(&json).unwrap();
+    let copied = imported.outputs()[0].node();
+    assert!(matches!(
+        imported.get_node(copied).unwrap().op(),
+        OpKind::Custom { name, .. } if name == "Relu"
+    ));
+    assert_eq!(imported.get_node(copied).unwrap().inputs()[0].port(), 1);
+    assert_eq!(imported.outputs()[1].value().port(), 1);
+}
+
+#[test]
+fn test_item_level_serialisation() {
+    let shape = TensorShape::from_dims(vec![Dim::Static(3), Dim::symbol("n")]);
+    assert_eq!(shape.to_json().to_string(), "[3, \"n\"]");
+    assert_eq!(TensorShape::from_json(&shape.to_json()).unwrap(), shape);
+
+    let op = OpKind::Reduce {
+        kind: xyntra::ir::types::ReduceKind::Mean,
+        axes: vec![-1],
+        keepdims: true,
+    };
+    assert_eq!(OpKind::from_json(&op.to_json()).unwrap(), op);
+
+    let node = Node::new(
+        NodeID::new(4),
+        OpKind::Softmax { axis: -1 },
+        vec![NodeID::new(2).output(1)],
+        vec![],
+    )
+    .with_output_type(create_test_tensor_type(DType::F32, vec![2, 3]));
+    let copy = Node::from_json(&node.to_json()).unwrap();
+    assert_eq!(copy.id(), node.id());
+    assert_eq!(copy.op(), node.op());
+    assert_eq!(copy.inputs(), node.inputs());
+    assert_eq!(copy.output_types(), node.output_types());
+}
+
+#[test]
+fn test_schema_errors_name_the_field() {
+    let json = export_json(&build_simple_graph());
+    let document = JsonValue::parse(&json).unwrap();
+    let edit = |from: &str, to: &str| {
+        assert!(json.contains(from), "{from}");
+        json.replacen(from, to, 1)
+    };
+
+    assert!(matches!(
+        import_json(&edit("\"version\": 1,", "")),
+        Err(ParsingError::MissingRequiredField { field }) if field == "version"
+    ));
+    assert!(matches!(
+        import_json(&edit("\"name\": \"MatMul\",", "")),
+        Err(ParsingError::MissingRequiredField { field }) if field == "nodes[2].op.name"
+    ));
+    assert!(matches!(
+        import_json(&edit("\"MatMul\"", "\"Frobnicate\"")),
+        Err(ParsingError::UnsupportedOperation { op_name }) if op_name == "Frobnicate"
+    ));
+
+    let cases = [
+        (
+            edit("\"version\": 1", "\"version\": 2"),
+            "version: unsupported version 2",
+        ),
+        (
+            edit("\"xyntra-ir\"", "\"other\""),
+            "format: expected \"xyntra-ir\"",
+        ),
+        (
+            edit("\"dtype\": \"f32\"", "\"dtype\": \"f64\""),
+            "nodes[0].output_types[0].dtype: unknown element type",
+        ),
+        (
+            edit("[2, 4]", "[2, \"4 +\"]"),
+            "nodes[0].output_types[0].shape[1]: bad dimension",
+        ),
+        (
+            edit("\"int\": 0", "\"int\": \"0\""),
+            "nodes[2].op.attributes.transpose_a.int: expected an integer",
+        ),
+        (
+            edit("\"int\": 0", "\"integer\": 0"),
+            "nodes[2].op.attributes.transpose_a: unknown attribute kind",
+        ),
+        (
+            edit("\"data\": \"0x", "\"data\": \"0x0"),
+            "nodes[1].tensor.data: malformed hex data",
+        ),
+        (
+            edit("\"node\": 1,", "\"node\": 7,"),
+            "nodes[2].inputs[1]: no node with ID 7",
+        ),
+        (
+            edit("\"id\": 1,", "\"id\": 0,"),
+            "nodes[1].id: node ID 0 is used twice",
+        ),
+        (
+            edit("\"inputs\": [0],", "\"inputs\": \"all\","),
+            "inputs: expected an array",
+        ),
+    ];
+    for (source, expected) in cases {
+        let reason = invalid_reason(import_json(&source));
+        assert!(
+            reason.starts_with(expected),
+            "{reason:?}, expected {expected:?}"
+        );
+    }
+
+    assert_eq!(
+        document.get("version").unwrap().as_i64(),
+        Some(JSON_VERSION)
+    );
+}
+
+#[test]
+fn test_import_validates_the_graph() {
+    // Relu takes one input; the document is well-formed but the graph is not
+    let mut graph = Graph::new();
+    let x = graph.add_input("x", create_test_tensor_type(DType::F32, vec![4]));
+    let relu = graph.add_node(OpKind::Relu, vec![x, x]);
+    graph.add_output("y", relu).unwrap();
+
+    let reason = invalid_reason(import_json(&export_json(&graph)));
+    assert!(reason.starts_with("graph is invalid: "), "{reason}");
+    assert!(reason.contains("Relu"), "{reason}");
+}
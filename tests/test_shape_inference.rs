@@ -0,0 +1,215 @@
+mod common;
+
+use common::{create_test_tensor_type, f32_type, infer};
+use xyntra::ir::{
+    errors::ValidationError,
+    graph::Graph,
+    shape_inference::ShapeInference,
+    types::{DType, OpKind},
+};
+
+#[test]
+fn test_matmul_shapes() {
+    // Plain matrix product
+    assert_eq!(
+        infer(
+            OpKind::matmul(),
+            vec![f32_type(vec![2, 3]), f32_type(vec![3, 4])]
+        )
+        .unwrap(),
+        f32_type(vec![2, 4])
+    );
+
+    // Batched with broadcast of the leading dimensions
+    assert_eq!(
+        infer(
+            OpKind::matmul(),
+            vec![f32_type(vec![8, 1, 2, 3]), f32_type(vec![5, 3, 4])]
+        )
+        .unwrap(),
+        f32_type(vec![8, 5, 2, 4])
+    );
+
+    // Vector operands drop their promoted dimension
+    assert_eq!(
+        infer(
+            OpKind::matmul(),
+            vec![f32_type(vec![3]), f32_type(vec![3, 4])]
+        )
+        .unwrap(),
+        f32_type(vec![4])
+    );
+    assert_eq!(
+        infer(
+            OpKind::matmul(),
+            vec![f32_type(vec![2, 3]), f32_type(vec![3])]
+        )
+        .unwrap(),
+        f32_type(vec![2])
+    );
+    assert_eq!(
+        infer(OpKind::matmul(), vec![f32_type(vec![3]), f32_type(vec![3])]).unwrap(),
+        f32_type(vec![])
+    );
+}
+
+#[test]
+fn test_matmul_mismatch_is_reported() {
+    match infer(
+        OpKind::matmul(),
+        vec![f32_type(vec![2, 3]), f32_type(vec![4, 5])],
+    ) {
+        Err(ValidationError::IncompatibleShapes { op, shapes }) => {
+            assert_eq!(op, "MatMul");
+            assert_eq!(shapes, vec!["f32[2, 3]", "f32[4, 5]"]);
+        }
+        other => panic!("Expected IncompatibleShapes, got {other:?}"),
+    }
+
+    // Batch dimensions that cannot broadcast
+    assert!(
+        infer(
+            OpKind::matmul(),
+            vec![f32_type(vec![2, 2, 3]), f32_type(vec![3, 3, 4])]
+        )
+        .is_err()
+    );
+
+    // Scalars are not valid matmul operands
+    assert!(infer(OpKind::matmul(), vec![f32_type(vec![]), f32_type(vec![3])]).is_err());
+}
+
+#[test]
+fn test_add_broadcasting() {
+    assert_eq!(
+        infer(OpKind::Add, vec![f32_type(vec![4, 3]), f32_type(vec![3])]).unwrap(),
+        f32_type(vec![4, 3])
+    );
+    assert_eq!(
+        infer(
+            OpKind::Add,
+            vec![f32_type(vec![4, 1]), f32_type(vec![1, 5])]
+        )
+        .unwrap(),
+        f32_type(vec![4, 5])
+    );
+    assert_eq!(
+        infer(OpKind::Add, vec![f32_type(vec![]), f32_type(vec![2, 2])]).unwrap(),
+        f32_type(vec![2, 2])
+    );
+    assert!(infer(OpKind::Add, vec![f32_type(vec![4, 3]), f32_type(vec![4])]).is_err());
+}
+
+#[test]
+fn test_dtype_mismatch_is_reported() {
+    let result = infer(
+        OpKind::Add,
+        vec![
+            f32_type(vec![2]),
+            create_test_tensor_type(DType::F16, vec![2]),
+        ],
+    );
+
+    match result {
+        Err(ValidationError::IncompatibleShapes { shapes, .. }) => {
+            assert_eq!(shapes, vec!["f32[2]", "f16[2]"]);
+        }
+        other => panic!("Expected IncompatibleShapes, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_shape_preserving_ops() {
+    for op in [
+        OpKind::Gelu,
+        OpKind::dropout(),
+        OpKind::softmax(),
+        OpKind::layer_norm(),
+    ] {
+        assert_eq!(
+            infer(op, vec![f32_type(vec![2, 16, 64])]).unwrap(),
+            f32_type(vec![2, 16, 64])
+        );
+    }
+}
+
+#[test]
+fn test_layer_norm_scale_and_bias() {
+    assert_eq!(
+        infer(
+            OpKind::layer_norm(),
+            vec![
+                f32_type(vec![2, 64]),
+                f32_type(vec![64]),
+                f32_type(vec![64])
+            ]
+        )
+        .unwrap(),
+        f32_type(vec![2, 64])
+    );
+
+    // A scale that would grow the output is rejected
+    assert!(
+        infer(
+            OpKind::layer_norm(),
+            vec![f32_type(vec![2, 64]), f32_type(vec![3, 2, 64])]
+        )
+        .is_err()
+    );
+}
+
+#[test]
+fn test_inference_propagates_through_graph() {
+    let mut graph = Graph::new();
+    let x = graph.add_node_with_type(OpKind::custom("Input"), vec![], f32_type(vec![4, 8]));
+    let w = graph.add_node_with_type(OpKind::custom("Weight"), vec![], f32_type(vec![8, 16]));
+    let b = graph.add_node_with_type(OpKind::custom("Bias"), vec![], f32_type(vec![16]));
+    let matmul = graph.add_node(OpKind::matmul(), vec![x, w]);
+    let add = graph.add_node(OpKind::Add, vec![matmul, b]);
+    let gelu = graph.add_node(OpKind::Gelu, vec![add]);
+
+    ShapeInference::new(&mut graph).run().unwrap();
+
+    assert_eq!(graph.output_type(matmul), Some(&f32_type(vec![4, 16])));
+    assert_eq!(graph.output_type(add), Some(&f32_type(vec![4, 16])));
+    assert_eq!(graph.output_type(gelu), Some(&f32_type(vec![4, 16])));
+}
+
+#[test]
+fn test_untyped_inputs_are_skipped() {
+    let mut graph = Graph::new();
+    let x = graph.add_node(OpKind::custom("Input"), vec![]);
+    let gelu = graph.add_node(OpKind::Gelu, vec![x]);
+
+    assert!(ShapeInference::new(&mut graph).run().is_ok());
+    assert!(graph.output_type(gelu).is_none());
+}
+
+#[test]
+fn test_declared_type_conflict_is_reported() {
+    let mut graph = Graph::new();
+    let x = graph.add_node_with_type(OpKind::custom("Input"), vec![], f32_type(vec![2, 3]));
+    graph.add_node_with_type(OpKind::Gelu, vec![x], f32_type(vec![3, 2]));
+
+    let errors = ShapeInference::new(&mut graph).run().unwrap_err();
+    match &errors[0] {
+        ValidationError::IncompatibleShapes { op, shapes } => {
+            assert_eq!(op, "Gelu");
+            assert_eq!(shapes, &["f32[2, 3]", "f32[3, 2]"]);
+        }
+        other => panic!("Expected IncompatibleShapes, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_cyclic_graph_is_rejected() {
+    let mut graph = Graph::new();
+    let first = graph.add_node(
+        OpKind::Gelu,
+        vec![common::create_test_node_id_with_value(1)],
+    );
+    graph.add_node(OpKind::Gelu, vec![first]);
+
+    let errors = ShapeInference::new(&mut graph).run().unwrap_err();
+    assert!(matches!(errors[0], ValidationError::CyclicGraph { .. }));
+}
@@ -0,0 +1,236 @@
+mod common;
+
+use common::{build_complex_graph, build_simple_graph, create_test_tensor_type};
+use xyntra::ir::{
+    errors::ValidationError,
+    graph::Graph,
+    shape_inference::ShapeInference,
+    tensor::Tensor,
+    types::{DType, NodeID, OpKind, TensorShape},
+    validation::GraphValidator,
+};
+
+/// x -> matmul(w) -> add(b) -> relu -> exp, with shapes inferred.
+fn mlp() -> (Graph, [NodeID; 7]) {
+    let mut graph = Graph::new();
+    let x = graph.add_input("x", create_test_tensor_type(DType::F32, vec![2, 4]));
+    let w = graph.add_initializer(
+        "w",
+        Tensor::from_f32(TensorShape::new(vec![4, 3]), &[0.25; 12]).unwrap(),
+    );
+    let b = graph.add_initializer(
+        "b",
+        Tensor::from_f32(TensorShape::new(vec![3]), &[1.0; 3]).unwrap(),
+    );
+    let matmul = graph.add_node(OpKind::matmul(), vec![x, w]);
+    let add = graph.add_node(OpKind::Add, vec![matmul, b]);
+    let relu = graph.add_node(OpKind::Relu, vec![add]);
+    let exp = graph.add_node(OpKind::Exp, vec![relu]);
+    graph.add_output("y", exp).unwrap();
+    ShapeInference::new(&mut graph).run().unwrap();
+    (graph, [x, w, b, matmul, add, relu, exp])
+}
+
+#[test]
+fn test_clone_with_mapping() {
+    let (graph, [x, w, _, matmul, _, _, exp]) = mlp();
+
+    let (copy, mapping) = graph.clone_with_mapping().unwrap();
+
+    assert_eq!(copy.len(), graph.len());
+    assert_eq!(mapping.len(), graph.len());
+    assert_eq!(mapping.node(x), Some(copy.inputs()[0]));
+    assert_eq!(copy.outputs()[0].node(), mapping.node(exp).unwrap());
+    assert_eq!(
+        copy.initializer(mapping.node(w).unwrap()),
+        graph.initializer(w)
+    );
+    assert_eq!(
+        copy.output_type(mapping.node(matmul).unwrap()),
+        graph.output_type(matmul)
+    );
+    assert_eq!(
+        copy.topological_order().unwrap().len(),
+        graph.topological_order().unwrap().len()
+    );
+    assert!(GraphValidator::new(&copy).validate().is_ok());
+}
+
+#[test]
+fn test_clone_pass_through_and_dangling_input() {
+    // An input exposed under its own name as an output
+    let mut graph = Graph::new();
+    let x = graph.add_input("x", create_test_tensor_type(DType::F32, vec![2]));
+    graph.add_output("x", x).unwrap();
+    let (copy, mapping) = graph.clone_with_mapping().unwrap();
+    assert_eq!(copy.outputs()[0].name(), "x");
+    assert_eq!(copy.outputs()[0].node(), mapping.node(x).unwrap());
+
+    let relu = graph.add_node(OpKind::Relu, vec![NodeID::new(99)]);
+    graph.add_output("y", relu).unwrap();
+    assert!(matches!(
+        graph.clone_with_mapping(),
+        Err(ValidationError::MissingNode { node_id: 99 })
+    ));
+}
+
+#[test]
+fn test_merge_allocates_fresh_ids() {
+    let mut graph = build_simple_graph();
+    let (other, _) = mlp();
+    let before = graph.len();
+
+    let mapping = graph.merge(&other).unwrap();
+
+    assert_eq!(graph.len(), before + other.len());
+    for (source, copy) in mapping.pairs() {
+        assert!(copy.id() as usize >= before);
+        assert_eq!(
+            graph.get_node(copy).unwrap().op(),
+            other.get_node(source).unwrap().op()
+        );
+    }
+    assert_eq!(graph.inputs().len(), 2);
+    assert_eq!(graph.outputs().len(), 2);
+    assert!(GraphValidator::new(&graph).validate().is_ok());
+
+    // Both graphs call their output "output"; nothing is copied
+    let len = graph.len();
+    assert!(matches!(
+        graph.merge(&build_complex_graph()),
+        Err(ValidationError::InvalidGraphBoundary { name, .. }) if name == "output"
+    ));
+    assert_eq!(graph.len(), len);
+
+    // A dangling input in the merged graph is found before copying
+    let mut dangling = Graph::new();
+    let h = dangling.add_input("h", create_test_tensor_type(DType::F32, vec![2]));
+    let add = dangling.add_node(OpKind::Add, vec![h, NodeID::new(99)]);
+    dangling.add_output("sum", add).unwrap();
+    let inputs = graph.inputs().len();
+    assert!(matches!(
+        graph.merge(&dangling),
+        Err(ValidationError::MissingNode { node_id: 99 })
+    ));
+    assert_eq!(graph.len(), len);
+    assert_eq!(graph.inputs().len(), inputs);
+}
+
+#[test]
+fn test_merge_then_stitch() {
+    let mut graph = build_simple_graph();
+    let mut tail = Graph::new();
+    let h = tail.add_input("h", create_test_tensor_type(DType::F32, vec![2, 3]));
+    let relu = tail.add_node(OpKind::Relu, vec![h]);
+    tail.add_output("activated", relu).unwrap();
+
+    let mapping = graph.merge(&tail).unwrap();
+    let matmul = NodeID::new(2);
+    let copied_input = mapping.node(h).unwrap();
+    graph
+        .replace_all_uses_with(copied_input.output(0), matmul.output(0))
+        .unwrap();
+
+    assert_eq!(graph.users(matmul), &[mapping.node(relu).unwrap()]);
+    assert!(graph.users(copied_input).is_empty());
+}
+
+#[test]
+fn test_inline_binds_arguments() {
+    let (mut graph, [_, _, _, _, _, _, exp]) = mlp();
+    let (block, _) = mlp();
+
+    // Feed the first block's output through a second copy of itself
+    let sigmoid = graph.add_node(OpKind::Sigmoid, vec![exp]);
+    let slice = graph.add_node(
+        OpKind::Slice {
+            starts: vec![0],
+            ends: vec![4],
+            axes: vec![1],
+            steps: vec![],
+        },
+        vec![sigmoid],
+    );
+    let mapping = graph.inline(&block, &[slice.output(0)]).unwrap();
+
+    let inlined_output = mapping.value(block.outputs()[0].value()).unwrap();
+    graph.add_output("z", inlined_output).unwrap();
+
+    assert_eq!(graph.inputs().len(), 1);
+    assert_eq!(graph.outputs().len(), 2);
+    // The second block's weights were renamed to stay unique
+    assert!(graph.find_value("w_1").is_some());
+    assert!(graph.find_value("b_1").is_some());
+    assert!(GraphValidator::new(&graph).validate().is_ok());
+
+    assert!(matches!(
+        graph.inline(&block, &[]),
+        Err(ValidationError::InvalidGraphBoundary { .. })
+    ));
+}
+
+#[test]
+fn test_extract_node_set() {
+    let (graph, [_, w, b, matmul, add, relu, exp]) = mlp();
+
+    let (block, mapping) = graph.extract(&[add, relu]).unwrap();
+
+    // The matmul result becomes an input, the bias travels along, and relu's
+    // result is needed outside
+    assert_eq!(block.inputs().len(), 1);
+    assert_eq!(
+        block.output_type(block.inputs()[0]),
+        graph.output_type(matmul)
+    );
+    assert_eq!(
+        mapping.value(matmul.output(0)),
+        Some(block.inputs()[0].output(0))
+    );
+    assert!(mapping.node(b).is_some());
+    assert!(mapping.node(w).is_none());
+    assert!(mapping.node(exp).is_none());
+    assert_eq!(block.outputs().len(), 1);
+    assert_eq!(block.outputs()[0].node(), mapping.node(relu).unwrap());
+    assert_eq!(block.len(), 4);
+    assert!(GraphValidator::new(&block).validate().is_ok());
+
+    assert!(matches!(
+        graph.extract(&[NodeID::new(99)]),
+        Err(ValidationError::MissingNode { node_id: 99 })
+    ));
+}
+
+#[test]
+fn test_extract_between_values() {
+    let (graph, [x, _, _, matmul, add, relu, exp]) = mlp();
+
+    let (block, mapping) = graph
+        .extract_between(&[matmul.output(0)], &[exp.output(0)])
+        .unwrap();
+
+    assert_eq!(block.inputs().len(), 1);
+    assert!(mapping.node(x).is_none());
+    assert!(mapping.node(matmul).is_none());
+    assert!(mapping.node(add).is_some());
+    assert!(mapping.node(relu).is_some());
+    assert_eq!(block.outputs()[0].name(), "y");
+    assert!(GraphValidator::new(&block).validate().is_ok());
+
+    // Without a cut the whole model comes along, graph input included
+    let (whole, _) = graph.extract_between(&[], &[exp.output(0)]).unwrap();
+    assert_eq!(whole.len(), graph.len());
+    assert_eq!(whole.inputs().len(), 1);
+}
+
+#[test]
+fn test_extract_needs_boundary_types() {
+    let mut graph = Graph::new();
+    let x = graph.add_input("x", create_test_tensor_type(DType::F32, vec![4]));
+    let relu = graph.add_node(OpKind::Relu, vec![x]);
+    let exp = graph.add_node(OpKind::Exp, vec![relu]);
+
+    assert!(matches!(
+        graph.extract(&[exp]),
+        Err(ValidationError::InvalidGraphBoundary { .. })
+    ));
+}
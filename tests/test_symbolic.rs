@@ -0,0 +1,269 @@
+mod common;
+
+use std::collections::HashMap;
+
+use common::create_test_tensor_shape;
+use xyntra::ir::{
+    errors::ValidationError,
+    graph::Graph,
+    shape_inference::ShapeInference,
+    symbolic::{Dim, DimExpr, ShapeConstraints},
+    types::{DType, OpKind, TensorShape, TensorType},
+};
+
+fn symbolic_shape(dims: Vec<Dim>) -> TensorShape {
+    TensorShape::from_dims(dims)
+}
+
+#[test]
+fn test_dim_arithmetic_and_display() {
+    let seq = Dim::symbol("seq");
+
+    assert_eq!((seq.clone() * 2).to_string(), "2*seq");
+    assert_eq!((seq.clone() * 2 + Dim::from(1)).to_string(), "2*seq + 1");
+    assert_eq!((Dim::symbol("a") - Dim::symbol("b")).to_string(), "a - b");
+    assert_eq!((Dim::from(3) - seq.clone()).to_string(), "-seq + 3");
+
+    // Symbols that cancel collapse back to a static dimension
+    assert_eq!(seq.clone() - seq, Dim::Static(0));
+    assert_eq!(Dim::from_expr(DimExpr::constant(5)), Dim::Static(5));
+}
+
+#[test]
+fn test_dim_evaluation() {
+    let mut bindings = HashMap::new();
+    bindings.insert("seq".to_string(), 7);
+
+    let dim = Dim::symbol("seq") * 2 + Dim::from(1);
+    assert_eq!(dim.evaluate(&bindings), Some(15));
+    assert_eq!(Dim::symbol("batch").evaluate(&bindings), None);
+    assert_eq!(Dim::from(4).evaluate(&bindings), Some(4));
+}
+
+#[test]
+fn test_symbolic_tensor_shape() {
+    let shape = symbolic_shape(vec![
+        Dim::symbol("batch"),
+        Dim::symbol("seq") * 2,
+        64.into(),
+    ]);
+
+    assert_eq!(shape.rank(), 3);
+    assert!(!shape.is_static());
+    assert_eq!(shape.size(), None);
+    assert_eq!(shape.static_dims(), None);
+    assert_eq!(
+        shape.symbols().into_iter().collect::<Vec<_>>(),
+        vec!["batch", "seq"]
+    );
+    assert_eq!(shape.to_string(), "[batch, 2*seq, 64]");
+
+    let static_shape = create_test_tensor_shape(vec![2, 3]);
+    assert!(static_shape.is_static());
+    assert_eq!(static_shape.size(), Some(6));
+    assert_eq!(static_shape.static_dims(), Some(vec![2, 3]));
+}
+
+#[test]
+fn test_constraints_solve_affine_equalities() {
+    let mut constraints = ShapeConstraints::new();
+
+    constraints.bind("batch", 4).unwrap();
+    assert_eq!(constraints.value_of("batch"), Some(4));
+
+    // 2*seq == 8  =>  seq == 4
+    constraints
+        .require_equal(&(Dim::symbol("seq") * 2), &Dim::from(8))
+        .unwrap();
+    assert_eq!(constraints.value_of("seq"), Some(4));
+
+    // a == b, then b == 3 makes a known as well
+    constraints
+        .require_equal(&Dim::symbol("a"), &Dim::symbol("b"))
+        .unwrap();
+    assert!(constraints.are_equal(&Dim::symbol("a"), &Dim::symbol("b")));
+    assert_eq!(constraints.value_of("a"), None);
+    constraints.bind("b", 3).unwrap();
+    assert_eq!(constraints.value_of("a"), Some(3));
+}
+
+#[test]
+fn test_constraints_detect_contradictions() {
+    let mut constraints = ShapeConstraints::new();
+    constraints.bind("n", 3).unwrap();
+
+    match constraints.bind("n", 4) {
+        Err(ValidationError::InvalidTensorShape { expected, found }) => {
+            assert_eq!(expected, "n");
+            assert_eq!(found, "4");
+        }
+        other => panic!("Expected InvalidTensorShape, got {other:?}"),
+    }
+
+    // 2*m == 7 has no integer solution, and dimensions cannot be negative
+    assert!(
+        constraints
+            .require_equal(&(Dim::symbol("m") * 2), &Dim::from(7))
+            .is_err()
+    );
+    assert!(
+        constraints
+            .require_equal(&(Dim::symbol("k") + Dim::from(5)), &Dim::from(2))
+            .is_err()
+    );
+}
+
+#[test]
+fn test_constraints_defer_unsolvable_equalities() {
+    let mut constraints = ShapeConstraints::new();
+
+    // 2*a == 2*b has no unit coefficient to solve for yet
+    constraints
+        .require_equal(&(Dim::symbol("a") * 2), &(Dim::symbol("b") * 2))
+        .unwrap();
+    assert_eq!(
+        constraints
+            .unresolved_symbols()
+            .into_iter()
+            .collect::<Vec<_>>(),
+        vec!["a", "b"]
+    );
+
+    constraints.bind("a", 5).unwrap();
+    assert_eq!(constraints.value_of("b"), Some(5));
+    assert!(constraints.unresolved_symbols().is_empty());
+}
+
+#[test]
+fn test_constraints_unchanged_after_contradiction() {
+    let mut constraints = ShapeConstraints::new();
+    let (a, b, c) = (Dim::symbol("a"), Dim::symbol("b"), Dim::symbol("c"));
+
+    // 2*a + 2*b == 10 and 2*a + 2*c == 20 both wait for more information
+    constraints
+        .require_equal(&(a.clone() * 2 + b * 2), &Dim::from(10))
+        .unwrap();
+    constraints
+        .require_equal(&(a * 2 + c * 2), &Dim::from(20))
+        .unwrap();
+
+    // a == 6 would make b negative, so nothing is recorded
+    assert!(constraints.bind("a", 6).is_err());
+    assert_eq!(constraints.value_of("a"), None);
+    assert_eq!(
+        constraints
+            .unresolved_symbols()
+            .into_iter()
+            .collect::<Vec<_>>(),
+        vec!["a", "b", "c"]
+    );
+
+    // c == 100 still contradicts the second equality
+    assert!(constraints.bind("c", 100).is_err());
+    constraints.bind("a", 4).unwrap();
+    assert_eq!(constraints.value_of("b"), Some(1));
+    assert_eq!(constraints.value_of("c"), Some(6));
+}
+
+#[test]
+fn test_broadcast_with_symbolic_dims() {
+    let batch = Dim::symbol("batch");
+
+    let lhs = symbolic_shape(vec![batch.clone(), 1.into()]);
+    let rhs = symbolic_shape(vec![batch.clone(), 64.into()]);
+    assert_eq!(
+        lhs.broadcast_with(&rhs).unwrap(),
+        symbolic_shape(vec![batch.clone(), 64.into()])
+    );
+
+    // A symbol against a static size is assumed equal to it
+    let mut constraints = ShapeConstraints::new();
+    let dynamic = symbolic_shape(vec![batch]);
+    let fixed = create_test_tensor_shape(vec![4]);
+    assert_eq!(
+        TensorShape::broadcast_constrained(&[&dynamic, &fixed], &mut constraints).unwrap(),
+        fixed
+    );
+    assert_eq!(constraints.value_of("batch"), Some(4));
+}
+
+#[test]
+fn test_shape_inference_on_dynamic_transformer_block() {
+    let batch = Dim::symbol("batch");
+    let seq = Dim::symbol("seq");
+    let f32_type = |dims: Vec<Dim>| TensorType::new(DType::F32, symbolic_shape(dims));
+
+    let mut graph = Graph::new();
+    let x = graph.add_node_with_type(
+        OpKind::custom("Input"),
+        vec![],
+        f32_type(vec![batch.clone(), seq.clone(), 64.into()]),
+    );
+    let w = graph.add_node_with_type(
+        OpKind::custom("Weight"),
+        vec![],
+        f32_type(vec![64.into(), 256.into()]),
+    );
+    let bias = graph.add_node_with_type(OpKind::custom("Bias"), vec![], f32_type(vec![256.into()]));
+    let matmul = graph.add_node(OpKind::matmul(), vec![x, w]);
+    let add = graph.add_node(OpKind::Add, vec![matmul, bias]);
+    let gelu = graph.add_node(OpKind::Gelu, vec![add]);
+
+    let mut inference = ShapeInference::new(&mut graph);
+    inference.run().unwrap();
+    assert_eq!(inference.constraints().value_of("batch"), None);
+
+    let expected = f32_type(vec![batch, seq, 256.into()]);
+    assert_eq!(graph.output_type(matmul), Some(&expected));
+    assert_eq!(graph.output_type(gelu), Some(&expected));
+}
+
+#[test]
+fn test_shape_inference_reports_symbolic_conflicts() {
+    let n = Dim::symbol("n");
+    let mut graph = Graph::new();
+
+    let square = graph.add_node_with_type(
+        OpKind::custom("Input"),
+        vec![],
+        TensorType::new(DType::F32, symbolic_shape(vec![n.clone(), n])),
+    );
+    let rect = graph.add_node_with_type(
+        OpKind::custom("Input"),
+        vec![],
+        TensorType::new(DType::F32, create_test_tensor_shape(vec![3, 4])),
+    );
+    graph.add_node(OpKind::Add, vec![square, rect]);
+
+    let errors = ShapeInference::new(&mut graph).run().unwrap_err();
+    assert!(matches!(
+        errors[0],
+        ValidationError::IncompatibleShapes { .. }
+    ));
+}
+
+#[test]
+fn test_shape_inference_with_bound_symbols() {
+    let mut constraints = ShapeConstraints::new();
+    constraints.bind("batch", 8).unwrap();
+
+    let mut graph = Graph::new();
+    let x = graph.add_node_with_type(
+        OpKind::custom("Input"),
+        vec![],
+        TensorType::new(
+            DType::F32,
+            symbolic_shape(vec![Dim::symbol("batch"), 10.into()]),
+        ),
+    );
+    let softmax = graph.add_node(OpKind::softmax(), vec![x]);
+
+    ShapeInference::with_constraints(&mut graph, constraints)
+        .run()
+        .unwrap();
+
+    assert_eq!(
+        graph.output_type(softmax).unwrap().shape(),
+        &create_test_tensor_shape(vec![8, 10])
+    );
+}
@@ -0,0 +1,270 @@
+mod common;
+
+use common::{build_complex_graph, build_simple_graph, create_test_tensor_type};
+use xyntra::ir::{
+    attributes::{AttributeValue, Attributes},
+    errors::ParsingError,
+    graph::Graph,
+    layout::TensorLayout,
+    shape_inference::ShapeInference,
+    symbolic::{Dim, DimExpr},
+    tensor::Tensor,
+    text::{parse_graph, print_graph},
+    types::{DType, OpKind, TensorShape, TensorType},
+    validation::GraphValidator,
+};
+
+fn assert_round_trips(graph: &Graph) -> String {
+    let text = print_graph(graph);
+    let parsed = parse_graph(&text).unwrap();
+    assert_eq!(print_graph(&parsed), text);
+    text
+}
+
+fn parse_error(source: &str) -> String {
+    match parse_graph(source) {
+        Err(ParsingError::InvalidFormat { format, reason }) => {
+            assert_eq!(format, "xir");
+            reason
+        }
+        Err(other) => panic!("expected InvalidFormat, got {other:?}"),
+        Ok(_) => panic!("expected {source:?} to be rejected"),
+    }
+}
+
+#[test]
+fn test_print_simple_graph() {
+    let text = assert_round_trips(&build_simple_graph());
+
+    assert_eq!(
+        text,
+        "graph {\n\
+         \x20 %0 = input \"input\" : f32[2, 4]\n\
+         \x20 %1 = constant \"weights\" : f32[4, 3] = dense<[0.5, 0.5, 0.5, 0.5, 0.5, 0.5, 0.5, 0.5, 0.5, 0.5, 0.5, 0.5]>\n\
+         \x20 %2 = MatMul(%0, %1) {transpose_a = 0, transpose_b = 0}\n\
+         \x20 output \"output\" = %2\n\
+         }\n"
+    );
+}
+
+#[test]
+fn test_round_trip_common_graphs() {
+    assert_round_trips(&build_complex_graph());
+
+    let mut graph = build_complex_graph();
+    ShapeInference::new(&mut graph).run().unwrap();
+    let text = assert_round_trips(&graph);
+    assert!(text.contains("Dropout(%3) {ratio = 0.5} : f32[8, 32]"));
+}
+
+#[test]
+fn test_round_trip_ports_and_custom_ops() {
+    let mut graph = Graph::new();
+    let x = graph.add_input("x", create_test_tensor_type(DType::F32, vec![2, 6]));
+    let split = graph.add_node(
+        OpKind::Split {
+            axis: 1,
+            sizes: vec![2, 4],
+        },
+        vec![x],
+    );
+    let attributes = Attributes::new()
+        .with("alpha", AttributeValue::Float(-0.0))
+        .with("label", AttributeValue::String("say \"hi\"\n".to_string()))
+        .with("empty", AttributeValue::Floats(vec![]))
+        .with("names", AttributeValue::Strings(vec!["a".to_string()]))
+        .with("inf", AttributeValue::Float(f32::NEG_INFINITY))
+        .with("scales", AttributeValue::Floats(vec![1e-8, 3.5]));
+    let custom = graph.add_node_with_values(
+        OpKind::Custom {
+            name: "Mix".to_string(),
+            attributes,
+        },
+        vec![split.output(1), split.output(0)],
+    );
+    graph.add_output("y", custom).unwrap();
+    graph.add_output("tail", split.output(1)).unwrap();
+    ShapeInference::new(&mut graph).run().unwrap();
+
+    let text = assert_round_trips(&graph);
+    assert!(text.contains("custom \"Mix\"(%1#1, %1)"));
+    assert!(text.contains(": (f32[2, 2], f32[2, 4])"));
+    assert!(text.contains("output \"tail\" = %1#1"));
+
+    let parsed = parse_graph(&text).unwrap();
+    let node = parsed.get_node(parsed.outputs()[0].node()).unwrap();
+    assert_eq!(node.op(), graph.get_node(custom).unwrap().op());
+    assert!(GraphValidator::new(&parsed).validate().is_ok());
+}
+
+#[test]
+fn test_round_trip_types_and_constants() {
+    let mut graph = Graph::new();
+    let batch = Dim::symbol("batch");
+    let doubled = Dim::from_expr(DimExpr::symbol("n").scale(2) - DimExpr::constant(1));
+    let x = graph.add_input(
+        "x",
+        TensorType::new(DType::F16, TensorShape::from_dims(vec![batch, doubled])),
+    );
+    let strided = TensorType::new(DType::F32, TensorShape::new(vec![2, 3]))
+        .with_layout(TensorLayout::strided(vec![1, 2], 0))
+        .unwrap();
+    let y = graph.add_input("y", strided);
+    let ints = graph.add_initializer(
+        "ints",
+        Tensor::from_i32(TensorShape::new(vec![3]), &[-1, 0, i32::MAX]).unwrap(),
+    );
+    let half = graph.add_initializer(
+        "half",
+        Tensor::new(
+            TensorType::new(DType::F16, TensorShape::new(vec![2])),
+            vec![0x00, 0x3c, 0x00, 0xc0],
+        )
+        .unwrap(),
+    );
+    let nan = graph.add_initializer(
+        "nan",
+        Tensor::new(
+            TensorType::new(DType::F32, TensorShape::new(vec![])),
+            0x7fc0_0001_u32.to_le_bytes().to_vec(),
+        )
+        .unwrap(),
+    );
+    let cast = graph.add_node(OpKind::Cast { to: DType::BF16 }, vec![x]);
+    graph.add_output("cast", cast).unwrap();
+    for id in [y, ints, half, nan] {
+        graph.add_output(format!("out_{}", id.id()), id).unwrap();
+    }
+
+    let text = assert_round_trips(&graph);
+    assert!(text.contains("f16[batch, 2*n - 1]"));
+    assert!(text.contains("f32[2, 3] layout<[1, 2], 0>"));
+    assert!(text.contains("dense<[-1, 0, 2147483647]>"));
+    assert!(text.contains("dense<\"0x003c00c0\">"));
+    // A NaN payload the list form would lose is kept as hex
+    assert!(text.contains("dense<\"0x0100c07f\">"));
+    assert!(text.contains("Cast(%0) {to = \"bf16\"}"));
+
+    let parsed = parse_graph(&text).unwrap();
+    for (name, id) in [("ints", ints), ("half", half), ("nan", nan)] {
+        let copy = parsed.find_value(name).unwrap();
+        assert_eq!(parsed.initializer(copy), graph.initializer(id));
+    }
+}
+
+#[test]
+fn test_parse_fixture() {
+    let mut graph = parse_graph(include_str!("data/mlp.xir")).unwrap();
+
+    assert_eq!(graph.len(), 8);
+    assert_eq!(graph.inputs().len(), 1);
+    assert_eq!(graph.initializers().len(), 2);
+    assert_eq!(graph.outputs()[0].value().port(), 0);
+    let scaled = graph.get_node(graph.outputs()[1].node()).unwrap();
+    assert_eq!(scaled.op().name(), "Scale");
+    assert_eq!(scaled.inputs()[0].port(), 1);
+    assert!(GraphValidator::new(&graph).validate().is_ok());
+
+    // The types written in the file agree with inference
+    let written = print_graph(&graph);
+    ShapeInference::new(&mut graph).run().unwrap();
+    let inferred = print_graph(&graph);
+    for line in written.lines().filter(|line| line.contains(" : ")) {
+        assert!(inferred.contains(line), "{line}");
+    }
+    assert_round_trips(&graph);
+}
+
+#[test]
+fn test_forward_references_and_comments() {
+    let graph = parse_graph(
+        "graph { // header\n\
+         output \"y\" = %b\n\
+         %b = Relu(%a)\n\
+         %a = input \"x\" : bool[3] // trailing\n\
+         }",
+    )
+    .unwrap();
+
+    let relu = graph.outputs()[0].node();
+    assert_eq!(graph.get_node(relu).unwrap().op(), &OpKind::Relu);
+    assert_eq!(graph.inputs().len(), 1);
+    assert_eq!(graph.users(graph.inputs()[0]), &[relu]);
+}
+
+#[test]
+fn test_errors_report_line_and_column() {
+    let cases = [
+        (
+            "graph {\n  %0 = input \"x\" : f64[2]\n}",
+            "line 2, column 20: unknown element type 'f64'",
+        ),
+        (
+            "graph {\n  %0 = Relu(%1)\n}",
+            "line 2, column 13: value '%1' is not defined",
+        ),
+        (
+            "graph {\n  %0 = input \"x\" : f32[2]\n  %0 = Relu(%0)\n}",
+            "line 3, column 3: value '%0' is defined twice",
+        ),
+        (
+            "graph {\n  %0 = Frobnicate()\n}",
+            "line 2, column 8: unknown op 'Frobnicate'",
+        ),
+        (
+            "graph {\n  %0 = Relu() {alpha = 1}\n}",
+            "line 2, column 8: ",
+        ),
+        (
+            "graph {\n  %0 = Relu(\n",
+            "line 3, column 1: expected a value, found end of input",
+        ),
+        (
+            "graph {\n  %0 = Concat() {axis = [1, \"a\"]}\n}",
+            "line 2, column 29: list mixes int and string elements",
+        ),
+        (
+            "graph {\n  %0 = constant \"c\" : f32[3] = dense<[1.0, 2.0]>\n}",
+            "line 2, column 37: ",
+        ),
+        (
+            "graph {\n  %0 = constant \"w\" : f32[4294967296, 4294967296] = dense<[1.0]>\n}",
+            "line 2, column 58: ",
+        ),
+        (
+            "graph {\n  %0 = constant \"c\" : i8[1] = dense<[300]>\n}",
+            "line 2, column 36: elements do not fit i8",
+        ),
+        (
+            "graph {\n  %0 = input \"x : f32[2]\n}",
+            "line 2, column 14: unterminated string",
+        ),
+        (
+            "graph {\n  %0 = Relu() ; \n}",
+            "line 2, column 15: unexpected character ';'",
+        ),
+        (
+            "graph {}\ngraph {}",
+            "line 2, column 1: expected end of input, found 'graph'",
+        ),
+    ];
+
+    for (source, expected) in cases {
+        let reason = parse_error(source);
+        assert!(reason.starts_with(expected), "{reason:?} for {source:?}");
+    }
+}
+
+#[test]
+fn test_printed_stale_references_do_not_parse() {
+    let mut graph = Graph::new();
+    let x = graph.add_input("x", create_test_tensor_type(DType::F32, vec![4]));
+    let dead = graph.add_node(OpKind::Relu, vec![x]);
+    graph.remove_node(dead).unwrap();
+    graph.add_node(OpKind::Exp, vec![x]);
+    graph.add_node(OpKind::Tanh, vec![dead]);
+
+    let text = print_graph(&graph);
+    assert!(text.contains("Tanh(%missing_1)"));
+    assert!(parse_error(&text).contains("value '%missing_1' is not defined"));
+}
@@ -0,0 +1,432 @@
+mod common;
+
+use common::{fixture, fixture_path, import_error, ops};
+use xyntra::{
+    ir::{
+        errors::{ParsingError, XyntraError},
+        graph::Graph,
+        symbolic::Dim,
+        types::{DType, OpKind, TensorShape, TensorType, Window2d},
+        validation::GraphValidator,
+    },
+    torchscript::{
+        archive::{ZipArchive, crc32},
+        import::{import_torchscript, load_torchscript},
+        inflate::inflate,
+        pickle::{Value, unpickle},
+        script::{Expr, Stmt, parse_source},
+    },
+};
+
+fn f32_type(dims: Vec<Dim>) -> TensorType {
+    TensorType::new(DType::F32, TensorShape::from_dims(dims))
+}
+
+fn invalid_format(bytes: &[u8], input_types: &[TensorType]) -> (String, String) {
+    match import_error(import_torchscript(bytes, input_types)) {
+        ParsingError::InvalidFormat { format, reason } => (format, reason),
+        other => panic!("expected InvalidFormat, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_zip_archive_and_pickle() {
+    let bytes = fixture("torchscript", "mlp.pt");
+    let archive = ZipArchive::parse(&bytes).unwrap();
+    let names: Vec<&str> = archive
+        .entries()
+        .iter()
+        .map(|entry| entry.name.as_str())
+        .collect();
+    assert!(names.contains(&"mlp/data.pkl"));
+    assert!(names.contains(&"mlp/code/__torch__/torch/nn/modules/linear.py"));
+    assert_eq!(archive.read("mlp/byteorder").unwrap().as_ref(), b"little");
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+
+    let root = unpickle(&archive.read("mlp/data.pkl").unwrap()).unwrap();
+    assert!(root.is_object("__torch__", "MLP"));
+    let Value::Object {
+        state: Some(state), ..
+    } = &root
+    else {
+        panic!("the root module has no state");
+    };
+    assert_eq!(state.get("training"), Some(&Value::Bool(false)));
+    assert_eq!(state.get("_is_full_backward_hook"), Some(&Value::None));
+
+    let fc1 = state.get("fc1").unwrap();
+    assert!(fc1.is_object("__torch__.torch.nn.modules.linear", "Linear"));
+    let Value::Object {
+        state: Some(fc1), ..
+    } = fc1
+    else {
+        panic!("fc1 has no state");
+    };
+    let Some(Value::Object {
+        module, name, args, ..
+    }) = fc1.get("weight")
+    else {
+        panic!("fc1.weight is not a tensor");
+    };
+    assert_eq!(
+        (module.as_str(), name.as_str()),
+        ("torch._utils", "_rebuild_tensor_v2")
+    );
+    assert_eq!(args[2], Value::Tuple(vec![Value::Int(8), Value::Int(4)]));
+    let Value::PersistentId(storage) = &args[0] else {
+        panic!("the storage is not a persistent id");
+    };
+    assert_eq!(
+        **storage,
+        Value::Tuple(vec![
+            Value::String("storage".to_string()),
+            Value::Global {
+                module: "torch".to_string(),
+                name: "FloatStorage".to_string()
+            },
+            Value::String("0".to_string()),
+            Value::String("cpu".to_string()),
+            Value::Int(32),
+        ])
+    );
+}
+
+#[test]
+fn test_parse_source() {
+    let source = parse_source(
+        "class Gate(Module):\n  \
+           scale : Final[float] = 0.5\n  \
+           def forward(self: __torch__.Gate,\n    x: Tensor) -> Tensor:\n    \
+             if self.training:\n      y, z = (x, x)\n    else:\n      y = -1\n    \
+             for i in range(3):\n      pass\n    \
+             return torch.add(x, y, alpha=2)\n",
+        "code/__torch__.py",
+    )
+    .unwrap();
+    let gate = source.class("Gate").unwrap();
+    assert_eq!(gate.constants, [("scale".to_string(), Expr::Float(0.5))]);
+
+    let forward = gate.method("forward").unwrap();
+    let params: Vec<&str> = forward
+        .params
+        .iter()
+        .map(|param| param.name.as_str())
+        .collect();
+    assert_eq!(params, ["self", "x"]);
+    let Stmt::If {
+        then_body,
+        else_body,
+        ..
+    } = &forward.body[0]
+    else {
+        panic!("expected an if statement");
+    };
+    assert!(
+        matches!(&then_body[0], Stmt::Assign { targets, unpack: true, .. } if targets.len() == 2)
+    );
+    assert!(matches!(
+        &else_body[0],
+        Stmt::Assign {
+            value: Expr::Int(-1),
+            ..
+        }
+    ));
+    assert!(matches!(
+        &forward.body[1],
+        Stmt::Unsupported { line: 9, .. }
+    ));
+    let Stmt::Return {
+        value: Some(Expr::Call {
+            function, keywords, ..
+        }),
+        ..
+    } = &forward.body[2]
+    else {
+        panic!("expected a returned call");
+    };
+    assert_eq!(function.dotted_name().as_deref(), Some("torch.add"));
+    assert_eq!(keywords, &[("alpha".to_string(), Expr::Int(2))]);
+
+    match parse_source("def f(x):\n  return (x\n", "code/f.py") {
+        Err(ParsingError::InvalidFormat { format, reason }) => {
+            assert_eq!(format, "torchscript");
+            assert!(reason.starts_with("code/f.py:"), "{reason}");
+        }
+        _ => panic!("expected a syntax error"),
+    }
+}
+
+#[test]
+fn test_import_mlp() {
+    let x = f32_type(vec![Dim::symbol("batch"), Dim::from(4)]);
+    let graph = load_torchscript(
+        fixture_path("torchscript", "mlp.pt"),
+        std::slice::from_ref(&x),
+    )
+    .unwrap();
+
+    // Each linear becomes MatMul against the transposed weight plus Add
+    assert_eq!(
+        ops(&graph),
+        [
+            "Input", "Constant", "Constant", "MatMul", "Add", "Relu", "Constant", "Constant",
+            "MatMul", "Add", "Softmax"
+        ]
+    );
+    let input = graph.inputs()[0];
+    assert_eq!(graph.find_value("x"), Some(input));
+    assert_eq!(graph.output_type(input), Some(&x));
+    let matmul = graph
+        .nodes()
+        .find(|node| node.op().name() == "MatMul")
+        .unwrap();
+    assert_eq!(
+        matmul.op(),
+        &OpKind::MatMul {
+            transpose_a: false,
+            transpose_b: true
+        }
+    );
+
+    // The transposed storage is gathered into row-major order
+    let fc2 = graph
+        .initializer(graph.find_value("fc2.weight").unwrap())
+        .unwrap();
+    assert_eq!(fc2.tensor_type().shape().static_dims(), Some(vec![2, 8]));
+    assert_eq!(fc2.to_f32_vec()[..3], [0.0, 10.0, 20.0]);
+    assert_eq!(fc2.to_f32_vec()[8..11], [1.0, 11.0, 21.0]);
+    let fc1 = graph
+        .initializer(graph.find_value("fc1.weight").unwrap())
+        .unwrap();
+    assert_eq!(fc1.to_f32_vec()[..3], [-0.5, -0.25, 0.0]);
+
+    assert_eq!(graph.outputs()[0].name(), "output");
+    let output = graph.get_node(graph.outputs()[0].node()).unwrap();
+    assert_eq!(output.op(), &OpKind::Softmax { axis: -1 });
+    assert_eq!(output.output_type().unwrap().to_string(), "f32[batch, 2]");
+    assert!(GraphValidator::new(&graph).validate().is_ok());
+}
+
+#[test]
+fn test_deflated_archive_matches_stored() {
+    let x = [f32_type(vec![Dim::from(3), Dim::from(4)])];
+    let stored = import_torchscript(&fixture("torchscript", "mlp.pt"), &x).unwrap();
+    let deflated = import_torchscript(&fixture("torchscript", "mlp_deflated.pt"), &x).unwrap();
+
+    let bytes = fixture("torchscript", "mlp_deflated.pt");
+    let archive = ZipArchive::parse(&bytes).unwrap();
+    assert!(archive.entries().iter().all(|entry| entry.method == 8));
+
+    assert_eq!(ops(&stored), ops(&deflated));
+    for name in ["fc1.weight", "fc1.bias", "fc2.weight", "fc2.bias"] {
+        let tensor = |graph: &Graph| {
+            graph
+                .initializer(graph.find_value(name).unwrap())
+                .unwrap()
+                .to_f32_vec()
+        };
+        assert_eq!(tensor(&stored), tensor(&deflated), "{name}");
+    }
+
+    // 1 MiB of zeros in 1033 bytes stops at the expected size
+    let mut bomb = vec![
+        0xED, 0xC1, 0x31, 0x01, 0x00, 0x00, 0x00, 0xC2, 0xA0, 0xF5, 0x4F, 0x6D, 0x08, 0x5F, 0xA0,
+    ];
+    bomb.resize(1031, 0);
+    bomb.extend([0x3E, 0x03]);
+    assert_eq!(inflate(&bomb, 1 << 20).unwrap().len(), 1 << 20);
+    assert_eq!(
+        inflate(&bomb, 4096),
+        Err("the data inflates to more than 4096 bytes".to_string())
+    );
+}
+
+#[test]
+fn test_import_conv_block() {
+    let x = f32_type(vec![Dim::symbol("batch"), 3.into(), 16.into(), 16.into()]);
+    let graph = import_torchscript(&fixture("torchscript", "conv_block.pt"), &[x]).unwrap();
+
+    let op = |name: &str| {
+        graph
+            .nodes()
+            .find(|node| node.op().name() == name)
+            .unwrap()
+            .op()
+            .clone()
+    };
+    assert_eq!(
+        op("Conv2d"),
+        OpKind::Conv2d {
+            window: Window2d {
+                strides: [2, 2],
+                pads: [1, 1, 1, 1],
+                dilations: [1, 1],
+            },
+            groups: 1,
+        }
+    );
+    assert_eq!(op("BatchNorm"), OpKind::BatchNorm { epsilon: 1e-5 });
+    // An empty stride means the kernel size
+    assert_eq!(
+        op("MaxPool"),
+        OpKind::MaxPool {
+            kernel_shape: [2, 2],
+            window: Window2d {
+                strides: [2, 2],
+                pads: [0, 0, 0, 0],
+                dilations: [1, 1],
+            },
+        }
+    );
+    assert_eq!(op("GlobalAvgPool"), OpKind::GlobalAvgPool);
+    // The dynamic batch size keeps its place, written as 0
+    assert_eq!(op("Reshape"), OpKind::Reshape { shape: vec![0, -1] });
+
+    // Submodules reached through getattr are named by their path
+    assert!(graph.find_value("features.0.weight").is_some());
+    assert!(graph.find_value("features.1.running_var").is_some());
+    assert!(graph.find_value("features.1.num_batches_tracked").is_none());
+
+    let output = graph.get_node(graph.outputs()[0].node()).unwrap();
+    assert_eq!(output.output_type().unwrap().to_string(), "f32[batch, 4]");
+}
+
+#[test]
+fn test_import_control_flow_and_constants() {
+    let x = f32_type(vec![Dim::from(2), Dim::from(3)]);
+    let mask = TensorType::new(DType::Bool, TensorShape::new(vec![2, 2]));
+    let graph = import_torchscript(&fixture("torchscript", "gated.pt"), &[x, mask]).unwrap();
+
+    // The gate is on, so x is scaled; the free function adds its argument
+    // to itself
+    assert_eq!(
+        ops(&graph),
+        [
+            "Input",
+            "Input",
+            "Constant",
+            "Mul",
+            "Constant",
+            "Gather",
+            "Constant",
+            "Where",
+            "Add",
+            "ReduceSum"
+        ]
+    );
+    let names: Vec<&str> = graph.outputs().iter().map(|output| output.name()).collect();
+    assert_eq!(names, ["output_0", "output_1"]);
+
+    // The int64 index constant is narrowed to I32
+    let index = graph
+        .initializer(graph.find_value("CONSTANTS.c0").unwrap())
+        .unwrap();
+    assert_eq!(index.tensor_type().dtype(), DType::I32);
+    assert_eq!(index.data(), [2, 0, 0, 0, 0, 0, 0, 0]);
+
+    let scale = graph
+        .nodes()
+        .find(|node| matches!(node.op(), OpKind::Constant { name } if name == "const_0"))
+        .unwrap();
+    assert_eq!(graph.initializer(scale.id()).unwrap().to_f32_vec(), [0.5]);
+
+    let reduce = graph.get_node(graph.outputs()[1].node()).unwrap();
+    assert_eq!(reduce.output_type().unwrap().to_string(), "f32[2, 1]");
+
+    // A 3D input reaches the exception branch
+    let x3 = f32_type(vec![Dim::from(1), Dim::from(2), Dim::from(3)]);
+    let mask = TensorType::new(DType::Bool, TensorShape::new(vec![2, 2]));
+    let (format, reason) = invalid_format(&fixture("torchscript", "gated.pt"), &[x3, mask]);
+    assert_eq!(format, "torchscript");
+    assert_eq!(
+        reason,
+        "code/__torch__.py:17: the module raises an exception: Expected a 2D input"
+    );
+}
+
+#[test]
+fn test_empty_dimensions_and_kernels() {
+    let split_sizes = |length: usize| {
+        let x = f32_type(vec![Dim::from(length), Dim::from(4)]);
+        let graph = import_torchscript(&fixture("torchscript", "chunked.pt"), &[x]).unwrap();
+        graph
+            .nodes()
+            .filter_map(|node| match node.op() {
+                OpKind::Split { sizes, .. } => Some(sizes.clone()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(split_sizes(5), [vec![2, 2, 1], vec![2, 2, 1]]);
+    // An empty dimension keeps every chunk but gives one split piece
+    assert_eq!(split_sizes(0), [vec![0, 0, 0], vec![0]]);
+
+    let x = f32_type(vec![Dim::from(1), Dim::from(3), Dim::from(8), Dim::from(8)]);
+    let (format, reason) = invalid_format(&fixture("torchscript", "empty_kernel.pt"), &[x]);
+    assert_eq!(format, "torchscript");
+    assert!(
+        reason.ends_with("the weight has an empty kernel [0, 3]"),
+        "{reason}"
+    );
+}
+
+#[test]
+fn test_unsupported_op_and_input_count() {
+    let x = f32_type(vec![Dim::from(4)]);
+    let (format, reason) = invalid_format(
+        &fixture("torchscript", "unknown_op.pt"),
+        std::slice::from_ref(&x),
+    );
+    assert_eq!(format, "torchscript");
+    assert_eq!(reason, "code/__torch__.py:8: torch.cumsum is not supported");
+
+    let (_, reason) = invalid_format(&fixture("torchscript", "gated.pt"), &[x]);
+    assert_eq!(
+        reason,
+        "forward takes 2 inputs (x, mask) but 1 input types were given"
+    );
+}
+
+#[test]
+fn test_damaged_archives() {
+    let x = [f32_type(vec![Dim::from(3), Dim::from(4)])];
+
+    let (format, reason) = invalid_format(b"not a zip archive at all", &x);
+    assert_eq!(format, "zip");
+    assert!(reason.contains("end of central directory"), "{reason}");
+
+    // Flip a byte of fc1.weight's stored data
+    let mut bytes = fixture("torchscript", "mlp.pt");
+    let archive = ZipArchive::parse(&bytes).unwrap();
+    let entry = archive.entry("mlp/data/0").unwrap();
+    let header = entry.header_offset as usize;
+    let data = header
+        + 30
+        + entry.name.len()
+        + u16::from_le_bytes([bytes[header + 28], bytes[header + 29]]) as usize;
+    bytes[data] ^= 0xFF;
+    let (format, reason) = invalid_format(&bytes, &x);
+    assert_eq!(format, "zip");
+    assert!(
+        reason.ends_with("entry 'mlp/data/0' fails its CRC-32 check"),
+        "{reason}"
+    );
+
+    // Tensor views too large to materialise or address
+    let (_, reason) = invalid_format(&fixture("torchscript", "mlp_huge_view.pt"), &x);
+    assert!(
+        reason.ends_with("has sizes [1099511627776, 1099511627776] and strides [0, 0]"),
+        "{reason}"
+    );
+    let (_, reason) = invalid_format(&fixture("torchscript", "mlp_huge_stride.pt"), &x);
+    assert!(
+        reason.ends_with("reads past the end of its storage"),
+        "{reason}"
+    );
+
+    match load_torchscript("/nonexistent/model.pt", &x) {
+        Err(XyntraError::System(_)) => {}
+        Err(other) => panic!("expected an I/O error, got {other:?}"),
+        Ok(_) => panic!("expected the load to fail"),
+    }
+}
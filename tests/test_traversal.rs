@@ -0,0 +1,96 @@
+mod common;
+
+use common::{build_complex_graph, create_test_tensor_type};
+use xyntra::ir::{
+    graph::Graph,
+    traversal::Direction,
+    types::{DType, NodeID, OpKind},
+};
+
+/// x -> a -> b, x -> c, and d = Add(b, c). The two branches interleave under
+/// ID order: a(1), c(2), b(3).
+fn diamond() -> (Graph, [NodeID; 5]) {
+    let mut graph = Graph::new();
+    let x = graph.add_input("x", create_test_tensor_type(DType::F32, vec![4]));
+    let a = graph.add_node(OpKind::Relu, vec![x]);
+    let c = graph.add_node(OpKind::Exp, vec![x]);
+    let b = graph.add_node(OpKind::Tanh, vec![a]);
+    let d = graph.add_node(OpKind::Add, vec![b, c]);
+    (graph, [x, a, b, c, d])
+}
+
+#[test]
+fn test_nodes_in_insertion_order() {
+    let graph = build_complex_graph();
+    let ids: Vec<u32> = graph.nodes().map(|node| node.id().id()).collect();
+    assert_eq!(ids, vec![0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn test_predecessors_and_successors() {
+    let (mut graph, [x, a, b, c, d]) = diamond();
+    let square = graph.add_node(OpKind::Mul, vec![d, d]);
+
+    assert_eq!(graph.predecessors(d), vec![b, c]);
+    assert_eq!(graph.predecessors(square), vec![d]);
+    assert!(graph.predecessors(x).is_empty());
+    assert_eq!(graph.successors(x), vec![a, c]);
+    assert_eq!(graph.neighbors(a, Direction::Successors), vec![b]);
+    assert_eq!(graph.neighbors(a, Direction::Predecessors), vec![x]);
+}
+
+#[test]
+fn test_topological_and_reverse_post_order() {
+    let (graph, [x, a, b, c, d]) = diamond();
+
+    assert_eq!(graph.topological_order().unwrap(), vec![x, a, c, b, d]);
+    assert_eq!(graph.reverse_post_order(), vec![x, c, a, b, d]);
+    assert_eq!(graph.post_order(), vec![d, b, a, c, x]);
+}
+
+#[test]
+fn test_bfs_and_dfs() {
+    let (graph, [x, a, b, c, d]) = diamond();
+
+    let bfs: Vec<NodeID> = graph.bfs(x, Direction::Successors).collect();
+    assert_eq!(bfs, vec![x, a, c, b, d]);
+
+    let dfs: Vec<NodeID> = graph.dfs(x, Direction::Successors).collect();
+    assert_eq!(dfs, vec![x, a, b, d, c]);
+
+    let upstream: Vec<NodeID> = graph.dfs(d, Direction::Predecessors).collect();
+    assert_eq!(upstream, vec![d, b, a, x, c]);
+
+    let upstream: Vec<NodeID> = graph.bfs(d, Direction::Predecessors).collect();
+    assert_eq!(upstream, vec![d, b, c, a, x]);
+
+    assert_eq!(graph.bfs(NodeID::new(99), Direction::Successors).count(), 0);
+}
+
+#[test]
+fn test_walks_terminate_on_cycles() {
+    let mut graph = Graph::new();
+    let first = graph.add_node(OpKind::Relu, vec![NodeID::new(1)]);
+    let second = graph.add_node(OpKind::Relu, vec![first]);
+
+    let bfs: Vec<NodeID> = graph.bfs(first, Direction::Successors).collect();
+    assert_eq!(bfs, vec![first, second]);
+    assert_eq!(graph.dfs(second, Direction::Predecessors).count(), 2);
+    assert_eq!(graph.reverse_post_order().len(), 2);
+    assert!(graph.topological_order().is_err());
+}
+
+#[test]
+fn test_orders_are_stable_across_runs() {
+    let orders: Vec<(Vec<NodeID>, Vec<NodeID>)> = (0..5)
+        .map(|_| {
+            let (graph, _) = diamond();
+            (
+                graph.topological_order().unwrap(),
+                graph.reverse_post_order(),
+            )
+        })
+        .collect();
+
+    assert!(orders.windows(2).all(|pair| pair[0] == pair[1]));
+}
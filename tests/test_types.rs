@@ -1,8 +1,11 @@
 mod common;
 
-use common::{create_scalar_tensor_shape, create_test_node_id, create_test_tensor_shape};
+use common::{
+    create_scalar_tensor_shape, create_test_node_id, create_test_tensor_shape,
+    create_test_tensor_type,
+};
 use std::collections::HashSet;
-use xyntra::ir::types::NodeID;
+use xyntra::ir::types::{DType, NodeID};
 
 #[test]
 fn test_node_id_creation_and_equality() {
@@ -67,27 +70,27 @@ fn test_tensor_shape_rank_calculation() {
 fn test_tensor_shape_size_calculation() {
     // Test scalar (size should be 1)
     let scalar = create_scalar_tensor_shape();
-    assert_eq!(scalar.size(), 1);
+    assert_eq!(scalar.size(), Some(1));
 
     // Test 1D tensor
     let vector = create_test_tensor_shape(vec![10]);
-    assert_eq!(vector.size(), 10);
+    assert_eq!(vector.size(), Some(10));
 
     // Test 2D tensor
     let matrix = create_test_tensor_shape(vec![3, 4]);
-    assert_eq!(matrix.size(), 12); // 3 * 4 = 12
+    assert_eq!(matrix.size(), Some(12)); // 3 * 4 = 12
 
     // Test 3D tensor
     let tensor_3d = create_test_tensor_shape(vec![2, 3, 4]);
-    assert_eq!(tensor_3d.size(), 24); // 2 * 3 * 4 = 24
+    assert_eq!(tensor_3d.size(), Some(24)); // 2 * 3 * 4 = 24
 
     // Test with ones (should not affect size)
     let tensor_with_ones = create_test_tensor_shape(vec![1, 5, 1, 3, 1]);
-    assert_eq!(tensor_with_ones.size(), 15); // 1 * 5 * 1 * 3 * 1 = 15
+    assert_eq!(tensor_with_ones.size(), Some(15)); // 1 * 5 * 1 * 3 * 1 = 15
 
     // Test edge case: dimension with zero
     let tensor_with_zero = create_test_tensor_shape(vec![2, 0, 3]);
-    assert_eq!(tensor_with_zero.size(), 0); // 2 * 0 * 3 = 0
+    assert_eq!(tensor_with_zero.size(), Some(0)); // 2 * 0 * 3 = 0
 }
 
 #[test]
@@ -128,13 +131,20 @@ fn test_tensor_shape_equality_and_hashing() {
 fn test_tensor_shape_edge_cases() {
     // Test very large tensor
     let large_tensor = create_test_tensor_shape(vec![1000, 1000]);
-    assert_eq!(large_tensor.size(), 1_000_000);
+    assert_eq!(large_tensor.size(), Some(1_000_000));
     assert_eq!(large_tensor.rank(), 2);
     assert!(!large_tensor.is_scalar());
 
     // Test single dimension with large value
     let long_vector = create_test_tensor_shape(vec![1_000_000]);
-    assert_eq!(long_vector.size(), 1_000_000);
+    assert_eq!(long_vector.size(), Some(1_000_000));
+
+    // Element and byte counts past usize::MAX are unknown rather than wrapped
+    let huge = create_test_tensor_shape(vec![1 << 32, 1 << 32]);
+    assert_eq!(huge.size(), None);
+    let bytes = create_test_tensor_type(DType::F32, vec![1 << 31, 1 << 31]);
+    assert_eq!(bytes.shape().size(), Some(1 << 62));
+    assert_eq!(bytes.size_in_bytes(), None);
     assert_eq!(long_vector.rank(), 1);
     assert!(!long_vector.is_scalar());
 }
@@ -147,8 +157,44 @@ fn test_helper_functions() {
 
     let test_shape = create_test_tensor_shape(vec![2, 3]);
     assert_eq!(test_shape.rank(), 2);
-    assert_eq!(test_shape.size(), 6);
+    assert_eq!(test_shape.size(), Some(6));
 
     let scalar = create_scalar_tensor_shape();
     assert!(scalar.is_scalar());
 }
+
+#[test]
+fn test_dtype_sizes_and_names() {
+    let cases = [
+        (DType::F32, "f32", 4, true),
+        (DType::F16, "f16", 2, true),
+        (DType::BF16, "bf16", 2, true),
+        (DType::I32, "i32", 4, false),
+        (DType::I8, "i8", 1, false),
+        (DType::Bool, "bool", 1, false),
+    ];
+
+    for (dtype, name, bytes, is_float) in cases {
+        assert_eq!(dtype.name(), name);
+        assert_eq!(dtype.to_string(), name);
+        assert_eq!(dtype.size_in_bytes(), bytes);
+        assert_eq!(dtype.is_floating_point(), is_float);
+    }
+}
+
+#[test]
+fn test_tensor_type_accessors_and_display() {
+    let tensor_type = create_test_tensor_type(DType::F16, vec![2, 3, 4]);
+
+    assert_eq!(tensor_type.dtype(), DType::F16);
+    assert_eq!(
+        tensor_type.shape(),
+        &create_test_tensor_shape(vec![2, 3, 4])
+    );
+    assert_eq!(tensor_type.size_in_bytes(), Some(48)); // 24 elements * 2 bytes
+    assert_eq!(tensor_type.to_string(), "f16[2, 3, 4]");
+
+    let scalar = create_test_tensor_type(DType::Bool, vec![]);
+    assert_eq!(scalar.to_string(), "bool[]");
+    assert_eq!(scalar.size_in_bytes(), Some(1));
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use common::{build_complex_graph, create_test_tensor_type};
+use xyntra::ir::{
+    graph::Graph,
+    types::{DType, NodeID, OpKind},
+    validation::GraphValidator,
+};
+
+#[test]
+fn test_users_are_recorded_on_add() {
+    let graph = build_complex_graph();
+    let (input1, input2, matmul, gelu, dropout) = (
+        NodeID::new(0),
+        NodeID::new(1),
+        NodeID::new(2),
+        NodeID::new(3),
+        NodeID::new(4),
+    );
+
+    assert_eq!(graph.users(input1), &[matmul]);
+    assert_eq!(graph.users(input2), &[matmul]);
+    assert_eq!(graph.users(matmul), &[gelu]);
+    assert_eq!(graph.users(gelu), &[dropout]);
+    assert!(graph.users(dropout).is_empty());
+    assert_eq!(graph.get_node(matmul).unwrap().outputs(), &vec![gelu]);
+    assert!(graph.users(NodeID::new(99)).is_empty());
+}
+
+#[test]
+fn test_use_count_counts_every_read() {
+    let mut graph = Graph::new();
+    let x = graph.add_input("x", create_test_tensor_type(DType::F32, vec![4]));
+    let square = graph.add_node(OpKind::Mul, vec![x, x]);
+    let relu = graph.add_node(OpKind::Relu, vec![x]);
+
+    // One user per node, however many times it reads the value
+    assert_eq!(graph.users(x), &[square, relu]);
+    assert_eq!(graph.use_count(x), 3);
+    assert_eq!(graph.use_count(square), 0);
+}
+
+#[test]
+fn test_users_follow_ports() {
+    let mut graph = Graph::new();
+    let x = graph.add_input("x", create_test_tensor_type(DType::F32, vec![2, 6]));
+    let split = graph.add_node(
+        OpKind::Split {
+            axis: 1,
+            sizes: vec![3, 3],
+        },
+        vec![x],
+    );
+    let concat = graph.add_node_with_values(
+        OpKind::Concat { axis: 1 },
+        vec![split.output(1), split.output(0)],
+    );
+
+    assert_eq!(graph.users(split), &[concat]);
+    assert_eq!(graph.use_count(split), 2);
+}
+
+#[test]
+fn test_users_track_rewiring() {
+    let mut graph = Graph::new();
+    let x = graph.add_input("x", create_test_tensor_type(DType::F32, vec![4]));
+    let relu = graph.add_node(OpKind::Relu, vec![x]);
+    let exp = graph.add_node(OpKind::Exp, vec![relu]);
+    let tanh = graph.add_node(OpKind::Tanh, vec![x]);
+
+    graph.set_inputs(exp, vec![tanh.output(0)]).unwrap();
+    assert!(graph.users(relu).is_empty());
+    assert_eq!(graph.users(tanh), &[exp]);
+
+    // A second input added later takes over both readers, listed in ID order
+    let y = graph.add_input("y", create_test_tensor_type(DType::F32, vec![4]));
+    graph.set_inputs(tanh, vec![y.output(0)]).unwrap();
+    assert_eq!(graph.users(y), &[tanh]);
+    graph
+        .replace_all_uses_with(x.output(0), y.output(0))
+        .unwrap();
+    assert!(graph.users(x).is_empty());
+    assert_eq!(graph.users(y), &[relu, tanh]);
+    assert!(
+        GraphValidator::new(&graph)
+            .validate_node_references()
+            .is_ok()
+    );
+}
+
+#[test]
+fn test_removed_node_leaves_no_users_behind() {
+    let mut graph = Graph::new();
+    let x = graph.add_input("x", create_test_tensor_type(DType::F32, vec![4]));
+    let relu = graph.add_node(OpKind::Relu, vec![x]);
+    let exp = graph.add_node(OpKind::Exp, vec![x, relu]);
+
+    graph.remove_node(exp).unwrap();
+
+    assert_eq!(graph.users(x), &[relu]);
+    assert!(graph.users(relu).is_empty());
+    assert_eq!(graph.use_count(x), 1);
+}
@@ -0,0 +1,164 @@
+mod common;
+
+use common::{build_complex_graph, create_test_node_id_with_value};
+use xyntra::ir::{
+    errors::ValidationError, graph::Graph, types::OpKind, validation::GraphValidator,
+};
+
+#[test]
+fn test_valid_graph_passes_all_checks() {
+    let graph = build_complex_graph();
+    let validator = GraphValidator::new(&graph);
+
+    assert!(validator.validate_node_references().is_ok());
+    assert!(validator.detect_cycles().is_ok());
+    assert!(validator.validate_operation_constraints().is_ok());
+    assert!(validator.validate().is_ok());
+}
+
+#[test]
+fn test_empty_graph_is_valid() {
+    let graph = Graph::new();
+    assert!(GraphValidator::new(&graph).validate().is_ok());
+}
+
+#[test]
+fn test_dangling_input_reports_missing_node() {
+    let mut graph = Graph::new();
+    let input_id = graph.add_node(OpKind::custom("Input"), vec![]);
+    let dangling = create_test_node_id_with_value(99);
+    graph.add_node(OpKind::Add, vec![input_id, dangling]);
+
+    let errors = GraphValidator::new(&graph)
+        .validate_node_references()
+        .unwrap_err();
+
+    assert_eq!(errors.len(), 1);
+    match &errors[0] {
+        ValidationError::MissingNode { node_id } => assert_eq!(*node_id, 99),
+        other => panic!("Expected MissingNode, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_recorded_consumers_pass_reference_checks() {
+    let mut graph = Graph::new();
+    // Node 0 reads node 2 before it exists; the consumer is recorded once it does
+    let id0 = graph.add_node(OpKind::Gelu, vec![create_test_node_id_with_value(2)]);
+    let id1 = graph.add_node(OpKind::custom("Input"), vec![]);
+    let id2 = graph.add_node(OpKind::Relu, vec![id1]);
+
+    assert_eq!(graph.users(id1), &[id2]);
+    assert_eq!(graph.users(id2), &[id0]);
+    assert!(
+        GraphValidator::new(&graph)
+            .validate_node_references()
+            .is_ok()
+    );
+}
+
+#[test]
+fn test_cycle_reports_path_in_data_flow_order() {
+    let mut graph = Graph::new();
+    // 0 -> 1 -> 2 -> 0
+    let id0 = graph.add_node(OpKind::Gelu, vec![create_test_node_id_with_value(2)]);
+    let id1 = graph.add_node(OpKind::Gelu, vec![id0]);
+    let _id2 = graph.add_node(OpKind::Gelu, vec![id1]);
+
+    let errors = GraphValidator::new(&graph).detect_cycles().unwrap_err();
+
+    assert_eq!(errors.len(), 1);
+    match &errors[0] {
+        ValidationError::CyclicGraph { cycle_path } => assert_eq!(cycle_path, &vec![0, 1, 2]),
+        other => panic!("Expected CyclicGraph, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_self_loop_is_a_cycle() {
+    let mut graph = Graph::new();
+    graph.add_node(OpKind::Gelu, vec![create_test_node_id_with_value(0)]);
+
+    let errors = GraphValidator::new(&graph).detect_cycles().unwrap_err();
+
+    match &errors[0] {
+        ValidationError::CyclicGraph { cycle_path } => assert_eq!(cycle_path, &vec![0]),
+        other => panic!("Expected CyclicGraph, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_diamond_is_not_a_cycle() {
+    let mut graph = Graph::new();
+    let input_id = graph.add_node(OpKind::custom("Input"), vec![]);
+    let left = graph.add_node(OpKind::Gelu, vec![input_id]);
+    let right = graph.add_node(OpKind::softmax(), vec![input_id]);
+    graph.add_node(OpKind::Add, vec![left, right]);
+
+    assert!(GraphValidator::new(&graph).detect_cycles().is_ok());
+}
+
+#[test]
+fn test_wrong_input_count_per_op() {
+    let mut graph = Graph::new();
+    let a = graph.add_node(OpKind::custom("Input"), vec![]);
+    let b = graph.add_node(OpKind::custom("Input"), vec![]);
+
+    graph.add_node(OpKind::matmul(), vec![a]);
+    graph.add_node(OpKind::Gelu, vec![a, b]);
+    graph.add_node(OpKind::layer_norm(), vec![a, b, a, b]);
+    // Custom ops accept any number of inputs
+    graph.add_node(OpKind::custom("Concat"), vec![a, b, a]);
+
+    let errors = GraphValidator::new(&graph)
+        .validate_operation_constraints()
+        .unwrap_err();
+
+    let found: Vec<(String, usize, usize)> = errors
+        .iter()
+        .map(|error| match error {
+            ValidationError::InvalidOpInputCount {
+                op,
+                expected,
+                found,
+            } => (op.clone(), *expected, *found),
+            other => panic!("Expected InvalidOpInputCount, got {other:?}"),
+        })
+        .collect();
+
+    assert_eq!(
+        found,
+        vec![
+            ("MatMul".to_string(), 2, 1),
+            ("Gelu".to_string(), 1, 2),
+            ("LayerNorm".to_string(), 3, 4),
+        ]
+    );
+}
+
+#[test]
+fn test_validate_aggregates_every_error() {
+    let mut graph = Graph::new();
+    let dangling = create_test_node_id_with_value(50);
+    let id0 = graph.add_node(OpKind::Gelu, vec![create_test_node_id_with_value(1)]);
+    graph.add_node(OpKind::Add, vec![id0]);
+    graph.add_node(OpKind::softmax(), vec![dangling]);
+
+    let errors = GraphValidator::new(&graph).validate().unwrap_err();
+
+    assert!(
+        errors
+            .iter()
+            .any(|e| matches!(e, ValidationError::MissingNode { node_id: 50 }))
+    );
+    assert!(
+        errors
+            .iter()
+            .any(|e| matches!(e, ValidationError::CyclicGraph { .. }))
+    );
+    assert!(
+        errors
+            .iter()
+            .any(|e| matches!(e, ValidationError::InvalidOpInputCount { .. }))
+    );
+}
@@ -0,0 +1,307 @@
+mod common;
+
+use common::{create_test_tensor_type, f32_type, infer};
+use xyntra::ir::{
+    errors::ValidationError,
+    graph::Graph,
+    symbolic::Dim,
+    types::{Arity, DType, OpKind, TensorShape, TensorType, Window2d},
+    validation::GraphValidator,
+};
+
+fn window(stride: usize, pad: usize, dilation: usize) -> Window2d {
+    Window2d {
+        strides: [stride; 2],
+        pads: [pad; 4],
+        dilations: [dilation; 2],
+    }
+}
+
+fn conv(window: Window2d, groups: usize) -> OpKind {
+    OpKind::Conv2d { window, groups }
+}
+
+#[test]
+fn test_vision_arity() {
+    assert_eq!(
+        conv(Window2d::default(), 1).arity(),
+        Arity::Range { min: 2, max: 3 }
+    );
+    assert_eq!(
+        OpKind::DepthwiseConv {
+            window: Window2d::default()
+        }
+        .arity(),
+        Arity::Range { min: 2, max: 3 }
+    );
+    assert_eq!(OpKind::GlobalAvgPool.arity(), Arity::Exact(1));
+    assert_eq!(OpKind::BatchNorm { epsilon: 1e-5 }.arity(), Arity::Exact(5));
+
+    let mut graph = Graph::new();
+    let x = graph.add_node(OpKind::custom("Input"), vec![]);
+    graph.add_node(conv(Window2d::default(), 1), vec![x]);
+    graph.add_node(OpKind::BatchNorm { epsilon: 1e-5 }, vec![x, x, x]);
+    graph.add_node(OpKind::GlobalAvgPool, vec![x, x]);
+
+    let errors = GraphValidator::new(&graph)
+        .validate_operation_constraints()
+        .unwrap_err();
+    assert_eq!(errors.len(), 3);
+    assert!(
+        errors
+            .iter()
+            .all(|error| matches!(error, ValidationError::InvalidOpInputCount { .. }))
+    );
+}
+
+#[test]
+fn test_conv_shape_inference() {
+    // ResNet stem: 7x7 stride 2 pad 3
+    let image = f32_type(vec![1, 3, 224, 224]);
+    let weights = f32_type(vec![64, 3, 7, 7]);
+    let bias = f32_type(vec![64]);
+
+    assert_eq!(
+        infer(
+            conv(window(2, 3, 1), 1),
+            vec![image.clone(), weights.clone(), bias]
+        )
+        .unwrap(),
+        f32_type(vec![1, 64, 112, 112])
+    );
+
+    // Dilation widens the kernel: 3x3 dilated by 2 spans 5
+    assert_eq!(
+        infer(
+            conv(window(1, 0, 2), 1),
+            vec![f32_type(vec![1, 8, 10, 10]), f32_type(vec![8, 8, 3, 3])]
+        )
+        .unwrap(),
+        f32_type(vec![1, 8, 6, 6])
+    );
+
+    assert!(
+        infer(
+            conv(window(1, 0, 1), 1),
+            vec![image.clone(), f32_type(vec![64, 4, 7, 7])]
+        )
+        .is_err()
+    );
+    assert!(
+        infer(
+            conv(window(1, 0, 1), 1),
+            vec![f32_type(vec![1, 3, 4, 4]), weights]
+        )
+        .is_err()
+    );
+}
+
+#[test]
+fn test_vit_patch_embedding() {
+    // 16x16 patches with a stride-16 convolution
+    let image = f32_type(vec![8, 3, 224, 224]);
+    let weights = f32_type(vec![768, 3, 16, 16]);
+    let patches = infer(conv(window(16, 0, 1), 1), vec![image, weights]).unwrap();
+    assert_eq!(patches, f32_type(vec![8, 768, 14, 14]));
+}
+
+#[test]
+fn test_grouped_and_depthwise_conv() {
+    let input = f32_type(vec![2, 32, 16, 16]);
+
+    assert_eq!(
+        infer(
+            conv(window(1, 1, 1), 4),
+            vec![input.clone(), f32_type(vec![64, 8, 3, 3])]
+        )
+        .unwrap(),
+        f32_type(vec![2, 64, 16, 16])
+    );
+    // Output channels must split evenly across the groups
+    assert!(
+        infer(
+            conv(window(1, 1, 1), 4),
+            vec![input.clone(), f32_type(vec![30, 8, 3, 3])]
+        )
+        .is_err()
+    );
+
+    let depthwise = OpKind::DepthwiseConv {
+        window: window(2, 1, 1),
+    };
+    assert_eq!(
+        infer(
+            depthwise.clone(),
+            vec![input.clone(), f32_type(vec![64, 1, 3, 3])]
+        )
+        .unwrap(),
+        f32_type(vec![2, 64, 8, 8])
+    );
+    assert!(
+        infer(
+            depthwise.clone(),
+            vec![input.clone(), f32_type(vec![48, 1, 3, 3])]
+        )
+        .is_err()
+    );
+    assert!(infer(depthwise, vec![input, f32_type(vec![32, 2, 3, 3])]).is_err());
+}
+
+#[test]
+fn test_conv_symbolic_spatial_dims() {
+    let input = TensorType::new(
+        DType::F32,
+        TensorShape::from_dims(vec![
+            Dim::symbol("batch"),
+            Dim::Static(3),
+            Dim::symbol("h"),
+            Dim::symbol("w"),
+        ]),
+    );
+    let weights = f32_type(vec![16, 3, 3, 3]);
+
+    let same = infer(
+        conv(window(1, 1, 1), 1),
+        vec![input.clone(), weights.clone()],
+    )
+    .unwrap();
+    assert_eq!(same.shape().to_string(), "[batch, 16, h, w]");
+
+    let valid = infer(
+        conv(window(1, 0, 1), 1),
+        vec![input.clone(), weights.clone()],
+    )
+    .unwrap();
+    assert_eq!(valid.shape().to_string(), "[batch, 16, h - 2, w - 2]");
+
+    // Strided windows over symbolic sizes need division
+    assert!(infer(conv(window(2, 1, 1), 1), vec![input, weights]).is_err());
+}
+
+#[test]
+fn test_pooling_shape_inference() {
+    let input = f32_type(vec![1, 64, 112, 112]);
+
+    let max_pool = OpKind::MaxPool {
+        kernel_shape: [3, 3],
+        window: window(2, 1, 1),
+    };
+    assert_eq!(
+        infer(max_pool, vec![input.clone()]).unwrap(),
+        f32_type(vec![1, 64, 56, 56])
+    );
+
+    let avg_pool = OpKind::AvgPool {
+        kernel_shape: [2, 2],
+        window: window(2, 0, 1),
+        count_include_pad: false,
+    };
+    assert_eq!(
+        infer(avg_pool, vec![input.clone()]).unwrap(),
+        f32_type(vec![1, 64, 56, 56])
+    );
+
+    assert_eq!(
+        infer(OpKind::GlobalAvgPool, vec![input]).unwrap(),
+        f32_type(vec![1, 64, 1, 1])
+    );
+    assert!(infer(OpKind::GlobalAvgPool, vec![f32_type(vec![64, 7])]).is_err());
+    assert!(
+        infer(
+            OpKind::GlobalAvgPool,
+            vec![create_test_tensor_type(DType::I8, vec![1, 2, 3, 3])]
+        )
+        .is_err()
+    );
+}
+
+#[test]
+fn test_batch_norm_inference() {
+    let input = f32_type(vec![4, 64, 28, 28]);
+    let channel = f32_type(vec![64]);
+    let params = vec![channel.clone(), channel.clone(), channel.clone(), channel];
+
+    let mut inputs = vec![input.clone()];
+    inputs.extend(params);
+    assert_eq!(
+        infer(OpKind::BatchNorm { epsilon: 1e-5 }, inputs.clone()).unwrap(),
+        input
+    );
+
+    inputs[3] = f32_type(vec![32]);
+    assert!(infer(OpKind::BatchNorm { epsilon: 1e-5 }, inputs).is_err());
+}
+
+#[test]
+fn test_vision_attribute_validation() {
+    let mut graph = Graph::new();
+    let x = graph.add_node(OpKind::custom("Input"), vec![]);
+    graph.add_node(conv(Window2d::default(), 0), vec![x, x]);
+    graph.add_node(conv(window(0, 0, 1), 1), vec![x, x]);
+    graph.add_node(
+        OpKind::DepthwiseConv {
+            window: window(1, 0, 0),
+        },
+        vec![x, x],
+    );
+    graph.add_node(
+        OpKind::MaxPool {
+            kernel_shape: [0, 2],
+            window: Window2d::default(),
+        },
+        vec![x],
+    );
+    graph.add_node(OpKind::BatchNorm { epsilon: 0.0 }, vec![x, x, x, x, x]);
+
+    let errors = GraphValidator::new(&graph)
+        .validate_operation_constraints()
+        .unwrap_err();
+    let attributes: Vec<&str> = errors
+        .iter()
+        .map(|error| match error {
+            ValidationError::InvalidAttribute { attribute, .. } => attribute.as_str(),
+            _ => "",
+        })
+        .collect();
+    assert_eq!(
+        attributes,
+        vec!["groups", "strides", "dilations", "kernel_shape", "epsilon"]
+    );
+}
+
+#[test]
+fn test_vision_attributes_round_trip() {
+    let ops = [
+        conv(
+            Window2d {
+                strides: [2, 1],
+                pads: [1, 0, 1, 0],
+                dilations: [1, 2],
+            },
+            2,
+        ),
+        OpKind::DepthwiseConv {
+            window: window(2, 1, 1),
+        },
+        OpKind::MaxPool {
+            kernel_shape: [3, 3],
+            window: window(2, 1, 1),
+        },
+        OpKind::AvgPool {
+            kernel_shape: [2, 2],
+            window: Window2d::default(),
+            count_include_pad: true,
+        },
+        OpKind::GlobalAvgPool,
+        OpKind::BatchNorm { epsilon: 1e-3 },
+    ];
+    for op in ops {
+        assert_eq!(OpKind::from_parts(op.name(), &op.attributes()).unwrap(), op);
+    }
+
+    let short_pads = Window2d::default().attributes().with(
+        "pads",
+        xyntra::ir::attributes::AttributeValue::Ints(vec![1, 1]),
+    );
+    assert!(OpKind::from_parts("Conv2d", &short_pads).is_err());
+}
@@ -0,0 +1,194 @@
+mod common;
+
+use common::{build_simple_graph, create_test_tensor_type};
+use xyntra::{
+    config::XyntraConfig,
+    fusion::candidates::find_candidates,
+    ir::{
+        graph::Graph,
+        shape_inference::ShapeInference,
+        types::{DType, NodeID, OpKind, ReduceKind},
+        validation::GraphValidator,
+        visualize::{NodeGroup, to_dot, to_mermaid},
+    },
+};
+
+/// x -> ReduceSum -> Sqrt -> Split, with the reduction chain fusable.
+fn reduce_graph() -> (Graph, Vec<NodeGroup>) {
+    let mut graph = Graph::new();
+    let x = graph.add_input("x", create_test_tensor_type(DType::F32, vec![2, 4]));
+    let sum = graph.add_node(
+        OpKind::Reduce {
+            kind: ReduceKind::Sum,
+            axes: vec![1],
+            keepdims: true,
+        },
+        vec![x],
+    );
+    let sqrt = graph.add_node(OpKind::Sqrt, vec![sum]);
+    let split = graph.add_node(
+        OpKind::Split {
+            axis: 0,
+            sizes: vec![1, 1],
+        },
+        vec![sqrt],
+    );
+    graph.add_output("low", split).unwrap();
+    graph.add_output("high", split.output(1)).unwrap();
+    ShapeInference::new(&mut graph).run().unwrap();
+
+    let groups = find_candidates(&graph)
+        .iter()
+        .map(NodeGroup::from)
+        .collect();
+    (graph, groups)
+}
+
+#[test]
+fn test_dot_labels_and_clusters() {
+    let (graph, groups) = reduce_graph();
+    assert_eq!(groups.len(), 1);
+
+    let dot = to_dot(&graph, &groups);
+
+    assert!(dot.starts_with("digraph xyntra {\n"));
+    assert!(dot.ends_with("}\n"));
+    assert!(dot.contains("  n0 [label=\"%0 input \\\"x\\\"\\nf32[2, 4]\", shape=ellipse];"));
+    assert!(
+        dot.contains("  subgraph cluster_0 {\n    label=\"ReduceElementwise\";\n    style=dashed;")
+    );
+    assert!(dot.contains(
+        "    n1 [label=\"%1 ReduceSum\\naxes = [1]\\nkeepdims = 1\\nf32[2, 1]\", shape=box];"
+    ));
+    assert!(dot.contains("    n2 [label=\"%2 Sqrt\\nf32[2, 1]\", shape=box];"));
+    assert!(dot.contains("f32[1, 1]\\nf32[1, 1]\", shape=box];"));
+    assert!(dot.contains("  n0 -> n1;\n  n1 -> n2;\n  n2 -> n3;\n"));
+    assert!(dot.contains(
+        "  output_1 [label=\"high\", shape=doubleoctagon];\n  n3 -> output_1 [label=\"#1\"];"
+    ));
+    assert!(!dot.contains("fillcolor"));
+}
+
+#[test]
+fn test_mermaid_labels_and_subgraphs() {
+    let (graph, groups) = reduce_graph();
+
+    let mermaid = to_mermaid(&graph, &groups);
+
+    assert!(mermaid.starts_with("flowchart TD\n"));
+    assert!(mermaid.contains("  n0([\"%0 input #quot;x#quot;<br/>f32[2, 4]\"])"));
+    assert!(mermaid.contains(
+        "  subgraph group_0 [\"ReduceElementwise\"]\n    n1[\"%1 ReduceSum<br/>axes = [1]"
+    ));
+    assert!(mermaid.contains("    n2[\"%2 Sqrt<br/>f32[2, 1]\"]\n  end\n"));
+    assert!(mermaid.contains("  n3 -->|\"#35;1\"| output_1"));
+    assert!(mermaid.contains("  output_0{{\"low\"}}"));
+    assert!(!mermaid.contains("classDef"));
+}
+
+#[test]
+fn test_constants_and_custom_ops() {
+    let mut graph = build_simple_graph();
+    let matmul = NodeID::new(2);
+    graph.add_node(
+        OpKind::Custom {
+            name: "Tag<\"a\">".to_string(),
+            attributes: Default::default(),
+        },
+        vec![matmul],
+    );
+
+    let dot = to_dot(&graph, &[]);
+    assert!(dot.contains("n1 [label=\"%1 constant \\\"weights\\\"\\nf32[4, 3]\", shape=note];"));
+    assert!(dot.contains("%3 custom \\\"Tag<\\\\\\\"a\\\\\\\">\\\"\""));
+
+    let mermaid = to_mermaid(&graph, &[]);
+    assert!(mermaid.contains("  n1[(\"%1 constant #quot;weights#quot;<br/>f32[4, 3]\")]"));
+    assert!(mermaid.contains("#lt;"));
+    assert!(!mermaid.contains("Tag<"));
+}
+
+#[test]
+fn test_highlights_nodes_with_errors() {
+    let mut graph = Graph::new();
+    let x = graph.add_input("x", create_test_tensor_type(DType::F32, vec![4]));
+    let dead = graph.add_node(OpKind::Exp, vec![x]);
+    graph.remove_node(dead).unwrap();
+    let relu = graph.add_node(OpKind::Relu, vec![x, x]);
+    let tanh = graph.add_node(OpKind::Tanh, vec![dead]);
+
+    let errors = GraphValidator::new(&graph).errors_by_node();
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[&relu].len(), 1);
+    assert_eq!(errors[&tanh].len(), 1);
+
+    let dot = to_dot(&graph, &[]);
+    assert!(dot.contains(&format!(
+        "n{} [label=\"%{0} Relu\", shape=box, style=filled, fillcolor=\"#fdd\", color=\"#c00\", penwidth=2, tooltip=\"Operation 'Relu' expects 1 inputs but received 2.\"];",
+        relu.id()
+    )));
+    assert!(dot.contains("missing_1 [label=\"missing node 1\", shape=box, style=dashed];"));
+    assert!(dot.contains(&format!("missing_1 -> n{};", tanh.id())));
+
+    let mermaid = to_mermaid(&graph, &[]);
+    assert!(mermaid.contains(&format!("  class n1,n{} error\n", tanh.id())));
+    assert!(mermaid.contains("  class missing_1 missing\n"));
+}
+
+#[test]
+fn test_cycles_mark_every_node_on_them() {
+    let mut graph = Graph::new();
+    let x = graph.add_input("x", create_test_tensor_type(DType::F32, vec![4]));
+    let add = graph.add_node(OpKind::Add, vec![x, x]);
+    let relu = graph.add_node(OpKind::Relu, vec![add]);
+    graph
+        .set_inputs(add, vec![x.output(0), relu.output(0)])
+        .unwrap();
+
+    let errors = GraphValidator::new(&graph).errors_by_node();
+
+    assert_eq!(errors.len(), 2);
+    assert!(errors.contains_key(&add) && errors.contains_key(&relu));
+    assert!(!errors.contains_key(&x));
+}
+
+#[test]
+fn test_config_export_ir_flag() {
+    let (graph, _) = reduce_graph();
+    let output_dir = std::env::temp_dir().join(format!("xyntra_export_{}", std::process::id()));
+    std::fs::create_dir_all(&output_dir).unwrap();
+
+    let mut config = XyntraConfig {
+        output_dir: output_dir.clone(),
+        ..XyntraConfig::default()
+    };
+    assert!(config.export_graph(&graph, "model").unwrap().is_empty());
+    assert!(!output_dir.join("model.dot").exists());
+
+    config.export_ir = true;
+    let written = config.export_graph(&graph, "model").unwrap();
+
+    assert_eq!(
+        written,
+        vec![output_dir.join("model.dot"), output_dir.join("model.mmd")]
+    );
+    let dot = std::fs::read_to_string(&written[0]).unwrap();
+    assert!(dot.contains("subgraph cluster_0"));
+    assert_eq!(
+        dot,
+        to_dot(
+            &graph,
+            &find_candidates(&graph)
+                .iter()
+                .map(NodeGroup::from)
+                .collect::<Vec<_>>()
+        )
+    );
+    let mermaid = std::fs::read_to_string(&written[1]).unwrap();
+    assert!(mermaid.contains("subgraph group_0"));
+
+    config.output_dir = output_dir.join("absent");
+    assert!(config.export_graph(&graph, "model").is_err());
+
+    std::fs::remove_dir_all(&output_dir).unwrap();
+}